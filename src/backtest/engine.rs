@@ -1,246 +1,162 @@
+use crate::backtest::orders::process_bar;
+use crate::backtest::rebalance::{asset_weight, rebalance_to};
 use crate::backtest::{
-    calculate_atr, BacktestResult, ExecutionModel, Portfolio, PositionSizingMethod, RiskLimits,
-    RiskMetrics, StopLossMethod, Trade, TradeStats,
+    calculate_atr, BacktestResult, CashFlowReturns, Contributions, ExecutionModel, Fill,
+    LevelStats, LimitOrder, OrderBook, OrderStats, OrderStrategy, Portfolio, PositionSizingMethod,
+    Rebalancer, RiskLimits, Side, StopLossMethod, TradeStats, TradingRules, TradingState,
 };
-use crate::data::OHLCV;
+use crate::data::{TimeframeContext, OHLCV};
+use crate::indicators::IndicatorCache;
 use crate::metrics::{
-    calculate_calmar_ratio, calculate_max_drawdown, calculate_sharpe_ratio, calculate_sortino_ratio,
+    calculate_calmar_ratio, calculate_max_drawdown, calculate_money_weighted_return,
+    calculate_sharpe_ratio, calculate_sortino_ratio, calculate_time_weighted_return, MS_PER_YEAR,
 };
 use crate::strategies::Strategy;
-
-pub struct BacktestEngine {
-    data: Vec<OHLCV>,
+use anyhow::{bail, Context, Result};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Backtester over a bar series it owns or borrows
+///
+/// Pass a slice to run many backtests over the same bars without copying
+/// them, e.g. one per parameter combination of a sweep.
+pub struct BacktestEngine<'a> {
+    data: Cow<'a, [OHLCV]>,
     initial_capital: f64,
-    execution_model: ExecutionModel,
-    position_sizing: PositionSizingMethod,
-    stop_loss: StopLossMethod,
-    risk_limits: RiskLimits,
+    rules: TradingRules,
+    timeframes: Option<TimeframeContext>,
+    indicators: Option<Arc<IndicatorCache>>,
 }
 
-impl BacktestEngine {
-    pub fn new(data: Vec<OHLCV>, initial_capital: f64, execution_model: ExecutionModel) -> Self {
+impl<'a> BacktestEngine<'a> {
+    pub fn new(
+        data: impl Into<Cow<'a, [OHLCV]>>,
+        initial_capital: f64,
+        execution_model: ExecutionModel,
+    ) -> Self {
         Self {
-            data,
+            data: data.into(),
             initial_capital,
-            execution_model,
-            position_sizing: PositionSizingMethod::default(),
-            stop_loss: StopLossMethod::default(),
-            risk_limits: RiskLimits::default(),
+            rules: TradingRules::new(execution_model),
+            timeframes: None,
+            indicators: None,
         }
     }
 
     pub fn with_position_sizing(mut self, method: PositionSizingMethod) -> Self {
-        self.position_sizing = method;
+        self.rules.position_sizing = method;
         self
     }
 
     pub fn with_stop_loss(mut self, method: StopLossMethod) -> Self {
-        self.stop_loss = method;
+        self.rules.stop_loss = method;
         self
     }
 
     pub fn with_risk_limits(mut self, limits: RiskLimits) -> Self {
-        self.risk_limits = limits;
+        self.rules.risk_limits = limits;
         self
     }
 
-    pub fn run(&self, strategy: &dyn Strategy) -> BacktestResult {
-        let signals = strategy.generate_signals(&self.data);
-
-        let mut portfolio = Portfolio::new(self.initial_capital);
-        let mut equity_curve = Vec::with_capacity(self.data.len());
-        let mut prev_position = 0.0;
-        let mut trades: Vec<Trade> = Vec::new();
-        let mut entry_bar: Option<usize> = None;
-        let mut entry_price_with_costs: Option<f64> = None;
-        let mut position_size: Option<f64> = None;
-        let mut highest_price: f64 = 0.0;
-        let mut risk_metrics = RiskMetrics::new(self.initial_capital);
+    /// Replace execution model, sizing, stops and risk limits at once
+    pub fn with_rules(mut self, rules: TradingRules) -> Self {
+        self.rules = rules;
+        self
+    }
 
-        // Pre-calculate ATR if needed
-        let atr_values = if matches!(self.stop_loss, StopLossMethod::ATR { .. }) {
-            let hlc_data: Vec<(f64, f64, f64)> = self
-                .data
-                .iter()
-                .map(|bar| (bar.high, bar.low, bar.close))
-                .collect();
-
-            let period = match self.stop_loss {
-                StopLossMethod::ATR { period, .. } => period,
-                _ => 14,
-            };
+    /// Higher-timeframe bars for strategies that request them; without a
+    /// context (or one missing a requested timeframe) strategies fall back to
+    /// their own resampling
+    pub fn with_timeframes(mut self, context: TimeframeContext) -> Self {
+        self.timeframes = Some(context);
+        self
+    }
 
-            calculate_atr(&hlc_data, period)
-        } else {
-            vec![f64::NAN; self.data.len()]
-        };
+    /// Indicator series shared with other engines over the same data, e.g.
+    /// by every run of a parameter sweep
+    pub fn with_indicator_cache(mut self, cache: Arc<IndicatorCache>) -> Self {
+        self.indicators = Some(cache);
+        self
+    }
 
-        for (i, bar) in self.data.iter().enumerate() {
-            let target_position = signals[i];
-
-            // Check for stop loss exit
-            let mut stop_hit = false;
-            if let (Some(entry_idx), Some(entry_px), Some(_pos_size)) =
-                (entry_bar, entry_price_with_costs, position_size)
-            {
-                let bars_held = i - entry_idx;
-                let atr = if atr_values[i].is_nan() {
-                    None
-                } else {
-                    Some(atr_values[i])
-                };
+    pub fn data(&self) -> &[OHLCV] {
+        &self.data
+    }
 
-                if self
-                    .stop_loss
-                    .is_hit(entry_px, bar.close, highest_price, bars_held, atr)
-                {
-                    stop_hit = true;
-                }
+    /// Backtest `strategy`; a strategy that fails to produce signals stays
+    /// flat, with the error printed (see `try_run`)
+    pub fn run(&self, strategy: &dyn Strategy) -> BacktestResult {
+        let signals = self.signals(strategy).unwrap_or_else(|e| {
+            eprintln!("{} failed, staying flat: {:#}", strategy.name(), e);
+            vec![0.0; self.data.len()]
+        });
+        self.simulate(&signals)
+    }
 
-                // Update highest price for trailing stop
-                if bar.close > highest_price {
-                    highest_price = bar.close;
-                }
-            }
+    /// Backtest `strategy`, returning its error if it fails to produce
+    /// signals, e.g. a script error
+    pub fn try_run(&self, strategy: &dyn Strategy) -> Result<BacktestResult> {
+        let signals = self
+            .signals(strategy)
+            .with_context(|| format!("{} failed", strategy.name()))?;
+        Ok(self.simulate(&signals))
+    }
 
-            // Execute stop loss exit
-            if stop_hit {
-                if let (Some(entry_idx), Some(entry_px), Some(pos_size)) =
-                    (entry_bar, entry_price_with_costs, position_size)
-                {
-                    let sell_price = self.execution_model.execute_market_sell(bar.close);
-
-                    // Actually sell the BTC
-                    portfolio.sell(pos_size, sell_price, self.execution_model.commission_bps);
-
-                    let mut trade = Trade::new(
-                        self.data[entry_idx].timestamp,
-                        bar.timestamp,
-                        entry_px,
-                        sell_price,
-                        pos_size,
-                    );
-                    trade.duration_bars = i - entry_idx;
-
-                    trades.push(trade);
-                    risk_metrics.on_trade();
-
-                    // Reset tracking
-                    entry_bar = None;
-                    entry_price_with_costs = None;
-                    position_size = None;
-                    highest_price = 0.0;
-                    prev_position = 0.0;
-                }
+    fn signals(&self, strategy: &dyn Strategy) -> Result<Vec<f64>> {
+        let requested = strategy.timeframes();
+        let signals = match &self.timeframes {
+            Some(context) if !requested.is_empty() && context.contains_all(&requested) => {
+                strategy.generate_signals_with_context(&self.data, context)?
             }
+            _ => match &self.indicators {
+                Some(cache) => strategy.generate_signals_cached(&self.data, cache)?,
+                None => strategy.try_generate_signals(&self.data)?,
+            },
+        };
+        if signals.len() != self.data.len() {
+            bail!(
+                "expected {} signals, got {}",
+                self.data.len(),
+                signals.len()
+            );
+        }
+        Ok(signals)
+    }
 
-            // Execute trades when position changes (strategy signal)
-            if !stop_hit && (target_position - prev_position).abs() > 1e-6 {
-                if target_position > prev_position {
-                    // Buy
-                    let equity = portfolio.equity(bar.close);
-
-                    // Check risk limits before entering
-                    if risk_metrics.risk_limit_violations == 0
-                        && self
-                            .risk_limits
-                            .check_drawdown(equity, risk_metrics.peak_equity)
-                        && self.risk_limits.can_trade(
-                            risk_metrics.bars_since_last_trade,
-                            risk_metrics.trades_today,
-                        )
-                    {
-                        // Calculate position size
-                        let position_value = self.position_sizing.calculate_size(equity, None);
-
-                        if self.risk_limits.check_position_size(position_value, equity) {
-                            let buy_price = self.execution_model.execute_market_buy(bar.close);
-                            let btc_to_buy = position_value / buy_price;
-
-                            if portfolio.cash >= btc_to_buy * buy_price {
-                                portfolio.buy(
-                                    btc_to_buy,
-                                    buy_price,
-                                    self.execution_model.commission_bps,
-                                );
-
-                                entry_bar = Some(i);
-                                entry_price_with_costs = Some(buy_price);
-                                position_size = Some(btc_to_buy);
-                                highest_price = bar.close;
-
-                                risk_metrics.on_trade();
-                            }
-                        } else {
-                            risk_metrics.risk_limit_violations += 1;
-                        }
-                    } else {
-                        risk_metrics.risk_limit_violations += 1;
-                    }
-                } else if target_position < prev_position {
-                    // Sell - close the trade
-                    if let (Some(entry_idx), Some(entry_px), Some(pos_size)) =
-                        (entry_bar, entry_price_with_costs, position_size)
-                    {
-                        let sell_price = self.execution_model.execute_market_sell(bar.close);
-
-                        // Actually sell the BTC
-                        portfolio.sell(pos_size, sell_price, self.execution_model.commission_bps);
-
-                        let mut trade = Trade::new(
-                            self.data[entry_idx].timestamp,
-                            bar.timestamp,
-                            entry_px,
-                            sell_price,
-                            pos_size,
-                        );
-                        trade.duration_bars = i - entry_idx;
-
-                        trades.push(trade);
-                        risk_metrics.on_trade();
-                    }
-
-                    // Reset tracking
-                    entry_bar = None;
-                    entry_price_with_costs = None;
-                    position_size = None;
-                    highest_price = 0.0;
-                }
+    fn simulate(&self, signals: &[f64]) -> BacktestResult {
+        let mut state = TradingState::new(self.initial_capital);
+        let mut equity_curve = Vec::with_capacity(self.data.len());
 
-                prev_position = target_position;
+        // Pre-calculate ATR if needed
+        let atr_values: Arc<[f64]> = match (self.rules.stop_loss.clone(), &self.indicators) {
+            (StopLossMethod::ATR { period, .. }, Some(cache)) => cache.atr(&self.data, period),
+            (StopLossMethod::ATR { period, .. }, None) => {
+                let hlc_data: Vec<(f64, f64, f64)> = self
+                    .data
+                    .iter()
+                    .map(|bar| (bar.high, bar.low, bar.close))
+                    .collect();
+
+                calculate_atr(&hlc_data, period).into()
             }
+            _ => vec![f64::NAN; self.data.len()].into(),
+        };
 
-            let equity = portfolio.equity(bar.close);
-            equity_curve.push(equity);
-
-            // Update risk metrics
-            let exposure = if let Some(pos_size) = position_size {
-                pos_size * bar.close
+        for (i, bar) in self.data.iter().enumerate() {
+            let atr = if atr_values[i].is_nan() {
+                None
             } else {
-                0.0
+                Some(atr_values[i])
             };
-            risk_metrics.update(equity, exposure);
+
+            let equity = state.on_bar(bar, signals[i], atr, &self.rules);
+            equity_curve.push(equity);
         }
 
         // Close any open position at the end
-        if let (Some(entry_idx), Some(entry_px), Some(pos_size)) =
-            (entry_bar, entry_price_with_costs, position_size)
-        {
-            let last_bar = self.data.last().unwrap();
-            let sell_price = self.execution_model.execute_market_sell(last_bar.close);
-
-            // Actually sell the BTC
-            portfolio.sell(pos_size, sell_price, self.execution_model.commission_bps);
-
-            let mut trade = Trade::new(
-                self.data[entry_idx].timestamp,
-                last_bar.timestamp,
-                entry_px,
-                sell_price,
-                pos_size,
-            );
-            trade.duration_bars = self.data.len() - 1 - entry_idx;
-            trades.push(trade);
+        if let Some(last_bar) = self.data.last() {
+            state.close_final(last_bar, &self.rules);
         }
 
         let final_equity = *equity_curve.last().unwrap();
@@ -256,6 +172,7 @@ impl BacktestEngine {
         let max_drawdown = calculate_max_drawdown(&equity_curve);
         let calmar_ratio = calculate_calmar_ratio(total_return, max_drawdown, self.data.len());
 
+        let trades = state.trades;
         let trade_stats = if !trades.is_empty() {
             Some(TradeStats::from_trades(&trades))
         } else {
@@ -267,13 +184,255 @@ impl BacktestEngine {
             final_equity,
             total_return,
             equity_curve,
-            total_trades: portfolio.total_trades,
+            total_trades: state.portfolio.total_trades,
             sharpe_ratio,
             sortino_ratio,
             calmar_ratio,
             max_drawdown,
             trades: Some(trades),
             trade_stats,
+            cash_flows: None,
+            order_stats: None,
+        }
+    }
+
+    /// Run a rebalancing or DCA portfolio, with optional periodic deposits
+    ///
+    /// Uses the execution model only: position sizing, stops and risk limits
+    /// apply to signal strategies. Ratios and drawdown are computed from
+    /// deposit-adjusted returns; `cash_flows` holds the time- and
+    /// money-weighted returns.
+    pub fn run_rebalancing(
+        &self,
+        rebalancer: &dyn Rebalancer,
+        contributions: Option<&Contributions>,
+    ) -> BacktestResult {
+        let model = &self.rules.execution_model;
+        let mut portfolio = Portfolio::new(self.initial_capital);
+        let mut equity_curve = Vec::with_capacity(self.data.len());
+        let mut deposits = Vec::with_capacity(self.data.len());
+        let mut flows = vec![(
+            self.data.first().map_or(0, |b| b.timestamp),
+            self.initial_capital,
+        )];
+
+        for (i, bar) in self.data.iter().enumerate() {
+            let previous = i.checked_sub(1).map(|p| &self.data[p]);
+
+            let deposit = contributions.map_or(0.0, |c| c.due(bar, previous));
+            if deposit > 0.0 {
+                portfolio.deposit(deposit);
+                flows.push((bar.timestamp, deposit));
+            }
+            deposits.push(deposit);
+
+            let weight = asset_weight(&portfolio, bar.close);
+            if let Some(target) = rebalancer.target(bar, previous, weight) {
+                rebalance_to(&mut portfolio, target, bar.close, model);
+            }
+
+            equity_curve.push(portfolio.equity(bar.close));
+        }
+
+        let final_equity = *equity_curve.last().unwrap();
+        let total_return = (final_equity - self.initial_capital) / self.initial_capital;
+
+        // Bar returns without the deposit made during the bar, starting from
+        // the initial capital so first-bar costs count
+        let mut marked = vec![self.initial_capital];
+        marked.extend_from_slice(&equity_curve);
+        let mut flow_per_bar = vec![0.0];
+        flow_per_bar.extend_from_slice(&deposits);
+        let returns: Vec<f64> = marked
+            .windows(2)
+            .zip(&flow_per_bar[1..])
+            .map(|(w, deposit)| (w[1] - deposit) / w[0] - 1.0)
+            .collect();
+        let mut growth = Vec::with_capacity(marked.len());
+        growth.push(1.0);
+        for r in &returns {
+            growth.push(growth[growth.len() - 1] * (1.0 + r));
+        }
+
+        let time_weighted_return = calculate_time_weighted_return(&marked, &flow_per_bar);
+        let years = match (self.data.first(), self.data.last()) {
+            (Some(first), Some(last)) => (last.timestamp - first.timestamp) as f64 / MS_PER_YEAR,
+            _ => 0.0,
+        };
+        let annualized_time_weighted_return = if years > 0.0 {
+            (1.0 + time_weighted_return).powf(1.0 / years) - 1.0
+        } else {
+            0.0
+        };
+        let money_weighted_return = self
+            .data
+            .last()
+            .and_then(|last| calculate_money_weighted_return(&flows, last.timestamp, final_equity));
+
+        let max_drawdown = calculate_max_drawdown(&growth);
+
+        BacktestResult {
+            initial_capital: self.initial_capital,
+            final_equity,
+            total_return,
+            equity_curve,
+            total_trades: portfolio.total_trades,
+            sharpe_ratio: calculate_sharpe_ratio(&returns, 252.0),
+            sortino_ratio: calculate_sortino_ratio(&returns, 252.0),
+            calmar_ratio: calculate_calmar_ratio(
+                time_weighted_return,
+                max_drawdown,
+                self.data.len(),
+            ),
+            max_drawdown,
+            trades: None,
+            trade_stats: None,
+            cash_flows: Some(CashFlowReturns {
+                total_deposits: portfolio.deposits,
+                net_profit: final_equity - self.initial_capital - portfolio.deposits,
+                time_weighted_return,
+                annualized_time_weighted_return,
+                money_weighted_return,
+            }),
+            order_stats: None,
+        }
+    }
+
+    /// Run a strategy that trades through resting limit orders, e.g. a grid
+    ///
+    /// Limit fills pay commission but no slippage; the initial inventory is
+    /// bought at market. Open inventory is marked to market at the end rather
+    /// than sold. `order_stats` holds fills per level, realized profit and the
+    /// inventory range.
+    pub fn run_orders(&self, strategy: &dyn OrderStrategy) -> BacktestResult {
+        let model = &self.rules.execution_model;
+        let fee_rate = model.commission_bps / 10000.0;
+        let mut portfolio = Portfolio::new(self.initial_capital);
+        let mut book = OrderBook::new();
+        let mut stats = OrderStats::default();
+        let mut levels: BTreeMap<usize, LevelStats> = BTreeMap::new();
+        let mut equity_curve = Vec::with_capacity(self.data.len());
+        // Cost of the open inventory including fees
+        let mut inventory_cost = 0.0;
+        let mut initial_price = 0.0;
+
+        let track_level = |levels: &mut BTreeMap<usize, LevelStats>, order: &LimitOrder| {
+            levels.entry(order.level).or_insert_with(|| LevelStats {
+                level: order.level,
+                price: order.price,
+                ..LevelStats::default()
+            });
+        };
+
+        if let Some(first) = self.data.first() {
+            let price = model.execute_market_buy(first.open);
+            let affordable = portfolio.cash / (price * (1.0 + fee_rate));
+            let quantity = strategy.initial_inventory(first.open).min(affordable);
+            if quantity > 0.0 {
+                portfolio.buy(quantity, price, model.commission_bps);
+                stats.fees += quantity * price * fee_rate;
+                inventory_cost += quantity * price * (1.0 + fee_rate);
+                initial_price = price;
+            }
+            for order in strategy.initial_orders(first.open) {
+                track_level(&mut levels, &order);
+                book.place(order);
+            }
+        }
+
+        stats.min_inventory = portfolio.btc_position;
+        stats.max_inventory = portfolio.btc_position;
+        let mut inventory_sum = 0.0;
+
+        for (i, bar) in self.data.iter().enumerate() {
+            let previous_close = if i == 0 {
+                bar.open
+            } else {
+                self.data[i - 1].close
+            };
+
+            process_bar(&mut book, bar, previous_close, |order, price| {
+                match order.side {
+                    Side::Buy => {
+                        let cost = order.quantity * price * (1.0 + fee_rate);
+                        if portfolio.cash < cost {
+                            stats.unfunded_fills += 1;
+                            return None;
+                        }
+                        portfolio.buy(order.quantity, price, model.commission_bps);
+                        stats.fees += order.quantity * price * fee_rate;
+                        stats.buy_fills += 1;
+                        inventory_cost += cost;
+                    }
+                    Side::Sell => {
+                        if portfolio.btc_position < order.quantity * (1.0 - 1e-9) {
+                            stats.unfunded_fills += 1;
+                            return None;
+                        }
+                        let quantity = order.quantity.min(portfolio.btc_position);
+                        let unit_cost = order.cost.unwrap_or(initial_price) * (1.0 + fee_rate);
+                        let fee = quantity * price * fee_rate;
+                        portfolio.sell(quantity, price, model.commission_bps);
+                        stats.fees += fee;
+                        stats.sell_fills += 1;
+                        stats.realized_profit += quantity * (price - unit_cost) - fee;
+                        inventory_cost -= quantity * unit_cost;
+                    }
+                }
+
+                track_level(&mut levels, order);
+                let level = levels.get_mut(&order.level).unwrap();
+                match order.side {
+                    Side::Buy => level.buys += 1,
+                    Side::Sell => level.sells += 1,
+                }
+                stats.min_inventory = stats.min_inventory.min(portfolio.btc_position);
+                stats.max_inventory = stats.max_inventory.max(portfolio.btc_position);
+
+                let fill = Fill {
+                    order: order.clone(),
+                    price,
+                    bar_index: i,
+                    timestamp: bar.timestamp,
+                };
+                let follow_ups = strategy.on_fill(&fill);
+                follow_ups.iter().for_each(|o| track_level(&mut levels, o));
+                Some(follow_ups)
+            });
+
+            inventory_sum += portfolio.btc_position;
+            equity_curve.push(portfolio.equity(bar.close));
+        }
+
+        let final_equity = *equity_curve.last().unwrap();
+        let total_return = (final_equity - self.initial_capital) / self.initial_capital;
+        let last_close = self.data.last().map_or(0.0, |b| b.close);
+
+        stats.final_inventory = portfolio.btc_position;
+        stats.average_inventory = inventory_sum / self.data.len() as f64;
+        stats.unrealized_profit = portfolio.btc_position * last_close - inventory_cost;
+        stats.levels = levels.into_values().collect();
+
+        let returns: Vec<f64> = equity_curve
+            .windows(2)
+            .map(|w| (w[1] - w[0]) / w[0])
+            .collect();
+        let max_drawdown = calculate_max_drawdown(&equity_curve);
+
+        BacktestResult {
+            initial_capital: self.initial_capital,
+            final_equity,
+            total_return,
+            equity_curve,
+            total_trades: portfolio.total_trades,
+            sharpe_ratio: calculate_sharpe_ratio(&returns, 252.0),
+            sortino_ratio: calculate_sortino_ratio(&returns, 252.0),
+            calmar_ratio: calculate_calmar_ratio(total_return, max_drawdown, self.data.len()),
+            max_drawdown,
+            trades: None,
+            trade_stats: None,
+            cash_flows: None,
+            order_stats: Some(stats),
         }
     }
 
@@ -1,15 +1,25 @@
 pub mod engine;
+pub mod multi_asset;
+pub mod orders;
 pub mod position_sizing;
+pub mod rebalance;
 pub mod result;
 pub mod risk;
+pub mod state;
 pub mod stops;
 pub mod trade;
 pub mod types;
 
 pub use engine::BacktestEngine;
+pub use multi_asset::{align_series, MultiAssetPortfolio};
+pub use orders::{Fill, LevelStats, LimitOrder, OrderBook, OrderStats, OrderStrategy, Side};
 pub use position_sizing::PositionSizingMethod;
-pub use result::BacktestResult;
+pub use rebalance::{
+    CalendarRebalance, Contributions, DollarCostAveraging, Rebalancer, Schedule, ThresholdRebalance,
+};
+pub use result::{save_trades_to_csv, BacktestResult, CashFlowReturns};
 pub use risk::{RiskLimits, RiskMetrics};
+pub use state::{TradingRules, TradingState};
 pub use stops::{calculate_atr, StopLossMethod};
 pub use trade::{Trade, TradeStats};
 pub use types::{ExecutionModel, Portfolio};
@@ -0,0 +1,122 @@
+use crate::data::OHLCV;
+use serde::{Deserialize, Serialize};
+
+/// Cash plus a signed position in each of several assets
+///
+/// Negative positions are shorts: selling short credits the proceeds to
+/// cash and the position is marked at the current price, so equity is
+/// always `cash + sum(position * price)`. Borrow costs are not modelled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultiAssetPortfolio {
+    pub cash: f64,
+    pub symbols: Vec<String>,
+    pub positions: Vec<f64>,
+    pub total_trades: u32,
+}
+
+impl MultiAssetPortfolio {
+    pub fn new(initial_cash: f64, symbols: &[&str]) -> Self {
+        Self {
+            cash: initial_cash,
+            symbols: symbols.iter().map(|s| s.to_string()).collect(),
+            positions: vec![0.0; symbols.len()],
+            total_trades: 0,
+        }
+    }
+
+    /// Buy (positive `quantity`) or sell (negative) asset `asset` at `price`
+    pub fn trade(&mut self, asset: usize, quantity: f64, price: f64, commission_bps: f64) {
+        let notional = quantity * price;
+        let commission = notional.abs() * (commission_bps / 10000.0);
+
+        self.cash -= notional + commission;
+        self.positions[asset] += quantity;
+        self.total_trades += 1;
+    }
+
+    /// Equity at `prices`, one per asset
+    pub fn equity(&self, prices: &[f64]) -> f64 {
+        self.cash
+            + self
+                .positions
+                .iter()
+                .zip(prices)
+                .map(|(q, p)| q * p)
+                .sum::<f64>()
+    }
+
+    /// Sum of the absolute position values at `prices`
+    pub fn gross_exposure(&self, prices: &[f64]) -> f64 {
+        self.positions
+            .iter()
+            .zip(prices)
+            .map(|(q, p)| (q * p).abs())
+            .sum()
+    }
+}
+
+/// Keep only the bars whose timestamp appears in every series
+///
+/// Each input must be sorted by timestamp; the outputs have equal length and
+/// matching timestamps bar for bar.
+pub fn align_series(series: &[&[OHLCV]]) -> Vec<Vec<OHLCV>> {
+    let mut aligned = vec![Vec::new(); series.len()];
+    let mut cursors = vec![0; series.len()];
+
+    'outer: loop {
+        // The latest timestamp among the current bars is the earliest possible match
+        let mut target = i64::MIN;
+        for (s, &c) in series.iter().zip(&cursors) {
+            match s.get(c) {
+                Some(bar) => target = target.max(bar.timestamp),
+                None => break 'outer,
+            }
+        }
+
+        let mut matched = true;
+        for (s, c) in series.iter().zip(cursors.iter_mut()) {
+            while *c < s.len() && s[*c].timestamp < target {
+                *c += 1;
+            }
+            match s.get(*c) {
+                Some(bar) if bar.timestamp == target => {}
+                Some(_) => matched = false,
+                None => break 'outer,
+            }
+        }
+
+        if matched {
+            for ((s, c), out) in series.iter().zip(cursors.iter_mut()).zip(&mut aligned) {
+                out.push(s[*c].clone());
+                *c += 1;
+            }
+        }
+    }
+
+    aligned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_positions_and_alignment() {
+        let mut portfolio = MultiAssetPortfolio::new(1_000.0, &["A", "B"]);
+        portfolio.trade(0, 10.0, 50.0, 0.0);
+        portfolio.trade(1, -5.0, 100.0, 0.0);
+        assert_eq!(portfolio.cash, 1_000.0);
+        assert_eq!(portfolio.equity(&[50.0, 100.0]), 1_000.0);
+        // The short gains when B falls
+        assert_eq!(portfolio.equity(&[50.0, 90.0]), 1_050.0);
+        assert_eq!(portfolio.gross_exposure(&[50.0, 100.0]), 1_000.0);
+
+        let bar = |t: i64| OHLCV::new(t, 1.0, 1.0, 1.0, 1.0, 1.0);
+        let a: Vec<OHLCV> = [1, 2, 3, 5, 6].iter().map(|&t| bar(t)).collect();
+        let b: Vec<OHLCV> = [2, 3, 4, 6, 7].iter().map(|&t| bar(t)).collect();
+        let aligned = align_series(&[&a, &b]);
+        let timestamps: Vec<i64> = aligned[1].iter().map(|b| b.timestamp).collect();
+        assert_eq!(timestamps, vec![2, 3, 6]);
+        assert_eq!(aligned[0].len(), 3);
+    }
+}
@@ -0,0 +1,295 @@
+use crate::data::OHLCV;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// Resting limit order at a strategy-defined level (e.g. a grid line)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitOrder {
+    pub level: usize,
+    pub side: Side,
+    pub price: f64,
+    pub quantity: f64,
+    /// Price the sold units were bought at, for realized profit; sells
+    /// without one are costed at the initial inventory price
+    pub cost: Option<f64>,
+}
+
+impl LimitOrder {
+    pub fn buy(level: usize, price: f64, quantity: f64) -> Self {
+        Self {
+            level,
+            side: Side::Buy,
+            price,
+            quantity,
+            cost: None,
+        }
+    }
+
+    pub fn sell(level: usize, price: f64, quantity: f64) -> Self {
+        Self {
+            level,
+            side: Side::Sell,
+            price,
+            quantity,
+            cost: None,
+        }
+    }
+
+    pub fn with_cost(mut self, cost: f64) -> Self {
+        self.cost = Some(cost);
+        self
+    }
+}
+
+/// A filled limit order
+#[derive(Debug, Clone)]
+pub struct Fill {
+    pub order: LimitOrder,
+    /// Execution price: the limit price, or the open when the bar gaps through it
+    pub price: f64,
+    pub bar_index: usize,
+    pub timestamp: i64,
+}
+
+/// Strategy that trades through resting limit orders rather than signals
+///
+/// The engine fills orders as the price path of each bar crosses them and
+/// hands every fill back so the strategy can place follow-up orders.
+pub trait OrderStrategy: Send + Sync {
+    fn name(&self) -> String;
+
+    /// Asset bought at market on the first bar's open, e.g. inventory for the
+    /// initial sell orders
+    fn initial_inventory(&self, price: f64) -> f64 {
+        let _ = price;
+        0.0
+    }
+
+    /// Orders resting from the first bar, given its open price
+    fn initial_orders(&self, price: f64) -> Vec<LimitOrder>;
+
+    /// Orders to place after a fill
+    fn on_fill(&self, fill: &Fill) -> Vec<LimitOrder>;
+}
+
+/// Fills and orders of one level
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LevelStats {
+    pub level: usize,
+    pub price: f64,
+    pub buys: usize,
+    pub sells: usize,
+}
+
+/// Order-strategy statistics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderStats {
+    pub buy_fills: usize,
+    pub sell_fills: usize,
+    /// Profit of every sell against the cost of the units sold, net of fees
+    pub realized_profit: f64,
+    /// Open inventory marked at the last close against its cost
+    pub unrealized_profit: f64,
+    pub fees: f64,
+    /// Buy fills skipped for lack of cash (the order keeps resting)
+    pub unfunded_fills: usize,
+    pub final_inventory: f64,
+    pub min_inventory: f64,
+    pub max_inventory: f64,
+    pub average_inventory: f64,
+    /// Fills per level, lowest level first
+    pub levels: Vec<LevelStats>,
+}
+
+/// Resting orders awaiting fills
+#[derive(Debug, Clone, Default)]
+pub struct OrderBook {
+    orders: Vec<LimitOrder>,
+}
+
+impl OrderBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn place(&mut self, order: LimitOrder) {
+        self.orders.push(order);
+    }
+
+    pub fn orders(&self) -> &[LimitOrder] {
+        &self.orders
+    }
+
+    /// Index of the next order crossed when the price moves from `from` to
+    /// `to`: the highest buy below `from` when falling, the lowest sell above
+    /// `from` when rising, including orders at `from` if `inclusive`
+    fn next_crossed(&self, from: f64, to: f64, inclusive: bool) -> Option<usize> {
+        let falling = to < from;
+        let mut best: Option<usize> = None;
+        for (i, order) in self.orders.iter().enumerate() {
+            let crossed = if falling {
+                order.side == Side::Buy
+                    && (order.price < from || inclusive && order.price == from)
+                    && order.price >= to
+            } else {
+                order.side == Side::Sell
+                    && (order.price > from || inclusive && order.price == from)
+                    && order.price <= to
+            };
+            if !crossed {
+                continue;
+            }
+            let closer = match best {
+                None => true,
+                Some(b) if falling => order.price > self.orders[b].price,
+                Some(b) => order.price < self.orders[b].price,
+            };
+            if closer {
+                best = Some(i);
+            }
+        }
+        best
+    }
+}
+
+/// Points the price visits within a bar: down first on an up bar, up first
+/// on a down bar
+pub fn price_path(bar: &OHLCV) -> [f64; 4] {
+    if bar.close >= bar.open {
+        [bar.open, bar.low, bar.high, bar.close]
+    } else {
+        [bar.open, bar.high, bar.low, bar.close]
+    }
+}
+
+/// Walk one bar's price path, filling crossed orders in the order they are
+/// reached
+///
+/// Orders already through the open (after a gap) fill at the open. Orders
+/// placed after a fill can fill later in the same bar, but only strictly
+/// beyond that fill's price, so a strategy re-placing an order at the level
+/// it just filled waits for the price to return. `fill` executes a fill and
+/// returns the follow-up orders, or `None` if it cannot be funded, which
+/// leaves the order resting.
+pub(crate) fn process_bar(
+    book: &mut OrderBook,
+    bar: &OHLCV,
+    previous_close: f64,
+    mut fill: impl FnMut(&LimitOrder, f64) -> Option<Vec<LimitOrder>>,
+) {
+    let path = price_path(bar);
+    let mut unfunded: Vec<LimitOrder> = Vec::new();
+
+    // The gap from the previous close, then the path within the bar
+    let segments = std::iter::once((previous_close, bar.open, true))
+        .chain(path.windows(2).map(|w| (w[0], w[1], false)));
+    for (from, to, gap) in segments {
+        if from == to {
+            continue;
+        }
+        // Orders placed during the segment only fill beyond the last fill
+        let mut cursor = from;
+        let mut placed = OrderBook::new();
+        let falling = to < from;
+        loop {
+            let resting = book.next_crossed(cursor, to, true);
+            let follow_up = placed.next_crossed(cursor, to, false);
+            let order = match (resting, follow_up) {
+                (Some(r), Some(p)) => {
+                    let (r_price, p_price) = (book.orders[r].price, placed.orders[p].price);
+                    if r_price == p_price || (r_price > p_price) == falling {
+                        book.orders.swap_remove(r)
+                    } else {
+                        placed.orders.swap_remove(p)
+                    }
+                }
+                (Some(r), None) => book.orders.swap_remove(r),
+                (None, Some(p)) => placed.orders.swap_remove(p),
+                (None, None) => break,
+            };
+            cursor = order.price;
+            let price = if gap { to } else { order.price };
+            match fill(&order, price) {
+                Some(follow_ups) => follow_ups.into_iter().for_each(|o| placed.place(o)),
+                None => unfunded.push(order),
+            }
+        }
+        book.orders.append(&mut placed.orders);
+    }
+
+    for order in unfunded {
+        book.place(order);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orders_fill_along_the_path() {
+        let mut book = OrderBook::new();
+        book.place(LimitOrder::buy(0, 95.0, 1.0));
+        book.place(LimitOrder::buy(1, 98.0, 1.0));
+        book.place(LimitOrder::sell(2, 103.0, 1.0));
+        book.place(LimitOrder::sell(3, 110.0, 1.0));
+
+        // Up bar: 100 -> 96 -> 104 -> 102
+        let bar = OHLCV::new(0, 100.0, 104.0, 96.0, 102.0, 1.0);
+        let mut fills = Vec::new();
+        process_bar(&mut book, &bar, 100.0, |order, price| {
+            fills.push((order.level, price));
+            // A buy places a sell one dollar higher, reachable later in the bar
+            Some(match order.side {
+                Side::Buy => vec![LimitOrder::sell(9, order.price + 1.0, 1.0)],
+                Side::Sell => vec![],
+            })
+        });
+
+        assert_eq!(fills, vec![(1, 98.0), (9, 99.0), (2, 103.0)]);
+        assert_eq!(book.orders().len(), 2);
+
+        // Gap down through the last buy fills at the open
+        let gap = OHLCV::new(1, 90.0, 91.0, 89.0, 90.5, 1.0);
+        let mut fills = Vec::new();
+        process_bar(&mut book, &gap, 102.0, |order, price| {
+            fills.push((order.level, price));
+            None
+        });
+        assert_eq!(fills, vec![(0, 90.0)]);
+        // Unfunded orders keep resting
+        assert_eq!(book.orders().len(), 2);
+    }
+
+    #[test]
+    fn test_replaced_order_waits_for_the_price_to_return() {
+        let mut book = OrderBook::new();
+        book.place(LimitOrder::buy(0, 98.0, 1.0));
+        book.place(LimitOrder::buy(1, 98.0, 1.0));
+
+        // A market maker re-placing each filled buy at the same level: both
+        // resting buys fill once, their replacements rest
+        let bar = OHLCV::new(0, 100.0, 101.0, 97.0, 100.5, 1.0);
+        let mut fills = Vec::new();
+        process_bar(&mut book, &bar, 100.0, |order, price| {
+            fills.push((order.level, price));
+            Some(vec![order.clone()])
+        });
+        assert_eq!(fills, vec![(0, 98.0), (1, 98.0)]);
+        assert_eq!(book.orders().len(), 2);
+
+        // The next visit to 98 fills the replacements
+        let mut fills = Vec::new();
+        process_bar(&mut book, &bar, 100.5, |order, price| {
+            fills.push((order.level, price));
+            Some(vec![])
+        });
+        assert_eq!(fills.len(), 2);
+        assert!(book.orders().is_empty());
+    }
+}
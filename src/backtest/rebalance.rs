@@ -0,0 +1,318 @@
+use crate::backtest::{ExecutionModel, Portfolio};
+use crate::data::OHLCV;
+use anyhow::{bail, Result};
+use chrono::{DateTime, Datelike};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Trades smaller than this share of equity are skipped
+const MIN_TRADE_FRACTION: f64 = 1e-6;
+
+/// Calendar period for rebalancing and contributions, in UTC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Schedule {
+    Daily,
+    Weekly,
+    Monthly,
+    Quarterly,
+    Yearly,
+}
+
+impl Schedule {
+    pub fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "daily" => Schedule::Daily,
+            "weekly" => Schedule::Weekly,
+            "monthly" => Schedule::Monthly,
+            "quarterly" => Schedule::Quarterly,
+            "yearly" => Schedule::Yearly,
+            _ => bail!(
+                "Unknown schedule '{}' (expected daily, weekly, monthly, quarterly or yearly)",
+                name
+            ),
+        })
+    }
+
+    /// Index of the period containing `timestamp`; weeks start on Monday
+    fn period(&self, timestamp: i64) -> i64 {
+        let days = timestamp.div_euclid(86_400_000);
+        let date = DateTime::from_timestamp_millis(timestamp)
+            .unwrap_or_default()
+            .date_naive();
+        let year = date.year() as i64;
+        let month = date.month0() as i64;
+        match self {
+            Schedule::Daily => days,
+            // 1970-01-01 was a Thursday
+            Schedule::Weekly => (days + 3).div_euclid(7),
+            Schedule::Monthly => year * 12 + month,
+            Schedule::Quarterly => year * 4 + month / 3,
+            Schedule::Yearly => year,
+        }
+    }
+
+    /// Whether `timestamp` falls in a later period than `previous`
+    pub fn starts_period(&self, previous: i64, timestamp: i64) -> bool {
+        self.period(timestamp) > self.period(previous)
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Schedule::Daily => "daily",
+            Schedule::Weekly => "weekly",
+            Schedule::Monthly => "monthly",
+            Schedule::Quarterly => "quarterly",
+            Schedule::Yearly => "yearly",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Fixed cash deposit on the first bar of every period after the first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Contributions {
+    pub amount: f64,
+    pub schedule: Schedule,
+}
+
+impl Contributions {
+    pub fn new(amount: f64, schedule: Schedule) -> Self {
+        assert!(amount >= 0.0, "Contribution must not be negative");
+        Self { amount, schedule }
+    }
+
+    /// Deposit due at `bar`
+    pub fn due(&self, bar: &OHLCV, previous: Option<&OHLCV>) -> f64 {
+        match previous {
+            Some(prev) if self.schedule.starts_period(prev.timestamp, bar.timestamp) => self.amount,
+            _ => 0.0,
+        }
+    }
+}
+
+/// Decides when to trade the portfolio back to a target asset weight
+///
+/// Unlike a `Strategy`, which picks all-in or flat from prices alone, a
+/// rebalancer sees the current weight of the asset in the portfolio.
+pub trait Rebalancer: Send + Sync {
+    fn name(&self) -> String;
+
+    /// Target asset weight if the portfolio should be rebalanced at `bar`'s
+    /// close, given the weight before trading; `None` to hold
+    fn target(&self, bar: &OHLCV, previous: Option<&OHLCV>, weight: f64) -> Option<f64>;
+}
+
+fn check_weight(weight: f64) {
+    assert!(
+        (0.0..=1.0).contains(&weight),
+        "Target weight must be between 0 and 1"
+    );
+}
+
+/// Rebalance to a fixed weight on the first bar of every period
+#[derive(Debug, Clone)]
+pub struct CalendarRebalance {
+    weight: f64,
+    schedule: Schedule,
+}
+
+impl CalendarRebalance {
+    pub fn new(weight: f64, schedule: Schedule) -> Self {
+        check_weight(weight);
+        Self { weight, schedule }
+    }
+}
+
+impl Rebalancer for CalendarRebalance {
+    fn name(&self) -> String {
+        format!("{:.0}% rebalanced {}", self.weight * 100.0, self.schedule)
+    }
+
+    fn target(&self, bar: &OHLCV, previous: Option<&OHLCV>, _weight: f64) -> Option<f64> {
+        match previous {
+            Some(prev) if !self.schedule.starts_period(prev.timestamp, bar.timestamp) => None,
+            _ => Some(self.weight),
+        }
+    }
+}
+
+/// Rebalance to a fixed weight whenever it drifts more than `band` away
+/// (e.g. 0.05 rebalances a 60% target below 55% or above 65%)
+#[derive(Debug, Clone)]
+pub struct ThresholdRebalance {
+    weight: f64,
+    band: f64,
+}
+
+impl ThresholdRebalance {
+    pub fn new(weight: f64, band: f64) -> Self {
+        check_weight(weight);
+        assert!(band > 0.0, "Band must be greater than 0");
+        Self { weight, band }
+    }
+}
+
+impl Rebalancer for ThresholdRebalance {
+    fn name(&self) -> String {
+        format!(
+            "{:.0}% rebalanced on {:.1}% drift",
+            self.weight * 100.0,
+            self.band * 100.0
+        )
+    }
+
+    fn target(&self, _bar: &OHLCV, previous: Option<&OHLCV>, weight: f64) -> Option<f64> {
+        if previous.is_none() || (weight - self.weight).abs() > self.band {
+            Some(self.weight)
+        } else {
+            None
+        }
+    }
+}
+
+/// Dollar-cost averaging: invest all cash, including every contribution, as
+/// soon as it arrives and never sell
+#[derive(Debug, Clone, Default)]
+pub struct DollarCostAveraging;
+
+impl DollarCostAveraging {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Rebalancer for DollarCostAveraging {
+    fn name(&self) -> String {
+        "Dollar-cost averaging".to_string()
+    }
+
+    fn target(&self, _bar: &OHLCV, _previous: Option<&OHLCV>, _weight: f64) -> Option<f64> {
+        Some(1.0)
+    }
+}
+
+/// Asset weight of the portfolio at `price`
+pub fn asset_weight(portfolio: &Portfolio, price: f64) -> f64 {
+    let equity = portfolio.equity(price);
+    if equity > 0.0 {
+        portfolio.btc_position * price / equity
+    } else {
+        0.0
+    }
+}
+
+/// Trade the portfolio towards `weight` at `price`; returns whether it traded
+///
+/// Buys are limited to the cash available after costs.
+pub fn rebalance_to(
+    portfolio: &mut Portfolio,
+    weight: f64,
+    price: f64,
+    model: &ExecutionModel,
+) -> bool {
+    let equity = portfolio.equity(price);
+    let difference = weight * equity - portfolio.btc_position * price;
+    if difference.abs() <= equity * MIN_TRADE_FRACTION {
+        return false;
+    }
+
+    if difference > 0.0 {
+        let buy_price = model.execute_market_buy(price);
+        let affordable = portfolio.cash / (buy_price * (1.0 + model.commission_bps / 10000.0));
+        let amount = (difference / buy_price).min(affordable);
+        if amount * buy_price <= equity * MIN_TRADE_FRACTION {
+            return false;
+        }
+        portfolio.buy(amount, buy_price, model.commission_bps);
+    } else {
+        let amount = (-difference / price).min(portfolio.btc_position);
+        portfolio.sell(
+            amount,
+            model.execute_market_sell(price),
+            model.commission_bps,
+        );
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::BacktestEngine;
+    use crate::indicators::test_support::sample_bars;
+
+    const DAY: i64 = 86_400_000;
+
+    #[test]
+    fn test_schedule_boundaries() {
+        // 2024-01-31, 2024-02-01 (Thursday), 2024-02-05 (Monday)
+        let jan31 = 1_706_659_200_000;
+        let feb1 = jan31 + DAY;
+        let feb5 = jan31 + 5 * DAY;
+
+        assert!(Schedule::Monthly.starts_period(jan31, feb1));
+        assert!(!Schedule::Monthly.starts_period(feb1, feb5));
+        assert!(!Schedule::Weekly.starts_period(feb1, feb5 - DAY));
+        assert!(Schedule::Weekly.starts_period(feb1, feb5));
+        assert!(!Schedule::Quarterly.starts_period(jan31, feb1));
+        assert!(Schedule::parse("fortnightly").is_err());
+    }
+
+    #[test]
+    fn test_rebalance_to_target_weight() {
+        let model = ExecutionModel::new(10.0, 5.0);
+        let mut portfolio = Portfolio::new(100_000.0);
+
+        assert!(rebalance_to(&mut portfolio, 0.6, 50_000.0, &model));
+        assert!((asset_weight(&portfolio, 50_000.0) - 0.6).abs() < 0.001);
+
+        // The asset doubles: 75% weight, sell back down to 60%
+        assert!(rebalance_to(&mut portfolio, 0.6, 100_000.0, &model));
+        assert!((asset_weight(&portfolio, 100_000.0) - 0.6).abs() < 0.001);
+
+        // Never spends more cash than there is
+        assert!(rebalance_to(&mut portfolio, 1.0, 100_000.0, &model));
+        assert!(portfolio.cash > -1e-6);
+        assert!(asset_weight(&portfolio, 100_000.0) > 0.999);
+    }
+
+    #[test]
+    fn test_rebalancing_backtests() {
+        let data = sample_bars();
+        let engine = BacktestEngine::new(&data, 100_000.0, ExecutionModel::new(10.0, 5.0));
+
+        // 500 daily bars from 2020-01-01: 16 month starts after the first
+        let monthly = engine.run_rebalancing(&CalendarRebalance::new(0.6, Schedule::Monthly), None);
+        assert_eq!(monthly.total_trades, 17);
+        let flows = monthly.cash_flows.unwrap();
+        assert_eq!(flows.total_deposits, 0.0);
+        assert!((flows.time_weighted_return - monthly.total_return).abs() < 1e-9);
+
+        // A band wider than any possible drift trades only once
+        let held = engine.run_rebalancing(&ThresholdRebalance::new(0.5, 0.5), None);
+        assert_eq!(held.total_trades, 1);
+    }
+
+    #[test]
+    fn test_contributions_are_not_returns() {
+        // Flat prices: deposits are the only source of equity growth
+        let data: Vec<OHLCV> = (0..365)
+            .map(|i| OHLCV::new(1_577_836_800_000 + i * DAY, 100.0, 100.0, 100.0, 100.0, 1.0))
+            .collect();
+        let contributions = Contributions::new(1_000.0, Schedule::Monthly);
+        let result = BacktestEngine::new(data, 10_000.0, ExecutionModel::new(10.0, 5.0))
+            .run_rebalancing(&DollarCostAveraging::new(), Some(&contributions));
+
+        let flows = result.cash_flows.unwrap();
+        assert_eq!(flows.total_deposits, 11_000.0);
+        assert_eq!(result.total_trades, 12);
+        assert!(result.total_return > 1.0);
+        // Only trading costs remain
+        assert!(flows.net_profit < 0.0 && flows.net_profit > -50.0);
+        assert!(flows.time_weighted_return < 0.0 && flows.time_weighted_return > -0.01);
+        let mwr = flows.money_weighted_return.unwrap();
+        assert!(mwr < 0.0 && mwr > -0.01);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::backtest::orders::OrderStats;
 use crate::backtest::trade::{Trade, TradeStats};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -19,6 +20,31 @@ pub struct BacktestResult {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub trade_stats: Option<TradeStats>,
+
+    /// Returns adjusted for deposits, for runs with cash contributions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cash_flows: Option<CashFlowReturns>,
+
+    /// Fill and inventory statistics, for order-based strategies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order_stats: Option<OrderStats>,
+}
+
+/// Returns of a portfolio that received deposits
+///
+/// `total_return` counts deposits as gains. The time-weighted return
+/// measures the investment decisions alone, while the money-weighted return
+/// (internal rate of return) also reflects the timing of the deposits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CashFlowReturns {
+    /// Deposits after the initial capital
+    pub total_deposits: f64,
+    /// Final equity minus everything paid in
+    pub net_profit: f64,
+    pub time_weighted_return: f64,
+    pub annualized_time_weighted_return: f64,
+    /// Annualized; `None` if no rate balances the flows
+    pub money_weighted_return: Option<f64>,
 }
 
 impl BacktestResult {
@@ -46,32 +72,36 @@ impl BacktestResult {
     }
 
     pub fn save_trades_to_csv(&self, path: &Path) -> Result<(), std::io::Error> {
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)?;
+        match &self.trades {
+            Some(trades) => save_trades_to_csv(trades, path),
+            None => Ok(()),
         }
+    }
+}
 
-        if let Some(trades) = &self.trades {
-            let mut csv = String::from("trade_num,entry_timestamp,exit_timestamp,entry_price,exit_price,position_size,pnl,pnl_pct,duration_days,is_win\n");
-
-            for (i, trade) in trades.iter().enumerate() {
-                csv.push_str(&format!(
-                    "{},{},{},{:.2},{:.2},{:.8},{:.2},{:.4},{:.1},{}\n",
-                    i + 1,
-                    trade.entry_timestamp,
-                    trade.exit_timestamp,
-                    trade.entry_price,
-                    trade.exit_price,
-                    trade.position_size,
-                    trade.pnl,
-                    trade.pnl_pct * 100.0,
-                    trade.duration_days(),
-                    trade.is_win
-                ));
-            }
+/// Write trades as CSV, one row per completed trade
+pub fn save_trades_to_csv(trades: &[Trade], path: &Path) -> Result<(), std::io::Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
 
-            std::fs::write(path, csv)?;
-        }
+    let mut csv = String::from("trade_num,entry_timestamp,exit_timestamp,entry_price,exit_price,position_size,pnl,pnl_pct,duration_days,is_win\n");
 
-        Ok(())
+    for (i, trade) in trades.iter().enumerate() {
+        csv.push_str(&format!(
+            "{},{},{},{:.2},{:.2},{:.8},{:.2},{:.4},{:.1},{}\n",
+            i + 1,
+            trade.entry_timestamp,
+            trade.exit_timestamp,
+            trade.entry_price,
+            trade.exit_price,
+            trade.position_size,
+            trade.pnl,
+            trade.pnl_pct * 100.0,
+            trade.duration_days(),
+            trade.is_win
+        ));
     }
+
+    std::fs::write(path, csv)
 }
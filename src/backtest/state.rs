@@ -0,0 +1,204 @@
+use crate::backtest::{
+    ExecutionModel, Portfolio, PositionSizingMethod, RiskLimits, RiskMetrics, StopLossMethod, Trade,
+};
+use crate::data::OHLCV;
+use serde::{Deserialize, Serialize};
+
+/// Execution costs, sizing, stops and risk limits applied to every bar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradingRules {
+    pub execution_model: ExecutionModel,
+    pub position_sizing: PositionSizingMethod,
+    pub stop_loss: StopLossMethod,
+    pub risk_limits: RiskLimits,
+}
+
+impl TradingRules {
+    pub fn new(execution_model: ExecutionModel) -> Self {
+        Self {
+            execution_model,
+            position_sizing: PositionSizingMethod::default(),
+            stop_loss: StopLossMethod::default(),
+            risk_limits: RiskLimits::default(),
+        }
+    }
+}
+
+/// Portfolio, open position and risk bookkeeping carried from bar to bar
+///
+/// `BacktestEngine` drives this over a whole series at once; the paper
+/// trader drives it one bar at a time and persists it between runs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradingState {
+    pub portfolio: Portfolio,
+    pub risk_metrics: RiskMetrics,
+    pub trades: Vec<Trade>,
+    /// Target position after the last processed bar
+    pub prev_position: f64,
+    pub entry_bar: Option<usize>,
+    pub entry_timestamp: Option<i64>,
+    pub entry_price: Option<f64>,
+    pub position_size: Option<f64>,
+    pub highest_price: f64,
+    /// Number of bars processed so far
+    pub bars_processed: usize,
+}
+
+impl TradingState {
+    pub fn new(initial_capital: f64) -> Self {
+        Self {
+            portfolio: Portfolio::new(initial_capital),
+            risk_metrics: RiskMetrics::new(initial_capital),
+            trades: Vec::new(),
+            prev_position: 0.0,
+            entry_bar: None,
+            entry_timestamp: None,
+            entry_price: None,
+            position_size: None,
+            highest_price: 0.0,
+            bars_processed: 0,
+        }
+    }
+
+    pub fn in_position(&self) -> bool {
+        self.position_size.is_some()
+    }
+
+    /// Process one bar with the strategy's target position; returns equity at the close
+    pub fn on_bar(
+        &mut self,
+        bar: &OHLCV,
+        target_position: f64,
+        atr: Option<f64>,
+        rules: &TradingRules,
+    ) -> f64 {
+        let i = self.bars_processed;
+
+        // Check for stop loss exit
+        let mut stop_hit = false;
+        if let (Some(entry_idx), Some(entry_px)) = (self.entry_bar, self.entry_price) {
+            let bars_held = i - entry_idx;
+            if rules
+                .stop_loss
+                .is_hit(entry_px, bar.close, self.highest_price, bars_held, atr)
+            {
+                stop_hit = true;
+            }
+
+            // Update highest price for trailing stop
+            if bar.close > self.highest_price {
+                self.highest_price = bar.close;
+            }
+        }
+
+        // Execute stop loss exit
+        if stop_hit && self.close_position(bar, i, rules) {
+            self.risk_metrics.on_trade();
+            self.prev_position = 0.0;
+        }
+
+        // Execute trades when position changes (strategy signal)
+        if !stop_hit && (target_position - self.prev_position).abs() > 1e-6 {
+            if target_position > self.prev_position {
+                self.try_enter(bar, rules);
+            } else if self.close_position(bar, i, rules) {
+                self.risk_metrics.on_trade();
+            }
+
+            self.prev_position = target_position;
+        }
+
+        let equity = self.portfolio.equity(bar.close);
+
+        // Update risk metrics
+        let exposure = self
+            .position_size
+            .map(|size| size * bar.close)
+            .unwrap_or(0.0);
+        self.risk_metrics.update(equity, exposure);
+        self.bars_processed += 1;
+
+        equity
+    }
+
+    /// Buy according to position sizing if risk limits allow it
+    fn try_enter(&mut self, bar: &OHLCV, rules: &TradingRules) {
+        let equity = self.portfolio.equity(bar.close);
+
+        // Check risk limits before entering
+        if self.risk_metrics.risk_limit_violations == 0
+            && rules
+                .risk_limits
+                .check_drawdown(equity, self.risk_metrics.peak_equity)
+            && rules.risk_limits.can_trade(
+                self.risk_metrics.bars_since_last_trade,
+                self.risk_metrics.trades_today,
+            )
+        {
+            let position_value = rules.position_sizing.calculate_size(equity, None);
+
+            if rules
+                .risk_limits
+                .check_position_size(position_value, equity)
+            {
+                let buy_price = rules.execution_model.execute_market_buy(bar.close);
+                let btc_to_buy = position_value / buy_price;
+
+                if self.portfolio.cash >= btc_to_buy * buy_price {
+                    self.portfolio
+                        .buy(btc_to_buy, buy_price, rules.execution_model.commission_bps);
+
+                    self.entry_bar = Some(self.bars_processed);
+                    self.entry_timestamp = Some(bar.timestamp);
+                    self.entry_price = Some(buy_price);
+                    self.position_size = Some(btc_to_buy);
+                    self.highest_price = bar.close;
+
+                    self.risk_metrics.on_trade();
+                }
+            } else {
+                self.risk_metrics.risk_limit_violations += 1;
+            }
+        } else {
+            self.risk_metrics.risk_limit_violations += 1;
+        }
+    }
+
+    /// Close any open position at the last processed bar, as at the end of a backtest
+    pub fn close_final(&mut self, bar: &OHLCV, rules: &TradingRules) -> bool {
+        let index = self.bars_processed.saturating_sub(1);
+        self.close_position(bar, index, rules)
+    }
+
+    /// Sell the open position at `bar`'s close and record the trade
+    ///
+    /// Returns false if there was no position to close. Tracking is reset
+    /// either way.
+    fn close_position(&mut self, bar: &OHLCV, bar_index: usize, rules: &TradingRules) -> bool {
+        let closed = if let (Some(entry_idx), Some(entry_ts), Some(entry_px), Some(pos_size)) = (
+            self.entry_bar,
+            self.entry_timestamp,
+            self.entry_price,
+            self.position_size,
+        ) {
+            let sell_price = rules.execution_model.execute_market_sell(bar.close);
+            self.portfolio
+                .sell(pos_size, sell_price, rules.execution_model.commission_bps);
+
+            let mut trade = Trade::new(entry_ts, bar.timestamp, entry_px, sell_price, pos_size);
+            trade.duration_bars = bar_index - entry_idx;
+            self.trades.push(trade);
+            true
+        } else {
+            false
+        };
+
+        self.entry_bar = None;
+        self.entry_timestamp = None;
+        self.entry_price = None;
+        self.position_size = None;
+        self.highest_price = 0.0;
+
+        closed
+    }
+}
@@ -1,3 +1,4 @@
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 
 /// Stop loss methods
@@ -21,6 +22,36 @@ pub enum StopLossMethod {
 }
 
 impl StopLossMethod {
+    /// Parse a stop spec: `none`, `fixed:5`, `trailing:8`, `atr:2` or `atr:2:14`, `time:20`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let parts: Vec<&str> = spec.trim().split(':').collect();
+        let number = |i: usize| -> Result<f64> {
+            parts
+                .get(i)
+                .context(format!("Missing value in stop spec: {}", spec))?
+                .parse::<f64>()
+                .context(format!("Invalid value in stop spec: {}", spec))
+        };
+
+        let method = match parts[0].to_lowercase().as_str() {
+            "none" => StopLossMethod::None,
+            "fixed" => StopLossMethod::FixedPercent(number(1)?),
+            "trailing" => StopLossMethod::Trailing(number(1)?),
+            "atr" => StopLossMethod::ATR {
+                multiplier: number(1)?,
+                period: if parts.len() > 2 {
+                    number(2)? as usize
+                } else {
+                    14
+                },
+            },
+            "time" => StopLossMethod::TimeLimit(number(1)? as usize),
+            _ => bail!("Unknown stop loss method: {}", spec),
+        };
+
+        Ok(method)
+    }
+
     /// Check if stop is hit
     /// Returns true if position should be closed
     pub fn is_hit(
@@ -82,10 +113,14 @@ impl StopLossMethod {
 }
 
 /// Calculate Average True Range (ATR)
+///
+/// The first value averages the first `period` true ranges; later values use
+/// Wilder smoothing, so the whole series takes one pass.
 pub fn calculate_atr(data: &[(f64, f64, f64)], period: usize) -> Vec<f64> {
     // data: Vec<(high, low, close)>
-    let mut atr = Vec::with_capacity(data.len());
-    let mut true_ranges = Vec::with_capacity(data.len());
+    assert!(period > 0, "ATR period must be greater than 0");
+    let mut atr = vec![f64::NAN; data.len()];
+    let mut current = 0.0;
 
     for i in 0..data.len() {
         let (high, low, _close) = data[i];
@@ -100,17 +135,16 @@ pub fn calculate_atr(data: &[(f64, f64, f64)], period: usize) -> Vec<f64> {
             tr1.max(tr2).max(tr3)
         };
 
-        true_ranges.push(tr);
-
-        if i < period - 1 {
-            atr.push(f64::NAN);
-        } else if i == period - 1 {
-            let avg_tr: f64 = true_ranges[..period].iter().sum::<f64>() / period as f64;
-            atr.push(avg_tr);
+        if i < period {
+            // Running sum of the first true ranges
+            current += tr;
+            if i == period - 1 {
+                current /= period as f64;
+                atr[i] = current;
+            }
         } else {
-            let prev_atr = atr[i - 1];
-            let smoothed_atr = (prev_atr * (period as f64 - 1.0) + tr) / period as f64;
-            atr.push(smoothed_atr);
+            current = (current * (period as f64 - 1.0) + tr) / period as f64;
+            atr[i] = current;
         }
     }
 
@@ -147,6 +181,24 @@ mod tests {
         assert!(stop.is_hit(100.0, 110.0, 110.0, 10, None));
     }
 
+    #[test]
+    fn test_parse_stop_spec() {
+        assert!(matches!(
+            StopLossMethod::parse("trailing:8").unwrap(),
+            StopLossMethod::Trailing(p) if p == 8.0
+        ));
+        assert!(matches!(
+            StopLossMethod::parse("atr:2").unwrap(),
+            StopLossMethod::ATR { period: 14, .. }
+        ));
+        assert!(matches!(
+            StopLossMethod::parse("time:20").unwrap(),
+            StopLossMethod::TimeLimit(20)
+        ));
+        assert!(StopLossMethod::parse("fixed").is_err());
+        assert!(StopLossMethod::parse("moon:1").is_err());
+    }
+
     #[test]
     fn test_atr_calculation() {
         let data = vec![(10.0, 9.0, 9.5), (10.5, 9.5, 10.0), (11.0, 10.0, 10.5)];
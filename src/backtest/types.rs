@@ -5,6 +5,9 @@ pub struct Portfolio {
     pub cash: f64,
     pub btc_position: f64,
     pub total_trades: u32,
+    /// Cash added after the initial capital
+    #[serde(default)]
+    pub deposits: f64,
 }
 
 impl Portfolio {
@@ -13,9 +16,16 @@ impl Portfolio {
             cash: initial_cash,
             btc_position: 0.0,
             total_trades: 0,
+            deposits: 0.0,
         }
     }
 
+    /// Add a cash contribution
+    pub fn deposit(&mut self, amount: f64) {
+        self.cash += amount;
+        self.deposits += amount;
+    }
+
     pub fn equity(&self, btc_price: f64) -> f64 {
         self.cash + (self.btc_position * btc_price)
     }
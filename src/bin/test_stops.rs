@@ -16,14 +16,14 @@ fn main() {
     let strategy = SMACrossover::new(20, 50);
 
     println!("=== SMA 20/50 - NO STOPS ===");
-    let engine = BacktestEngine::new(data.clone(), capital, execution_model.clone());
+    let engine = BacktestEngine::new(&data, capital, execution_model.clone());
     let result = engine.run(&strategy);
     println!("Return: {:.2}%", result.total_return * 100.0);
     println!("Max DD: {:.2}%", result.max_drawdown * 100.0);
     println!("Trades: {}\n", result.total_trades);
 
     println!("=== SMA 20/50 - 10% TRAILING STOP ===");
-    let engine = BacktestEngine::new(data.clone(), capital, execution_model.clone())
+    let engine = BacktestEngine::new(&data, capital, execution_model.clone())
         .with_stop_loss(StopLossMethod::Trailing(10.0));
     let result = engine.run(&strategy);
     println!("Return: {:.2}%", result.total_return * 100.0);
@@ -31,7 +31,7 @@ fn main() {
     println!("Trades: {}\n", result.total_trades);
 
     println!("=== SMA 20/50 - 5% TRAILING STOP ===");
-    let engine = BacktestEngine::new(data.clone(), capital, execution_model.clone())
+    let engine = BacktestEngine::new(&data, capital, execution_model.clone())
         .with_stop_loss(StopLossMethod::Trailing(5.0));
     let result = engine.run(&strategy);
     println!("Return: {:.2}%", result.total_return * 100.0);
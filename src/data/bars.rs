@@ -0,0 +1,184 @@
+use crate::data::types::{AggTrade, OHLCV};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+/// Length of a bar interval such as `1m`, `15m`, `4h`, `1d` or `1w` in milliseconds
+pub fn interval_to_millis(interval: &str) -> Result<i64> {
+    let split = interval
+        .find(|c: char| !c.is_ascii_digit())
+        .context(format!("Invalid interval: {}", interval))?;
+    let (count, unit) = interval.split_at(split);
+    let count: i64 = count
+        .parse()
+        .context(format!("Invalid interval: {}", interval))?;
+
+    if count <= 0 {
+        bail!("Invalid interval: {}", interval);
+    }
+
+    let unit_ms = match unit {
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        "w" => 7 * 86_400_000,
+        _ => bail!("Invalid interval unit: {}", interval),
+    };
+
+    Ok(count * unit_ms)
+}
+
+/// A bar built from trades, with the VWAP that kline data does not carry
+///
+/// Trade count and taker buy volumes are filled in on `bar` itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeBar {
+    pub bar: OHLCV,
+    pub vwap: f64,
+}
+
+/// Incrementally aggregates trades into fixed-interval bars
+///
+/// Bars are aligned to multiples of the interval since epoch and stamped with
+/// their open time, matching Binance klines. Intervals without trades produce
+/// no bar.
+pub struct BarBuilder {
+    interval_ms: i64,
+    current: Option<TradeBar>,
+}
+
+impl BarBuilder {
+    pub fn new(interval_ms: i64) -> Self {
+        assert!(interval_ms > 0, "Interval must be greater than 0");
+        Self {
+            interval_ms,
+            current: None,
+        }
+    }
+
+    /// Add a trade; returns the previous bar once a trade opens a new interval
+    ///
+    /// Trades must arrive in timestamp order.
+    pub fn push(&mut self, trade: &AggTrade) -> Option<TradeBar> {
+        let open_time = trade.timestamp - trade.timestamp.rem_euclid(self.interval_ms);
+
+        let completed = match &self.current {
+            Some(bar) if bar.bar.timestamp != open_time => self.finish(),
+            _ => None,
+        };
+
+        let quote = trade.price * trade.quantity;
+        let taker_buy = trade.is_taker_buy();
+
+        match &mut self.current {
+            Some(bar) => {
+                bar.bar.high = bar.bar.high.max(trade.price);
+                bar.bar.low = bar.bar.low.min(trade.price);
+                bar.bar.close = trade.price;
+                bar.bar.volume += trade.quantity;
+                bar.bar.quote_volume += quote;
+                bar.bar.trade_count += trade.trade_count();
+                if taker_buy {
+                    bar.bar.taker_buy_base_volume += trade.quantity;
+                    bar.bar.taker_buy_quote_volume += quote;
+                }
+            }
+            None => {
+                self.current = Some(TradeBar {
+                    bar: OHLCV::new(
+                        open_time,
+                        trade.price,
+                        trade.price,
+                        trade.price,
+                        trade.price,
+                        trade.quantity,
+                    )
+                    .with_order_flow(
+                        quote,
+                        trade.trade_count(),
+                        if taker_buy { trade.quantity } else { 0.0 },
+                        if taker_buy { quote } else { 0.0 },
+                    ),
+                    vwap: trade.price,
+                });
+            }
+        }
+
+        completed
+    }
+
+    /// Emit the bar in progress, if any
+    pub fn finish(&mut self) -> Option<TradeBar> {
+        self.current.take().map(|mut bar| {
+            if bar.bar.volume > 0.0 {
+                bar.vwap = bar.bar.quote_volume / bar.bar.volume;
+            }
+            bar
+        })
+    }
+}
+
+/// Build bars from a time-ordered slice of trades
+pub fn build_bars(trades: &[AggTrade], interval_ms: i64) -> Vec<TradeBar> {
+    let mut builder = BarBuilder::new(interval_ms);
+    let mut bars: Vec<TradeBar> = trades.iter().filter_map(|t| builder.push(t)).collect();
+    bars.extend(builder.finish());
+    bars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(id: i64, price: f64, quantity: f64, timestamp: i64, is_buyer_maker: bool) -> AggTrade {
+        AggTrade {
+            agg_trade_id: id,
+            price,
+            quantity,
+            first_trade_id: id * 10,
+            last_trade_id: id * 10 + 1,
+            timestamp,
+            is_buyer_maker,
+        }
+    }
+
+    #[test]
+    fn test_interval_to_millis() {
+        assert_eq!(interval_to_millis("1m").unwrap(), 60_000);
+        assert_eq!(interval_to_millis("15m").unwrap(), 900_000);
+        assert_eq!(interval_to_millis("4h").unwrap(), 14_400_000);
+        assert_eq!(interval_to_millis("1d").unwrap(), 86_400_000);
+        assert!(interval_to_millis("m").is_err());
+        assert!(interval_to_millis("0m").is_err());
+        assert!(interval_to_millis("1y").is_err());
+    }
+
+    #[test]
+    fn test_build_bars_from_trades() {
+        let trades = vec![
+            trade(1, 100.0, 1.0, 0, false),
+            trade(2, 105.0, 1.0, 10_000, true),
+            trade(3, 95.0, 2.0, 20_000, false),
+            trade(4, 101.0, 1.0, 59_999, true),
+            // Gap: no trades in the second minute
+            trade(5, 110.0, 3.0, 125_000, false),
+        ];
+
+        let bars = build_bars(&trades, 60_000);
+        assert_eq!(bars.len(), 2);
+
+        let first = &bars[0];
+        assert_eq!(first.bar.timestamp, 0);
+        assert_eq!(first.bar.open, 100.0);
+        assert_eq!(first.bar.high, 105.0);
+        assert_eq!(first.bar.low, 95.0);
+        assert_eq!(first.bar.close, 101.0);
+        assert_eq!(first.bar.volume, 5.0);
+        assert_eq!(first.bar.trade_count, 8);
+        assert_eq!(first.bar.taker_buy_base_volume, 3.0);
+        assert!((first.vwap - 496.0 / 5.0).abs() < 1e-9);
+
+        assert_eq!(bars[1].bar.timestamp, 120_000);
+        assert_eq!(bars[1].vwap, 110.0);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::data::types::OHLCV;
+use crate::data::types::{AggTrade, OHLCV};
 use anyhow::{Context, Result};
 use chrono::{DateTime, Duration, Utc};
 use reqwest::blocking::Client;
@@ -21,21 +21,59 @@ struct BinanceKline(
     String, // Ignore
 );
 
+#[derive(Debug, Deserialize)]
+struct BinanceAggTrade {
+    #[serde(rename = "a")]
+    agg_trade_id: i64,
+    #[serde(rename = "p")]
+    price: String,
+    #[serde(rename = "q")]
+    quantity: String,
+    #[serde(rename = "f")]
+    first_trade_id: i64,
+    #[serde(rename = "l")]
+    last_trade_id: i64,
+    #[serde(rename = "T")]
+    timestamp: i64,
+    #[serde(rename = "m")]
+    is_buyer_maker: bool,
+}
+
+/// Binance caps aggTrades requests by time at one hour per window
+const AGG_TRADES_WINDOW_MS: i64 = 60 * 60 * 1000;
+const AGG_TRADES_LIMIT: usize = 1000;
+
 pub struct BinanceDownloader {
     client: Client,
+    base_url: String,
     symbol: String,
     interval: String,
+    request_delay: std::time::Duration,
 }
 
 impl BinanceDownloader {
     pub fn new(symbol: &str, interval: &str) -> Self {
         Self {
             client: Client::new(),
+            base_url: "https://api.binance.us".to_string(),
             symbol: symbol.to_string(),
             interval: interval.to_string(),
+            request_delay: std::time::Duration::from_millis(100),
         }
     }
 
+    /// Point the downloader at a different REST endpoint (e.g. a local stub)
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Pause between paginated requests to respect rate limits
+    pub fn with_request_delay(mut self, delay: std::time::Duration) -> Self {
+        self.request_delay = delay;
+        self
+    }
+
     pub fn fetch_range(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<OHLCV>> {
         let mut all_data = Vec::new();
         let mut current = start;
@@ -54,7 +92,8 @@ impl BinanceDownloader {
             let chunk_end = (current + chunk_size).min(end);
 
             let url = format!(
-                "https://api.binance.us/api/v3/klines?symbol={}&interval={}&startTime={}&endTime={}&limit=1000",
+                "{}/api/v3/klines?symbol={}&interval={}&startTime={}&endTime={}&limit=1000",
+                self.base_url,
                 self.symbol,
                 self.interval,
                 current.timestamp_millis(),
@@ -77,21 +116,180 @@ impl BinanceDownloader {
             std::io::Write::flush(&mut std::io::stdout()).unwrap();
 
             for kline in response {
-                all_data.push(OHLCV::new(
-                    kline.0,
-                    kline.1.parse().context("Failed to parse open price")?,
-                    kline.2.parse().context("Failed to parse high price")?,
-                    kline.3.parse().context("Failed to parse low price")?,
-                    kline.4.parse().context("Failed to parse close price")?,
-                    kline.5.parse().context("Failed to parse volume")?,
-                ));
+                all_data.push(
+                    OHLCV::new(
+                        kline.0,
+                        kline.1.parse().context("Failed to parse open price")?,
+                        kline.2.parse().context("Failed to parse high price")?,
+                        kline.3.parse().context("Failed to parse low price")?,
+                        kline.4.parse().context("Failed to parse close price")?,
+                        kline.5.parse().context("Failed to parse volume")?,
+                    )
+                    .with_order_flow(
+                        kline.7.parse().context("Failed to parse quote volume")?,
+                        kline.8,
+                        kline
+                            .9
+                            .parse()
+                            .context("Failed to parse taker buy base volume")?,
+                        kline
+                            .10
+                            .parse()
+                            .context("Failed to parse taker buy quote volume")?,
+                    ),
+                );
             }
 
             current = chunk_end;
-            std::thread::sleep(std::time::Duration::from_millis(100));
+            std::thread::sleep(self.request_delay);
         }
 
         println!();
         Ok(all_data)
     }
+
+    /// Download aggregate trades in `[start, end)`
+    ///
+    /// Walks the range in one-hour windows and pages through busy windows by
+    /// aggregate trade id.
+    pub fn fetch_agg_trades(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<AggTrade>> {
+        let mut all_trades = Vec::new();
+        let end_ms = end.timestamp_millis();
+        let mut current = start.timestamp_millis();
+
+        println!("Fetching aggregate trades in hourly windows...");
+
+        while current < end_ms {
+            let window_end = (current + AGG_TRADES_WINDOW_MS).min(end_ms);
+
+            let url = format!(
+                "{}/api/v3/aggTrades?symbol={}&startTime={}&endTime={}&limit={}",
+                self.base_url,
+                self.symbol,
+                current,
+                window_end - 1,
+                AGG_TRADES_LIMIT
+            );
+            let mut batch = self.get_agg_trades(&url)?;
+
+            // A full page means the window has more trades; continue by id
+            while batch.len() == AGG_TRADES_LIMIT {
+                let last_id = batch.last().map(|t| t.agg_trade_id).unwrap_or(0);
+                let reached_end = batch.last().is_some_and(|t| t.timestamp >= window_end);
+                all_trades.extend(batch.into_iter().filter(|t| t.timestamp < window_end));
+
+                if reached_end {
+                    batch = Vec::new();
+                    break;
+                }
+
+                std::thread::sleep(self.request_delay);
+                let url = format!(
+                    "{}/api/v3/aggTrades?symbol={}&fromId={}&limit={}",
+                    self.base_url,
+                    self.symbol,
+                    last_id + 1,
+                    AGG_TRADES_LIMIT
+                );
+                batch = self.get_agg_trades(&url)?;
+            }
+            all_trades.extend(batch.into_iter().filter(|t| t.timestamp < window_end));
+
+            print!(".");
+            std::io::Write::flush(&mut std::io::stdout()).unwrap();
+
+            current = window_end;
+            std::thread::sleep(self.request_delay);
+        }
+
+        println!();
+        Ok(all_trades)
+    }
+
+    fn get_agg_trades(&self, url: &str) -> Result<Vec<AggTrade>> {
+        let response: Vec<BinanceAggTrade> = self
+            .client
+            .get(url)
+            .send()
+            .context("Failed to fetch aggregate trades from Binance")?
+            .json()
+            .context("Failed to parse response")?;
+
+        response
+            .into_iter()
+            .map(|t| {
+                Ok(AggTrade {
+                    agg_trade_id: t.agg_trade_id,
+                    price: t.price.parse().context("Failed to parse trade price")?,
+                    quantity: t
+                        .quantity
+                        .parse()
+                        .context("Failed to parse trade quantity")?,
+                    first_trade_id: t.first_trade_id,
+                    last_trade_id: t.last_trade_id,
+                    timestamp: t.timestamp,
+                    is_buyer_maker: t.is_buyer_maker,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Serve one canned JSON body per incoming request, in order
+    fn serve_stub(bodies: Vec<String>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+
+        std::thread::spawn(move || {
+            for body in bodies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                    line.clear();
+                }
+
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        address
+    }
+
+    #[test]
+    fn test_fetch_agg_trades_from_stub() {
+        let body = r#"[
+            {"a":1,"p":"42000.00","q":"0.5","f":100,"l":101,"T":1704067200000,"m":true,"M":true},
+            {"a":2,"p":"42010.50","q":"1.25","f":102,"l":102,"T":1704067230000,"m":false,"M":true}
+        ]"#;
+        let address = serve_stub(vec![body.to_string()]);
+
+        let downloader = BinanceDownloader::new("BTCUSDT", "1m")
+            .with_base_url(&address)
+            .with_request_delay(std::time::Duration::ZERO);
+
+        let start = DateTime::from_timestamp_millis(1_704_067_200_000).unwrap();
+        let end = start + Duration::minutes(30);
+        let trades = downloader.fetch_agg_trades(start, end).unwrap();
+
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].price, 42000.0);
+        assert_eq!(trades[0].trade_count(), 2);
+        assert!(trades[1].is_taker_buy());
+    }
 }
@@ -0,0 +1,287 @@
+use crate::data::storage::{load_range_from_parquet, save_to_parquet};
+use crate::data::types::{DateRange, OHLCV};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Identifies one dataset in the catalog
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DatasetKey {
+    pub exchange: String,
+    pub symbol: String,
+    pub interval: String,
+}
+
+impl DatasetKey {
+    pub fn new(exchange: &str, symbol: &str, interval: &str) -> Self {
+        Self {
+            exchange: exchange.to_lowercase(),
+            symbol: symbol.to_uppercase(),
+            interval: interval.to_string(),
+        }
+    }
+}
+
+impl Default for DatasetKey {
+    fn default() -> Self {
+        Self::new("binance", "BTCUSDT", "1d")
+    }
+}
+
+impl fmt::Display for DatasetKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}/{}", self.exchange, self.symbol, self.interval)
+    }
+}
+
+/// Metadata sidecar stored next to each dataset
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatasetMetadata {
+    pub key: DatasetKey,
+    pub source: String,
+    pub first_timestamp: i64,
+    pub last_timestamp: i64,
+    pub row_count: usize,
+    /// SHA-256 of the parquet file contents
+    pub content_hash: String,
+    /// RFC 3339 time the dataset was written
+    pub downloaded_at: String,
+}
+
+/// Dataset storage laid out as `{root}/{exchange}/{symbol}/{interval}.parquet`
+/// with a `{interval}.json` metadata sidecar
+pub struct DataCatalog {
+    root: PathBuf,
+}
+
+impl DataCatalog {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn dataset_path(&self, key: &DatasetKey) -> PathBuf {
+        self.root
+            .join(&key.exchange)
+            .join(&key.symbol)
+            .join(format!("{}.parquet", key.interval))
+    }
+
+    pub fn metadata_path(&self, key: &DatasetKey) -> PathBuf {
+        self.root
+            .join(&key.exchange)
+            .join(&key.symbol)
+            .join(format!("{}.json", key.interval))
+    }
+
+    /// Aggregate trades for a symbol, kept beside its bar datasets
+    pub fn trades_path(&self, exchange: &str, symbol: &str) -> PathBuf {
+        self.root
+            .join(exchange.to_lowercase())
+            .join(symbol.to_uppercase())
+            .join("trades")
+            .join("aggtrades.parquet")
+    }
+
+    /// Pre-catalog location used by v0.5 for BTCUSDT downloads
+    fn legacy_path(&self, key: &DatasetKey) -> Option<PathBuf> {
+        if key.exchange == "binance" && key.symbol == "BTCUSDT" {
+            Some(
+                self.root
+                    .join("processed")
+                    .join(format!("btc_{}.parquet", key.interval)),
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Resolve the parquet file for a dataset, falling back to the legacy
+    /// `processed/btc_{interval}.parquet` location
+    pub fn resolve(&self, key: &DatasetKey) -> Option<PathBuf> {
+        let path = self.dataset_path(key);
+        if path.exists() {
+            return Some(path);
+        }
+
+        self.legacy_path(key).filter(|p| p.exists())
+    }
+
+    pub fn exists(&self, key: &DatasetKey) -> bool {
+        self.resolve(key).is_some()
+    }
+
+    pub fn load(&self, key: &DatasetKey) -> Result<Vec<OHLCV>> {
+        self.load_range(key, &DateRange::default())
+    }
+
+    /// Load only the bars of a dataset inside `range`
+    pub fn load_range(&self, key: &DatasetKey, range: &DateRange) -> Result<Vec<OHLCV>> {
+        match self.resolve(key) {
+            Some(path) => load_range_from_parquet(&path, range),
+            None => bail!(
+                "Dataset {} not found in catalog at {}",
+                key,
+                self.dataset_path(key).display()
+            ),
+        }
+    }
+
+    /// Write a dataset and its metadata sidecar
+    pub fn save(&self, key: &DatasetKey, data: &[OHLCV], source: &str) -> Result<DatasetMetadata> {
+        let path = self.dataset_path(key);
+        save_to_parquet(data, &path)?;
+
+        let metadata = DatasetMetadata {
+            key: key.clone(),
+            source: source.to_string(),
+            first_timestamp: data.first().map(|d| d.timestamp).unwrap_or(0),
+            last_timestamp: data.last().map(|d| d.timestamp).unwrap_or(0),
+            row_count: data.len(),
+            content_hash: hash_file(&path)?,
+            downloaded_at: chrono::Utc::now().to_rfc3339(),
+        };
+
+        let json = serde_json::to_string_pretty(&metadata)?;
+        std::fs::write(self.metadata_path(key), json)?;
+
+        Ok(metadata)
+    }
+
+    /// Read the metadata sidecar for a dataset
+    pub fn metadata(&self, key: &DatasetKey) -> Result<DatasetMetadata> {
+        let path = self.metadata_path(key);
+        let json = std::fs::read_to_string(&path)
+            .context(format!("Failed to read metadata: {}", path.display()))?;
+        serde_json::from_str(&json).context("Failed to parse metadata")
+    }
+
+    /// Check that a dataset still matches the hash recorded in its sidecar
+    pub fn verify(&self, key: &DatasetKey) -> Result<bool> {
+        let metadata = self.metadata(key)?;
+        let hash = hash_file(&self.dataset_path(key))?;
+        Ok(hash == metadata.content_hash)
+    }
+
+    /// List every dataset stored in the catalog layout, sorted by key
+    pub fn list(&self) -> Result<Vec<DatasetKey>> {
+        let mut keys = Vec::new();
+
+        if !self.root.exists() {
+            return Ok(keys);
+        }
+
+        for exchange in subdirectories(&self.root)? {
+            for symbol in subdirectories(&exchange)? {
+                for entry in std::fs::read_dir(&symbol)? {
+                    let path = entry?.path();
+                    if path.extension().and_then(|e| e.to_str()) != Some("parquet") {
+                        continue;
+                    }
+
+                    if let (Some(exchange), Some(symbol), Some(interval)) = (
+                        file_name(&exchange),
+                        file_name(&symbol),
+                        path.file_stem().and_then(|s| s.to_str()),
+                    ) {
+                        keys.push(DatasetKey::new(exchange, symbol, interval));
+                    }
+                }
+            }
+        }
+
+        keys.sort_by_key(|k| k.to_string());
+        Ok(keys)
+    }
+}
+
+impl Default for DataCatalog {
+    fn default() -> Self {
+        Self::new("data")
+    }
+}
+
+fn subdirectories(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            dirs.push(path);
+        }
+    }
+    Ok(dirs)
+}
+
+fn file_name(path: &Path) -> Option<&str> {
+    path.file_name().and_then(|n| n.to_str())
+}
+
+fn hash_file(path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path).context(format!("Failed to read file: {}", path.display()))?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_data() -> Vec<OHLCV> {
+        (0..5)
+            .map(|i| {
+                let price = 100.0 + i as f64;
+                OHLCV::new(i * 86_400_000, price, price + 1.0, price - 1.0, price, 10.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_save_list_and_load() {
+        let root = std::env::temp_dir().join("strataquant_catalog_test");
+        std::fs::remove_dir_all(&root).ok();
+        let catalog = DataCatalog::new(&root);
+
+        let key = DatasetKey::new("Binance", "ethusdt", "1h");
+        let metadata = catalog.save(&key, &sample_data(), "test").unwrap();
+
+        assert_eq!(
+            catalog.dataset_path(&key),
+            root.join("binance").join("ETHUSDT").join("1h.parquet")
+        );
+        assert_eq!(metadata.row_count, 5);
+        assert_eq!(metadata.last_timestamp, 4 * 86_400_000);
+        assert!(catalog.verify(&key).unwrap());
+
+        assert_eq!(catalog.list().unwrap(), vec![key.clone()]);
+        assert_eq!(catalog.load(&key).unwrap().len(), 5);
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_legacy_fallback() {
+        let root = std::env::temp_dir().join("strataquant_catalog_legacy_test");
+        std::fs::remove_dir_all(&root).ok();
+        let catalog = DataCatalog::new(&root);
+
+        let key = DatasetKey::default();
+        assert!(!catalog.exists(&key));
+
+        save_to_parquet(
+            &sample_data(),
+            &root.join("processed").join("btc_1d.parquet"),
+        )
+        .unwrap();
+        assert!(catalog.exists(&key));
+        assert_eq!(catalog.load(&key).unwrap().len(), 5);
+
+        // Legacy layout only applies to Binance BTCUSDT
+        assert!(!catalog.exists(&DatasetKey::new("binance", "ETHUSDT", "1d")));
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,375 @@
+use crate::data::types::OHLCV;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use polars::prelude::*;
+use std::path::Path;
+
+/// Unit of numeric timestamps in a source file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampUnit {
+    Seconds,
+    Milliseconds,
+    Microseconds,
+    Nanoseconds,
+    /// Guess the unit from the magnitude of the values
+    Auto,
+}
+
+impl TimestampUnit {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value {
+            "s" | "sec" | "seconds" => Ok(Self::Seconds),
+            "ms" | "millis" | "milliseconds" => Ok(Self::Milliseconds),
+            "us" | "micros" | "microseconds" => Ok(Self::Microseconds),
+            "ns" | "nanos" | "nanoseconds" => Ok(Self::Nanoseconds),
+            "auto" => Ok(Self::Auto),
+            _ => bail!(
+                "Unknown timestamp unit: {} (expected s, ms, us, ns or auto)",
+                value
+            ),
+        }
+    }
+
+    /// Detect the unit of an epoch timestamp from its magnitude
+    ///
+    /// Any date between 1973 and 5138 is unambiguous: seconds stay below 1e11,
+    /// milliseconds below 1e14, microseconds below 1e17.
+    pub fn detect(sample: f64) -> Self {
+        let magnitude = sample.abs();
+        if magnitude < 1e11 {
+            Self::Seconds
+        } else if magnitude < 1e14 {
+            Self::Milliseconds
+        } else if magnitude < 1e17 {
+            Self::Microseconds
+        } else {
+            Self::Nanoseconds
+        }
+    }
+
+    fn to_millis(self, value: f64) -> i64 {
+        let millis = match self {
+            Self::Seconds => value * 1000.0,
+            Self::Milliseconds | Self::Auto => value,
+            Self::Microseconds => value / 1000.0,
+            Self::Nanoseconds => value / 1_000_000.0,
+        };
+        millis.round() as i64
+    }
+}
+
+/// Maps source column names to the canonical OHLCV fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnMapping {
+    pub timestamp: String,
+    pub open: String,
+    pub high: String,
+    pub low: String,
+    pub close: String,
+    pub volume: String,
+}
+
+impl ColumnMapping {
+    /// Parse a mapping spec such as `timestamp=open_time,volume=vol`
+    ///
+    /// Fields that are not mentioned keep their canonical name.
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mut mapping = Self::default();
+
+        for entry in spec.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (field, column) = entry
+                .split_once('=')
+                .context(format!("Invalid column mapping entry: {}", entry))?;
+            let column = column.trim().to_string();
+
+            match field.trim() {
+                "timestamp" => mapping.timestamp = column,
+                "open" => mapping.open = column,
+                "high" => mapping.high = column,
+                "low" => mapping.low = column,
+                "close" => mapping.close = column,
+                "volume" => mapping.volume = column,
+                other => bail!("Unknown OHLCV field in column mapping: {}", other),
+            }
+        }
+
+        Ok(mapping)
+    }
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        Self {
+            timestamp: "timestamp".to_string(),
+            open: "open".to_string(),
+            high: "high".to_string(),
+            low: "low".to_string(),
+            close: "close".to_string(),
+            volume: "volume".to_string(),
+        }
+    }
+}
+
+/// Import a CSV or Parquet file with arbitrary column names into OHLCV bars
+///
+/// The format is chosen from the file extension. Bars are returned sorted by
+/// timestamp (milliseconds since epoch), ready for `save_to_parquet`.
+pub fn import_file(
+    path: &Path,
+    mapping: &ColumnMapping,
+    unit: TimestampUnit,
+) -> Result<Vec<OHLCV>> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+
+    let df = match extension.as_deref() {
+        Some("csv") => CsvReadOptions::default()
+            .with_has_header(true)
+            .try_into_reader_with_file_path(Some(path.to_path_buf()))
+            .context(format!("Failed to open file: {}", path.display()))?
+            .finish()
+            .context("Failed to read CSV file")?,
+        Some("parquet") => {
+            let file = std::fs::File::open(path)
+                .context(format!("Failed to open file: {}", path.display()))?;
+            ParquetReader::new(file)
+                .finish()
+                .context("Failed to read Parquet file")?
+        }
+        _ => bail!(
+            "Unsupported file type: {} (expected .csv or .parquet)",
+            path.display()
+        ),
+    };
+
+    import_dataframe(&df, mapping, unit)
+}
+
+/// Convert an already loaded DataFrame into OHLCV bars using a column mapping
+pub fn import_dataframe(
+    df: &DataFrame,
+    mapping: &ColumnMapping,
+    unit: TimestampUnit,
+) -> Result<Vec<OHLCV>> {
+    let timestamps = timestamp_column(df, &mapping.timestamp, unit)?;
+    let opens = price_column(df, &mapping.open)?;
+    let highs = price_column(df, &mapping.high)?;
+    let lows = price_column(df, &mapping.low)?;
+    let closes = price_column(df, &mapping.close)?;
+    let volumes = price_column(df, &mapping.volume)?;
+
+    let mut data = Vec::with_capacity(df.height());
+    for (i, &timestamp) in timestamps.iter().enumerate() {
+        data.push(OHLCV::new(
+            timestamp,
+            opens.get(i).context(format!("Missing open at row {}", i))?,
+            highs.get(i).context(format!("Missing high at row {}", i))?,
+            lows.get(i).context(format!("Missing low at row {}", i))?,
+            closes
+                .get(i)
+                .context(format!("Missing close at row {}", i))?,
+            volumes
+                .get(i)
+                .context(format!("Missing volume at row {}", i))?,
+        ));
+    }
+
+    data.sort_by_key(|bar| bar.timestamp);
+    Ok(data)
+}
+
+fn price_column(df: &DataFrame, name: &str) -> Result<Float64Chunked> {
+    let column = df
+        .column(name)
+        .context(format!("Missing {} column", name))?
+        .cast(&DataType::Float64)
+        .context(format!("Invalid {} type", name))?;
+
+    Ok(column.f64()?.clone())
+}
+
+fn timestamp_column(df: &DataFrame, name: &str, unit: TimestampUnit) -> Result<Vec<i64>> {
+    let column = df
+        .column(name)
+        .context(format!("Missing {} column", name))?;
+
+    match column.dtype() {
+        DataType::String => column
+            .str()?
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let value = value.context(format!("Missing timestamp at row {}", i))?;
+                parse_timestamp_str(value, unit)
+            })
+            .collect(),
+        DataType::Datetime(time_unit, _) => {
+            let unit = match time_unit {
+                TimeUnit::Milliseconds => TimestampUnit::Milliseconds,
+                TimeUnit::Microseconds => TimestampUnit::Microseconds,
+                TimeUnit::Nanoseconds => TimestampUnit::Nanoseconds,
+            };
+            let raw = column.cast(&DataType::Int64)?;
+            raw.i64()?
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    let value = value.context(format!("Missing timestamp at row {}", i))?;
+                    Ok(unit.to_millis(value as f64))
+                })
+                .collect()
+        }
+        DataType::Date => {
+            let days = column.cast(&DataType::Int32)?;
+            days.i32()?
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    let value = value.context(format!("Missing timestamp at row {}", i))?;
+                    Ok(value as i64 * 86_400_000)
+                })
+                .collect()
+        }
+        _ => {
+            let values = column
+                .cast(&DataType::Float64)
+                .context("Invalid timestamp type")?;
+            let values = values.f64()?;
+
+            let unit = match unit {
+                TimestampUnit::Auto => match values.into_iter().flatten().next() {
+                    Some(sample) => TimestampUnit::detect(sample),
+                    None if values.is_empty() => return Ok(Vec::new()),
+                    None => bail!("Timestamp column contains no values"),
+                },
+                unit => unit,
+            };
+
+            values
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    let value = value.context(format!("Missing timestamp at row {}", i))?;
+                    Ok(unit.to_millis(value))
+                })
+                .collect()
+        }
+    }
+}
+
+/// Parse a textual timestamp: ISO 8601 / RFC 3339, `YYYY-MM-DD HH:MM:SS`,
+/// a plain date, or a numeric epoch value stored as text
+fn parse_timestamp_str(value: &str, unit: TimestampUnit) -> Result<i64> {
+    let value = value.trim();
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.timestamp_millis());
+    }
+
+    for format in [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M",
+    ] {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(dt.and_utc().timestamp_millis());
+        }
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date
+            .and_hms_opt(0, 0, 0)
+            .context("Invalid date")?
+            .and_utc()
+            .timestamp_millis());
+    }
+
+    if let Ok(number) = value.parse::<f64>() {
+        let unit = match unit {
+            TimestampUnit::Auto => TimestampUnit::detect(number),
+            unit => unit,
+        };
+        return Ok(unit.to_millis(number));
+    }
+
+    bail!("Unrecognized timestamp: {}", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_timestamp_unit() {
+        // 2024-01-01T00:00:00Z in each unit
+        assert_eq!(
+            TimestampUnit::detect(1_704_067_200.0),
+            TimestampUnit::Seconds
+        );
+        assert_eq!(
+            TimestampUnit::detect(1_704_067_200_000.0),
+            TimestampUnit::Milliseconds
+        );
+        assert_eq!(
+            TimestampUnit::detect(1_704_067_200_000_000.0),
+            TimestampUnit::Microseconds
+        );
+        assert_eq!(
+            TimestampUnit::detect(1_704_067_200_000_000_000.0),
+            TimestampUnit::Nanoseconds
+        );
+    }
+
+    #[test]
+    fn test_parse_column_mapping() {
+        let mapping = ColumnMapping::parse("timestamp=open_time, volume=vol").unwrap();
+        assert_eq!(mapping.timestamp, "open_time");
+        assert_eq!(mapping.volume, "vol");
+        assert_eq!(mapping.close, "close");
+
+        assert!(ColumnMapping::parse("price=close").is_err());
+        assert!(ColumnMapping::parse("timestamp").is_err());
+    }
+
+    #[test]
+    fn test_parse_timestamp_strings() {
+        let expected = 1_704_067_200_000;
+        let auto = TimestampUnit::Auto;
+        assert_eq!(
+            parse_timestamp_str("2024-01-01T00:00:00Z", auto).unwrap(),
+            expected
+        );
+        assert_eq!(
+            parse_timestamp_str("2024-01-01 00:00:00", auto).unwrap(),
+            expected
+        );
+        assert_eq!(parse_timestamp_str("2024-01-01", auto).unwrap(), expected);
+        assert_eq!(parse_timestamp_str("1704067200", auto).unwrap(), expected);
+        assert!(parse_timestamp_str("yesterday", auto).is_err());
+    }
+
+    #[test]
+    fn test_import_csv_with_mapping() {
+        let path = std::env::temp_dir().join("strataquant_import_test.csv");
+        std::fs::write(
+            &path,
+            "time,o,h,l,c,vol\n\
+             1704153600,101,103,100,102,12.5\n\
+             1704067200,100,102,99,101,10\n",
+        )
+        .unwrap();
+
+        let mapping =
+            ColumnMapping::parse("timestamp=time,open=o,high=h,low=l,close=c,volume=vol").unwrap();
+        let data = import_file(&path, &mapping, TimestampUnit::Auto).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0].timestamp, 1_704_067_200_000);
+        assert_eq!(data[0].close, 101.0);
+        assert_eq!(data[1].volume, 12.5);
+    }
+}
@@ -1,7 +1,22 @@
+pub mod bars;
 pub mod binance;
+pub mod catalog;
+pub mod import;
 pub mod storage;
+pub mod stream;
+pub mod synthetic;
+pub mod timeframe;
 pub mod types;
 
+pub use bars::{build_bars, interval_to_millis, BarBuilder, TradeBar};
 pub use binance::BinanceDownloader;
-pub use storage::{load_from_parquet, save_to_parquet};
-pub use types::OHLCV;
+pub use catalog::{DataCatalog, DatasetKey, DatasetMetadata};
+pub use import::{import_file, ColumnMapping, TimestampUnit};
+pub use storage::{
+    load_from_parquet, load_range_from_parquet, load_trades_from_parquet, save_to_parquet,
+    save_trades_to_parquet,
+};
+pub use stream::{parse_kline_message, replay_bars, KlineStream, KlineStreamConfig, KlineUpdate};
+pub use synthetic::{block_bootstrap, SeededRng, SyntheticConfig, SyntheticModel};
+pub use timeframe::{infer_interval, resample, AlignedTimeframe, TimeframeContext};
+pub use types::{AggTrade, DateRange, OHLCV};
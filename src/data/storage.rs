@@ -1,4 +1,4 @@
-use crate::data::types::OHLCV;
+use crate::data::types::{AggTrade, DateRange, OHLCV};
 use anyhow::{Context, Result};
 use polars::prelude::*;
 use std::path::Path;
@@ -10,6 +10,10 @@ pub fn save_to_parquet(data: &[OHLCV], path: &Path) -> Result<()> {
     let lows: Vec<f64> = data.iter().map(|d| d.low).collect();
     let closes: Vec<f64> = data.iter().map(|d| d.close).collect();
     let volumes: Vec<f64> = data.iter().map(|d| d.volume).collect();
+    let quote_volumes: Vec<f64> = data.iter().map(|d| d.quote_volume).collect();
+    let trade_counts: Vec<u64> = data.iter().map(|d| d.trade_count).collect();
+    let taker_buy_base: Vec<f64> = data.iter().map(|d| d.taker_buy_base_volume).collect();
+    let taker_buy_quote: Vec<f64> = data.iter().map(|d| d.taker_buy_quote_volume).collect();
 
     let df = DataFrame::new(vec![
         Column::Series(Series::new("timestamp".into(), timestamps)),
@@ -18,6 +22,13 @@ pub fn save_to_parquet(data: &[OHLCV], path: &Path) -> Result<()> {
         Column::Series(Series::new("low".into(), lows)),
         Column::Series(Series::new("close".into(), closes)),
         Column::Series(Series::new("volume".into(), volumes)),
+        Column::Series(Series::new("quote_volume".into(), quote_volumes)),
+        Column::Series(Series::new("trade_count".into(), trade_counts)),
+        Column::Series(Series::new("taker_buy_base_volume".into(), taker_buy_base)),
+        Column::Series(Series::new(
+            "taker_buy_quote_volume".into(),
+            taker_buy_quote,
+        )),
     ])
     .context("Failed to create DataFrame")?;
 
@@ -32,60 +43,293 @@ pub fn save_to_parquet(data: &[OHLCV], path: &Path) -> Result<()> {
 }
 
 pub fn load_from_parquet(path: &Path) -> Result<Vec<OHLCV>> {
-    let file =
-        std::fs::File::open(path).context(format!("Failed to open file: {}", path.display()))?;
+    load_range_from_parquet(path, &DateRange::default())
+}
 
-    let df = ParquetReader::new(file)
-        .finish()
-        .context("Failed to read Parquet file")?;
+/// Load the bars of a parquet file that fall inside `range`
+///
+/// Uses a lazy scan so the timestamp filter is pushed down to the reader and
+/// row groups outside the range are skipped. Only the OHLCV and order-flow
+/// columns are read; files written before the order-flow columns existed load
+/// with those fields set to zero.
+pub fn load_range_from_parquet(path: &Path, range: &DateRange) -> Result<Vec<OHLCV>> {
+    if !path.exists() {
+        anyhow::bail!("Failed to open file: {}", path.display());
+    }
+
+    let mut lf = LazyFrame::scan_parquet(path, ScanArgsParquet::default())
+        .context(format!("Failed to open file: {}", path.display()))?;
+
+    let schema = lf
+        .collect_schema()
+        .context("Failed to read Parquet schema")?;
+    let has_order_flow = ORDER_FLOW_COLUMNS.iter().all(|name| schema.contains(name));
+
+    let mut columns = vec![
+        col("timestamp"),
+        col("open"),
+        col("high"),
+        col("low"),
+        col("close"),
+        col("volume"),
+    ];
+    if has_order_flow {
+        columns.extend(ORDER_FLOW_COLUMNS.iter().map(|name| col(*name)));
+    }
+    lf = lf.select(columns);
+
+    if let Some(from) = range.from {
+        lf = lf.filter(col("timestamp").gt_eq(lit(from)));
+    }
+    if let Some(to) = range.to {
+        lf = lf.filter(col("timestamp").lt(lit(to)));
+    }
+
+    let df = lf.collect().context("Failed to read Parquet file")?;
 
     let timestamps = df
         .column("timestamp")
         .context("Missing timestamp column")?
+        .rechunk();
+    let timestamps = timestamps
         .i64()
-        .context("Invalid timestamp type")?;
+        .context("Invalid timestamp type")?
+        .cont_slice()
+        .context("Missing timestamp")?;
 
-    let opens = df
-        .column("open")
-        .context("Missing open column")?
-        .f64()
-        .context("Invalid open type")?;
+    let opens = f64_column(&df, "open")?;
+    let highs = f64_column(&df, "high")?;
+    let lows = f64_column(&df, "low")?;
+    let closes = f64_column(&df, "close")?;
+    let volumes = f64_column(&df, "volume")?;
 
-    let highs = df
-        .column("high")
-        .context("Missing high column")?
-        .f64()
-        .context("Invalid high type")?;
+    let mut data: Vec<OHLCV> = timestamps
+        .iter()
+        .enumerate()
+        .map(|(i, &timestamp)| {
+            OHLCV::new(
+                timestamp, opens[i], highs[i], lows[i], closes[i], volumes[i],
+            )
+        })
+        .collect();
 
-    let lows = df
-        .column("low")
-        .context("Missing low column")?
-        .f64()
-        .context("Invalid low type")?;
+    if has_order_flow {
+        let quote_volumes = f64_column(&df, "quote_volume")?;
+        let trade_counts = df
+            .column("trade_count")
+            .context("Missing trade_count column")?
+            .cast(&DataType::UInt64)
+            .context("Invalid trade_count type")?;
+        let trade_counts = trade_counts.u64().context("Invalid trade_count type")?;
+        let taker_buy_base = f64_column(&df, "taker_buy_base_volume")?;
+        let taker_buy_quote = f64_column(&df, "taker_buy_quote_volume")?;
 
-    let closes = df
-        .column("close")
-        .context("Missing close column")?
-        .f64()
-        .context("Invalid close type")?;
+        for (i, bar) in data.iter_mut().enumerate() {
+            bar.quote_volume = quote_volumes[i];
+            bar.trade_count = trade_counts.get(i).unwrap_or(0);
+            bar.taker_buy_base_volume = taker_buy_base[i];
+            bar.taker_buy_quote_volume = taker_buy_quote[i];
+        }
+    }
+
+    Ok(data)
+}
+
+/// Columns added in v0.6 to carry Binance kline order-flow fields
+const ORDER_FLOW_COLUMNS: [&str; 4] = [
+    "quote_volume",
+    "trade_count",
+    "taker_buy_base_volume",
+    "taker_buy_quote_volume",
+];
 
-    let volumes = df
-        .column("volume")
-        .context("Missing volume column")?
+fn f64_column(df: &DataFrame, name: &str) -> Result<Vec<f64>> {
+    let column = df
+        .column(name)
+        .context(format!("Missing {} column", name))?
+        .rechunk();
+
+    let values = column
         .f64()
-        .context("Invalid volume type")?;
+        .context(format!("Invalid {} type", name))?
+        .cont_slice()
+        .context(format!("Missing {}", name))?;
+
+    Ok(values.to_vec())
+}
+
+pub fn save_trades_to_parquet(trades: &[AggTrade], path: &Path) -> Result<()> {
+    let ids: Vec<i64> = trades.iter().map(|t| t.agg_trade_id).collect();
+    let prices: Vec<f64> = trades.iter().map(|t| t.price).collect();
+    let quantities: Vec<f64> = trades.iter().map(|t| t.quantity).collect();
+    let first_ids: Vec<i64> = trades.iter().map(|t| t.first_trade_id).collect();
+    let last_ids: Vec<i64> = trades.iter().map(|t| t.last_trade_id).collect();
+    let timestamps: Vec<i64> = trades.iter().map(|t| t.timestamp).collect();
+    let buyer_maker: Vec<bool> = trades.iter().map(|t| t.is_buyer_maker).collect();
 
-    let mut data = Vec::new();
+    let mut df = DataFrame::new(vec![
+        Column::Series(Series::new("agg_trade_id".into(), ids)),
+        Column::Series(Series::new("price".into(), prices)),
+        Column::Series(Series::new("quantity".into(), quantities)),
+        Column::Series(Series::new("first_trade_id".into(), first_ids)),
+        Column::Series(Series::new("last_trade_id".into(), last_ids)),
+        Column::Series(Series::new("timestamp".into(), timestamps)),
+        Column::Series(Series::new("is_buyer_maker".into(), buyer_maker)),
+    ])
+    .context("Failed to create DataFrame")?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut file = std::fs::File::create(path)?;
+    ParquetWriter::new(&mut file).finish(&mut df)?;
+
+    Ok(())
+}
+
+pub fn load_trades_from_parquet(path: &Path) -> Result<Vec<AggTrade>> {
+    let file =
+        std::fs::File::open(path).context(format!("Failed to open file: {}", path.display()))?;
+
+    let df = ParquetReader::new(file)
+        .finish()
+        .context("Failed to read Parquet file")?;
+
+    let ids = i64_column(&df, "agg_trade_id")?;
+    let prices = f64_column(&df, "price")?;
+    let quantities = f64_column(&df, "quantity")?;
+    let first_ids = i64_column(&df, "first_trade_id")?;
+    let last_ids = i64_column(&df, "last_trade_id")?;
+    let timestamps = i64_column(&df, "timestamp")?;
+    let buyer_maker = df
+        .column("is_buyer_maker")
+        .context("Missing is_buyer_maker column")?
+        .bool()
+        .context("Invalid is_buyer_maker type")?;
+
+    let mut trades = Vec::with_capacity(df.height());
     for i in 0..df.height() {
-        data.push(OHLCV::new(
-            timestamps.get(i).context("Missing timestamp")?,
-            opens.get(i).context("Missing open")?,
-            highs.get(i).context("Missing high")?,
-            lows.get(i).context("Missing low")?,
-            closes.get(i).context("Missing close")?,
-            volumes.get(i).context("Missing volume")?,
-        ));
+        trades.push(AggTrade {
+            agg_trade_id: ids[i],
+            price: prices[i],
+            quantity: quantities[i],
+            first_trade_id: first_ids[i],
+            last_trade_id: last_ids[i],
+            timestamp: timestamps[i],
+            is_buyer_maker: buyer_maker.get(i).context("Missing is_buyer_maker")?,
+        });
     }
 
-    Ok(data)
+    Ok(trades)
+}
+
+fn i64_column(df: &DataFrame, name: &str) -> Result<Vec<i64>> {
+    let column = df
+        .column(name)
+        .context(format!("Missing {} column", name))?
+        .rechunk();
+
+    let values = column
+        .i64()
+        .context(format!("Invalid {} type", name))?
+        .cont_slice()
+        .context(format!("Missing {}", name))?;
+
+    Ok(values.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_date_range() {
+        let path = std::env::temp_dir().join("strataquant_storage_range_test.parquet");
+        let day = 86_400_000;
+        let data: Vec<OHLCV> = (0..10)
+            .map(|i| OHLCV::new(i * day, 1.0, 2.0, 0.5, 1.5, 100.0))
+            .collect();
+        save_to_parquet(&data, &path).unwrap();
+
+        assert_eq!(load_from_parquet(&path).unwrap().len(), 10);
+
+        let range = DateRange::new(Some(3 * day), Some(7 * day));
+        let subset = load_range_from_parquet(&path, &range).unwrap();
+        assert_eq!(subset.len(), 4);
+        assert_eq!(subset[0].timestamp, 3 * day);
+        assert_eq!(subset[3].timestamp, 6 * day);
+
+        let open_ended = DateRange::new(Some(8 * day), None);
+        assert_eq!(
+            load_range_from_parquet(&path, &open_ended).unwrap().len(),
+            2
+        );
+
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_order_flow_roundtrip_and_legacy_files() {
+        let path = std::env::temp_dir().join("strataquant_storage_flow_test.parquet");
+        let data =
+            vec![OHLCV::new(0, 1.0, 2.0, 0.5, 1.5, 10.0).with_order_flow(15.0, 42, 6.0, 9.0)];
+        save_to_parquet(&data, &path).unwrap();
+
+        let loaded = load_from_parquet(&path).unwrap();
+        assert_eq!(loaded[0].trade_count, 42);
+        assert_eq!(loaded[0].taker_buy_base_volume, 6.0);
+        assert_eq!(loaded[0].taker_buy_ratio(), Some(0.6));
+
+        // Files written before v0.6 only have the six OHLCV columns
+        let mut legacy = DataFrame::new(vec![
+            Column::Series(Series::new("timestamp".into(), vec![0i64])),
+            Column::Series(Series::new("open".into(), vec![1.0])),
+            Column::Series(Series::new("high".into(), vec![2.0])),
+            Column::Series(Series::new("low".into(), vec![0.5])),
+            Column::Series(Series::new("close".into(), vec![1.5])),
+            Column::Series(Series::new("volume".into(), vec![10.0])),
+        ])
+        .unwrap();
+        let mut file = std::fs::File::create(&path).unwrap();
+        ParquetWriter::new(&mut file).finish(&mut legacy).unwrap();
+
+        let loaded = load_from_parquet(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(loaded[0].close, 1.5);
+        assert_eq!(loaded[0].trade_count, 0);
+        assert_eq!(loaded[0].taker_buy_ratio(), None);
+    }
+
+    #[test]
+    fn test_trades_roundtrip() {
+        let path = std::env::temp_dir().join("strataquant_storage_trades_test.parquet");
+        let trades = vec![
+            AggTrade {
+                agg_trade_id: 1,
+                price: 42000.5,
+                quantity: 0.25,
+                first_trade_id: 10,
+                last_trade_id: 12,
+                timestamp: 1_700_000_000_000,
+                is_buyer_maker: true,
+            },
+            AggTrade {
+                agg_trade_id: 2,
+                price: 42001.0,
+                quantity: 1.0,
+                first_trade_id: 13,
+                last_trade_id: 13,
+                timestamp: 1_700_000_000_500,
+                is_buyer_maker: false,
+            },
+        ];
+
+        save_trades_to_parquet(&trades, &path).unwrap();
+        let loaded = load_trades_from_parquet(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(loaded, trades);
+    }
 }
@@ -0,0 +1,527 @@
+use crate::data::bars::interval_to_millis;
+use crate::data::binance::BinanceDownloader;
+use crate::data::types::OHLCV;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+#[derive(Debug, Deserialize)]
+struct KlineEvent {
+    #[serde(rename = "e")]
+    event_type: String,
+    #[serde(rename = "k")]
+    kline: KlinePayload,
+}
+
+#[derive(Debug, Deserialize)]
+struct CombinedStreamEvent {
+    data: KlineEvent,
+}
+
+#[derive(Debug, Deserialize)]
+struct KlinePayload {
+    #[serde(rename = "t")]
+    open_time: i64,
+    #[serde(rename = "o")]
+    open: String,
+    #[serde(rename = "h")]
+    high: String,
+    #[serde(rename = "l")]
+    low: String,
+    #[serde(rename = "c")]
+    close: String,
+    #[serde(rename = "v")]
+    volume: String,
+    #[serde(rename = "n")]
+    trade_count: u64,
+    #[serde(rename = "x")]
+    is_closed: bool,
+    #[serde(rename = "q")]
+    quote_volume: String,
+    #[serde(rename = "V")]
+    taker_buy_base_volume: String,
+    #[serde(rename = "Q")]
+    taker_buy_quote_volume: String,
+}
+
+/// A kline update decoded from a Binance-format WebSocket message
+#[derive(Debug, Clone)]
+pub struct KlineUpdate {
+    pub bar: OHLCV,
+    /// True once the bar's interval has ended and its values are final
+    pub is_closed: bool,
+}
+
+/// Decode a raw or combined-stream Binance kline message
+///
+/// Returns `Ok(None)` for other message types such as subscription acks.
+pub fn parse_kline_message(text: &str) -> Result<Option<KlineUpdate>> {
+    let value: serde_json::Value = serde_json::from_str(text).context("Invalid JSON message")?;
+
+    let event: KlineEvent = if value.get("data").is_some() {
+        serde_json::from_value::<CombinedStreamEvent>(value)
+            .context("Invalid combined stream message")?
+            .data
+    } else if value.get("e").is_some() {
+        serde_json::from_value(value).context("Invalid kline message")?
+    } else {
+        return Ok(None);
+    };
+
+    if event.event_type != "kline" {
+        return Ok(None);
+    }
+
+    let k = event.kline;
+    let bar = OHLCV::new(
+        k.open_time,
+        k.open.parse().context("Failed to parse open price")?,
+        k.high.parse().context("Failed to parse high price")?,
+        k.low.parse().context("Failed to parse low price")?,
+        k.close.parse().context("Failed to parse close price")?,
+        k.volume.parse().context("Failed to parse volume")?,
+    )
+    .with_order_flow(
+        k.quote_volume
+            .parse()
+            .context("Failed to parse quote volume")?,
+        k.trade_count,
+        k.taker_buy_base_volume
+            .parse()
+            .context("Failed to parse taker buy base volume")?,
+        k.taker_buy_quote_volume
+            .parse()
+            .context("Failed to parse taker buy quote volume")?,
+    );
+
+    Ok(Some(KlineUpdate {
+        bar,
+        is_closed: k.is_closed,
+    }))
+}
+
+/// Settings for a streaming kline connection
+#[derive(Debug, Clone)]
+pub struct KlineStreamConfig {
+    /// WebSocket endpoint, e.g. `wss://stream.binance.us:9443/ws`
+    pub url: String,
+    pub symbol: String,
+    pub interval: String,
+    /// Wait between reconnect attempts
+    pub reconnect_delay: Duration,
+    /// Give up after this many consecutive failed connections (0 = never)
+    pub max_reconnects: usize,
+}
+
+impl KlineStreamConfig {
+    pub fn new(url: &str, symbol: &str, interval: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            symbol: symbol.to_string(),
+            interval: interval.to_string(),
+            reconnect_delay: Duration::from_secs(5),
+            max_reconnects: 0,
+        }
+    }
+
+    /// Public Binance.US kline stream
+    pub fn binance(symbol: &str, interval: &str) -> Self {
+        Self::new("wss://stream.binance.us:9443/ws", symbol, interval)
+    }
+
+    fn stream_name(&self) -> String {
+        format!("{}@kline_{}", self.symbol.to_lowercase(), self.interval)
+    }
+
+    fn subscribe_message(&self) -> String {
+        serde_json::json!({
+            "method": "SUBSCRIBE",
+            "params": [self.stream_name()],
+            "id": 1,
+        })
+        .to_string()
+    }
+}
+
+/// Streams closed bars from a kline WebSocket on a background thread
+///
+/// The connection is re-established and the subscription re-sent whenever it
+/// drops. If bars were missed in between, they are fetched through the REST
+/// downloader (when one is configured) and delivered in order before the next
+/// live bar, so consumers see a gap-free series.
+pub struct KlineStream {
+    receiver: Receiver<OHLCV>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Result<()>>>,
+}
+
+impl KlineStream {
+    /// Connect and start streaming; `last_timestamp` is the open time of the
+    /// last bar the consumer already has, used to detect and fill gaps
+    pub fn spawn(
+        config: KlineStreamConfig,
+        backfill: Option<BinanceDownloader>,
+        last_timestamp: Option<i64>,
+    ) -> Result<Self> {
+        let interval_ms = interval_to_millis(&config.interval)?;
+        let (sender, receiver) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let worker = StreamWorker {
+            config,
+            backfill,
+            interval_ms,
+            last_timestamp,
+            sender,
+            stop: Arc::clone(&stop),
+        };
+        let handle = std::thread::spawn(move || worker.run());
+
+        Ok(Self {
+            receiver,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// Closed bars in timestamp order
+    pub fn receiver(&self) -> &Receiver<OHLCV> {
+        &self.receiver
+    }
+
+    /// Wait for the next closed bar
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<OHLCV> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+
+    /// Stop the background thread and return its final status
+    pub fn stop(mut self) -> Result<()> {
+        self.stop.store(true, Ordering::SeqCst);
+        match self.handle.take() {
+            Some(handle) => handle
+                .join()
+                .map_err(|_| anyhow::anyhow!("Stream thread panicked"))?,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for KlineStream {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+struct StreamWorker {
+    config: KlineStreamConfig,
+    backfill: Option<BinanceDownloader>,
+    interval_ms: i64,
+    last_timestamp: Option<i64>,
+    sender: Sender<OHLCV>,
+    stop: Arc<AtomicBool>,
+}
+
+impl StreamWorker {
+    fn run(mut self) -> Result<()> {
+        let mut failures = 0;
+
+        while !self.stopped() {
+            match self.connect() {
+                Ok(mut socket) => {
+                    failures = 0;
+                    match self.read_loop(&mut socket) {
+                        Ok(()) => return Ok(()),
+                        Err(e) => println!("Stream disconnected: {:#}", e),
+                    }
+                }
+                Err(e) => {
+                    failures += 1;
+                    println!("Stream connection failed: {:#}", e);
+                    if self.config.max_reconnects > 0 && failures >= self.config.max_reconnects {
+                        bail!("Giving up after {} failed connections", failures);
+                    }
+                }
+            }
+
+            if self.stopped() {
+                break;
+            }
+            std::thread::sleep(self.config.reconnect_delay);
+        }
+
+        Ok(())
+    }
+
+    fn stopped(&self) -> bool {
+        self.stop.load(Ordering::SeqCst)
+    }
+
+    fn connect(&self) -> Result<WebSocket<MaybeTlsStream<TcpStream>>> {
+        let (mut socket, _) =
+            tungstenite::connect(self.config.url.as_str()).context("WebSocket connect failed")?;
+
+        // Periodic read timeouts let the worker notice stop requests
+        let timeout = Some(Duration::from_millis(250));
+        match socket.get_mut() {
+            MaybeTlsStream::Plain(stream) => stream.set_read_timeout(timeout)?,
+            MaybeTlsStream::NativeTls(stream) => stream.get_mut().set_read_timeout(timeout)?,
+            _ => {}
+        }
+
+        socket
+            .send(Message::Text(self.config.subscribe_message()))
+            .context("Failed to subscribe")?;
+
+        Ok(socket)
+    }
+
+    /// Returns `Ok(())` when stopped or the consumer went away, `Err` on disconnect
+    fn read_loop(&mut self, socket: &mut WebSocket<MaybeTlsStream<TcpStream>>) -> Result<()> {
+        loop {
+            if self.stopped() {
+                socket.close(None).ok();
+                return Ok(());
+            }
+
+            let message = match socket.read() {
+                Ok(m) => m,
+                Err(tungstenite::Error::Io(e))
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            let text = match message {
+                Message::Text(text) => text,
+                Message::Close(_) => bail!("Server closed the connection"),
+                _ => continue,
+            };
+
+            let update = match parse_kline_message(&text) {
+                Ok(Some(u)) if u.is_closed => u,
+                Ok(_) => continue,
+                Err(e) => {
+                    println!("Ignoring malformed message: {:#}", e);
+                    continue;
+                }
+            };
+
+            if !self.deliver(update.bar) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Send a closed bar, backfilling any missed bars first
+    ///
+    /// Returns false when the consumer has dropped the receiver.
+    fn deliver(&mut self, bar: OHLCV) -> bool {
+        if let Some(last) = self.last_timestamp {
+            if bar.timestamp <= last {
+                return true;
+            }
+
+            if bar.timestamp > last + self.interval_ms {
+                for missing in self.fetch_gap(last + self.interval_ms, bar.timestamp) {
+                    if self.sender.send(missing.clone()).is_err() {
+                        return false;
+                    }
+                    self.last_timestamp = Some(missing.timestamp);
+                }
+            }
+        }
+
+        self.last_timestamp = Some(bar.timestamp);
+        self.sender.send(bar).is_ok()
+    }
+
+    /// Bars with open time in `[from, to)` from the REST API
+    fn fetch_gap(&self, from: i64, to: i64) -> Vec<OHLCV> {
+        let downloader = match &self.backfill {
+            Some(d) => d,
+            None => {
+                println!(
+                    "Missed bars from {} to {} (no backfill configured)",
+                    from, to
+                );
+                return Vec::new();
+            }
+        };
+
+        let (Some(start), Some(end)) = (
+            chrono::DateTime::from_timestamp_millis(from),
+            chrono::DateTime::from_timestamp_millis(to - 1),
+        ) else {
+            return Vec::new();
+        };
+
+        match downloader.fetch_range(start, end) {
+            Ok(bars) => bars
+                .into_iter()
+                .filter(|b| b.timestamp >= from && b.timestamp < to)
+                .collect(),
+            Err(e) => {
+                println!("Backfill failed: {:#}", e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Replay stored bars through a channel as if they were arriving live
+///
+/// Bars are spaced `interval_ms / speed` apart in wall-clock time; a `speed`
+/// of zero sends them as fast as the consumer takes them.
+pub fn replay_bars(bars: Vec<OHLCV>, interval_ms: i64, speed: f64) -> Receiver<OHLCV> {
+    let (sender, receiver) = mpsc::channel();
+    let delay = if speed > 0.0 {
+        Some(Duration::from_secs_f64(interval_ms as f64 / 1000.0 / speed))
+    } else {
+        None
+    };
+
+    std::thread::spawn(move || {
+        for bar in bars {
+            if sender.send(bar).is_err() {
+                break;
+            }
+            if let Some(delay) = delay {
+                std::thread::sleep(delay);
+            }
+        }
+    });
+
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    fn kline_json(open_time: i64, close: f64, is_closed: bool) -> String {
+        format!(
+            r#"{{"e":"kline","E":{e},"s":"BTCUSDT","k":{{"t":{t},"T":{ct},"s":"BTCUSDT","i":"1m","o":"100.0","c":"{c}","h":"{c}","l":"99.0","v":"5.0","n":12,"x":{x},"q":"500.0","V":"3.0","Q":"300.0","B":"0"}}}}"#,
+            e = open_time + 1,
+            t = open_time,
+            ct = open_time + 59_999,
+            c = close,
+            x = is_closed
+        )
+    }
+
+    /// REST stub answering every klines request with one fixed bar
+    fn serve_rest_stub(body: String) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                    line.clear();
+                }
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        address
+    }
+
+    #[test]
+    fn test_parse_kline_message() {
+        let update = parse_kline_message(&kline_json(60_000, 101.5, true))
+            .unwrap()
+            .unwrap();
+        assert!(update.is_closed);
+        assert_eq!(update.bar.timestamp, 60_000);
+        assert_eq!(update.bar.close, 101.5);
+        assert_eq!(update.bar.trade_count, 12);
+        assert_eq!(update.bar.taker_buy_base_volume, 3.0);
+
+        let combined = format!(
+            r#"{{"stream":"btcusdt@kline_1m","data":{}}}"#,
+            kline_json(0, 100.0, false)
+        );
+        assert!(!parse_kline_message(&combined).unwrap().unwrap().is_closed);
+
+        assert!(parse_kline_message(r#"{"result":null,"id":1}"#)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_stream_reconnects_and_backfills_gap() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let ws_url = format!("ws://{}", listener.local_addr().unwrap());
+
+        let (subscriptions_tx, subscriptions_rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            // First session: one forming and one closed bar, then drop
+            let (stream, _) = listener.accept().unwrap();
+            let mut ws = tungstenite::accept(stream).unwrap();
+            subscriptions_tx
+                .send(ws.read().unwrap().to_string())
+                .unwrap();
+            ws.send(Message::Text(kline_json(0, 100.0, false))).unwrap();
+            ws.send(Message::Text(kline_json(0, 101.0, true))).unwrap();
+            drop(ws);
+
+            // Second session skips the bar at 60_000
+            let (stream, _) = listener.accept().unwrap();
+            let mut ws = tungstenite::accept(stream).unwrap();
+            subscriptions_tx
+                .send(ws.read().unwrap().to_string())
+                .unwrap();
+            ws.send(Message::Text(kline_json(120_000, 103.0, true)))
+                .unwrap();
+            std::thread::sleep(Duration::from_secs(2));
+        });
+
+        let rest_body =
+            r#"[[60000,"101.0","102.5","100.5","102.0","4.0",119999,"400.0",8,"2.0","200.0","0"]]"#;
+        let rest_url = serve_rest_stub(rest_body.to_string());
+        let backfill = BinanceDownloader::new("BTCUSDT", "1m")
+            .with_base_url(&rest_url)
+            .with_request_delay(Duration::ZERO);
+
+        let mut config = KlineStreamConfig::new(&ws_url, "BTCUSDT", "1m");
+        config.reconnect_delay = Duration::from_millis(50);
+        let stream = KlineStream::spawn(config, Some(backfill), None).unwrap();
+
+        let timeout = Duration::from_secs(10);
+        let closes: Vec<(i64, f64)> = (0..3)
+            .map(|_| stream.recv_timeout(timeout).expect("missing bar"))
+            .map(|b| (b.timestamp, b.close))
+            .collect();
+        assert_eq!(closes, vec![(0, 101.0), (60_000, 102.0), (120_000, 103.0)]);
+
+        for _ in 0..2 {
+            let subscribe = subscriptions_rx.recv_timeout(timeout).unwrap();
+            assert!(subscribe.contains("SUBSCRIBE"));
+            assert!(subscribe.contains("btcusdt@kline_1m"));
+        }
+
+        stream.stop().unwrap();
+    }
+}
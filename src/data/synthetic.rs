@@ -0,0 +1,487 @@
+use crate::data::types::OHLCV;
+use crate::metrics::MS_PER_YEAR;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Small deterministic PRNG (SplitMix64)
+///
+/// Synthetic series must be reproducible from a seed across platforms and
+/// dependency upgrades, so the generator is kept in-crate.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform sample in [0, 1)
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform index in [0, n)
+    pub fn index(&mut self, n: usize) -> usize {
+        (self.uniform() * n as f64) as usize % n.max(1)
+    }
+
+    /// Standard normal sample (Box-Muller)
+    pub fn normal(&mut self) -> f64 {
+        let u1 = 1.0 - self.uniform();
+        let u2 = self.uniform();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    /// Poisson sample (Knuth), suitable for small rates
+    pub fn poisson(&mut self, lambda: f64) -> u32 {
+        let limit = (-lambda).exp();
+        let mut k = 0;
+        let mut p = self.uniform();
+        while p > limit {
+            k += 1;
+            p *= self.uniform();
+        }
+        k
+    }
+}
+
+/// Shape of the generated series shared by all models
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyntheticConfig {
+    pub bars: usize,
+    pub start_price: f64,
+    pub start_timestamp: i64,
+    pub interval_ms: i64,
+    pub seed: u64,
+}
+
+impl SyntheticConfig {
+    pub fn new(bars: usize, seed: u64) -> Self {
+        Self {
+            bars,
+            seed,
+            ..Self::default()
+        }
+    }
+
+    /// Fraction of a year covered by one bar
+    fn dt(&self) -> f64 {
+        self.interval_ms as f64 / MS_PER_YEAR
+    }
+}
+
+impl Default for SyntheticConfig {
+    fn default() -> Self {
+        Self {
+            bars: 2000,
+            start_price: 10_000.0,
+            start_timestamp: 1_577_836_800_000, // 2020-01-01
+            interval_ms: 86_400_000,
+            seed: 42,
+        }
+    }
+}
+
+/// Parametric price process models
+///
+/// Drift and volatility are annualized; they are scaled to the bar interval.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SyntheticModel {
+    /// Geometric Brownian motion
+    Gbm { drift: f64, volatility: f64 },
+
+    /// GARCH(1,1) volatility clustering around a long-run volatility
+    Garch {
+        drift: f64,
+        volatility: f64,
+        alpha: f64,
+        beta: f64,
+    },
+
+    /// Merton jump-diffusion; negative `jump_mean` models crash events
+    JumpDiffusion {
+        drift: f64,
+        volatility: f64,
+        /// Expected jumps per year
+        jump_intensity: f64,
+        /// Mean log jump size
+        jump_mean: f64,
+        jump_std: f64,
+    },
+
+    /// Two-state Markov switching between a trending and a mean-reverting range regime
+    RegimeSwitching {
+        trend_drift: f64,
+        trend_volatility: f64,
+        range_volatility: f64,
+        /// Pull back towards the range anchor per year (mean reversion speed)
+        range_reversion: f64,
+        /// Probability of switching regime on any bar
+        switch_probability: f64,
+    },
+}
+
+impl SyntheticModel {
+    pub fn gbm() -> Self {
+        SyntheticModel::Gbm {
+            drift: 0.3,
+            volatility: 0.7,
+        }
+    }
+
+    pub fn garch() -> Self {
+        SyntheticModel::Garch {
+            drift: 0.3,
+            volatility: 0.7,
+            alpha: 0.10,
+            beta: 0.85,
+        }
+    }
+
+    pub fn jump_diffusion() -> Self {
+        SyntheticModel::JumpDiffusion {
+            drift: 0.3,
+            volatility: 0.6,
+            jump_intensity: 2.0,
+            jump_mean: -0.15,
+            jump_std: 0.08,
+        }
+    }
+
+    pub fn regime_switching() -> Self {
+        SyntheticModel::RegimeSwitching {
+            trend_drift: 1.0,
+            trend_volatility: 0.6,
+            range_volatility: 0.5,
+            range_reversion: 20.0,
+            switch_probability: 0.01,
+        }
+    }
+
+    /// Look up a model with its default parameters by CLI name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "gbm" => Some(Self::gbm()),
+            "garch" => Some(Self::garch()),
+            "jump" => Some(Self::jump_diffusion()),
+            "regime" => Some(Self::regime_switching()),
+            _ => None,
+        }
+    }
+
+    /// Override the annualized drift (the trend regime's drift for regime switching)
+    pub fn with_drift(mut self, value: f64) -> Self {
+        match &mut self {
+            SyntheticModel::Gbm { drift, .. }
+            | SyntheticModel::Garch { drift, .. }
+            | SyntheticModel::JumpDiffusion { drift, .. } => *drift = value,
+            SyntheticModel::RegimeSwitching { trend_drift, .. } => *trend_drift = value,
+        }
+        self
+    }
+
+    /// Override the annualized diffusion volatility (both regimes for regime switching)
+    pub fn with_volatility(mut self, value: f64) -> Self {
+        match &mut self {
+            SyntheticModel::Gbm { volatility, .. }
+            | SyntheticModel::Garch { volatility, .. }
+            | SyntheticModel::JumpDiffusion { volatility, .. } => *volatility = value,
+            SyntheticModel::RegimeSwitching {
+                trend_volatility,
+                range_volatility,
+                ..
+            } => {
+                *trend_volatility = value;
+                *range_volatility = value;
+            }
+        }
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            SyntheticModel::Gbm { .. } => "gbm",
+            SyntheticModel::Garch { .. } => "garch",
+            SyntheticModel::JumpDiffusion { .. } => "jump",
+            SyntheticModel::RegimeSwitching { .. } => "regime",
+        }
+    }
+
+    /// Generate a bar series from this model
+    pub fn generate(&self, config: &SyntheticConfig) -> Vec<OHLCV> {
+        let mut rng = SeededRng::new(config.seed);
+        let dt = config.dt();
+        let mut log_returns = Vec::with_capacity(config.bars);
+        let mut bar_vols = Vec::with_capacity(config.bars);
+
+        match self {
+            SyntheticModel::Gbm { drift, volatility } => {
+                let sigma = volatility * dt.sqrt();
+                for _ in 0..config.bars {
+                    log_returns
+                        .push((drift - 0.5 * volatility.powi(2)) * dt + sigma * rng.normal());
+                    bar_vols.push(sigma);
+                }
+            }
+
+            SyntheticModel::Garch {
+                drift,
+                volatility,
+                alpha,
+                beta,
+            } => {
+                let long_run_var = volatility.powi(2) * dt;
+                let omega = long_run_var * (1.0 - alpha - beta).max(1e-6);
+                let mut variance = long_run_var;
+                let mut shock: f64 = 0.0;
+
+                for _ in 0..config.bars {
+                    variance = omega + alpha * shock.powi(2) + beta * variance;
+                    let sigma = variance.sqrt();
+                    shock = sigma * rng.normal();
+                    log_returns.push((drift - 0.5 * volatility.powi(2)) * dt + shock);
+                    bar_vols.push(sigma);
+                }
+            }
+
+            SyntheticModel::JumpDiffusion {
+                drift,
+                volatility,
+                jump_intensity,
+                jump_mean,
+                jump_std,
+            } => {
+                let sigma = volatility * dt.sqrt();
+                for _ in 0..config.bars {
+                    let mut r = (drift - 0.5 * volatility.powi(2)) * dt + sigma * rng.normal();
+                    for _ in 0..rng.poisson(jump_intensity * dt) {
+                        r += jump_mean + jump_std * rng.normal();
+                    }
+                    log_returns.push(r);
+                    bar_vols.push(sigma);
+                }
+            }
+
+            SyntheticModel::RegimeSwitching {
+                trend_drift,
+                trend_volatility,
+                range_volatility,
+                range_reversion,
+                switch_probability,
+            } => {
+                let mut trending = true;
+                let mut log_price = config.start_price.ln();
+                let mut anchor = log_price;
+
+                for _ in 0..config.bars {
+                    if rng.uniform() < *switch_probability {
+                        trending = !trending;
+                        anchor = log_price;
+                    }
+
+                    let (r, sigma) = if trending {
+                        let sigma = trend_volatility * dt.sqrt();
+                        (
+                            (trend_drift - 0.5 * trend_volatility.powi(2)) * dt
+                                + sigma * rng.normal(),
+                            sigma,
+                        )
+                    } else {
+                        let sigma = range_volatility * dt.sqrt();
+                        let pull = (range_reversion * dt).min(1.0) * (anchor - log_price);
+                        (pull + sigma * rng.normal(), sigma)
+                    };
+
+                    log_price += r;
+                    log_returns.push(r);
+                    bar_vols.push(sigma);
+                }
+            }
+        }
+
+        bars_from_log_returns(config, &log_returns, &bar_vols, &mut rng)
+    }
+}
+
+/// Turn a close-to-close log return path into OHLCV bars with plausible
+/// intrabar ranges and volume
+fn bars_from_log_returns(
+    config: &SyntheticConfig,
+    log_returns: &[f64],
+    bar_vols: &[f64],
+    rng: &mut SeededRng,
+) -> Vec<OHLCV> {
+    let mut data = Vec::with_capacity(log_returns.len());
+    let mut prev_close = config.start_price;
+
+    for (i, (&r, &sigma)) in log_returns.iter().zip(bar_vols).enumerate() {
+        let open = prev_close;
+        let close = open * r.exp();
+        let wick_up = (0.5 * sigma * rng.normal().abs()).exp();
+        let wick_down = (0.5 * sigma * rng.normal().abs()).exp();
+        let high = open.max(close) * wick_up;
+        let low = open.min(close) / wick_down;
+
+        // Volume rises with the size of the move relative to typical volatility
+        let surprise = if sigma > 0.0 { r.abs() / sigma } else { 0.0 };
+        let volume = 1000.0 * (1.0 + surprise) * (0.25 * rng.normal()).exp();
+
+        data.push(OHLCV::new(
+            config.start_timestamp + i as i64 * config.interval_ms,
+            open,
+            high,
+            low,
+            close,
+            volume,
+        ));
+        prev_close = close;
+    }
+
+    data
+}
+
+/// Resample real bars in contiguous blocks to build a new series
+///
+/// Each bar is expressed relative to the previous close, so blocks keep the
+/// intrabar shape, short-range autocorrelation and volatility clustering of
+/// the source while the overall path is new. Blocks longer than the source
+/// are shortened to fit.
+pub fn block_bootstrap(
+    source: &[OHLCV],
+    block_size: usize,
+    config: &SyntheticConfig,
+) -> Result<Vec<OHLCV>> {
+    if source.len() < 2 {
+        bail!("Block bootstrap needs at least two source bars");
+    }
+    if block_size == 0 {
+        bail!("Block size must be at least one bar");
+    }
+    let block_size = block_size.min(source.len() - 1);
+    let mut rng = SeededRng::new(config.seed);
+
+    let mut data = Vec::with_capacity(config.bars);
+    let mut prev_close = config.start_price;
+
+    while data.len() < config.bars {
+        let start = 1 + rng.index(source.len() - block_size);
+        for j in start..start + block_size {
+            if data.len() >= config.bars {
+                break;
+            }
+
+            let base = source[j - 1].close;
+            let bar = &source[j];
+            data.push(OHLCV::new(
+                config.start_timestamp + data.len() as i64 * config.interval_ms,
+                prev_close * bar.open / base,
+                prev_close * bar.high / base,
+                prev_close * bar.low / base,
+                prev_close * bar.close / base,
+                bar.volume,
+            ));
+            prev_close = prev_close * bar.close / base;
+        }
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log_returns(data: &[OHLCV]) -> Vec<f64> {
+        data.windows(2)
+            .map(|w| (w[1].close / w[0].close).ln())
+            .collect()
+    }
+
+    fn std_dev(values: &[f64]) -> f64 {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt()
+    }
+
+    #[test]
+    fn test_seeded_reproducibility() {
+        let config = SyntheticConfig::new(500, 7);
+        for model in [
+            SyntheticModel::gbm(),
+            SyntheticModel::garch(),
+            SyntheticModel::jump_diffusion(),
+            SyntheticModel::regime_switching(),
+        ] {
+            let a = model.generate(&config);
+            let b = model.generate(&config);
+            let c = model.generate(&SyntheticConfig::new(500, 8));
+
+            assert_eq!(a.len(), 500);
+            assert!(a.iter().zip(&b).all(|(x, y)| x.close == y.close));
+            assert!(a.iter().zip(&c).any(|(x, y)| x.close != y.close));
+
+            for bar in &a {
+                assert!(bar.high >= bar.open.max(bar.close));
+                assert!(bar.low <= bar.open.min(bar.close));
+                assert!(bar.low > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_gbm_volatility() {
+        let config = SyntheticConfig::new(20_000, 1);
+        let data = SyntheticModel::Gbm {
+            drift: 0.0,
+            volatility: 0.8,
+        }
+        .generate(&config);
+
+        let annualized = std_dev(&log_returns(&data)) * (365.25f64).sqrt();
+        assert!((annualized - 0.8).abs() < 0.03);
+    }
+
+    #[test]
+    fn test_jump_diffusion_produces_crashes() {
+        let config = SyntheticConfig::new(5000, 3);
+        let data = SyntheticModel::JumpDiffusion {
+            drift: 0.0,
+            volatility: 0.2,
+            jump_intensity: 5.0,
+            jump_mean: -0.3,
+            jump_std: 0.01,
+        }
+        .generate(&config);
+
+        // Daily diffusion moves are ~1%, so anything below -20% is a jump
+        let crashes = log_returns(&data).iter().filter(|&&r| r < -0.2).count();
+        assert!(crashes > 20);
+    }
+
+    #[test]
+    fn test_block_bootstrap_reuses_source_returns() {
+        let source = SyntheticModel::gbm().generate(&SyntheticConfig::new(300, 11));
+        let source_returns = log_returns(&source);
+
+        let config = SyntheticConfig::new(1000, 5);
+        let data = block_bootstrap(&source, 20, &config).unwrap();
+        assert_eq!(data.len(), 1000);
+        assert_eq!(data[0].timestamp, config.start_timestamp);
+
+        for r in log_returns(&data) {
+            assert!(source_returns.iter().any(|s| (s - r).abs() < 1e-9));
+        }
+
+        assert!(block_bootstrap(&source[..1], 1, &config).is_err());
+        assert!(block_bootstrap(&source, 0, &config).is_err());
+    }
+}
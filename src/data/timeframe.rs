@@ -0,0 +1,288 @@
+use crate::data::bars::interval_to_millis;
+use crate::data::catalog::{DataCatalog, DatasetKey};
+use crate::data::types::{DateRange, OHLCV};
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+
+/// Aggregate bars into `interval_ms` buckets aligned to multiples of the
+/// interval since epoch, stamped with the bucket open time
+///
+/// The first and last buckets may cover only part of the interval when the
+/// input does not start or end on a boundary. Bars must be in time order.
+pub fn resample(bars: &[OHLCV], interval_ms: i64) -> Vec<OHLCV> {
+    assert!(interval_ms > 0, "Interval must be greater than 0");
+    let mut result: Vec<OHLCV> = Vec::new();
+
+    for bar in bars {
+        let open_time = bar.timestamp - bar.timestamp.rem_euclid(interval_ms);
+        match result.last_mut() {
+            Some(current) if current.timestamp == open_time => {
+                current.high = current.high.max(bar.high);
+                current.low = current.low.min(bar.low);
+                current.close = bar.close;
+                current.volume += bar.volume;
+                current.quote_volume += bar.quote_volume;
+                current.trade_count += bar.trade_count;
+                current.taker_buy_base_volume += bar.taker_buy_base_volume;
+                current.taker_buy_quote_volume += bar.taker_buy_quote_volume;
+            }
+            _ => {
+                let mut first = bar.clone();
+                first.timestamp = open_time;
+                result.push(first);
+            }
+        }
+    }
+
+    result
+}
+
+/// Bar interval of a series, taken as the smallest gap between timestamps
+pub fn infer_interval(bars: &[OHLCV]) -> Option<i64> {
+    bars.windows(2)
+        .map(|w| w[1].timestamp - w[0].timestamp)
+        .filter(|gap| *gap > 0)
+        .min()
+}
+
+/// Higher-timeframe bars aligned to a base series
+///
+/// A higher-timeframe bar becomes visible at the first base bar that closes
+/// at or after the higher-timeframe bar closes, so a strategy acting on the
+/// close of base bar `i` never sees a bar that is still forming.
+#[derive(Debug, Clone)]
+pub struct AlignedTimeframe {
+    interval: String,
+    bars: Vec<OHLCV>,
+    /// Number of completed higher-timeframe bars as of each base bar
+    visible: Vec<usize>,
+}
+
+impl AlignedTimeframe {
+    pub fn new(
+        interval: &str,
+        base: &[OHLCV],
+        base_interval_ms: i64,
+        bars: Vec<OHLCV>,
+        interval_ms: i64,
+    ) -> Self {
+        let mut visible = Vec::with_capacity(base.len());
+        let mut completed = 0;
+        for bar in base {
+            let base_close = bar.timestamp + base_interval_ms;
+            while completed < bars.len() && bars[completed].timestamp + interval_ms <= base_close {
+                completed += 1;
+            }
+            visible.push(completed);
+        }
+
+        Self {
+            interval: interval.to_string(),
+            bars,
+            visible,
+        }
+    }
+
+    pub fn interval(&self) -> &str {
+        &self.interval
+    }
+
+    /// Every higher-timeframe bar, including ones after the base series ends
+    pub fn bars(&self) -> &[OHLCV] {
+        &self.bars
+    }
+
+    /// Higher-timeframe bars completed by the close of base bar `index`
+    pub fn completed(&self, index: usize) -> &[OHLCV] {
+        &self.bars[..self.visible[index]]
+    }
+
+    /// Most recent completed higher-timeframe bar at base bar `index`
+    pub fn last_completed(&self, index: usize) -> Option<&OHLCV> {
+        self.completed(index).last()
+    }
+
+    /// Map a series computed over `bars()` (e.g. an indicator) onto the base
+    /// bars, using the value of the last completed bar; NaN before the first
+    pub fn align(&self, values: &[f64]) -> Vec<f64> {
+        assert_eq!(
+            values.len(),
+            self.bars.len(),
+            "Series must have one value per higher-timeframe bar"
+        );
+        self.visible
+            .iter()
+            .map(|&n| if n > 0 { values[n - 1] } else { f64::NAN })
+            .collect()
+    }
+}
+
+/// Additional timeframes supplied to a strategy alongside its base bars
+#[derive(Debug, Clone, Default)]
+pub struct TimeframeContext {
+    frames: BTreeMap<String, AlignedTimeframe>,
+}
+
+impl TimeframeContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, frame: AlignedTimeframe) {
+        self.frames.insert(frame.interval.clone(), frame);
+    }
+
+    pub fn get(&self, interval: &str) -> Option<&AlignedTimeframe> {
+        self.frames.get(interval)
+    }
+
+    pub fn contains_all<S: AsRef<str>>(&self, intervals: &[S]) -> bool {
+        intervals
+            .iter()
+            .all(|i| self.frames.contains_key(i.as_ref()))
+    }
+
+    pub fn intervals(&self) -> impl Iterator<Item = &str> {
+        self.frames.keys().map(|k| k.as_str())
+    }
+
+    /// Context built by resampling the base bars; the base interval is
+    /// inferred from the timestamps when not given
+    pub fn resampled<S: AsRef<str>>(
+        base: &[OHLCV],
+        base_interval_ms: Option<i64>,
+        intervals: &[S],
+    ) -> Result<Self> {
+        let base_ms = match base_interval_ms.or_else(|| infer_interval(base)) {
+            Some(ms) => ms,
+            None => bail!("Cannot infer the bar interval of fewer than two bars"),
+        };
+
+        let mut context = Self::new();
+        for interval in intervals {
+            let interval = interval.as_ref();
+            let interval_ms = higher_interval(interval, base_ms)?;
+            let bars = resample(base, interval_ms);
+            context.insert(AlignedTimeframe::new(
+                interval,
+                base,
+                base_ms,
+                bars,
+                interval_ms,
+            ));
+        }
+        Ok(context)
+    }
+
+    /// Context loading each timeframe from the catalog when the dataset exists
+    /// (with its full history before the base series, for indicator warm-up)
+    /// and resampling the base bars otherwise
+    pub fn from_catalog<S: AsRef<str>>(
+        catalog: &DataCatalog,
+        base_key: &DatasetKey,
+        base: &[OHLCV],
+        intervals: &[S],
+    ) -> Result<Self> {
+        let base_ms = interval_to_millis(&base_key.interval)?;
+        let end = base.last().map(|b| b.timestamp + base_ms);
+
+        let mut context = Self::new();
+        for interval in intervals {
+            let interval = interval.as_ref();
+            let interval_ms = higher_interval(interval, base_ms)?;
+            let key = DatasetKey::new(&base_key.exchange, &base_key.symbol, interval);
+
+            let bars = if catalog.exists(&key) {
+                catalog.load_range(&key, &DateRange::new(None, end))?
+            } else {
+                resample(base, interval_ms)
+            };
+            context.insert(AlignedTimeframe::new(
+                interval,
+                base,
+                base_ms,
+                bars,
+                interval_ms,
+            ));
+        }
+        Ok(context)
+    }
+}
+
+fn higher_interval(interval: &str, base_ms: i64) -> Result<i64> {
+    let interval_ms = interval_to_millis(interval)?;
+    if interval_ms < base_ms {
+        bail!(
+            "Timeframe {} is shorter than the base bar interval ({} ms)",
+            interval,
+            base_ms
+        );
+    }
+    Ok(interval_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3_600_000;
+
+    fn hourly(n: usize) -> Vec<OHLCV> {
+        (0..n)
+            .map(|i| {
+                let price = 100.0 + i as f64;
+                OHLCV::new(
+                    i as i64 * HOUR,
+                    price,
+                    price + 0.5,
+                    price - 0.5,
+                    price + 0.25,
+                    1.0,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resample_aggregates_buckets() {
+        let bars = resample(&hourly(10), 4 * HOUR);
+        assert_eq!(bars.len(), 3);
+        assert_eq!(bars[1].timestamp, 4 * HOUR);
+        assert_eq!(bars[1].open, 104.0);
+        assert_eq!(bars[1].high, 107.5);
+        assert_eq!(bars[1].low, 103.5);
+        assert_eq!(bars[1].close, 107.25);
+        assert_eq!(bars[1].volume, 4.0);
+        assert_eq!(bars[2].volume, 2.0);
+        assert_eq!(infer_interval(&hourly(10)), Some(HOUR));
+    }
+
+    #[test]
+    fn test_only_completed_bars_are_visible() {
+        let base = hourly(10);
+        let context = TimeframeContext::resampled(&base, None, &["4h"]).unwrap();
+        let frame = context.get("4h").unwrap();
+
+        // The first 4h bar closes with the hourly bar at index 3
+        assert!(frame.last_completed(2).is_none());
+        assert_eq!(frame.last_completed(3).unwrap().close, 103.25);
+        assert_eq!(frame.last_completed(6).unwrap().timestamp, 0);
+        assert_eq!(frame.last_completed(7).unwrap().timestamp, 4 * HOUR);
+        // The partial last bucket never completes
+        assert_eq!(frame.completed(9).len(), 2);
+
+        let closes: Vec<f64> = frame.bars().iter().map(|b| b.close).collect();
+        let aligned = frame.align(&closes);
+        assert!(aligned[2].is_nan());
+        assert_eq!(aligned[3..8], [103.25, 103.25, 103.25, 103.25, 107.25]);
+
+        // No aligned value may depend on a base bar after it
+        for (i, bar) in base.iter().enumerate() {
+            if let Some(htf) = frame.last_completed(i) {
+                assert!(htf.close <= bar.close);
+            }
+        }
+
+        assert!(TimeframeContext::resampled(&base, None, &["30m"]).is_err());
+    }
+}
@@ -8,6 +8,22 @@ pub struct OHLCV {
     pub low: f64,
     pub close: f64,
     pub volume: f64,
+
+    /// Volume in the quote asset (e.g. USDT); 0.0 when the source lacks it
+    #[serde(default)]
+    pub quote_volume: f64,
+
+    /// Number of trades in the bar; 0 when the source lacks it
+    #[serde(default)]
+    pub trade_count: u64,
+
+    /// Base asset volume bought by takers (aggressive buyers)
+    #[serde(default)]
+    pub taker_buy_base_volume: f64,
+
+    /// Quote asset volume bought by takers
+    #[serde(default)]
+    pub taker_buy_quote_volume: f64,
 }
 
 impl OHLCV {
@@ -19,6 +35,141 @@ impl OHLCV {
             low,
             close,
             volume,
+            quote_volume: 0.0,
+            trade_count: 0,
+            taker_buy_base_volume: 0.0,
+            taker_buy_quote_volume: 0.0,
         }
     }
+
+    /// Attach the order-flow fields Binance reports with each kline
+    pub fn with_order_flow(
+        mut self,
+        quote_volume: f64,
+        trade_count: u64,
+        taker_buy_base_volume: f64,
+        taker_buy_quote_volume: f64,
+    ) -> Self {
+        self.quote_volume = quote_volume;
+        self.trade_count = trade_count;
+        self.taker_buy_base_volume = taker_buy_base_volume;
+        self.taker_buy_quote_volume = taker_buy_quote_volume;
+        self
+    }
+
+    /// Share of volume bought by takers, 0.5 = balanced flow
+    ///
+    /// Returns `None` when the bar has no volume or no taker data.
+    pub fn taker_buy_ratio(&self) -> Option<f64> {
+        if self.volume > 0.0 && (self.taker_buy_base_volume > 0.0 || self.trade_count > 0) {
+            Some(self.taker_buy_base_volume / self.volume)
+        } else {
+            None
+        }
+    }
+}
+
+/// Half-open time window `[from, to)` in milliseconds since epoch
+///
+/// Either bound may be open. Used to load sub-periods of a dataset without
+/// rewriting files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DateRange {
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+}
+
+impl DateRange {
+    pub fn new(from: Option<i64>, to: Option<i64>) -> Self {
+        Self { from, to }
+    }
+
+    /// Parse CLI bounds given as `YYYY-MM-DD` or RFC 3339 timestamps
+    ///
+    /// A date-only `to` includes that whole day.
+    pub fn parse(from: Option<&str>, to: Option<&str>) -> anyhow::Result<Self> {
+        let from = from.map(|s| parse_bound(s, false)).transpose()?;
+        let to = to.map(|s| parse_bound(s, true)).transpose()?;
+
+        if let (Some(f), Some(t)) = (from, to) {
+            if f >= t {
+                anyhow::bail!("Date range is empty: --from must be before --to");
+            }
+        }
+
+        Ok(Self { from, to })
+    }
+
+    pub fn is_unbounded(&self) -> bool {
+        self.from.is_none() && self.to.is_none()
+    }
+
+    pub fn contains(&self, timestamp: i64) -> bool {
+        self.from.is_none_or(|f| timestamp >= f) && self.to.is_none_or(|t| timestamp < t)
+    }
+}
+
+fn parse_bound(value: &str, end_of_day: bool) -> anyhow::Result<i64> {
+    use anyhow::Context;
+
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.timestamp_millis());
+    }
+
+    let date = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .context(format!("Invalid date: {} (expected YYYY-MM-DD)", value))?;
+    let date = if end_of_day {
+        date.succ_opt().context("Date out of range")?
+    } else {
+        date
+    };
+
+    Ok(date
+        .and_hms_opt(0, 0, 0)
+        .context("Invalid date")?
+        .and_utc()
+        .timestamp_millis())
+}
+
+/// A Binance aggregate trade: fills at one price from one taker order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AggTrade {
+    pub agg_trade_id: i64,
+    pub price: f64,
+    pub quantity: f64,
+    pub first_trade_id: i64,
+    pub last_trade_id: i64,
+    pub timestamp: i64,
+    /// True when the buyer was the maker, i.e. the taker sold
+    pub is_buyer_maker: bool,
+}
+
+impl AggTrade {
+    pub fn is_taker_buy(&self) -> bool {
+        !self.is_buyer_maker
+    }
+
+    /// Number of individual trades folded into this aggregate
+    pub fn trade_count(&self) -> u64 {
+        (self.last_trade_id - self.first_trade_id + 1).max(1) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date_range() {
+        let range = DateRange::parse(Some("2024-01-01"), Some("2024-01-31")).unwrap();
+        assert_eq!(range.from, Some(1_704_067_200_000));
+        // Date-only end bound includes the whole day
+        assert_eq!(range.to, Some(1_706_745_600_000));
+        assert!(range.contains(1_706_745_599_999));
+        assert!(!range.contains(1_706_745_600_000));
+
+        assert!(DateRange::parse(Some("2024-02-01"), Some("2024-01-01")).is_err());
+        assert!(DateRange::parse(Some("last week"), None).is_err());
+        assert!(DateRange::parse(None, None).unwrap().is_unbounded());
+    }
 }
@@ -0,0 +1,161 @@
+use crate::backtest::calculate_atr;
+use crate::data::OHLCV;
+use crate::indicators::sma;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Series {
+    SmaClose(usize),
+    Atr(usize),
+}
+
+/// Filled by the first caller, shared by the rest
+type SeriesCell = Arc<OnceLock<Arc<[f64]>>>;
+
+/// Indicator series of one bar series, shared between the backtests of a
+/// parameter sweep
+///
+/// Each distinct indicator is computed once, by the first caller that needs
+/// it; concurrent callers wait for that result instead of computing their
+/// own. A cache belongs to the data it was created for and panics if used
+/// with other bars.
+#[derive(Debug)]
+pub struct IndicatorCache {
+    bars: usize,
+    span: Option<(i64, i64)>,
+    series: Mutex<HashMap<Series, SeriesCell>>,
+}
+
+impl IndicatorCache {
+    pub fn new(data: &[OHLCV]) -> Self {
+        Self {
+            bars: data.len(),
+            span: span(data),
+            series: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// SMA of the closes, as [`sma`]
+    pub fn sma(&self, data: &[OHLCV], period: usize) -> Arc<[f64]> {
+        self.get(data, Series::SmaClose(period), || {
+            let closes: Vec<f64> = data.iter().map(|bar| bar.close).collect();
+            sma(&closes, period)
+        })
+    }
+
+    /// ATR used by ATR stops, as [`calculate_atr`]
+    pub fn atr(&self, data: &[OHLCV], period: usize) -> Arc<[f64]> {
+        self.get(data, Series::Atr(period), || {
+            let hlc: Vec<(f64, f64, f64)> = data
+                .iter()
+                .map(|bar| (bar.high, bar.low, bar.close))
+                .collect();
+            calculate_atr(&hlc, period)
+        })
+    }
+
+    /// Number of series computed so far
+    pub fn len(&self) -> usize {
+        self.series
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|cell| cell.get().is_some())
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get(&self, data: &[OHLCV], key: Series, compute: impl FnOnce() -> Vec<f64>) -> Arc<[f64]> {
+        assert!(
+            data.len() == self.bars && span(data) == self.span,
+            "IndicatorCache used with bars it was not created for"
+        );
+        // Hold the map lock only to find the cell, so different series
+        // compute in parallel
+        let cell = self.series.lock().unwrap().entry(key).or_default().clone();
+        cell.get_or_init(|| compute().into()).clone()
+    }
+}
+
+fn span(data: &[OHLCV]) -> Option<(i64, i64)> {
+    Some((data.first()?.timestamp, data.last()?.timestamp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{SyntheticConfig, SyntheticModel};
+    use crate::strategies::{SMACrossover, Strategy};
+    use std::time::Instant;
+
+    /// SMA that re-sums the whole window at every bar
+    fn windowed_sma(values: &[f64], period: usize) -> Vec<f64> {
+        (0..values.len())
+            .map(|i| {
+                if i + 1 < period {
+                    f64::NAN
+                } else {
+                    values[i + 1 - period..=i].iter().sum::<f64>() / period as f64
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_million_bar_benchmarks() {
+        let data = SyntheticModel::gbm().generate(&SyntheticConfig::new(1_000_000, 5));
+        let closes: Vec<f64> = data.iter().map(|bar| bar.close).collect();
+
+        // Rolling sums against re-summing a 200-bar window
+        let start = Instant::now();
+        let windowed = windowed_sma(&closes, 200);
+        let windowed_time = start.elapsed();
+        let start = Instant::now();
+        let rolling = sma(&closes, 200);
+        let rolling_time = start.elapsed();
+        println!(
+            "SMA(200) over 1M bars: windowed {:?}, rolling {:?}",
+            windowed_time, rolling_time
+        );
+        assert!(rolling_time < windowed_time);
+        for (a, b) in windowed.iter().zip(&rolling).skip(199) {
+            assert!((a - b).abs() <= 1e-9 * a.abs());
+        }
+
+        // A 4 x 4 crossover sweep needs 8 distinct SMAs instead of 32
+        let combos: Vec<SMACrossover> = [10, 20, 30, 40]
+            .iter()
+            .flat_map(|&fast| {
+                [100, 150, 200, 250]
+                    .iter()
+                    .map(move |&slow| SMACrossover::new(fast, slow))
+            })
+            .collect();
+
+        let start = Instant::now();
+        let uncached: Vec<Vec<f64>> = combos.iter().map(|s| s.generate_signals(&data)).collect();
+        let uncached_time = start.elapsed();
+        let cache = IndicatorCache::new(&data);
+        let start = Instant::now();
+        let cached: Vec<Vec<f64>> = combos
+            .iter()
+            .map(|s| s.generate_signals_cached(&data, &cache).unwrap())
+            .collect();
+        let cached_time = start.elapsed();
+        println!(
+            "16 SMA crossovers over 1M bars: uncached {:?}, cached {:?}",
+            uncached_time, cached_time
+        );
+        assert_eq!(cached, uncached);
+        assert_eq!(cache.len(), 8);
+
+        // Cached series are shared, not recomputed
+        let atr = cache.atr(&data, 14);
+        assert!(Arc::ptr_eq(&atr, &cache.atr(&data, 14)));
+        assert_eq!(cache.len(), 9);
+    }
+}
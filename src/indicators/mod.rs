@@ -0,0 +1,67 @@
+//! Technical indicators
+//!
+//! Every indicator comes in two forms: a batch function over a slice that
+//! returns one value per input (`NaN` until enough data has been seen), and a
+//! stateful struct implementing [`Indicator`] that is fed one value or bar at
+//! a time and returns `None` during the same warm-up period.
+
+pub mod cache;
+pub mod momentum;
+pub mod moving_average;
+pub mod statistics;
+pub mod trend;
+pub mod volatility;
+pub mod volume;
+
+pub use cache::IndicatorCache;
+pub use momentum::{macd, rsi, stochastic, Macd, MacdValue, Rsi, Stochastic, StochasticValue};
+pub use moving_average::{ema, sma, wma, Ema, Sma, Wma};
+pub use statistics::{zscore, ZScore};
+pub use trend::{adx, Adx, AdxValue};
+pub use volatility::{
+    atr, bollinger, donchian, keltner, Atr, Bands, BollingerBands, Donchian, Keltner,
+};
+pub use volume::{obv, rolling_vwap, vwap, Obv, Vwap};
+
+/// Incremental indicator fed one observation at a time
+pub trait Indicator {
+    type Input: ?Sized;
+    type Output;
+
+    /// Add the next observation; returns `None` until the indicator is warmed up
+    fn update(&mut self, input: &Self::Input) -> Option<Self::Output>;
+
+    /// Forget all observations
+    fn reset(&mut self);
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use crate::data::{SyntheticConfig, SyntheticModel, OHLCV};
+
+    pub fn sample_bars() -> Vec<OHLCV> {
+        SyntheticModel::garch().generate(&SyntheticConfig::new(500, 17))
+    }
+
+    pub fn closes(bars: &[OHLCV]) -> Vec<f64> {
+        bars.iter().map(|b| b.close).collect()
+    }
+
+    /// Batch output must be NaN exactly where streaming returned None and
+    /// match it everywhere else
+    pub fn assert_matches(batch: &[f64], stream: &[Option<f64>]) {
+        assert_eq!(batch.len(), stream.len());
+        for (i, (b, s)) in batch.iter().zip(stream).enumerate() {
+            match s {
+                None => assert!(b.is_nan(), "index {}: batch {} but stream None", i, b),
+                Some(s) => assert!(
+                    (b - s).abs() <= 1e-8 * s.abs().max(1.0),
+                    "index {}: batch {} vs stream {}",
+                    i,
+                    b,
+                    s
+                ),
+            }
+        }
+    }
+}
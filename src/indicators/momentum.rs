@@ -0,0 +1,341 @@
+use crate::data::OHLCV;
+use crate::indicators::moving_average::{ema, sma, Ema, Sma};
+use crate::indicators::Indicator;
+use std::collections::VecDeque;
+
+/// RSI from Wilder-smoothed average gain and loss
+fn rsi_value(avg_gain: f64, avg_loss: f64) -> f64 {
+    if avg_loss == 0.0 {
+        if avg_gain == 0.0 {
+            50.0
+        } else {
+            100.0
+        }
+    } else {
+        100.0 - 100.0 / (1.0 + avg_gain / avg_loss)
+    }
+}
+
+/// Relative Strength Index (Wilder), first value at index `period`
+pub fn rsi(values: &[f64], period: usize) -> Vec<f64> {
+    assert!(period > 0, "Period must be greater than 0");
+    let mut result = vec![f64::NAN; values.len()];
+    if values.len() <= period {
+        return result;
+    }
+
+    let mut avg_gain = 0.0;
+    let mut avg_loss = 0.0;
+    for i in 1..=period {
+        let change = values[i] - values[i - 1];
+        avg_gain += change.max(0.0);
+        avg_loss += (-change).max(0.0);
+    }
+    avg_gain /= period as f64;
+    avg_loss /= period as f64;
+    result[period] = rsi_value(avg_gain, avg_loss);
+
+    let p = period as f64;
+    for i in period + 1..values.len() {
+        let change = values[i] - values[i - 1];
+        avg_gain = (avg_gain * (p - 1.0) + change.max(0.0)) / p;
+        avg_loss = (avg_loss * (p - 1.0) + (-change).max(0.0)) / p;
+        result[i] = rsi_value(avg_gain, avg_loss);
+    }
+
+    result
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MacdValue {
+    /// Fast EMA minus slow EMA
+    pub macd: f64,
+    pub signal: f64,
+    pub histogram: f64,
+}
+
+impl MacdValue {
+    pub const NAN: Self = Self {
+        macd: f64::NAN,
+        signal: f64::NAN,
+        histogram: f64::NAN,
+    };
+}
+
+/// MACD line, signal line and histogram
+///
+/// Values are `NaN` until the signal line is defined, i.e. for the first
+/// `slow + signal - 2` bars.
+pub fn macd(values: &[f64], fast: usize, slow: usize, signal: usize) -> Vec<MacdValue> {
+    let fast_ema = ema(values, fast);
+    let slow_ema = ema(values, slow);
+    let line: Vec<f64> = fast_ema.iter().zip(&slow_ema).map(|(f, s)| f - s).collect();
+    let signal_line = ema(&line, signal);
+
+    line.iter()
+        .zip(&signal_line)
+        .map(|(&m, &s)| {
+            if s.is_nan() {
+                MacdValue::NAN
+            } else {
+                MacdValue {
+                    macd: m,
+                    signal: s,
+                    histogram: m - s,
+                }
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StochasticValue {
+    /// %K: close relative to the recent high-low range, 0-100
+    pub k: f64,
+    /// %D: simple average of %K
+    pub d: f64,
+}
+
+impl StochasticValue {
+    pub const NAN: Self = Self {
+        k: f64::NAN,
+        d: f64::NAN,
+    };
+}
+
+fn stochastic_k(close: f64, highest: f64, lowest: f64) -> f64 {
+    if highest > lowest {
+        100.0 * (close - lowest) / (highest - lowest)
+    } else {
+        50.0
+    }
+}
+
+/// Stochastic oscillator; defined once both %K and %D are available
+pub fn stochastic(data: &[OHLCV], k_period: usize, d_period: usize) -> Vec<StochasticValue> {
+    assert!(k_period > 0, "Period must be greater than 0");
+    let mut k = vec![f64::NAN; data.len()];
+    for i in k_period.saturating_sub(1)..data.len() {
+        let window = &data[i + 1 - k_period..=i];
+        let highest = window.iter().map(|b| b.high).fold(f64::MIN, f64::max);
+        let lowest = window.iter().map(|b| b.low).fold(f64::MAX, f64::min);
+        k[i] = stochastic_k(data[i].close, highest, lowest);
+    }
+
+    let offset = k_period - 1;
+    let mut d = vec![f64::NAN; data.len()];
+    if data.len() > offset {
+        for (i, value) in sma(&k[offset..], d_period).into_iter().enumerate() {
+            d[offset + i] = value;
+        }
+    }
+
+    k.into_iter()
+        .zip(d)
+        .map(|(k, d)| {
+            if d.is_nan() {
+                StochasticValue::NAN
+            } else {
+                StochasticValue { k, d }
+            }
+        })
+        .collect()
+}
+
+/// Streaming RSI
+#[derive(Debug, Clone)]
+pub struct Rsi {
+    period: usize,
+    prev: Option<f64>,
+    count: usize,
+    avg_gain: f64,
+    avg_loss: f64,
+}
+
+impl Rsi {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0, "Period must be greater than 0");
+        Self {
+            period,
+            prev: None,
+            count: 0,
+            avg_gain: 0.0,
+            avg_loss: 0.0,
+        }
+    }
+}
+
+impl Indicator for Rsi {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, value: &f64) -> Option<f64> {
+        let prev = self.prev.replace(*value)?;
+        let change = value - prev;
+        let p = self.period as f64;
+        self.count += 1;
+
+        if self.count <= self.period {
+            // Simple average over the first `period` changes
+            self.avg_gain += change.max(0.0) / p;
+            self.avg_loss += (-change).max(0.0) / p;
+            if self.count < self.period {
+                return None;
+            }
+        } else {
+            self.avg_gain = (self.avg_gain * (p - 1.0) + change.max(0.0)) / p;
+            self.avg_loss = (self.avg_loss * (p - 1.0) + (-change).max(0.0)) / p;
+        }
+
+        Some(rsi_value(self.avg_gain, self.avg_loss))
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.period);
+    }
+}
+
+/// Streaming MACD
+#[derive(Debug, Clone)]
+pub struct Macd {
+    fast: Ema,
+    slow: Ema,
+    signal: Ema,
+}
+
+impl Macd {
+    pub fn new(fast: usize, slow: usize, signal: usize) -> Self {
+        Self {
+            fast: Ema::new(fast),
+            slow: Ema::new(slow),
+            signal: Ema::new(signal),
+        }
+    }
+}
+
+impl Indicator for Macd {
+    type Input = f64;
+    type Output = MacdValue;
+
+    fn update(&mut self, value: &f64) -> Option<MacdValue> {
+        let fast = self.fast.update(value);
+        let slow = self.slow.update(value);
+        let macd = fast? - slow?;
+        let signal = self.signal.update(&macd)?;
+        Some(MacdValue {
+            macd,
+            signal,
+            histogram: macd - signal,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.fast.reset();
+        self.slow.reset();
+        self.signal.reset();
+    }
+}
+
+/// Streaming stochastic oscillator
+#[derive(Debug, Clone)]
+pub struct Stochastic {
+    k_period: usize,
+    window: VecDeque<(f64, f64)>,
+    d: Sma,
+}
+
+impl Stochastic {
+    pub fn new(k_period: usize, d_period: usize) -> Self {
+        assert!(k_period > 0, "Period must be greater than 0");
+        Self {
+            k_period,
+            window: VecDeque::new(),
+            d: Sma::new(d_period),
+        }
+    }
+}
+
+impl Indicator for Stochastic {
+    type Input = OHLCV;
+    type Output = StochasticValue;
+
+    fn update(&mut self, bar: &OHLCV) -> Option<StochasticValue> {
+        self.window.push_back((bar.high, bar.low));
+        if self.window.len() > self.k_period {
+            self.window.pop_front();
+        }
+        if self.window.len() < self.k_period {
+            return None;
+        }
+
+        let highest = self.window.iter().map(|w| w.0).fold(f64::MIN, f64::max);
+        let lowest = self.window.iter().map(|w| w.1).fold(f64::MAX, f64::min);
+        let k = stochastic_k(bar.close, highest, lowest);
+        let d = self.d.update(&k)?;
+        Some(StochasticValue { k, d })
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+        self.d.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::test_support::{assert_matches, closes, sample_bars};
+
+    #[test]
+    fn test_rsi_bounds_and_extremes() {
+        let rising: Vec<f64> = (0..30).map(|i| 100.0 + i as f64).collect();
+        let r = rsi(&rising, 14);
+        assert!(r[13].is_nan());
+        assert_eq!(r[14], 100.0);
+
+        let bars = sample_bars();
+        let r = rsi(&closes(&bars), 14);
+        assert!(r[14..].iter().all(|v| (0.0..=100.0).contains(v)));
+    }
+
+    #[test]
+    fn test_batch_matches_streaming() {
+        let bars = sample_bars();
+        let values = closes(&bars);
+
+        let mut stream = Rsi::new(14);
+        let streamed: Vec<_> = values.iter().map(|v| stream.update(v)).collect();
+        assert_matches(&rsi(&values, 14), &streamed);
+
+        let batch = macd(&values, 12, 26, 9);
+        let mut stream = Macd::new(12, 26, 9);
+        let streamed: Vec<_> = values.iter().map(|v| stream.update(v)).collect();
+        assert!(batch[32].macd.is_nan() && !batch[33].macd.is_nan());
+        assert_matches(
+            &batch.iter().map(|m| m.macd).collect::<Vec<_>>(),
+            &streamed
+                .iter()
+                .map(|m| m.map(|m| m.macd))
+                .collect::<Vec<_>>(),
+        );
+        assert_matches(
+            &batch.iter().map(|m| m.histogram).collect::<Vec<_>>(),
+            &streamed
+                .iter()
+                .map(|m| m.map(|m| m.histogram))
+                .collect::<Vec<_>>(),
+        );
+
+        let batch = stochastic(&bars, 14, 3);
+        let mut stream = Stochastic::new(14, 3);
+        let streamed: Vec<_> = bars.iter().map(|b| stream.update(b)).collect();
+        assert_matches(
+            &batch.iter().map(|s| s.k).collect::<Vec<_>>(),
+            &streamed.iter().map(|s| s.map(|s| s.k)).collect::<Vec<_>>(),
+        );
+        assert_matches(
+            &batch.iter().map(|s| s.d).collect::<Vec<_>>(),
+            &streamed.iter().map(|s| s.map(|s| s.d)).collect::<Vec<_>>(),
+        );
+    }
+}
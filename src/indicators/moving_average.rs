@@ -0,0 +1,290 @@
+use crate::indicators::Indicator;
+use std::collections::VecDeque;
+
+/// Simple moving average, computed with a running sum in O(n)
+///
+/// `NaN` while a `NaN` is inside the window; the average recovers once it
+/// has left.
+pub fn sma(values: &[f64], period: usize) -> Vec<f64> {
+    assert!(period > 0, "Period must be greater than 0");
+    let mut result = vec![f64::NAN; values.len()];
+    let mut sum = 0.0;
+    let mut nans = 0;
+
+    for i in 0..values.len() {
+        add_to_window(&mut sum, &mut nans, values[i]);
+        if i >= period {
+            remove_from_window(&mut sum, &mut nans, values[i - period]);
+        }
+        if i + 1 >= period && nans == 0 {
+            result[i] = sum / period as f64;
+        }
+    }
+
+    result
+}
+
+fn add_to_window(sum: &mut f64, nans: &mut usize, value: f64) {
+    if value.is_nan() {
+        *nans += 1;
+    } else {
+        *sum += value;
+    }
+}
+
+fn remove_from_window(sum: &mut f64, nans: &mut usize, value: f64) {
+    if value.is_nan() {
+        *nans -= 1;
+    } else {
+        *sum -= value;
+    }
+}
+
+/// Exponential moving average seeded with the SMA of the first `period` values
+///
+/// `NaN`s are skipped: they neither count towards the seed nor move the
+/// average, and the output is `NaN` at those positions. An EMA can therefore
+/// be taken of another indicator.
+pub fn ema(values: &[f64], period: usize) -> Vec<f64> {
+    let mut stream = Ema::new(period);
+    values
+        .iter()
+        .map(|v| stream.update(v).unwrap_or(f64::NAN))
+        .collect()
+}
+
+/// Linearly weighted moving average (most recent value has weight `period`)
+pub fn wma(values: &[f64], period: usize) -> Vec<f64> {
+    assert!(period > 0, "Period must be greater than 0");
+    let mut result = vec![f64::NAN; values.len()];
+    let denominator = (period * (period + 1)) as f64 / 2.0;
+
+    for i in period.saturating_sub(1)..values.len() {
+        let window = &values[i + 1 - period..=i];
+        let weighted: f64 = window
+            .iter()
+            .enumerate()
+            .map(|(w, v)| (w + 1) as f64 * v)
+            .sum();
+        result[i] = weighted / denominator;
+    }
+
+    result
+}
+
+/// Streaming simple moving average
+#[derive(Debug, Clone)]
+pub struct Sma {
+    period: usize,
+    window: VecDeque<f64>,
+    sum: f64,
+    nans: usize,
+}
+
+impl Sma {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0, "Period must be greater than 0");
+        Self {
+            period,
+            window: VecDeque::new(),
+            sum: 0.0,
+            nans: 0,
+        }
+    }
+}
+
+impl Indicator for Sma {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, value: &f64) -> Option<f64> {
+        self.window.push_back(*value);
+        add_to_window(&mut self.sum, &mut self.nans, *value);
+        if self.window.len() > self.period {
+            if let Some(old) = self.window.pop_front() {
+                remove_from_window(&mut self.sum, &mut self.nans, old);
+            }
+        }
+        (self.window.len() == self.period && self.nans == 0).then(|| self.sum / self.period as f64)
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+        self.sum = 0.0;
+        self.nans = 0;
+    }
+}
+
+/// Streaming exponential moving average
+#[derive(Debug, Clone)]
+pub struct Ema {
+    period: usize,
+    alpha: f64,
+    seed: Sma,
+    current: Option<f64>,
+}
+
+impl Ema {
+    pub fn new(period: usize) -> Self {
+        Self {
+            period,
+            alpha: 2.0 / (period as f64 + 1.0),
+            seed: Sma::new(period),
+            current: None,
+        }
+    }
+
+    pub fn period(&self) -> usize {
+        self.period
+    }
+
+    /// Latest value without adding an observation
+    pub fn value(&self) -> Option<f64> {
+        self.current
+    }
+}
+
+impl Indicator for Ema {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, value: &f64) -> Option<f64> {
+        if value.is_nan() {
+            return None;
+        }
+        self.current = match self.current {
+            Some(current) => Some(current + self.alpha * (value - current)),
+            None => self.seed.update(value),
+        };
+        self.current
+    }
+
+    fn reset(&mut self) {
+        self.seed.reset();
+        self.current = None;
+    }
+}
+
+/// Streaming linearly weighted moving average
+#[derive(Debug, Clone)]
+pub struct Wma {
+    period: usize,
+    window: VecDeque<f64>,
+}
+
+impl Wma {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0, "Period must be greater than 0");
+        Self {
+            period,
+            window: VecDeque::new(),
+        }
+    }
+}
+
+impl Indicator for Wma {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, value: &f64) -> Option<f64> {
+        self.window.push_back(*value);
+        if self.window.len() > self.period {
+            self.window.pop_front();
+        }
+        if self.window.len() < self.period {
+            return None;
+        }
+
+        let denominator = (self.period * (self.period + 1)) as f64 / 2.0;
+        let weighted: f64 = self
+            .window
+            .iter()
+            .enumerate()
+            .map(|(w, v)| (w + 1) as f64 * v)
+            .sum();
+        Some(weighted / denominator)
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::test_support::{assert_matches, closes, sample_bars};
+
+    #[test]
+    fn test_known_values() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+
+        let s = sma(&values, 3);
+        assert!(s[1].is_nan());
+        assert_eq!(&s[2..], &[2.0, 3.0, 4.0]);
+
+        // (1*3 + 2*4 + 3*5) / 6
+        assert!((wma(&values, 3)[4] - 26.0 / 6.0).abs() < 1e-12);
+
+        // Seeded at 2.0, then 2 + 0.5 * (4 - 2) = 3
+        let e = ema(&values, 3);
+        assert_eq!(e[2], 2.0);
+        assert_eq!(e[3], 3.0);
+    }
+
+    #[test]
+    fn test_batch_matches_streaming() {
+        let values = closes(&sample_bars());
+
+        for period in [1, 5, 20] {
+            let mut stream = Sma::new(period);
+            let streamed: Vec<_> = values.iter().map(|v| stream.update(v)).collect();
+            assert_matches(&sma(&values, period), &streamed);
+
+            let mut stream = Ema::new(period);
+            let streamed: Vec<_> = values.iter().map(|v| stream.update(v)).collect();
+            assert_matches(&ema(&values, period), &streamed);
+
+            let mut stream = Wma::new(period);
+            let streamed: Vec<_> = values.iter().map(|v| stream.update(v)).collect();
+            assert_matches(&wma(&values, period), &streamed);
+        }
+    }
+
+    #[test]
+    fn test_ema_skips_leading_nan() {
+        let values = [f64::NAN, f64::NAN, 1.0, 2.0, 3.0];
+        let e = ema(&values, 2);
+        assert!(e[2].is_nan());
+        assert_eq!(e[3], 1.5);
+    }
+
+    #[test]
+    fn test_nan_does_not_poison() {
+        let mut values = closes(&sample_bars());
+        values[0] = f64::NAN;
+        values[100] = f64::NAN;
+        values[101] = f64::NAN;
+
+        for period in [1, 5, 20] {
+            let mut stream = Sma::new(period);
+            let streamed: Vec<_> = values.iter().map(|v| stream.update(v)).collect();
+            let batch = sma(&values, period);
+            assert_matches(&batch, &streamed);
+            assert!(batch[100].is_nan());
+            assert!(!batch[101 + period].is_nan());
+
+            let mut stream = Ema::new(period);
+            let streamed: Vec<_> = values.iter().map(|v| stream.update(v)).collect();
+            let batch = ema(&values, period);
+            assert_matches(&batch, &streamed);
+            assert!(batch[100].is_nan());
+            assert!(!batch[102].is_nan());
+        }
+
+        // The EMA carries on from its value before the gap
+        let e = ema(&[1.0, 2.0, f64::NAN, 4.0], 2);
+        assert!(e[2].is_nan());
+        assert_eq!(e[3], 1.5 + 2.0 / 3.0 * (4.0 - 1.5));
+    }
+}
@@ -0,0 +1,87 @@
+use crate::indicators::Indicator;
+use std::collections::VecDeque;
+
+fn window_zscore<'a>(
+    window: impl Iterator<Item = &'a f64> + Clone,
+    period: usize,
+    value: f64,
+) -> f64 {
+    let n = period as f64;
+    let mean = window.clone().sum::<f64>() / n;
+    let variance = window.map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    let std = variance.sqrt();
+    if std > 0.0 {
+        (value - mean) / std
+    } else {
+        0.0
+    }
+}
+
+/// Rolling z-score of each value against the mean and population standard
+/// deviation of the last `period` values (including itself)
+pub fn zscore(values: &[f64], period: usize) -> Vec<f64> {
+    assert!(period > 0, "Period must be greater than 0");
+    let mut result = vec![f64::NAN; values.len()];
+
+    for i in period.saturating_sub(1)..values.len() {
+        let window = &values[i + 1 - period..=i];
+        result[i] = window_zscore(window.iter(), period, values[i]);
+    }
+
+    result
+}
+
+/// Streaming rolling z-score
+#[derive(Debug, Clone)]
+pub struct ZScore {
+    period: usize,
+    window: VecDeque<f64>,
+}
+
+impl ZScore {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0, "Period must be greater than 0");
+        Self {
+            period,
+            window: VecDeque::new(),
+        }
+    }
+}
+
+impl Indicator for ZScore {
+    type Input = f64;
+    type Output = f64;
+
+    fn update(&mut self, value: &f64) -> Option<f64> {
+        self.window.push_back(*value);
+        if self.window.len() > self.period {
+            self.window.pop_front();
+        }
+        (self.window.len() == self.period)
+            .then(|| window_zscore(self.window.iter(), self.period, *value))
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::test_support::{assert_matches, closes, sample_bars};
+
+    #[test]
+    fn test_zscore() {
+        let values = [1.0, 1.0, 1.0, 3.0];
+        let z = zscore(&values, 4);
+        // mean 1.5, std sqrt(0.75)
+        assert!((z[3] - 1.5 / 0.75f64.sqrt()).abs() < 1e-12);
+        assert_eq!(zscore(&[2.0, 2.0], 2)[1], 0.0);
+
+        let values = closes(&sample_bars());
+        let mut stream = ZScore::new(30);
+        let streamed: Vec<_> = values.iter().map(|v| stream.update(v)).collect();
+        assert_matches(&zscore(&values, 30), &streamed);
+    }
+}
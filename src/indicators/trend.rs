@@ -0,0 +1,225 @@
+use crate::data::OHLCV;
+use crate::indicators::Indicator;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdxValue {
+    /// Trend strength, 0-100
+    pub adx: f64,
+    pub plus_di: f64,
+    pub minus_di: f64,
+}
+
+impl AdxValue {
+    pub const NAN: Self = Self {
+        adx: f64::NAN,
+        plus_di: f64::NAN,
+        minus_di: f64::NAN,
+    };
+}
+
+/// +DM, -DM and true range of `bar` relative to `prev`
+fn directional_movement(bar: &OHLCV, prev: &OHLCV) -> (f64, f64, f64) {
+    let up = bar.high - prev.high;
+    let down = prev.low - bar.low;
+    let plus_dm = if up > down && up > 0.0 { up } else { 0.0 };
+    let minus_dm = if down > up && down > 0.0 { down } else { 0.0 };
+    let tr = (bar.high - bar.low)
+        .max((bar.high - prev.close).abs())
+        .max((bar.low - prev.close).abs());
+    (plus_dm, minus_dm, tr)
+}
+
+/// +DI, -DI and DX from Wilder-smoothed sums
+fn directional_index(plus_dm: f64, minus_dm: f64, tr: f64) -> (f64, f64, f64) {
+    if tr <= 0.0 {
+        return (0.0, 0.0, 0.0);
+    }
+    let plus_di = 100.0 * plus_dm / tr;
+    let minus_di = 100.0 * minus_dm / tr;
+    let sum = plus_di + minus_di;
+    let dx = if sum > 0.0 {
+        100.0 * (plus_di - minus_di).abs() / sum
+    } else {
+        0.0
+    };
+    (plus_di, minus_di, dx)
+}
+
+/// Average Directional Index (Wilder)
+///
+/// The directional indicators need `period` price changes and the ADX a
+/// further `period` DX values, so the first output is at index `2 * period - 1`.
+pub fn adx(data: &[OHLCV], period: usize) -> Vec<AdxValue> {
+    assert!(period > 0, "Period must be greater than 0");
+    let mut result = vec![AdxValue::NAN; data.len()];
+    if data.len() < 2 * period {
+        return result;
+    }
+
+    let p = period as f64;
+    let (mut s_plus, mut s_minus, mut s_tr) = (0.0, 0.0, 0.0);
+    let mut dx_values = Vec::with_capacity(data.len());
+    let mut current_adx = 0.0;
+
+    for i in 1..data.len() {
+        let (plus_dm, minus_dm, tr) = directional_movement(&data[i], &data[i - 1]);
+        if i <= period {
+            s_plus += plus_dm;
+            s_minus += minus_dm;
+            s_tr += tr;
+            if i < period {
+                continue;
+            }
+        } else {
+            s_plus = s_plus - s_plus / p + plus_dm;
+            s_minus = s_minus - s_minus / p + minus_dm;
+            s_tr = s_tr - s_tr / p + tr;
+        }
+
+        let (plus_di, minus_di, dx) = directional_index(s_plus, s_minus, s_tr);
+        dx_values.push(dx);
+
+        if dx_values.len() == period {
+            current_adx = dx_values.iter().sum::<f64>() / p;
+        } else if dx_values.len() > period {
+            current_adx = (current_adx * (p - 1.0) + dx) / p;
+        } else {
+            continue;
+        }
+
+        result[i] = AdxValue {
+            adx: current_adx,
+            plus_di,
+            minus_di,
+        };
+    }
+
+    result
+}
+
+/// Streaming Average Directional Index
+#[derive(Debug, Clone)]
+pub struct Adx {
+    period: usize,
+    prev: Option<OHLCV>,
+    changes: usize,
+    s_plus: f64,
+    s_minus: f64,
+    s_tr: f64,
+    dx_count: usize,
+    dx_sum: f64,
+    current: f64,
+}
+
+impl Adx {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0, "Period must be greater than 0");
+        Self {
+            period,
+            prev: None,
+            changes: 0,
+            s_plus: 0.0,
+            s_minus: 0.0,
+            s_tr: 0.0,
+            dx_count: 0,
+            dx_sum: 0.0,
+            current: 0.0,
+        }
+    }
+}
+
+impl Indicator for Adx {
+    type Input = OHLCV;
+    type Output = AdxValue;
+
+    fn update(&mut self, bar: &OHLCV) -> Option<AdxValue> {
+        let prev = self.prev.replace(bar.clone())?;
+        let (plus_dm, minus_dm, tr) = directional_movement(bar, &prev);
+        let p = self.period as f64;
+        self.changes += 1;
+
+        if self.changes <= self.period {
+            self.s_plus += plus_dm;
+            self.s_minus += minus_dm;
+            self.s_tr += tr;
+            if self.changes < self.period {
+                return None;
+            }
+        } else {
+            self.s_plus = self.s_plus - self.s_plus / p + plus_dm;
+            self.s_minus = self.s_minus - self.s_minus / p + minus_dm;
+            self.s_tr = self.s_tr - self.s_tr / p + tr;
+        }
+
+        let (plus_di, minus_di, dx) = directional_index(self.s_plus, self.s_minus, self.s_tr);
+        self.dx_count += 1;
+        if self.dx_count < self.period {
+            self.dx_sum += dx;
+            return None;
+        } else if self.dx_count == self.period {
+            self.current = (self.dx_sum + dx) / p;
+        } else {
+            self.current = (self.current * (p - 1.0) + dx) / p;
+        }
+
+        Some(AdxValue {
+            adx: self.current,
+            plus_di,
+            minus_di,
+        })
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.period);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::test_support::{assert_matches, sample_bars};
+
+    #[test]
+    fn test_batch_matches_streaming() {
+        let bars = sample_bars();
+        let batch = adx(&bars, 14);
+        let mut stream = Adx::new(14);
+        let streamed: Vec<_> = bars.iter().map(|b| stream.update(b)).collect();
+
+        assert!(batch[26].adx.is_nan() && !batch[27].adx.is_nan());
+        assert_matches(
+            &batch.iter().map(|v| v.adx).collect::<Vec<_>>(),
+            &streamed
+                .iter()
+                .map(|v| v.map(|v| v.adx))
+                .collect::<Vec<_>>(),
+        );
+        assert_matches(
+            &batch.iter().map(|v| v.plus_di).collect::<Vec<_>>(),
+            &streamed
+                .iter()
+                .map(|v| v.map(|v| v.plus_di))
+                .collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn test_strong_trend_has_high_adx() {
+        let bars: Vec<OHLCV> = (0..60)
+            .map(|i| {
+                let price = 100.0 + 2.0 * i as f64;
+                OHLCV::new(
+                    i * 60_000,
+                    price,
+                    price + 1.0,
+                    price - 1.0,
+                    price + 0.5,
+                    1.0,
+                )
+            })
+            .collect();
+        let last = adx(&bars, 14)[59];
+        assert!(last.adx > 90.0);
+        assert!(last.plus_di > last.minus_di);
+    }
+}
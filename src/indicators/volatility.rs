@@ -0,0 +1,381 @@
+use crate::data::OHLCV;
+use crate::indicators::moving_average::{ema, Ema};
+use crate::indicators::Indicator;
+use std::collections::VecDeque;
+
+/// Upper/middle/lower envelope shared by the channel indicators
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bands {
+    pub upper: f64,
+    pub middle: f64,
+    pub lower: f64,
+}
+
+impl Bands {
+    pub const NAN: Self = Self {
+        upper: f64::NAN,
+        middle: f64::NAN,
+        lower: f64::NAN,
+    };
+
+    /// Distance between the outer bands
+    pub fn width(&self) -> f64 {
+        self.upper - self.lower
+    }
+
+    /// Position of `value` within the bands: 0 at the lower band, 1 at the upper
+    pub fn percent_b(&self, value: f64) -> f64 {
+        if self.width() > 0.0 {
+            (value - self.lower) / self.width()
+        } else {
+            0.5
+        }
+    }
+}
+
+fn true_range(bar: &OHLCV, prev_close: Option<f64>) -> f64 {
+    match prev_close {
+        Some(prev) => (bar.high - bar.low)
+            .max((bar.high - prev).abs())
+            .max((bar.low - prev).abs()),
+        None => bar.high - bar.low,
+    }
+}
+
+/// Average True Range with Wilder smoothing, first value at index `period - 1`
+pub fn atr(data: &[OHLCV], period: usize) -> Vec<f64> {
+    assert!(period > 0, "Period must be greater than 0");
+    let mut result = vec![f64::NAN; data.len()];
+    let p = period as f64;
+    let mut current = 0.0;
+
+    for i in 0..data.len() {
+        let prev_close = i.checked_sub(1).map(|j| data[j].close);
+        let tr = true_range(&data[i], prev_close);
+        if i < period {
+            current += tr / p;
+        } else {
+            current = (current * (p - 1.0) + tr) / p;
+        }
+        if i + 1 >= period {
+            result[i] = current;
+        }
+    }
+
+    result
+}
+
+/// Bollinger Bands: SMA ± `multiplier` population standard deviations
+pub fn bollinger(values: &[f64], period: usize, multiplier: f64) -> Vec<Bands> {
+    assert!(period > 0, "Period must be greater than 0");
+    let mut result = vec![Bands::NAN; values.len()];
+
+    for i in period.saturating_sub(1)..values.len() {
+        let window = &values[i + 1 - period..=i];
+        let mean = window.iter().sum::<f64>() / period as f64;
+        let variance = window.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / period as f64;
+        let std = variance.sqrt();
+        result[i] = Bands {
+            upper: mean + multiplier * std,
+            middle: mean,
+            lower: mean - multiplier * std,
+        };
+    }
+
+    result
+}
+
+/// Donchian channel: highest high and lowest low over `period` bars
+/// (including the current one)
+pub fn donchian(data: &[OHLCV], period: usize) -> Vec<Bands> {
+    assert!(period > 0, "Period must be greater than 0");
+    let mut result = vec![Bands::NAN; data.len()];
+
+    for i in period.saturating_sub(1)..data.len() {
+        let window = &data[i + 1 - period..=i];
+        let upper = window.iter().map(|b| b.high).fold(f64::MIN, f64::max);
+        let lower = window.iter().map(|b| b.low).fold(f64::MAX, f64::min);
+        result[i] = Bands {
+            upper,
+            middle: (upper + lower) / 2.0,
+            lower,
+        };
+    }
+
+    result
+}
+
+/// Keltner channel: EMA of close ± `multiplier` × ATR
+pub fn keltner(
+    data: &[OHLCV],
+    ema_period: usize,
+    atr_period: usize,
+    multiplier: f64,
+) -> Vec<Bands> {
+    let closes: Vec<f64> = data.iter().map(|b| b.close).collect();
+    let middle = ema(&closes, ema_period);
+    let ranges = atr(data, atr_period);
+
+    middle
+        .iter()
+        .zip(&ranges)
+        .map(|(&m, &a)| {
+            if m.is_nan() || a.is_nan() {
+                Bands::NAN
+            } else {
+                Bands {
+                    upper: m + multiplier * a,
+                    middle: m,
+                    lower: m - multiplier * a,
+                }
+            }
+        })
+        .collect()
+}
+
+/// Streaming Average True Range
+#[derive(Debug, Clone)]
+pub struct Atr {
+    period: usize,
+    prev_close: Option<f64>,
+    count: usize,
+    current: f64,
+}
+
+impl Atr {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0, "Period must be greater than 0");
+        Self {
+            period,
+            prev_close: None,
+            count: 0,
+            current: 0.0,
+        }
+    }
+}
+
+impl Indicator for Atr {
+    type Input = OHLCV;
+    type Output = f64;
+
+    fn update(&mut self, bar: &OHLCV) -> Option<f64> {
+        let tr = true_range(bar, self.prev_close);
+        let p = self.period as f64;
+        self.prev_close = Some(bar.close);
+        self.count += 1;
+
+        if self.count <= self.period {
+            self.current += tr / p;
+        } else {
+            self.current = (self.current * (p - 1.0) + tr) / p;
+        }
+        (self.count >= self.period).then_some(self.current)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::new(self.period);
+    }
+}
+
+/// Streaming Bollinger Bands
+#[derive(Debug, Clone)]
+pub struct BollingerBands {
+    period: usize,
+    multiplier: f64,
+    window: VecDeque<f64>,
+    sum: f64,
+}
+
+impl BollingerBands {
+    pub fn new(period: usize, multiplier: f64) -> Self {
+        assert!(period > 0, "Period must be greater than 0");
+        Self {
+            period,
+            multiplier,
+            window: VecDeque::new(),
+            sum: 0.0,
+        }
+    }
+}
+
+impl Indicator for BollingerBands {
+    type Input = f64;
+    type Output = Bands;
+
+    fn update(&mut self, value: &f64) -> Option<Bands> {
+        self.window.push_back(*value);
+        self.sum += value;
+        if self.window.len() > self.period {
+            self.sum -= self.window.pop_front().unwrap_or(0.0);
+        }
+        if self.window.len() < self.period {
+            return None;
+        }
+
+        let n = self.period as f64;
+        let mean = self.sum / n;
+        // Deviations from the window rather than a running sum of squares,
+        // which loses precision at high price levels
+        let variance = self.window.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+        let std = variance.sqrt();
+        Some(Bands {
+            upper: mean + self.multiplier * std,
+            middle: mean,
+            lower: mean - self.multiplier * std,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+        self.sum = 0.0;
+    }
+}
+
+/// Streaming Donchian channel
+#[derive(Debug, Clone)]
+pub struct Donchian {
+    period: usize,
+    window: VecDeque<(f64, f64)>,
+}
+
+impl Donchian {
+    pub fn new(period: usize) -> Self {
+        assert!(period > 0, "Period must be greater than 0");
+        Self {
+            period,
+            window: VecDeque::new(),
+        }
+    }
+}
+
+impl Indicator for Donchian {
+    type Input = OHLCV;
+    type Output = Bands;
+
+    fn update(&mut self, bar: &OHLCV) -> Option<Bands> {
+        self.window.push_back((bar.high, bar.low));
+        if self.window.len() > self.period {
+            self.window.pop_front();
+        }
+        if self.window.len() < self.period {
+            return None;
+        }
+
+        let upper = self.window.iter().map(|w| w.0).fold(f64::MIN, f64::max);
+        let lower = self.window.iter().map(|w| w.1).fold(f64::MAX, f64::min);
+        Some(Bands {
+            upper,
+            middle: (upper + lower) / 2.0,
+            lower,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+    }
+}
+
+/// Streaming Keltner channel
+#[derive(Debug, Clone)]
+pub struct Keltner {
+    ema: Ema,
+    atr: Atr,
+    multiplier: f64,
+}
+
+impl Keltner {
+    pub fn new(ema_period: usize, atr_period: usize, multiplier: f64) -> Self {
+        Self {
+            ema: Ema::new(ema_period),
+            atr: Atr::new(atr_period),
+            multiplier,
+        }
+    }
+}
+
+impl Indicator for Keltner {
+    type Input = OHLCV;
+    type Output = Bands;
+
+    fn update(&mut self, bar: &OHLCV) -> Option<Bands> {
+        let middle = self.ema.update(&bar.close);
+        let range = self.atr.update(bar);
+        let (middle, range) = (middle?, range?);
+        Some(Bands {
+            upper: middle + self.multiplier * range,
+            middle,
+            lower: middle - self.multiplier * range,
+        })
+    }
+
+    fn reset(&mut self) {
+        self.ema.reset();
+        self.atr.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::calculate_atr;
+    use crate::indicators::test_support::{assert_matches, closes, sample_bars};
+
+    fn assert_bands_match(batch: &[Bands], stream: &[Option<Bands>]) {
+        let field = |f: fn(&Bands) -> f64| {
+            (
+                batch.iter().map(f).collect::<Vec<_>>(),
+                stream.iter().map(|b| b.as_ref().map(f)).collect::<Vec<_>>(),
+            )
+        };
+        for f in [
+            |b: &Bands| b.upper,
+            |b: &Bands| b.middle,
+            |b: &Bands| b.lower,
+        ] {
+            let (batch, stream) = field(f);
+            assert_matches(&batch, &stream);
+        }
+    }
+
+    #[test]
+    fn test_atr_matches_backtest_atr() {
+        let bars = sample_bars();
+        let hlc: Vec<_> = bars.iter().map(|b| (b.high, b.low, b.close)).collect();
+        let expected = calculate_atr(&hlc, 14);
+        let actual = atr(&bars, 14);
+        for (e, a) in expected.iter().zip(&actual) {
+            assert!((e.is_nan() && a.is_nan()) || (e - a).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_batch_matches_streaming() {
+        let bars = sample_bars();
+        let values = closes(&bars);
+
+        let mut stream = Atr::new(14);
+        let streamed: Vec<_> = bars.iter().map(|b| stream.update(b)).collect();
+        assert_matches(&atr(&bars, 14), &streamed);
+
+        let mut stream = BollingerBands::new(20, 2.0);
+        let streamed: Vec<_> = values.iter().map(|v| stream.update(v)).collect();
+        assert_bands_match(&bollinger(&values, 20, 2.0), &streamed);
+
+        let mut stream = Donchian::new(20);
+        let streamed: Vec<_> = bars.iter().map(|b| stream.update(b)).collect();
+        assert_bands_match(&donchian(&bars, 20), &streamed);
+
+        let mut stream = Keltner::new(20, 10, 2.0);
+        let streamed: Vec<_> = bars.iter().map(|b| stream.update(b)).collect();
+        assert_bands_match(&keltner(&bars, 20, 10, 2.0), &streamed);
+    }
+
+    #[test]
+    fn test_bands_contain_price_range() {
+        let bars = sample_bars();
+        for (bar, band) in bars.iter().zip(donchian(&bars, 20)).skip(19) {
+            assert!(band.upper >= bar.high && band.lower <= bar.low);
+            assert!((0.0..=1.0).contains(&band.percent_b(bar.close)));
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use crate::data::OHLCV;
+use crate::indicators::Indicator;
+use std::collections::VecDeque;
+
+/// Traded notional and volume of a bar
+///
+/// Uses the exchange-reported quote volume when present, which gives the true
+/// VWAP; otherwise falls back to typical price × volume.
+fn notional(bar: &OHLCV) -> (f64, f64) {
+    if bar.quote_volume > 0.0 && bar.volume > 0.0 {
+        (bar.quote_volume, bar.volume)
+    } else {
+        let typical = (bar.high + bar.low + bar.close) / 3.0;
+        (typical * bar.volume, bar.volume)
+    }
+}
+
+/// On-balance volume, starting at 0 on the first bar
+pub fn obv(data: &[OHLCV]) -> Vec<f64> {
+    let mut result = Vec::with_capacity(data.len());
+    let mut current = 0.0;
+
+    for i in 0..data.len() {
+        if i > 0 {
+            let change = data[i].close - data[i - 1].close;
+            if change > 0.0 {
+                current += data[i].volume;
+            } else if change < 0.0 {
+                current -= data[i].volume;
+            }
+        }
+        result.push(current);
+    }
+
+    result
+}
+
+/// Cumulative VWAP from the first bar; `NaN` until any volume has traded
+pub fn vwap(data: &[OHLCV]) -> Vec<f64> {
+    let mut total_notional = 0.0;
+    let mut total_volume = 0.0;
+
+    data.iter()
+        .map(|bar| {
+            let (n, v) = notional(bar);
+            total_notional += n;
+            total_volume += v;
+            if total_volume > 0.0 {
+                total_notional / total_volume
+            } else {
+                f64::NAN
+            }
+        })
+        .collect()
+}
+
+/// VWAP over the last `period` bars
+pub fn rolling_vwap(data: &[OHLCV], period: usize) -> Vec<f64> {
+    assert!(period > 0, "Period must be greater than 0");
+    let mut result = vec![f64::NAN; data.len()];
+
+    for i in period.saturating_sub(1)..data.len() {
+        let (n, v) = data[i + 1 - period..=i]
+            .iter()
+            .map(notional)
+            .fold((0.0, 0.0), |acc, x| (acc.0 + x.0, acc.1 + x.1));
+        if v > 0.0 {
+            result[i] = n / v;
+        }
+    }
+
+    result
+}
+
+/// Streaming on-balance volume
+#[derive(Debug, Clone, Default)]
+pub struct Obv {
+    prev_close: Option<f64>,
+    current: f64,
+}
+
+impl Obv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Indicator for Obv {
+    type Input = OHLCV;
+    type Output = f64;
+
+    fn update(&mut self, bar: &OHLCV) -> Option<f64> {
+        if let Some(prev) = self.prev_close {
+            if bar.close > prev {
+                self.current += bar.volume;
+            } else if bar.close < prev {
+                self.current -= bar.volume;
+            }
+        }
+        self.prev_close = Some(bar.close);
+        Some(self.current)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Streaming VWAP, either cumulative or over a rolling window
+#[derive(Debug, Clone, Default)]
+pub struct Vwap {
+    period: Option<usize>,
+    window: VecDeque<(f64, f64)>,
+    total_notional: f64,
+    total_volume: f64,
+}
+
+impl Vwap {
+    /// Cumulative VWAP; call [`Indicator::reset`] to anchor a new session
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rolling(period: usize) -> Self {
+        assert!(period > 0, "Period must be greater than 0");
+        Self {
+            period: Some(period),
+            ..Self::default()
+        }
+    }
+}
+
+impl Indicator for Vwap {
+    type Input = OHLCV;
+    type Output = f64;
+
+    fn update(&mut self, bar: &OHLCV) -> Option<f64> {
+        let (n, v) = notional(bar);
+
+        if let Some(period) = self.period {
+            self.window.push_back((n, v));
+            if self.window.len() > period {
+                self.window.pop_front();
+            }
+            if self.window.len() < period {
+                return None;
+            }
+            // Re-summed each bar so rounding does not accumulate
+            self.total_notional = self.window.iter().map(|w| w.0).sum();
+            self.total_volume = self.window.iter().map(|w| w.1).sum();
+        } else {
+            self.total_notional += n;
+            self.total_volume += v;
+        }
+
+        (self.total_volume > 0.0).then(|| self.total_notional / self.total_volume)
+    }
+
+    fn reset(&mut self) {
+        self.window.clear();
+        self.total_notional = 0.0;
+        self.total_volume = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::test_support::{assert_matches, sample_bars};
+
+    #[test]
+    fn test_known_values() {
+        let bars = vec![
+            OHLCV::new(0, 10.0, 10.0, 10.0, 10.0, 1.0),
+            OHLCV::new(1, 12.0, 12.0, 12.0, 12.0, 3.0),
+            OHLCV::new(2, 11.0, 11.0, 11.0, 11.0, 2.0),
+        ];
+        assert_eq!(obv(&bars), vec![0.0, 3.0, 1.0]);
+        // (10*1 + 12*3) / 4
+        assert_eq!(vwap(&bars)[1], 11.5);
+
+        let mut with_quote = bars[0].clone();
+        with_quote.quote_volume = 9.5;
+        assert_eq!(vwap(&[with_quote])[0], 9.5);
+    }
+
+    #[test]
+    fn test_batch_matches_streaming() {
+        let bars = sample_bars();
+
+        let mut stream = Obv::new();
+        let streamed: Vec<_> = bars.iter().map(|b| stream.update(b)).collect();
+        assert_matches(&obv(&bars), &streamed);
+
+        let mut stream = Vwap::new();
+        let streamed: Vec<_> = bars.iter().map(|b| stream.update(b)).collect();
+        assert_matches(&vwap(&bars), &streamed);
+
+        let mut stream = Vwap::rolling(24);
+        let streamed: Vec<_> = bars.iter().map(|b| stream.update(b)).collect();
+        assert_matches(&rolling_vwap(&bars, 24), &streamed);
+    }
+}
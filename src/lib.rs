@@ -1,6 +1,12 @@
 pub mod backtest;
 pub mod data;
+pub mod indicators;
+mod linalg;
+pub mod live;
 pub mod metrics;
+pub mod ml;
 pub mod optimization;
+pub mod pairs;
 pub mod plotting;
+pub mod regime;
 pub mod strategies;
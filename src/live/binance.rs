@@ -0,0 +1,636 @@
+use crate::live::broker::{
+    Balance, Broker, Fill, Order, OrderRequest, OrderSide, OrderStatus, OrderType,
+};
+use anyhow::{bail, Context, Result};
+use hmac::{Hmac, Mac};
+use reqwest::blocking::Client;
+use reqwest::Method;
+use serde::Deserialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceOrder {
+    symbol: String,
+    order_id: u64,
+    client_order_id: String,
+    price: String,
+    orig_qty: String,
+    executed_qty: String,
+    cummulative_quote_qty: String,
+    status: String,
+    #[serde(rename = "type")]
+    order_type: String,
+    side: String,
+    #[serde(default)]
+    time: Option<i64>,
+    #[serde(default)]
+    transact_time: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceTrade {
+    id: u64,
+    symbol: String,
+    order_id: u64,
+    price: String,
+    qty: String,
+    commission: String,
+    commission_asset: String,
+    time: i64,
+    is_buyer: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceAccount {
+    balances: Vec<BinanceBalance>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceBalance {
+    asset: String,
+    free: String,
+    locked: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceExchangeInfo {
+    symbols: Vec<BinanceSymbolInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceSymbolInfo {
+    symbol: String,
+    filters: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinanceError {
+    code: i64,
+    msg: String,
+}
+
+fn parse_f64(value: &str, field: &str) -> Result<f64> {
+    value
+        .parse()
+        .context(format!("Failed to parse {}: {}", field, value))
+}
+
+/// A symbol's `LOT_SIZE` filter: order quantities must be a whole number of
+/// steps and at least the minimum
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LotSize {
+    pub step_size: f64,
+    pub min_qty: f64,
+}
+
+impl LotSize {
+    pub fn new(step_size: f64, min_qty: f64) -> Self {
+        Self { step_size, min_qty }
+    }
+
+    /// Round down to a whole number of steps
+    pub fn round_down(&self, quantity: f64) -> f64 {
+        if self.step_size <= 0.0 {
+            return quantity;
+        }
+        // Nudge so quantities already on a step don't fall one step short
+        (quantity / self.step_size + 1e-9).floor() * self.step_size
+    }
+
+    /// Quantity formatted with the step's precision, as the exchange expects
+    pub fn format(&self, quantity: f64) -> String {
+        let mut decimals = 0;
+        while decimals < 16 {
+            let scaled = self.step_size * 10f64.powi(decimals);
+            if (scaled - scaled.round()).abs() < 1e-9 {
+                break;
+            }
+            decimals += 1;
+        }
+        format!("{:.*}", decimals as usize, quantity)
+    }
+}
+
+impl BinanceSymbolInfo {
+    fn lot_size(&self) -> Result<LotSize> {
+        let filter = self
+            .filters
+            .iter()
+            .find(|f| f["filterType"] == "LOT_SIZE")
+            .context(format!("No LOT_SIZE filter for {}", self.symbol))?;
+        let field = |name: &str| -> Result<f64> {
+            let value = filter[name]
+                .as_str()
+                .context(format!("Missing {} in LOT_SIZE filter", name))?;
+            parse_f64(value, name)
+        };
+        Ok(LotSize::new(field("stepSize")?, field("minQty")?))
+    }
+}
+
+impl BinanceTrade {
+    fn into_fill(self) -> Result<Fill> {
+        Ok(Fill {
+            order_id: self.order_id,
+            side: if self.is_buyer {
+                OrderSide::Buy
+            } else {
+                OrderSide::Sell
+            },
+            price: parse_f64(&self.price, "price")?,
+            quantity: parse_f64(&self.qty, "qty")?,
+            commission: parse_f64(&self.commission, "commission")?,
+            commission_asset: self.commission_asset,
+            symbol: self.symbol,
+            timestamp: self.time,
+        })
+    }
+}
+
+impl BinanceOrder {
+    fn into_order(self) -> Result<Order> {
+        let quantity = parse_f64(&self.orig_qty, "origQty")?;
+        let filled_quantity = parse_f64(&self.executed_qty, "executedQty")?;
+        let quote = parse_f64(&self.cummulative_quote_qty, "cummulativeQuoteQty")?;
+
+        let order_type = match self.order_type.as_str() {
+            "MARKET" => OrderType::Market,
+            "LIMIT" => OrderType::Limit {
+                price: parse_f64(&self.price, "price")?,
+            },
+            other => bail!("Unsupported order type: {}", other),
+        };
+
+        Ok(Order {
+            order_id: self.order_id,
+            client_order_id: self.client_order_id,
+            symbol: self.symbol,
+            side: OrderSide::parse(&self.side)?,
+            order_type,
+            quantity,
+            filled_quantity,
+            average_price: if filled_quantity > 0.0 {
+                quote / filled_quantity
+            } else {
+                0.0
+            },
+            status: OrderStatus::parse(&self.status)?,
+            timestamp: self.transact_time.or(self.time).unwrap_or(0),
+        })
+    }
+}
+
+/// Binance spot REST adapter for the signed `/api/v3` order endpoints
+///
+/// Requests are signed with HMAC-SHA256 as the exchange requires. The base
+/// URL defaults to the spot testnet; a production host has to be set
+/// explicitly with `with_base_url`. Order quantities are rounded down to the
+/// symbol's `LOT_SIZE` step, fetched from `exchangeInfo` on first use.
+pub struct BinanceBroker {
+    client: Client,
+    base_url: String,
+    api_key: String,
+    secret_key: String,
+    recv_window: u64,
+    lot_sizes: HashMap<String, LotSize>,
+}
+
+impl BinanceBroker {
+    pub const TESTNET_URL: &'static str = "https://testnet.binance.vision";
+    /// Most trades `myTrades` returns per request
+    const TRADES_LIMIT: usize = 1000;
+
+    pub fn new(api_key: &str, secret_key: &str) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: Self::TESTNET_URL.to_string(),
+            api_key: api_key.to_string(),
+            secret_key: secret_key.to_string(),
+            recv_window: 5000,
+            lot_sizes: HashMap::new(),
+        }
+    }
+
+    /// Credentials from `BINANCE_API_KEY` and `BINANCE_SECRET_KEY`
+    pub fn from_env() -> Result<Self> {
+        let api_key = std::env::var("BINANCE_API_KEY").context("BINANCE_API_KEY is not set")?;
+        let secret_key =
+            std::env::var("BINANCE_SECRET_KEY").context("BINANCE_SECRET_KEY is not set")?;
+        Ok(Self::new(&api_key, &secret_key))
+    }
+
+    /// Point the adapter at a different REST endpoint (e.g. testnet or a local mock)
+    pub fn with_base_url(mut self, base_url: &str) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Use a known lot size for `symbol` instead of fetching it
+    pub fn with_lot_size(mut self, symbol: &str, lot_size: LotSize) -> Self {
+        self.lot_sizes.insert(symbol.to_uppercase(), lot_size);
+        self
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// True when orders go to the spot testnet or a mock on this machine,
+    /// i.e. nowhere they can move real funds
+    pub fn is_sandbox(&self) -> bool {
+        let Ok(url) = reqwest::Url::parse(&self.base_url) else {
+            return false;
+        };
+        match url.host_str() {
+            Some("localhost" | "testnet.binance.vision") => true,
+            Some(host) => host
+                .trim_matches(|c| c == '[' || c == ']')
+                .parse::<std::net::IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback()),
+            None => false,
+        }
+    }
+
+    fn sign(&self, query: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret_key.as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(query.as_bytes());
+        format!("{:x}", mac.finalize().into_bytes())
+    }
+
+    /// Send a signed request and decode the JSON response
+    fn signed<T: for<'de> Deserialize<'de>>(
+        &self,
+        method: Method,
+        path: &str,
+        params: &[(&str, String)],
+    ) -> Result<T> {
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        let mut query: Vec<String> = params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        query.push(format!("recvWindow={}", self.recv_window));
+        query.push(format!("timestamp={}", timestamp));
+        let query = query.join("&");
+        let signature = self.sign(&query);
+
+        let url = format!(
+            "{}{}?{}&signature={}",
+            self.base_url, path, query, signature
+        );
+        let response = self
+            .client
+            .request(method, &url)
+            .header("X-MBX-APIKEY", &self.api_key)
+            .send()
+            .context(format!("Request to {} failed", path))?;
+        Self::decode(response, path)
+    }
+
+    /// Send an unauthenticated GET to a public endpoint
+    fn public<T: for<'de> Deserialize<'de>>(&self, path: &str, query: &str) -> Result<T> {
+        let url = format!("{}{}?{}", self.base_url, path, query);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .context(format!("Request to {} failed", path))?;
+        Self::decode(response, path)
+    }
+
+    fn decode<T: for<'de> Deserialize<'de>>(
+        response: reqwest::blocking::Response,
+        path: &str,
+    ) -> Result<T> {
+        let status = response.status();
+        let body = response.text()?;
+        if !status.is_success() {
+            match serde_json::from_str::<BinanceError>(&body) {
+                Ok(e) => bail!("Binance error {}: {}", e.code, e.msg),
+                Err(_) => bail!("HTTP {}: {}", status, body),
+            }
+        }
+
+        serde_json::from_str(&body).context(format!("Failed to parse response from {}", path))
+    }
+
+    fn lot_size(&mut self, symbol: &str) -> Result<LotSize> {
+        if let Some(lot_size) = self.lot_sizes.get(symbol) {
+            return Ok(*lot_size);
+        }
+
+        let info: BinanceExchangeInfo =
+            self.public("/api/v3/exchangeInfo", &format!("symbol={}", symbol))?;
+        let lot_size = info
+            .symbols
+            .iter()
+            .find(|s| s.symbol == symbol)
+            .context(format!("Symbol {} not listed in exchangeInfo", symbol))?
+            .lot_size()?;
+        self.lot_sizes.insert(symbol.to_string(), lot_size);
+        Ok(lot_size)
+    }
+}
+
+impl Broker for BinanceBroker {
+    fn name(&self) -> &str {
+        "binance"
+    }
+
+    fn submit_order(&mut self, request: &OrderRequest) -> Result<Order> {
+        let symbol = request.symbol.to_uppercase();
+        let lot_size = self.lot_size(&symbol)?;
+        let quantity = lot_size.round_down(request.quantity);
+        if quantity <= 0.0 || quantity < lot_size.min_qty {
+            bail!(
+                "Quantity {} is below the minimum {} for {}",
+                request.quantity,
+                lot_size.min_qty,
+                symbol
+            );
+        }
+
+        let mut params = vec![
+            ("symbol", symbol),
+            ("side", request.side.as_str().to_string()),
+            ("quantity", lot_size.format(quantity)),
+            ("newOrderRespType", "FULL".to_string()),
+        ];
+        match request.order_type {
+            OrderType::Market => params.push(("type", "MARKET".to_string())),
+            OrderType::Limit { price } => {
+                params.push(("type", "LIMIT".to_string()));
+                params.push(("timeInForce", "GTC".to_string()));
+                params.push(("price", price.to_string()));
+            }
+        }
+        if let Some(id) = &request.client_order_id {
+            params.push(("newClientOrderId", id.clone()));
+        }
+
+        let order: BinanceOrder = self.signed(Method::POST, "/api/v3/order", &params)?;
+        order.into_order()
+    }
+
+    fn cancel_order(&mut self, symbol: &str, order_id: u64) -> Result<Order> {
+        let params = [
+            ("symbol", symbol.to_uppercase()),
+            ("orderId", order_id.to_string()),
+        ];
+        let order: BinanceOrder = self.signed(Method::DELETE, "/api/v3/order", &params)?;
+        order.into_order()
+    }
+
+    fn order(&mut self, symbol: &str, order_id: u64) -> Result<Order> {
+        let params = [
+            ("symbol", symbol.to_uppercase()),
+            ("orderId", order_id.to_string()),
+        ];
+        let order: BinanceOrder = self.signed(Method::GET, "/api/v3/order", &params)?;
+        order.into_order()
+    }
+
+    fn open_orders(&mut self, symbol: &str) -> Result<Vec<Order>> {
+        let params = [("symbol", symbol.to_uppercase())];
+        let orders: Vec<BinanceOrder> = self.signed(Method::GET, "/api/v3/openOrders", &params)?;
+        orders.into_iter().map(BinanceOrder::into_order).collect()
+    }
+
+    fn balances(&mut self) -> Result<Vec<Balance>> {
+        let account: BinanceAccount = self.signed(Method::GET, "/api/v3/account", &[])?;
+        account
+            .balances
+            .into_iter()
+            .map(|b| {
+                Ok(Balance {
+                    free: parse_f64(&b.free, "free")?,
+                    locked: parse_f64(&b.locked, "locked")?,
+                    asset: b.asset,
+                })
+            })
+            .collect()
+    }
+
+    /// Every trade on `symbol`, paged by trade id since `myTrades` alone
+    /// only returns the most recent ones
+    fn fills(&mut self, symbol: &str) -> Result<Vec<Fill>> {
+        let symbol = symbol.to_uppercase();
+        let mut fills = Vec::new();
+        let mut from_id = 0;
+        loop {
+            let params = [
+                ("symbol", symbol.clone()),
+                ("fromId", from_id.to_string()),
+                ("limit", Self::TRADES_LIMIT.to_string()),
+            ];
+            let trades: Vec<BinanceTrade> =
+                self.signed(Method::GET, "/api/v3/myTrades", &params)?;
+            let full_page = trades.len() == Self::TRADES_LIMIT;
+            if let Some(last) = trades.last() {
+                from_id = last.id + 1;
+            }
+            for trade in trades {
+                fills.push(trade.into_fill()?);
+            }
+            if !full_page {
+                return Ok(fills);
+            }
+        }
+    }
+
+    fn order_fills(&mut self, symbol: &str, order_id: u64) -> Result<Vec<Fill>> {
+        let params = [
+            ("symbol", symbol.to_uppercase()),
+            ("orderId", order_id.to_string()),
+            ("limit", Self::TRADES_LIMIT.to_string()),
+        ];
+        let trades: Vec<BinanceTrade> = self.signed(Method::GET, "/api/v3/myTrades", &params)?;
+        trades.into_iter().map(BinanceTrade::into_fill).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    /// Mock exchange answering each request with the next canned (status, body)
+    /// and reporting the request line and API key header it received
+    fn serve_mock(responses: Vec<(u16, String)>) -> (String, mpsc::Receiver<(String, String)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+
+                let mut api_key = String::new();
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                    if let Some(value) = line.to_lowercase().strip_prefix("x-mbx-apikey:") {
+                        api_key = value.trim().to_string();
+                    }
+                    line.clear();
+                }
+                sender
+                    .send((request_line.trim().to_string(), api_key))
+                    .unwrap();
+
+                let response = format!(
+                    "HTTP/1.1 {} OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        (address, receiver)
+    }
+
+    #[test]
+    fn test_signature_matches_binance_example() {
+        // Example from the Binance API documentation
+        let broker = BinanceBroker::new(
+            "vmPUZE6mv9SD5VNHk4HlWFsOr6aKE2zvsw0MuIgwCIPy6utIco14y7Ju91duEh8A",
+            "NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j",
+        );
+        let query = "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000&timestamp=1499827319559";
+        assert_eq!(
+            broker.sign(query),
+            "c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71"
+        );
+    }
+
+    #[test]
+    fn test_sandbox_hosts() {
+        let broker = BinanceBroker::new("key", "secret");
+        assert!(broker.is_sandbox());
+        for url in [
+            "http://127.0.0.1:8080",
+            "http://localhost:9000/",
+            "http://[::1]:80",
+        ] {
+            assert!(BinanceBroker::new("key", "secret")
+                .with_base_url(url)
+                .is_sandbox());
+        }
+        for url in [
+            "https://api.binance.com",
+            "https://api.binance.us",
+            "not a url",
+        ] {
+            assert!(!BinanceBroker::new("key", "secret")
+                .with_base_url(url)
+                .is_sandbox());
+        }
+    }
+
+    #[test]
+    fn test_lot_size_rounding() {
+        let lot_size = LotSize::new(0.00001, 0.0001);
+        assert_eq!(lot_size.format(lot_size.round_down(0.123456789)), "0.12345");
+        // Already on a step despite floating point noise
+        assert_eq!(lot_size.format(lot_size.round_down(0.3)), "0.30000");
+        let whole = LotSize::new(1.0, 1.0);
+        assert_eq!(whole.format(whole.round_down(7.9)), "7");
+    }
+
+    #[test]
+    fn test_orders_against_mock() {
+        let info_body = r#"{"symbols":[{"symbol":"BTCUSDT","filters":[{"filterType":"PRICE_FILTER","tickSize":"0.01"},{"filterType":"LOT_SIZE","minQty":"0.00010000","maxQty":"9000.00000000","stepSize":"0.00001000"}]}]}"#;
+        let order_body = r#"{"symbol":"BTCUSDT","orderId":42,"clientOrderId":"abc","transactTime":1700000000000,"price":"0.00","origQty":"0.5","executedQty":"0.5","cummulativeQuoteQty":"15000.0","status":"FILLED","type":"MARKET","side":"BUY"}"#;
+        let account_body = r#"{"balances":[{"asset":"BTC","free":"0.5","locked":"0.0"},{"asset":"USDT","free":"85000.0","locked":"100.0"}]}"#;
+        let error_body = r#"{"code":-2011,"msg":"Unknown order sent."}"#;
+
+        let (url, requests) = serve_mock(vec![
+            (200, info_body.to_string()),
+            (200, order_body.to_string()),
+            (200, account_body.to_string()),
+            (400, error_body.to_string()),
+        ]);
+        let mut broker = BinanceBroker::new("key", "secret").with_base_url(&url);
+        assert!(broker.is_sandbox());
+
+        let order = broker
+            .submit_order(&OrderRequest::market("BTCUSDT", OrderSide::Buy, 0.500004))
+            .unwrap();
+        assert_eq!(order.order_id, 42);
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert_eq!(order.average_price, 30_000.0);
+
+        let (request_line, _) = requests.recv().unwrap();
+        assert!(request_line.starts_with("GET /api/v3/exchangeInfo?symbol=BTCUSDT"));
+
+        let (request_line, api_key) = requests.recv().unwrap();
+        assert!(request_line
+            .starts_with("POST /api/v3/order?symbol=BTCUSDT&side=BUY&quantity=0.50000&"));
+        assert!(request_line.contains("type=MARKET"));
+        assert!(request_line.contains("&signature="));
+        assert_eq!(api_key, "key");
+
+        // Below minQty: refused without a request, using the cached filter
+        let err = broker
+            .submit_order(&OrderRequest::market("BTCUSDT", OrderSide::Sell, 0.00005))
+            .unwrap_err();
+        assert!(err.to_string().contains("below the minimum"));
+
+        let balances = broker.balances().unwrap();
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances[1].total(), 85_100.0);
+
+        let err = broker.cancel_order("BTCUSDT", 7).unwrap_err();
+        assert!(err.to_string().contains("-2011"));
+    }
+
+    #[test]
+    fn test_fills_page_past_one_response() {
+        let trade = |id: u64, order_id: u64| {
+            format!(
+                r#"{{"id":{},"symbol":"BTCUSDT","orderId":{},"price":"30000.0","qty":"0.01","commission":"0.00001","commissionAsset":"BTC","time":1700000000000,"isBuyer":true}}"#,
+                id, order_id
+            )
+        };
+        let page = |ids: std::ops::Range<u64>| {
+            format!(
+                "[{}]",
+                ids.map(|id| trade(id, id / 10))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            )
+        };
+
+        let (url, requests) = serve_mock(vec![
+            (200, page(0..1000)),
+            (200, page(1000..1003)),
+            (200, format!("[{},{}]", trade(5, 42), trade(6, 42))),
+        ]);
+        let mut broker = BinanceBroker::new("key", "secret").with_base_url(&url);
+
+        let fills = broker.fills("btcusdt").unwrap();
+        assert_eq!(fills.len(), 1003);
+        assert_eq!(fills[1002].order_id, 100);
+        let (request_line, _) = requests.recv().unwrap();
+        assert!(
+            request_line.starts_with("GET /api/v3/myTrades?symbol=BTCUSDT&fromId=0&limit=1000&")
+        );
+        let (request_line, _) = requests.recv().unwrap();
+        assert!(request_line.contains("&fromId=1000&"));
+
+        let fills = broker.order_fills("BTCUSDT", 42).unwrap();
+        assert_eq!(fills.len(), 2);
+        assert!(fills
+            .iter()
+            .all(|f| f.order_id == 42 && f.side == OrderSide::Buy));
+        let (request_line, _) = requests.recv().unwrap();
+        assert!(request_line.starts_with("GET /api/v3/myTrades?symbol=BTCUSDT&orderId=42&"));
+    }
+}
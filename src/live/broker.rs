@@ -0,0 +1,212 @@
+use crate::data::OHLCV;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+impl OrderSide {
+    /// Exchange wire name (`BUY` / `SELL`)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderSide::Buy => "BUY",
+            OrderSide::Sell => "SELL",
+        }
+    }
+
+    pub fn parse(side: &str) -> Result<Self> {
+        match side.to_uppercase().as_str() {
+            "BUY" => Ok(OrderSide::Buy),
+            "SELL" => Ok(OrderSide::Sell),
+            _ => bail!("Unknown order side: {}", side),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OrderType {
+    Market,
+    /// Good-till-cancelled limit order
+    Limit {
+        price: f64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderStatus {
+    New,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    Rejected,
+}
+
+impl OrderStatus {
+    pub fn is_open(&self) -> bool {
+        matches!(self, OrderStatus::New | OrderStatus::PartiallyFilled)
+    }
+
+    /// Parse a Binance order status; expired orders count as cancelled
+    pub fn parse(status: &str) -> Result<Self> {
+        match status {
+            "NEW" | "PENDING_NEW" => Ok(OrderStatus::New),
+            "PARTIALLY_FILLED" => Ok(OrderStatus::PartiallyFilled),
+            "FILLED" => Ok(OrderStatus::Filled),
+            "CANCELED" | "PENDING_CANCEL" | "EXPIRED" | "EXPIRED_IN_MATCH" => {
+                Ok(OrderStatus::Canceled)
+            }
+            "REJECTED" => Ok(OrderStatus::Rejected),
+            _ => bail!("Unknown order status: {}", status),
+        }
+    }
+}
+
+/// An order to be placed with a broker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderRequest {
+    pub symbol: String,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    /// Quantity in the base asset
+    pub quantity: f64,
+    pub client_order_id: Option<String>,
+}
+
+impl OrderRequest {
+    pub fn market(symbol: &str, side: OrderSide, quantity: f64) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            side,
+            order_type: OrderType::Market,
+            quantity,
+            client_order_id: None,
+        }
+    }
+
+    pub fn limit(symbol: &str, side: OrderSide, quantity: f64, price: f64) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            side,
+            order_type: OrderType::Limit { price },
+            quantity,
+            client_order_id: None,
+        }
+    }
+
+    pub fn with_client_order_id(mut self, id: &str) -> Self {
+        self.client_order_id = Some(id.to_string());
+        self
+    }
+}
+
+/// Broker-side view of an order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Order {
+    pub order_id: u64,
+    pub client_order_id: String,
+    pub symbol: String,
+    pub side: OrderSide,
+    pub order_type: OrderType,
+    pub quantity: f64,
+    pub filled_quantity: f64,
+    /// Volume-weighted fill price (0 until something fills)
+    pub average_price: f64,
+    pub status: OrderStatus,
+    /// Time the order was accepted, in milliseconds
+    pub timestamp: i64,
+}
+
+impl Order {
+    pub fn remaining(&self) -> f64 {
+        (self.quantity - self.filled_quantity).max(0.0)
+    }
+}
+
+/// One execution against an order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fill {
+    pub order_id: u64,
+    pub symbol: String,
+    pub side: OrderSide,
+    pub price: f64,
+    pub quantity: f64,
+    pub commission: f64,
+    pub commission_asset: String,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Balance {
+    pub asset: String,
+    pub free: f64,
+    /// Reserved by open orders
+    pub locked: f64,
+}
+
+impl Balance {
+    pub fn total(&self) -> f64 {
+        self.free + self.locked
+    }
+}
+
+/// Order routing to an exchange, real or simulated
+///
+/// The paper and live runners only talk to this trait, so the same loop can
+/// drive the in-process simulator, an exchange testnet or a production account.
+pub trait Broker: Send {
+    /// Name for display/logging
+    fn name(&self) -> &str;
+
+    fn submit_order(&mut self, request: &OrderRequest) -> Result<Order>;
+
+    fn cancel_order(&mut self, symbol: &str, order_id: u64) -> Result<Order>;
+
+    /// Current state of a single order
+    fn order(&mut self, symbol: &str, order_id: u64) -> Result<Order>;
+
+    fn open_orders(&mut self, symbol: &str) -> Result<Vec<Order>>;
+
+    fn balances(&mut self) -> Result<Vec<Balance>>;
+
+    /// Executions on `symbol`, oldest first
+    fn fills(&mut self, symbol: &str) -> Result<Vec<Fill>>;
+
+    /// Executions of a single order, oldest first
+    fn order_fills(&mut self, symbol: &str, order_id: u64) -> Result<Vec<Fill>> {
+        Ok(self
+            .fills(symbol)?
+            .into_iter()
+            .filter(|f| f.order_id == order_id)
+            .collect())
+    }
+
+    /// Feed the latest closed bar; simulated venues fill resting orders
+    /// against it, real exchanges ignore it
+    fn on_bar(&mut self, _bar: &OHLCV) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Quote assets recognised when splitting a pair symbol, in match order
+const QUOTE_ASSETS: [&str; 9] = [
+    "USDT", "USDC", "BUSD", "FDUSD", "USD", "EUR", "BTC", "ETH", "BNB",
+];
+
+/// Split a pair such as `BTCUSDT` into base and quote assets
+///
+/// Symbols without a recognised quote asset (e.g. synthetic series) are
+/// treated as the base asset priced in USD.
+pub fn split_symbol(symbol: &str) -> (String, String) {
+    let symbol = symbol.to_uppercase();
+    for quote in QUOTE_ASSETS {
+        if let Some(base) = symbol.strip_suffix(quote) {
+            if !base.is_empty() {
+                return (base.to_string(), quote.to_string());
+            }
+        }
+    }
+    (symbol, "USD".to_string())
+}
@@ -0,0 +1,11 @@
+pub mod binance;
+pub mod broker;
+pub mod paper;
+pub mod simulated;
+
+pub use binance::{BinanceBroker, LotSize};
+pub use broker::{
+    split_symbol, Balance, Broker, Fill, Order, OrderRequest, OrderSide, OrderStatus, OrderType,
+};
+pub use paper::{PaperState, PaperTrader, PaperUpdate};
+pub use simulated::SimulatedExchange;
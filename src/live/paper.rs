@@ -0,0 +1,631 @@
+use crate::backtest::{
+    calculate_atr, save_trades_to_csv, StopLossMethod, Trade, TradingRules, TradingState,
+};
+use crate::data::OHLCV;
+use crate::live::broker::{split_symbol, Broker, Order, OrderRequest, OrderSide, OrderStatus};
+use crate::strategies::Strategy;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Share of equity the model and broker positions may differ by before the
+/// difference is reported; covers commission trimming and lot rounding
+const MISMATCH_TOLERANCE: f64 = 0.01;
+
+/// Everything needed to resume a paper trading session after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaperState {
+    pub strategy: String,
+    pub initial_capital: f64,
+    /// Open time of the last bar traded on
+    pub last_timestamp: Option<i64>,
+    pub last_close: f64,
+    pub trading: TradingState,
+    /// Base asset bought through the broker and not yet sold, from its fills
+    /// and net of any commission charged in the base asset
+    #[serde(default)]
+    pub broker_position: f64,
+    /// Routed orders whose fills have not been settled yet
+    #[serde(default)]
+    pub pending_orders: Vec<u64>,
+}
+
+impl PaperState {
+    pub fn new(strategy: &str, initial_capital: f64) -> Self {
+        Self {
+            strategy: strategy.to_string(),
+            initial_capital,
+            last_timestamp: None,
+            last_close: 0.0,
+            trading: TradingState::new(initial_capital),
+            broker_position: 0.0,
+            pending_orders: Vec::new(),
+        }
+    }
+
+    pub fn equity(&self) -> f64 {
+        self.trading.portfolio.equity(self.last_close)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .context(format!("Failed to read paper state: {}", path.display()))?;
+        serde_json::from_str(&json).context("Failed to parse paper state")
+    }
+
+    /// Write via a temporary file so a crash never leaves a truncated state
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Outcome of feeding one new bar to the paper trader
+#[derive(Debug, Clone)]
+pub struct PaperUpdate {
+    pub timestamp: i64,
+    pub close: f64,
+    pub target_position: f64,
+    pub equity: f64,
+    pub in_position: bool,
+    /// Trades closed on this bar
+    pub closed_trades: Vec<Trade>,
+    /// Orders sent to the broker on this bar
+    pub orders: Vec<Order>,
+    /// Orders the broker refused, failed to accept or did not fill in full
+    pub order_errors: Vec<String>,
+    /// Model position minus the position held at the broker, once all routed
+    /// orders have settled and the two disagree
+    pub position_mismatch: Option<f64>,
+}
+
+/// Runs a strategy forward one bar at a time with the backtest engine's rules
+///
+/// Signals are recomputed over the full history on every bar, so any
+/// `Strategy` works unchanged and path-dependent ones (e.g. crossovers that
+/// only enter on a cross) see the same state as `BacktestEngine`. Bars already covered by the history or by a
+/// restored state only extend the history and are not traded again.
+///
+/// With a broker attached, every position change is also sent as a market
+/// order through the `Broker` trait, so the same loop drives the simulated
+/// exchange or a real venue. Routed orders are settled from the broker's
+/// fills, exits sell what the broker actually holds, and any drift between
+/// the broker and the model (rejections, partial fills, latency) is reported
+/// on the update.
+pub struct PaperTrader {
+    strategy: Box<dyn Strategy>,
+    rules: TradingRules,
+    history: Vec<OHLCV>,
+    max_history: Option<usize>,
+    state: PaperState,
+    state_path: Option<PathBuf>,
+    trades_path: Option<PathBuf>,
+    broker: Option<Box<dyn Broker>>,
+    symbol: String,
+}
+
+impl PaperTrader {
+    /// `history` is the warm-up data; trading starts with the first newer bar
+    pub fn new(
+        strategy: Box<dyn Strategy>,
+        initial_capital: f64,
+        rules: TradingRules,
+        history: Vec<OHLCV>,
+    ) -> Self {
+        let state = PaperState::new(strategy.name(), initial_capital);
+        Self {
+            strategy,
+            rules,
+            history,
+            max_history: None,
+            state,
+            state_path: None,
+            trades_path: None,
+            broker: None,
+            symbol: String::new(),
+        }
+    }
+
+    /// Only pass the last `bars` bars to the strategy on each update
+    ///
+    /// Safe only when every signal depends on at most `bars` bars of lookback;
+    /// strategies that carry state from bar to bar can read differently from
+    /// the backtest once the window truncates.
+    pub fn with_max_history(mut self, bars: usize) -> Self {
+        self.max_history = Some(bars.max(1));
+        self
+    }
+
+    /// Persist state to `path` after every bar, resuming from it if it exists
+    pub fn with_state_file(mut self, path: &Path) -> Result<Self> {
+        if path.exists() {
+            let state = PaperState::load(path)?;
+            if state.strategy != self.strategy.name() {
+                bail!(
+                    "State file {} belongs to strategy '{}', not '{}'",
+                    path.display(),
+                    state.strategy,
+                    self.strategy.name()
+                );
+            }
+            self.state = state;
+        }
+        self.state_path = Some(path.to_path_buf());
+        Ok(self)
+    }
+
+    /// Rewrite the trade log at `path` whenever a trade closes
+    pub fn with_trades_file(mut self, path: &Path) -> Self {
+        self.trades_path = Some(path.to_path_buf());
+        self
+    }
+
+    /// Route position changes on `symbol` through `broker`
+    pub fn with_broker(mut self, symbol: &str, broker: Box<dyn Broker>) -> Self {
+        self.symbol = symbol.to_uppercase();
+        self.broker = Some(broker);
+        self
+    }
+
+    pub fn broker_mut(&mut self) -> Option<&mut (dyn Broker + 'static)> {
+        self.broker.as_deref_mut()
+    }
+
+    pub fn state(&self) -> &PaperState {
+        &self.state
+    }
+
+    pub fn history(&self) -> &[OHLCV] {
+        &self.history
+    }
+
+    pub fn strategy(&self) -> &dyn Strategy {
+        self.strategy.as_ref()
+    }
+
+    /// Feed a new closed bar; returns `None` for bars that were not traded
+    pub fn on_bar(&mut self, bar: OHLCV) -> Result<Option<PaperUpdate>> {
+        if let Some(last) = self.history.last() {
+            if bar.timestamp <= last.timestamp {
+                return Ok(None);
+            }
+        }
+
+        self.history.push(bar.clone());
+        if let Some(max_history) = self.max_history {
+            if self.history.len() > 2 * max_history {
+                let excess = self.history.len() - max_history;
+                self.history.drain(..excess);
+            }
+        }
+
+        if let Some(broker) = &mut self.broker {
+            broker.on_bar(&bar)?;
+        }
+
+        // Already traded before a restart
+        if matches!(self.state.last_timestamp, Some(ts) if bar.timestamp <= ts) {
+            return Ok(None);
+        }
+
+        let window_start = self
+            .max_history
+            .map_or(0, |bars| self.history.len().saturating_sub(bars));
+        let window = &self.history[window_start..];
+        let target_position = self
+            .strategy
+            .generate_signals(window)
+            .last()
+            .copied()
+            .unwrap_or(0.0);
+        let atr = self.current_atr(window);
+
+        let trades_before = self.state.trading.trades.len();
+        let position_before = self.state.trading.position_size;
+        let equity = self
+            .state
+            .trading
+            .on_bar(&bar, target_position, atr, &self.rules);
+        self.state.last_timestamp = Some(bar.timestamp);
+        self.state.last_close = bar.close;
+
+        let closed_trades = self.state.trading.trades[trades_before..].to_vec();
+        let mut order_errors = self.settle_orders();
+        let (orders, route_errors) =
+            self.route_orders(&bar, position_before, self.state.trading.position_size);
+        order_errors.extend(route_errors);
+        order_errors.extend(self.settle_orders());
+        let position_mismatch = self.position_mismatch(bar.close, equity);
+
+        if let Some(path) = &self.state_path {
+            self.state.save(path)?;
+        }
+        if !closed_trades.is_empty() {
+            if let Some(path) = &self.trades_path {
+                save_trades_to_csv(&self.state.trading.trades, path)?;
+            }
+        }
+
+        Ok(Some(PaperUpdate {
+            timestamp: bar.timestamp,
+            close: bar.close,
+            target_position,
+            equity,
+            in_position: self.state.trading.in_position(),
+            closed_trades,
+            orders,
+            order_errors,
+            position_mismatch,
+        }))
+    }
+
+    /// Send market orders for the change in modelled position
+    fn route_orders(
+        &mut self,
+        bar: &OHLCV,
+        before: Option<f64>,
+        after: Option<f64>,
+    ) -> (Vec<Order>, Vec<String>) {
+        let mut orders = Vec::new();
+        let mut errors = Vec::new();
+        if before == after {
+            return (orders, errors);
+        }
+        let Some(broker) = self.broker.as_deref_mut() else {
+            return (orders, errors);
+        };
+        let (base, quote) = split_symbol(&self.symbol);
+
+        let mut requests = Vec::new();
+        if before.is_some() {
+            // Exit with what the broker filled, not what the model assumed
+            let held = self.state.broker_position;
+            let free = free_balance(broker, &base).unwrap_or(held);
+            requests.push((OrderSide::Sell, held.min(free)));
+        }
+        if let Some(quantity) = after {
+            requests.push((OrderSide::Buy, quantity));
+        }
+
+        for (side, mut quantity) in requests {
+            if side == OrderSide::Buy {
+                let model = &self.rules.execution_model;
+                let unit_cost =
+                    model.execute_market_buy(bar.close) * (1.0 + model.commission_bps / 10000.0);
+                // Spend at most the paper portfolio's cash before the entry; the
+                // model lets commission push it slightly negative, an exchange will not
+                let capital = self.state.trading.portfolio.cash + quantity * unit_cost;
+                quantity = quantity.min(capital / unit_cost);
+                // The account may hold more than the paper portfolio, so its
+                // free balance is only a ceiling
+                if let Ok(free) = free_balance(broker, &quote) {
+                    // Shave a hair off so rounding never exceeds the free balance
+                    quantity = quantity.min(free / unit_cost * (1.0 - 1e-9));
+                }
+            }
+            if quantity <= 0.0 {
+                continue;
+            }
+
+            match broker.submit_order(&OrderRequest::market(&self.symbol, side, quantity)) {
+                Ok(order) => {
+                    self.state.pending_orders.push(order.order_id);
+                    orders.push(order);
+                }
+                Err(e) => errors.push(format!("{} {:.8}: {:#}", side.as_str(), quantity, e)),
+            }
+        }
+
+        (orders, errors)
+    }
+
+    /// Credit the fills of routed orders that are done, reporting any that
+    /// did not fill in full; orders still open stay pending
+    fn settle_orders(&mut self) -> Vec<String> {
+        let mut errors = Vec::new();
+        let Some(broker) = self.broker.as_deref_mut() else {
+            return errors;
+        };
+        if self.state.pending_orders.is_empty() {
+            return errors;
+        }
+        let (base, _) = split_symbol(&self.symbol);
+
+        let mut pending = Vec::new();
+        for order_id in std::mem::take(&mut self.state.pending_orders) {
+            let order = match broker.order(&self.symbol, order_id) {
+                Ok(order) => order,
+                Err(e) => {
+                    errors.push(format!("order {}: {:#}", order_id, e));
+                    continue;
+                }
+            };
+            if order.status.is_open() {
+                pending.push(order_id);
+                continue;
+            }
+            let fills = match broker.order_fills(&self.symbol, order_id) {
+                Ok(fills) => fills,
+                Err(e) => {
+                    errors.push(format!("fills of order {}: {:#}", order_id, e));
+                    pending.push(order_id);
+                    continue;
+                }
+            };
+
+            let received: f64 = fills
+                .iter()
+                .map(|f| {
+                    let fee = if f.commission_asset == base {
+                        f.commission
+                    } else {
+                        0.0
+                    };
+                    match f.side {
+                        OrderSide::Buy => f.quantity - fee,
+                        OrderSide::Sell => -f.quantity - fee,
+                    }
+                })
+                .sum();
+            self.state.broker_position += received;
+
+            if order.status != OrderStatus::Filled {
+                errors.push(format!(
+                    "{} {:.8}: {:?} with {:.8} filled",
+                    order.side.as_str(),
+                    order.quantity,
+                    order.status,
+                    order.filled_quantity
+                ));
+            }
+        }
+
+        self.state.pending_orders = pending;
+        errors
+    }
+
+    /// Model position minus broker position, if it matters at `price`
+    fn position_mismatch(&self, price: f64, equity: f64) -> Option<f64> {
+        if self.broker.is_none() || !self.state.pending_orders.is_empty() {
+            return None;
+        }
+        let model = self.state.trading.position_size.unwrap_or(0.0);
+        let difference = model - self.state.broker_position;
+        (difference.abs() * price > MISMATCH_TOLERANCE * equity.abs()).then_some(difference)
+    }
+
+    /// Write all trades closed so far
+    pub fn save_trades(&self, path: &Path) -> Result<()> {
+        save_trades_to_csv(&self.state.trading.trades, path)?;
+        Ok(())
+    }
+
+    fn current_atr(&self, window: &[OHLCV]) -> Option<f64> {
+        let StopLossMethod::ATR { period, .. } = self.rules.stop_loss else {
+            return None;
+        };
+
+        let hlc: Vec<(f64, f64, f64)> = window.iter().map(|b| (b.high, b.low, b.close)).collect();
+        calculate_atr(&hlc, period)
+            .last()
+            .copied()
+            .filter(|v| !v.is_nan())
+    }
+}
+
+fn free_balance(broker: &mut dyn Broker, asset: &str) -> Result<f64> {
+    Ok(broker
+        .balances()?
+        .into_iter()
+        .find(|b| b.asset == asset)
+        .map(|b| b.free)
+        .unwrap_or(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::{BacktestEngine, ExecutionModel};
+    use crate::data::{SyntheticConfig, SyntheticModel};
+    use crate::strategies::SMACrossover;
+
+    fn trader(history: &[OHLCV], rules: &TradingRules) -> PaperTrader {
+        PaperTrader::new(
+            Box::new(SMACrossover::new(10, 30)),
+            100_000.0,
+            rules.clone(),
+            history.to_vec(),
+        )
+    }
+
+    #[test]
+    fn test_paper_matches_backtest() {
+        let data = SyntheticModel::garch().generate(&SyntheticConfig::new(600, 11));
+        let mut rules = TradingRules::new(ExecutionModel::new(10.0, 5.0));
+        rules.stop_loss = StopLossMethod::Trailing(8.0);
+
+        // Warming up on bar 0 only: each later bar sees the same prefix as the engine
+        let mut paper = trader(&data[..1], &rules);
+        for bar in &data[1..] {
+            paper.on_bar(bar.clone()).unwrap();
+        }
+
+        let engine = BacktestEngine::new(&data[1..], 100_000.0, rules.execution_model.clone())
+            .with_rules(rules.clone());
+        let result = engine.run(&SMACrossover::new(10, 30));
+        let backtest_trades = result.trades.unwrap();
+
+        let paper_trades = &paper.state().trading.trades;
+        assert!(!paper_trades.is_empty());
+        // The engine closes its final open position; paper trading keeps it open
+        assert!(backtest_trades.len() - paper_trades.len() <= 1);
+        for (a, b) in paper_trades.iter().zip(&backtest_trades) {
+            assert_eq!(a.entry_timestamp, b.entry_timestamp);
+            assert_eq!(a.exit_timestamp, b.exit_timestamp);
+        }
+    }
+
+    #[test]
+    fn test_full_history_keeps_old_crossover() {
+        // Golden cross near bar 80, then a steady uptrend with no further cross
+        let data: Vec<OHLCV> = (0..600)
+            .map(|i| {
+                let price = if i < 60 {
+                    200.0 - i as f64
+                } else {
+                    140.0 + (i - 60) as f64
+                };
+                OHLCV::new(i as i64 * 3_600_000, price, price, price, price, 1.0)
+            })
+            .collect();
+        let rules = TradingRules::new(ExecutionModel::new(10.0, 5.0));
+
+        let mut full = trader(&data[..1], &rules);
+        let mut windowed = trader(&data[..1], &rules).with_max_history(100);
+        for bar in &data[1..] {
+            full.on_bar(bar.clone()).unwrap();
+            windowed.on_bar(bar.clone()).unwrap();
+        }
+
+        let engine = BacktestEngine::new(&data[1..], 100_000.0, rules.execution_model.clone())
+            .with_rules(rules.clone());
+        let backtest_trades = engine.run(&SMACrossover::new(10, 30)).trades.unwrap();
+        // Only the final forced exit
+        assert_eq!(backtest_trades.len(), 1);
+
+        assert!(full.state().trading.trades.is_empty());
+        assert_eq!(
+            full.state().trading.entry_timestamp,
+            Some(backtest_trades[0].entry_timestamp)
+        );
+        // Once the cross leaves a 100-bar window the strategy reads flat and exits
+        assert!(!windowed.state().trading.in_position());
+        assert_eq!(windowed.state().trading.trades.len(), 1);
+    }
+
+    #[test]
+    fn test_orders_routed_through_broker() {
+        use crate::live::SimulatedExchange;
+
+        let data = SyntheticModel::garch().generate(&SyntheticConfig::new(400, 2));
+        let rules = TradingRules::new(ExecutionModel::new(10.0, 5.0));
+        let exchange = SimulatedExchange::new("BTCUSDT", rules.execution_model.clone())
+            .with_balance("USDT", 100_000.0);
+
+        let mut paper = trader(&data[..50], &rules).with_broker("BTCUSDT", Box::new(exchange));
+        let mut routed = 0;
+        for bar in &data[50..] {
+            let update = paper.on_bar(bar.clone()).unwrap().unwrap();
+            assert!(update.order_errors.is_empty());
+            assert!(update.position_mismatch.is_none());
+            routed += update.orders.len();
+        }
+
+        let state = paper.state().clone();
+        assert!(routed > 0);
+        let broker = paper.broker_mut().unwrap();
+        assert_eq!(broker.fills("BTCUSDT").unwrap().len(), routed);
+
+        let balances = broker.balances().unwrap();
+        let btc = balances.iter().find(|b| b.asset == "BTC").unwrap().total();
+        let usdt = balances.iter().find(|b| b.asset == "USDT").unwrap().total();
+        assert!((btc - state.broker_position).abs() < 1e-9);
+        let broker_equity = usdt + btc * state.last_close;
+        // Entries are trimmed by the commission the model lets go negative
+        assert!((broker_equity - state.equity()).abs() / state.equity() < 0.01);
+    }
+
+    #[test]
+    fn test_broker_drift_is_reported() {
+        use crate::live::SimulatedExchange;
+
+        let data = SyntheticModel::garch().generate(&SyntheticConfig::new(400, 2));
+        let rules = TradingRules::new(ExecutionModel::new(10.0, 5.0));
+
+        // The account holds less than the paper portfolio, so entries fill short
+        let exchange = SimulatedExchange::new("BTCUSDT", rules.execution_model.clone())
+            .with_balance("USDT", 30_000.0);
+        let mut paper = trader(&data[..50], &rules).with_broker("BTCUSDT", Box::new(exchange));
+        let mut mismatches = 0;
+        for bar in &data[50..] {
+            let update = paper.on_bar(bar.clone()).unwrap().unwrap();
+            if let Some(difference) = update.position_mismatch {
+                assert!(update.in_position);
+                assert!(difference > 0.0);
+                mismatches += 1;
+            }
+        }
+        assert!(mismatches > 0);
+
+        // Latency leaves orders pending until a later bar fills them
+        let exchange = SimulatedExchange::new("BTCUSDT", rules.execution_model.clone())
+            .with_balance("USDT", 100_000.0)
+            .with_latency(std::time::Duration::from_secs(3600));
+        let mut paper = trader(&data[..50], &rules).with_broker("BTCUSDT", Box::new(exchange));
+        let mut pending_seen = false;
+        for bar in &data[50..] {
+            paper.on_bar(bar.clone()).unwrap();
+            pending_seen |= !paper.state().pending_orders.is_empty();
+        }
+        assert!(pending_seen);
+
+        let broker_position = paper.state().broker_position;
+        let balances = paper.broker_mut().unwrap().balances().unwrap();
+        let btc = balances.iter().find(|b| b.asset == "BTC").unwrap().total();
+        assert!((btc - broker_position).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_state_survives_restart() {
+        let dir = std::env::temp_dir().join("strataquant_paper_test");
+        std::fs::remove_dir_all(&dir).ok();
+        let state_path = dir.join("state.json");
+        let trades_path = dir.join("trades.csv");
+
+        let data = SyntheticModel::gbm().generate(&SyntheticConfig::new(400, 5));
+        let rules = TradingRules::new(ExecutionModel::new(10.0, 5.0));
+
+        let mut uninterrupted = trader(&data[..50], &rules);
+        for bar in &data[50..] {
+            uninterrupted.on_bar(bar.clone()).unwrap();
+        }
+
+        let mut first = trader(&data[..50], &rules)
+            .with_state_file(&state_path)
+            .unwrap()
+            .with_trades_file(&trades_path);
+        for bar in &data[50..200] {
+            first.on_bar(bar.clone()).unwrap();
+        }
+        drop(first);
+
+        // Restart from the same warm-up; replayed bars before the saved state are skipped
+        let mut second = trader(&data[..50], &rules)
+            .with_state_file(&state_path)
+            .unwrap()
+            .with_trades_file(&trades_path);
+        let traded = data[50..]
+            .iter()
+            .filter_map(|bar| second.on_bar(bar.clone()).unwrap())
+            .count();
+        assert_eq!(traded, 200);
+
+        assert_eq!(
+            second.state().trading.trades.len(),
+            uninterrupted.state().trading.trades.len()
+        );
+        assert!((second.state().equity() - uninterrupted.state().equity()).abs() < 1e-6);
+
+        if !second.state().trading.trades.is_empty() {
+            let csv = std::fs::read_to_string(&trades_path).unwrap();
+            assert!(csv.starts_with("trade_num,entry_timestamp"));
+        }
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,449 @@
+use crate::backtest::ExecutionModel;
+use crate::data::OHLCV;
+use crate::live::broker::{
+    split_symbol, Balance, Broker, Fill, Order, OrderRequest, OrderSide, OrderStatus, OrderType,
+};
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+struct SimOrder {
+    order: Order,
+    /// Market time from which the order may fill
+    ready_at: i64,
+    /// Funds locked for the order: quote asset for buys, base asset for sells
+    reserved: f64,
+}
+
+/// In-process exchange for a single symbol, filling orders against bars
+///
+/// Market orders fill at the close of the latest bar (or, with latency, the
+/// open of the first bar after the latency has passed) with the
+/// `ExecutionModel`'s slippage. Limit orders rest until a bar trades through
+/// their price and fill at the limit, or at the open if the bar gaps past it.
+/// Commission is charged in the quote asset, as in `Portfolio`.
+pub struct SimulatedExchange {
+    symbol: String,
+    base_asset: String,
+    quote_asset: String,
+    execution_model: ExecutionModel,
+    latency_ms: i64,
+    balances: BTreeMap<String, Balance>,
+    orders: Vec<SimOrder>,
+    fills: Vec<Fill>,
+    last_bar: Option<OHLCV>,
+    next_order_id: u64,
+}
+
+impl SimulatedExchange {
+    pub fn new(symbol: &str, execution_model: ExecutionModel) -> Self {
+        let (base_asset, quote_asset) = split_symbol(symbol);
+        let mut balances = BTreeMap::new();
+        for asset in [&base_asset, &quote_asset] {
+            balances.insert(
+                asset.clone(),
+                Balance {
+                    asset: asset.clone(),
+                    free: 0.0,
+                    locked: 0.0,
+                },
+            );
+        }
+
+        Self {
+            symbol: symbol.to_uppercase(),
+            base_asset,
+            quote_asset,
+            execution_model,
+            latency_ms: 0,
+            balances,
+            orders: Vec::new(),
+            fills: Vec::new(),
+            last_bar: None,
+            next_order_id: 1,
+        }
+    }
+
+    /// Set the free balance of an asset
+    pub fn with_balance(mut self, asset: &str, amount: f64) -> Self {
+        let asset = asset.to_uppercase();
+        self.balances.insert(
+            asset.clone(),
+            Balance {
+                asset,
+                free: amount,
+                locked: 0.0,
+            },
+        );
+        self
+    }
+
+    /// Delay, in market time, between accepting an order and letting it fill
+    ///
+    /// Orders are stamped with the open time of the latest bar.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency_ms = latency.as_millis() as i64;
+        self
+    }
+
+    pub fn balance(&self, asset: &str) -> f64 {
+        self.balances
+            .get(&asset.to_uppercase())
+            .map(|b| b.total())
+            .unwrap_or(0.0)
+    }
+
+    fn now(&self) -> i64 {
+        self.last_bar.as_ref().map(|b| b.timestamp).unwrap_or(0)
+    }
+
+    fn balance_mut(&mut self, asset: &str) -> &mut Balance {
+        self.balances
+            .entry(asset.to_string())
+            .or_insert_with(|| Balance {
+                asset: asset.to_string(),
+                free: 0.0,
+                locked: 0.0,
+            })
+    }
+
+    fn find(&self, order_id: u64) -> Result<usize> {
+        self.orders
+            .iter()
+            .position(|o| o.order.order_id == order_id)
+            .context(format!("Unknown order: {}", order_id))
+    }
+
+    /// Funds to lock for a request at the current reference price
+    fn reservation(&self, request: &OrderRequest, last_close: f64) -> f64 {
+        match request.side {
+            OrderSide::Sell => request.quantity,
+            OrderSide::Buy => {
+                let price = match request.order_type {
+                    OrderType::Market => self.execution_model.execute_market_buy(last_close),
+                    OrderType::Limit { price } => price,
+                };
+                request.quantity * price * (1.0 + self.execution_model.commission_bps / 10000.0)
+            }
+        }
+    }
+
+    /// Fill price for an order on `bar`, or `None` if it does not trade
+    fn fill_price(&self, order: &Order, bar: &OHLCV, at_close: bool) -> Option<f64> {
+        let reference = if at_close { bar.close } else { bar.open };
+        match (order.order_type, order.side) {
+            (OrderType::Market, OrderSide::Buy) => {
+                Some(self.execution_model.execute_market_buy(reference))
+            }
+            (OrderType::Market, OrderSide::Sell) => {
+                Some(self.execution_model.execute_market_sell(reference))
+            }
+            (OrderType::Limit { price }, OrderSide::Buy) => {
+                let low = if at_close { bar.close } else { bar.low };
+                (low <= price).then(|| price.min(reference))
+            }
+            (OrderType::Limit { price }, OrderSide::Sell) => {
+                let high = if at_close { bar.close } else { bar.high };
+                (high >= price).then(|| price.max(reference))
+            }
+        }
+    }
+
+    fn execute(&mut self, index: usize, price: f64, timestamp: i64) {
+        let (side, quantity, reserved, order_id) = {
+            let sim = &self.orders[index];
+            (
+                sim.order.side,
+                sim.order.remaining(),
+                sim.reserved,
+                sim.order.order_id,
+            )
+        };
+        let commission_rate = self.execution_model.commission_bps / 10000.0;
+        let notional = quantity * price;
+        let commission = notional * commission_rate;
+        let (base, quote) = (self.base_asset.clone(), self.quote_asset.clone());
+
+        match side {
+            OrderSide::Buy => {
+                let cost = notional + commission;
+                let quote_balance = self.balance_mut(&quote);
+                if quote_balance.free + reserved < cost {
+                    // Price moved beyond what was reserved and there is no spare cash
+                    quote_balance.locked -= reserved;
+                    quote_balance.free += reserved;
+                    self.orders[index].order.status = OrderStatus::Rejected;
+                    self.orders[index].reserved = 0.0;
+                    return;
+                }
+                quote_balance.locked -= reserved;
+                quote_balance.free += reserved - cost;
+                self.balance_mut(&base).free += quantity;
+            }
+            OrderSide::Sell => {
+                self.balance_mut(&base).locked -= reserved;
+                self.balance_mut(&quote).free += notional - commission;
+            }
+        }
+
+        let order = &mut self.orders[index];
+        order.reserved = 0.0;
+        order.order.filled_quantity = order.order.quantity;
+        order.order.average_price = price;
+        order.order.status = OrderStatus::Filled;
+
+        self.fills.push(Fill {
+            order_id,
+            symbol: self.symbol.clone(),
+            side,
+            price,
+            quantity,
+            commission,
+            commission_asset: quote,
+            timestamp,
+        });
+    }
+}
+
+impl Broker for SimulatedExchange {
+    fn name(&self) -> &str {
+        "simulated"
+    }
+
+    fn submit_order(&mut self, request: &OrderRequest) -> Result<Order> {
+        if request.symbol.to_uppercase() != self.symbol {
+            bail!(
+                "Simulated exchange trades {}, not {}",
+                self.symbol,
+                request.symbol
+            );
+        }
+        if request.quantity <= 0.0 {
+            bail!("Order quantity must be positive");
+        }
+        let last_bar = self
+            .last_bar
+            .clone()
+            .context("No market data yet: feed a bar before submitting orders")?;
+
+        let order_id = self.next_order_id;
+        self.next_order_id += 1;
+
+        let mut order = Order {
+            order_id,
+            client_order_id: request
+                .client_order_id
+                .clone()
+                .unwrap_or_else(|| format!("sim-{}", order_id)),
+            symbol: self.symbol.clone(),
+            side: request.side,
+            order_type: request.order_type,
+            quantity: request.quantity,
+            filled_quantity: 0.0,
+            average_price: 0.0,
+            status: OrderStatus::New,
+            timestamp: self.now(),
+        };
+
+        let reserved = self.reservation(request, last_bar.close);
+        let asset = match request.side {
+            OrderSide::Buy => self.quote_asset.clone(),
+            OrderSide::Sell => self.base_asset.clone(),
+        };
+        let balance = self.balance_mut(&asset);
+        if balance.free + 1e-12 < reserved {
+            order.status = OrderStatus::Rejected;
+            self.orders.push(SimOrder {
+                order: order.clone(),
+                ready_at: i64::MAX,
+                reserved: 0.0,
+            });
+            return Ok(order);
+        }
+        balance.free -= reserved;
+        balance.locked += reserved;
+
+        self.orders.push(SimOrder {
+            order,
+            ready_at: self.now() + self.latency_ms,
+            reserved,
+        });
+        let index = self.orders.len() - 1;
+
+        if self.latency_ms == 0 {
+            if let Some(price) = self.fill_price(&self.orders[index].order, &last_bar, true) {
+                self.execute(index, price, last_bar.timestamp);
+            }
+        }
+
+        Ok(self.orders[index].order.clone())
+    }
+
+    fn cancel_order(&mut self, _symbol: &str, order_id: u64) -> Result<Order> {
+        let index = self.find(order_id)?;
+        if !self.orders[index].order.status.is_open() {
+            bail!("Order {} is not open", order_id);
+        }
+
+        let asset = match self.orders[index].order.side {
+            OrderSide::Buy => self.quote_asset.clone(),
+            OrderSide::Sell => self.base_asset.clone(),
+        };
+        let reserved = self.orders[index].reserved;
+        let balance = self.balance_mut(&asset);
+        balance.locked -= reserved;
+        balance.free += reserved;
+
+        let sim = &mut self.orders[index];
+        sim.reserved = 0.0;
+        sim.order.status = OrderStatus::Canceled;
+        Ok(sim.order.clone())
+    }
+
+    fn order(&mut self, _symbol: &str, order_id: u64) -> Result<Order> {
+        let index = self.find(order_id)?;
+        Ok(self.orders[index].order.clone())
+    }
+
+    fn open_orders(&mut self, _symbol: &str) -> Result<Vec<Order>> {
+        Ok(self
+            .orders
+            .iter()
+            .filter(|o| o.order.status.is_open())
+            .map(|o| o.order.clone())
+            .collect())
+    }
+
+    fn balances(&mut self) -> Result<Vec<Balance>> {
+        Ok(self.balances.values().cloned().collect())
+    }
+
+    fn fills(&mut self, _symbol: &str) -> Result<Vec<Fill>> {
+        Ok(self.fills.clone())
+    }
+
+    fn on_bar(&mut self, bar: &OHLCV) -> Result<()> {
+        self.last_bar = Some(bar.clone());
+
+        for index in 0..self.orders.len() {
+            let sim = &self.orders[index];
+            if !sim.order.status.is_open() || sim.ready_at > bar.timestamp {
+                continue;
+            }
+            if let Some(price) = self.fill_price(&sim.order, bar, false) {
+                self.execute(index, price, bar.timestamp);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::Portfolio;
+
+    fn bar(timestamp: i64, open: f64, high: f64, low: f64, close: f64) -> OHLCV {
+        OHLCV::new(timestamp, open, high, low, close, 10.0)
+    }
+
+    fn exchange() -> SimulatedExchange {
+        SimulatedExchange::new("BTCUSDT", ExecutionModel::new(10.0, 5.0))
+            .with_balance("USDT", 100_000.0)
+    }
+
+    #[test]
+    fn test_market_order_matches_portfolio() {
+        let mut exchange = exchange();
+        exchange.on_bar(&bar(0, 100.0, 101.0, 99.0, 100.0)).unwrap();
+
+        let model = ExecutionModel::new(10.0, 5.0);
+        let price = model.execute_market_buy(100.0);
+        let quantity = 50_000.0 / price;
+
+        let order = exchange
+            .submit_order(&OrderRequest::market("BTCUSDT", OrderSide::Buy, quantity))
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert_eq!(order.average_price, price);
+
+        let mut portfolio = Portfolio::new(100_000.0);
+        portfolio.buy(quantity, price, model.commission_bps);
+        assert!((exchange.balance("USDT") - portfolio.cash).abs() < 1e-6);
+        assert!((exchange.balance("BTC") - portfolio.btc_position).abs() < 1e-12);
+        assert_eq!(exchange.fills("BTCUSDT").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_latency_delays_fill_to_next_bar() {
+        let mut exchange = exchange().with_latency(Duration::from_millis(500));
+        exchange.on_bar(&bar(0, 100.0, 101.0, 99.0, 100.0)).unwrap();
+
+        let order = exchange
+            .submit_order(&OrderRequest::market("BTCUSDT", OrderSide::Buy, 1.0))
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::New);
+        assert_eq!(exchange.open_orders("BTCUSDT").unwrap().len(), 1);
+
+        exchange
+            .on_bar(&bar(60_000, 110.0, 112.0, 108.0, 111.0))
+            .unwrap();
+        let order = exchange.order("BTCUSDT", order.order_id).unwrap();
+        assert_eq!(order.status, OrderStatus::Filled);
+        assert!((order.average_price - 110.0 * 1.0005).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_limit_orders_rest_and_cancel() {
+        let mut exchange = exchange();
+        exchange.on_bar(&bar(0, 100.0, 101.0, 99.0, 100.0)).unwrap();
+
+        let resting = exchange
+            .submit_order(&OrderRequest::limit("BTCUSDT", OrderSide::Buy, 1.0, 95.0))
+            .unwrap();
+        let cancelled = exchange
+            .submit_order(&OrderRequest::limit("BTCUSDT", OrderSide::Buy, 1.0, 90.0))
+            .unwrap();
+        assert_eq!(resting.status, OrderStatus::New);
+
+        let usdt = |e: &mut SimulatedExchange| {
+            e.balances()
+                .unwrap()
+                .into_iter()
+                .find(|b| b.asset == "USDT")
+                .unwrap()
+        };
+        assert!(usdt(&mut exchange).locked > 0.0);
+
+        exchange
+            .cancel_order("BTCUSDT", cancelled.order_id)
+            .unwrap();
+        exchange
+            .on_bar(&bar(60_000, 98.0, 99.0, 94.0, 96.0))
+            .unwrap();
+
+        let filled = exchange.order("BTCUSDT", resting.order_id).unwrap();
+        assert_eq!(filled.status, OrderStatus::Filled);
+        assert_eq!(filled.average_price, 95.0);
+        assert!(usdt(&mut exchange).locked.abs() < 1e-9);
+        assert!(exchange.cancel_order("BTCUSDT", resting.order_id).is_err());
+    }
+
+    #[test]
+    fn test_insufficient_funds_rejected() {
+        let mut exchange = exchange();
+        exchange.on_bar(&bar(0, 100.0, 101.0, 99.0, 100.0)).unwrap();
+
+        let order = exchange
+            .submit_order(&OrderRequest::market("BTCUSDT", OrderSide::Buy, 2_000.0))
+            .unwrap();
+        assert_eq!(order.status, OrderStatus::Rejected);
+
+        let sell = exchange
+            .submit_order(&OrderRequest::market("BTCUSDT", OrderSide::Sell, 1.0))
+            .unwrap();
+        assert_eq!(sell.status, OrderStatus::Rejected);
+        assert_eq!(exchange.balance("USDT"), 100_000.0);
+    }
+}
@@ -1,11 +1,33 @@
 use chrono::{DateTime, Utc};
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use std::path::Path;
-use strataquant::backtest::{BacktestEngine, ExecutionModel};
-use strataquant::data::{load_from_parquet, save_to_parquet, BinanceDownloader};
+use strataquant::backtest::{
+    align_series, BacktestEngine, CalendarRebalance, Contributions, DollarCostAveraging,
+    ExecutionModel, OrderStrategy, Portfolio, Rebalancer, RiskLimits, Schedule, StopLossMethod,
+    ThresholdRebalance, TradingRules,
+};
+use strataquant::data::{
+    block_bootstrap, build_bars, import_file, interval_to_millis, load_range_from_parquet,
+    load_trades_from_parquet, replay_bars, save_to_parquet, save_trades_to_parquet,
+    BinanceDownloader, ColumnMapping, DataCatalog, DatasetKey, DateRange, KlineStream,
+    KlineStreamConfig, SyntheticConfig, SyntheticModel, TimeframeContext, TimestampUnit, OHLCV,
+};
+use strataquant::live::{split_symbol, BinanceBroker, Broker, PaperTrader, SimulatedExchange};
+use strataquant::ml::{
+    FeaturePipeline, ModelKind, ModelWalkForward, OnnxStrategy, WalkForwardModel,
+};
 use strataquant::optimization::{ParameterSweep, WalkForward};
+use strataquant::pairs::{
+    engle_granger, Direction, HedgeMethod, LegTrade, PairsBacktest, PairsStrategy,
+};
 use strataquant::plotting;
-use strataquant::strategies::{BuyAndHold, SMACrossover};
+use strataquant::regime::{classifier_by_name, RegimeClassifier, RegimeReport};
+use strataquant::strategies::{
+    BollingerReversion, BuyAndHold, DonchianBreakout, EMACrossover, Ensemble, Filter, GridSpacing,
+    GridStrategy, HigherTimeframeFilter, LookaheadCheck, MACDCrossover, MultiLookbackMomentum,
+    ParamGrid, ParamKind, Params, RSIMeanReversion, RuleStrategy, SMACrossover, Script, Strategy,
+    StrategyDefinition, StrategyRegistry,
+};
 
 #[derive(Parser)]
 #[command(name = "strataquant")]
@@ -15,6 +37,96 @@ struct Cli {
     command: Commands,
 }
 
+/// Selects a dataset from the data catalog
+#[derive(Args)]
+struct DatasetArgs {
+    /// Exchange the data was sourced from
+    #[arg(long, default_value = "binance")]
+    exchange: String,
+
+    /// Trading pair symbol
+    #[arg(long, default_value = "BTCUSDT")]
+    symbol: String,
+
+    /// Bar interval (1d, 1h, 5m, etc)
+    #[arg(long, default_value = "1d")]
+    interval: String,
+}
+
+impl DatasetArgs {
+    fn key(&self) -> DatasetKey {
+        DatasetKey::new(&self.exchange, &self.symbol, &self.interval)
+    }
+}
+
+/// Restricts loaded data to a sub-period
+#[derive(Args)]
+struct DateRangeArgs {
+    /// First date to include (YYYY-MM-DD or RFC 3339)
+    #[arg(long)]
+    from: Option<String>,
+
+    /// Last date to include (YYYY-MM-DD or RFC 3339)
+    #[arg(long)]
+    to: Option<String>,
+}
+
+impl DateRangeArgs {
+    fn range(&self) -> DateRange {
+        match DateRange::parse(self.from.as_deref(), self.to.as_deref()) {
+            Ok(r) => r,
+            Err(e) => {
+                eprintln!("Invalid date range: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Parameters for a registered strategy; unset values use the strategy defaults
+#[derive(Args, Default)]
+struct StrategyArgs {
+    /// Strategy parameter as name=value, repeatable (see `strataquant strategies`)
+    #[arg(short = 'p', long = "param")]
+    params: Vec<String>,
+
+    /// Shorthand for --param fast=N
+    #[arg(short = 'f', long)]
+    fast: Option<usize>,
+
+    /// Shorthand for --param slow=N
+    #[arg(short = 'w', long)]
+    slow: Option<usize>,
+}
+
+impl StrategyArgs {
+    fn overrides(&self) -> Params {
+        let mut params = match Params::parse(&self.params) {
+            Ok(p) => p,
+            Err(e) => {
+                eprintln!("Invalid parameter: {:#}", e);
+                std::process::exit(1);
+            }
+        };
+        if let Some(fast) = self.fast {
+            params.set("fast", fast as f64);
+        }
+        if let Some(slow) = self.slow {
+            params.set("slow", slow as f64);
+        }
+        params
+    }
+}
+
+/// Parameter grid for optimizer runs
+#[derive(Args)]
+struct GridArgs {
+    /// Parameter range as name=start:end:step or name=value, repeatable;
+    /// parameters not given use the strategy's default sweep range
+    #[arg(short = 'g', long = "grid")]
+    grid: Vec<String>,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Download historical BTC data from Binance
@@ -30,21 +142,143 @@ enum Commands {
         /// Interval (1d, 1h, 5m, etc)
         #[arg(short, long, default_value = "1d")]
         interval: String,
+
+        /// Trading pair symbol
+        #[arg(long, default_value = "BTCUSDT")]
+        symbol: String,
+    },
+
+    /// Import OHLCV data from a CSV or Parquet file with custom columns
+    Import {
+        /// Source file (.csv or .parquet)
+        #[arg(short, long)]
+        input: String,
+
+        /// Destination Parquet file (default: store in the data catalog)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        #[command(flatten)]
+        dataset: DatasetArgs,
+
+        /// Column mapping (e.g., timestamp=open_time,volume=vol)
+        #[arg(long, default_value = "")]
+        columns: String,
+
+        /// Timestamp unit for numeric timestamps (s, ms, us, ns, auto)
+        #[arg(long, default_value = "auto")]
+        timestamp_unit: String,
+    },
+
+    /// Download aggregate trades from Binance
+    DownloadTrades {
+        /// Start date (YYYY-MM-DD)
+        #[arg(short, long)]
+        start: String,
+
+        /// End date (YYYY-MM-DD)
+        #[arg(short, long)]
+        end: String,
+
+        /// Trading pair symbol
+        #[arg(long, default_value = "BTCUSDT")]
+        symbol: String,
+    },
+
+    /// Build OHLCV bars from downloaded aggregate trades
+    BuildBars {
+        #[command(flatten)]
+        dataset: DatasetArgs,
+
+        #[command(flatten)]
+        range: DateRangeArgs,
+    },
+
+    /// Generate synthetic market data for stress testing
+    Synth {
+        /// Model (gbm, garch, jump, regime, bootstrap)
+        #[arg(long, default_value = "gbm")]
+        model: String,
+
+        /// Number of bars to generate
+        #[arg(long, default_value = "2000")]
+        bars: usize,
+
+        /// Random seed (same seed = same series)
+        #[arg(long, default_value = "42")]
+        seed: u64,
+
+        /// Bar interval (1d, 1h, 5m, etc)
+        #[arg(long, default_value = "1d")]
+        interval: String,
+
+        /// Catalog symbol to store under (default: MODEL-SEED)
+        #[arg(long)]
+        symbol: Option<String>,
+
+        /// Starting price
+        #[arg(long, default_value = "10000")]
+        start_price: f64,
+
+        /// Annualized drift override
+        #[arg(long)]
+        drift: Option<f64>,
+
+        /// Annualized volatility override
+        #[arg(long)]
+        volatility: Option<f64>,
+
+        /// Block length in bars (bootstrap model)
+        #[arg(long, default_value = "20")]
+        block_size: usize,
+
+        /// Symbol of the real Binance dataset to resample (bootstrap model)
+        #[arg(long, default_value = "BTCUSDT")]
+        source_symbol: String,
+    },
+
+    /// Inspect datasets in the data catalog
+    Data {
+        #[command(subcommand)]
+        action: DataCommands,
     },
 
     /// Run backtest on downloaded data
     Backtest {
-        /// Strategy to use (buy-and-hold, sma)
+        /// Strategy to use (buy-and-hold, sma, ema, rsi, donchian, macd, bollinger,
+        /// multi-momentum)
         #[arg(short = 't', long, default_value = "buy-and-hold")]
         strategy: String,
 
-        /// Fast SMA period (for SMA strategy)
-        #[arg(short = 'f', long, default_value = "50")]
-        fast: usize,
+        /// Rule file (TOML or JSON) defining entry/exit conditions; overrides --strategy
+        #[arg(long)]
+        strategy_file: Option<String>,
+
+        /// Rhai strategy script; overrides --strategy
+        #[arg(long, conflicts_with = "strategy_file")]
+        script: Option<String>,
+
+        /// Walk-forward model saved by the ml command; overrides --strategy
+        #[arg(long, conflicts_with_all = ["strategy_file", "script"])]
+        model_file: Option<String>,
+
+        /// ONNX model spec (TOML or JSON); overrides --strategy, and its
+        /// thresholds take --param enter_above=X / exit_below=Y
+        #[arg(long, conflicts_with_all = ["strategy_file", "script", "model_file"])]
+        onnx: Option<String>,
+
+        /// Trade only while a higher timeframe closes above its SMA, as
+        /// interval:period (e.g. 1d:50)
+        #[arg(long)]
+        trend_filter: Option<String>,
+
+        /// Break results down by market regime: trend, volatility, drawdown, hmm
+        /// (comma-separated or repeated)
+        #[arg(long, value_delimiter = ',')]
+        regime: Vec<String>,
 
-        /// Slow SMA period (for SMA strategy)
-        #[arg(short = 'w', long, default_value = "200")]
-        slow: usize,
+        #[command(flatten)]
+        params: StrategyArgs,
 
         /// Initial capital in USD
         #[arg(short, long, default_value = "100000")]
@@ -58,22 +292,44 @@ enum Commands {
         #[arg(short = 'l', long, default_value = "5")]
         slippage: f64,
 
+        #[command(flatten)]
+        dataset: DatasetArgs,
+
+        #[command(flatten)]
+        range: DateRangeArgs,
+
         /// Generate equity and drawdown charts
         #[arg(long)]
         plot: bool,
     },
 
-    /// Optimize SMA parameters with grid search
+    /// Optimize strategy parameters with grid search
     Optimize {
-        /// Fast period range (min-max)
-        #[arg(long, default_value = "20-100")]
-        fast_range: String,
+        /// Strategy to optimize
+        #[arg(short = 't', long, default_value = "sma")]
+        strategy: String,
+
+        /// Rhai strategy script to optimize instead of --strategy
+        #[arg(long)]
+        script: Option<String>,
+
+        /// ONNX model spec whose enter_above/exit_below thresholds to
+        /// optimize instead of --strategy
+        #[arg(long, conflicts_with = "script")]
+        onnx: Option<String>,
+
+        #[command(flatten)]
+        grid: GridArgs,
+
+        /// Fast period range (min-max), shorthand for --grid fast=min:max:step
+        #[arg(long)]
+        fast_range: Option<String>,
 
-        /// Slow period range (min-max)
-        #[arg(long, default_value = "50-200")]
-        slow_range: String,
+        /// Slow period range (min-max), shorthand for --grid slow=min:max:step
+        #[arg(long)]
+        slow_range: Option<String>,
 
-        /// Step size for parameter sweep
+        /// Step size for --fast-range and --slow-range
         #[arg(long, default_value = "10")]
         step: usize,
 
@@ -88,10 +344,32 @@ enum Commands {
         /// Slippage in basis points
         #[arg(short = 'l', long, default_value = "5")]
         slippage: f64,
+
+        #[command(flatten)]
+        dataset: DatasetArgs,
+
+        #[command(flatten)]
+        range: DateRangeArgs,
     },
 
     /// Walk-forward validation
     Walkforward {
+        /// Strategy to validate
+        #[arg(short = 't', long, default_value = "sma")]
+        strategy: String,
+
+        /// Rhai strategy script to validate instead of --strategy
+        #[arg(long)]
+        script: Option<String>,
+
+        /// ONNX model spec to validate instead of --strategy, re-tuning its
+        /// enter_above/exit_below thresholds on each training window
+        #[arg(long, conflicts_with = "script")]
+        onnx: Option<String>,
+
+        #[command(flatten)]
+        grid: GridArgs,
+
         /// Train/test split ratio (0.0-1.0)
         #[arg(long, default_value = "0.7")]
         train_ratio: f64,
@@ -107,10 +385,23 @@ enum Commands {
         /// Slippage in basis points
         #[arg(short = 'l', long, default_value = "5")]
         slippage: f64,
+
+        #[command(flatten)]
+        dataset: DatasetArgs,
+
+        #[command(flatten)]
+        range: DateRangeArgs,
     },
 
-    /// Compare all strategies
-    Compare {
+    /// Paper trade a strategy on new bars from a feed
+    Paper {
+        /// Strategy to use (see backtest)
+        #[arg(short = 't', long, default_value = "sma")]
+        strategy: String,
+
+        #[command(flatten)]
+        params: StrategyArgs,
+
         /// Initial capital in USD
         #[arg(short, long, default_value = "100000")]
         capital: f64,
@@ -122,379 +413,2526 @@ enum Commands {
         /// Slippage in basis points
         #[arg(short = 'l', long, default_value = "5")]
         slippage: f64,
-    },
-}
 
-fn main() {
-    let cli = Cli::parse();
+        /// Stop loss (none, fixed:5, trailing:8, atr:2:14, time:20)
+        #[arg(long, default_value = "none")]
+        stop_loss: String,
 
-    match cli.command {
-        Commands::Download {
-            start,
-            end,
-            interval,
-        } => {
-            download_data(&start, &end, &interval);
-        }
-        Commands::Backtest {
-            strategy,
-            fast,
-            slow,
-            capital,
-            commission,
-            slippage,
-            plot,
-        } => {
-            run_backtest(&strategy, fast, slow, capital, commission, slippage, plot);
-        }
-        Commands::Optimize {
-            fast_range,
-            slow_range,
-            step,
-            capital,
-            commission,
-            slippage,
-        } => {
-            run_optimization(
-                &fast_range,
-                &slow_range,
-                step,
-                capital,
-                commission,
-                slippage,
-            );
-        }
-        Commands::Walkforward {
-            train_ratio,
-            capital,
-            commission,
-            slippage,
-        } => {
-            run_walkforward(train_ratio, capital, commission, slippage);
-        }
-        Commands::Compare {
-            capital,
-            commission,
-            slippage,
-        } => {
-            run_comparison(capital, commission, slippage);
-        }
-    }
-}
+        /// Stop opening positions beyond this drawdown (percent)
+        #[arg(long, default_value = "30")]
+        max_drawdown: f64,
 
-fn download_data(start: &str, end: &str, interval: &str) {
-    println!("StrataQuant - Data Download");
-    println!("===========================\n");
+        #[command(flatten)]
+        dataset: DatasetArgs,
 
-    let downloader = BinanceDownloader::new("BTCUSDT", interval);
+        #[command(flatten)]
+        range: DateRangeArgs,
 
-    let start_dt = DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", start))
-        .expect("Invalid start date")
-        .with_timezone(&Utc);
+        /// Bar source: replay (stored data) or live (WebSocket feed)
+        #[arg(long, default_value = "replay")]
+        source: String,
 
-    let end_dt = DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", end))
-        .expect("Invalid end date")
-        .with_timezone(&Utc);
+        /// Parquet file to replay (default: the dataset after the warm-up)
+        #[arg(long)]
+        replay_file: Option<String>,
 
-    println!("Downloading BTC/USDT {} data", interval);
-    println!("From: {}", start_dt);
-    println!("To:   {}\n", end_dt);
+        /// Replay speed as a multiple of real time (0 = as fast as possible)
+        #[arg(long, default_value = "0")]
+        speed: f64,
 
-    match downloader.fetch_range(start_dt, end_dt) {
-        Ok(data) => {
-            println!("\nDownloaded {} candles", data.len());
+        /// Bars of stored history to warm up on
+        #[arg(long, default_value = "500")]
+        warmup: usize,
 
-            let filename = format!("data/processed/btc_{}.parquet", interval);
-            let output_path = Path::new(&filename);
-            println!("Saving to: {}", output_path.display());
+        /// WebSocket endpoint for the live source
+        #[arg(long, default_value = "wss://stream.binance.us:9443/ws")]
+        url: String,
 
-            match save_to_parquet(&data, output_path) {
-                Ok(_) => {
-                    let file_size = std::fs::metadata(output_path).unwrap().len();
-                    println!(
-                        "Success! File size: {:.2} MB",
-                        file_size as f64 / 1_024_000.0
-                    );
-                }
-                Err(e) => {
-                    eprintln!("Failed to save: {}", e);
-                    std::process::exit(1);
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!("Download failed: {}", e);
-            std::process::exit(1);
-        }
-    }
-}
+        /// Portfolio state file, resumed if it exists
+        #[arg(long)]
+        state: Option<String>,
 
-fn run_backtest(
-    strategy_name: &str,
-    fast: usize,
-    slow: usize,
-    capital: f64,
-    commission: f64,
-    slippage: f64,
-    plot: bool,
-) {
-    println!("StrataQuant - Backtest");
-    println!("======================\n");
+        /// Trade log CSV
+        #[arg(long)]
+        trades: Option<String>,
 
-    let data_path = Path::new("data/processed/btc_1d.parquet");
+        /// Order routing: simulated (in-process exchange) or binance (REST API,
+        /// credentials from BINANCE_API_KEY / BINANCE_SECRET_KEY)
+        #[arg(long, default_value = "simulated")]
+        broker: String,
 
-    if !data_path.exists() {
-        eprintln!("Error: Data file not found at {}", data_path.display());
-        eprintln!("Run 'strataquant download' first");
-        std::process::exit(1);
-    }
+        /// REST endpoint for the binance broker (testnet by default, or a local mock)
+        #[arg(long, default_value = BinanceBroker::TESTNET_URL)]
+        broker_url: String,
 
-    println!("Loading data from: {}", data_path.display());
-    let data = match load_from_parquet(data_path) {
-        Ok(d) => d,
-        Err(e) => {
-            eprintln!("Failed to load data: {}", e);
-            std::process::exit(1);
-        }
-    };
+        /// Let the binance broker send real orders to a production host
+        #[arg(long)]
+        live_orders: bool,
 
-    println!("Loaded {} candles", data.len());
-    println!(
-        "Period: {} to {}\n",
-        chrono::DateTime::from_timestamp_millis(data[0].timestamp).unwrap(),
-        chrono::DateTime::from_timestamp_millis(data[data.len() - 1].timestamp).unwrap()
-    );
+        /// Simulated order latency in milliseconds of market time
+        #[arg(long, default_value = "0")]
+        latency_ms: u64,
+    },
 
-    let execution_model = ExecutionModel::new(commission, slippage);
+    /// List registered strategies and their parameters
+    Strategies,
 
-    let (strategy_display, output_filename): (Box<dyn strataquant::strategies::Strategy>, &str) =
-        match strategy_name {
-            "buy-and-hold" => (Box::new(BuyAndHold::new()), "buy_and_hold.json"),
-            "sma" => (
-                Box::new(SMACrossover::new(fast, slow)),
-                &format!("sma_{}_{}.json", fast, slow),
-            ),
-            _ => {
-                eprintln!("Unknown strategy: {}", strategy_name);
-                eprintln!("Available strategies: buy-and-hold, sma");
-                std::process::exit(1);
-            }
-        };
+    /// Compare all strategies
+    Compare {
+        /// Rhai strategy script to add to the comparison (repeatable)
+        #[arg(long)]
+        script: Vec<String>,
 
-    println!("Strategy: {}", strategy_display.name());
-    println!("Description: {}", strategy_display.description());
-    println!("Initial capital: ${:.2}", capital);
-    println!("Commission: {} bps", commission);
-    println!("Slippage: {} bps\n", slippage);
+        /// ONNX model spec to add to the comparison (repeatable)
+        #[arg(long)]
+        onnx: Vec<String>,
 
-    let engine = BacktestEngine::new(data, capital, execution_model);
+        /// Initial capital in USD
+        #[arg(short, long, default_value = "100000")]
+        capital: f64,
 
-    println!("Running backtest...\n");
-    let result = engine.run(strategy_display.as_ref());
+        /// Commission in basis points
+        #[arg(short = 'm', long, default_value = "10")]
+        commission: f64,
 
-    println!("=== RESULTS ===");
-    println!("Initial capital: ${:>12.2}", result.initial_capital);
-    println!("Final equity:    ${:>12.2}", result.final_equity);
-    println!("Total return:    {:>11.2}%", result.total_return * 100.0);
-    println!("Sharpe ratio:    {:>12.2}", result.sharpe_ratio);
-    println!("Max drawdown:    {:>11.2}%", result.max_drawdown * 100.0);
-    println!("Total trades:    {:>12}", result.total_trades);
+        /// Slippage in basis points
+        #[arg(short = 'l', long, default_value = "5")]
+        slippage: f64,
 
-    let output_path = Path::new("results/backtests").join(output_filename);
-    match result.save_to_file(&output_path) {
-        Ok(_) => println!("\nSaved to: {}", output_path.display()),
-        Err(e) => eprintln!("Failed to save: {}", e),
-    }
+        #[command(flatten)]
+        dataset: DatasetArgs,
 
-    // Generate charts if --plot flag is set
-    if plot {
-        let chart_dir = Path::new("results/charts");
-        if let Err(e) = std::fs::create_dir_all(chart_dir) {
-            eprintln!("Failed to create charts directory: {}", e);
-        } else {
-            let strategy_prefix = strategy_name.replace("-", "_");
-            let equity_path = chart_dir.join(format!("{strategy_prefix}_{fast}_{slow}_equity.png"));
-            let drawdown_path =
-                chart_dir.join(format!("{strategy_prefix}_{fast}_{slow}_drawdown.png"));
+        #[command(flatten)]
+        range: DateRangeArgs,
+    },
 
-            match plotting::plot_equity_curve(&result, &equity_path) {
-                Ok(_) => println!("Equity chart: {}", equity_path.display()),
-                Err(e) => eprintln!("Failed to generate equity chart: {}", e),
-            }
+    /// Backtest a rebalanced or dollar-cost averaged portfolio
+    Rebalance {
+        /// Target BTC weight in percent; the rest is held in cash
+        #[arg(long, default_value = "60")]
+        weight: f64,
 
-            match plotting::plot_drawdown(&result, &drawdown_path) {
-                Ok(_) => println!("Drawdown chart: {}", drawdown_path.display()),
-                Err(e) => eprintln!("Failed to generate drawdown chart: {}", e),
+        /// Rebalance on the first bar of each period: daily, weekly, monthly,
+        /// quarterly or yearly (default: monthly)
+        #[arg(long, conflicts_with_all = ["drift", "dca"])]
+        every: Option<String>,
+
+        /// Rebalance when the weight drifts more than this many percentage points
+        #[arg(long, conflicts_with = "dca")]
+        drift: Option<f64>,
+
+        /// Dollar-cost average: invest all cash as it arrives, never sell
+        #[arg(long)]
+        dca: bool,
+
+        /// Cash deposited every --contribute-every period, in USD
+        #[arg(long, default_value = "0")]
+        contribute: f64,
+
+        /// Deposit period
+        #[arg(long, default_value = "monthly")]
+        contribute_every: String,
+
+        /// Initial capital in USD
+        #[arg(short, long, default_value = "100000")]
+        capital: f64,
+
+        /// Commission in basis points
+        #[arg(short = 'm', long, default_value = "10")]
+        commission: f64,
+
+        /// Slippage in basis points
+        #[arg(short = 'l', long, default_value = "5")]
+        slippage: f64,
+
+        #[command(flatten)]
+        dataset: DatasetArgs,
+
+        #[command(flatten)]
+        range: DateRangeArgs,
+    },
+
+    /// Backtest a grid bot with resting limit orders between two prices
+    Grid {
+        /// Lowest grid level
+        #[arg(long)]
+        lower: f64,
+
+        /// Highest grid level
+        #[arg(long)]
+        upper: f64,
+
+        /// Number of levels, including both bounds
+        #[arg(long, default_value = "10")]
+        levels: usize,
+
+        /// Level spacing: arithmetic (equal price steps) or geometric (equal
+        /// percentage steps)
+        #[arg(long, default_value = "arithmetic")]
+        spacing: String,
+
+        /// USD spent by each buy order
+        #[arg(long, default_value = "1000")]
+        order_size: f64,
+
+        /// Initial capital in USD
+        #[arg(short, long, default_value = "100000")]
+        capital: f64,
+
+        /// Commission in basis points
+        #[arg(short = 'm', long, default_value = "10")]
+        commission: f64,
+
+        /// Slippage in basis points, for the initial market buy
+        #[arg(short = 'l', long, default_value = "5")]
+        slippage: f64,
+
+        #[command(flatten)]
+        dataset: DatasetArgs,
+
+        #[command(flatten)]
+        range: DateRangeArgs,
+    },
+
+    /// Backtest a pairs (spread mean-reversion) strategy on two symbols
+    Pairs {
+        /// Symbol bought when the spread is long
+        #[arg(long)]
+        symbol_a: String,
+
+        /// Symbol sold when the spread is long
+        #[arg(long)]
+        symbol_b: String,
+
+        /// Exchange both datasets were sourced from
+        #[arg(long, default_value = "binance")]
+        exchange: String,
+
+        /// Bar interval of both datasets
+        #[arg(long, default_value = "1d")]
+        interval: String,
+
+        /// Hedge ratio estimate: ols (full sample, in-sample), rolling or kalman
+        #[arg(long, default_value = "rolling")]
+        hedge: String,
+
+        /// Rolling OLS window in bars
+        #[arg(long, default_value = "60")]
+        hedge_window: usize,
+
+        /// Z-score lookback in bars
+        #[arg(long, default_value = "20")]
+        window: usize,
+
+        /// Open when the spread z-score is beyond this
+        #[arg(long, default_value = "2.0")]
+        entry: f64,
+
+        /// Close when the spread z-score is back within this
+        #[arg(long, default_value = "0.5")]
+        exit: f64,
+
+        /// Close and stand aside when the z-score is beyond this
+        #[arg(long)]
+        stop: Option<f64>,
+
+        /// Gross exposure of both legs in percent of equity
+        #[arg(long, default_value = "100")]
+        allocation: f64,
+
+        /// Lagged differences in the Engle-Granger ADF regression
+        #[arg(long, default_value = "1")]
+        lags: usize,
+
+        /// Initial capital in USD
+        #[arg(short, long, default_value = "100000")]
+        capital: f64,
+
+        /// Commission in basis points
+        #[arg(short = 'm', long, default_value = "10")]
+        commission: f64,
+
+        /// Slippage in basis points
+        #[arg(short = 'l', long, default_value = "5")]
+        slippage: f64,
+
+        #[command(flatten)]
+        range: DateRangeArgs,
+    },
+
+    /// Train an ML signal model walk-forward and backtest it out of sample
+    Ml {
+        /// Model: logistic, ridge or boosting
+        #[arg(long, default_value = "logistic")]
+        model: String,
+
+        /// Comma-separated features as name:n (return, momentum, sma, rsi,
+        /// volatility, atr, volume, zscore); defaults to a standard set
+        #[arg(long)]
+        features: Option<String>,
+
+        /// Bars in each training window
+        #[arg(long, default_value = "500")]
+        train: usize,
+
+        /// Bars traded by each model before it is retrained
+        #[arg(long, default_value = "100")]
+        test: usize,
+
+        /// Go long above this prediction (default 0.5 for logistic and
+        /// boosting, 0 for ridge)
+        #[arg(long)]
+        threshold: Option<f64>,
+
+        /// Initial capital in USD
+        #[arg(short, long, default_value = "100000")]
+        capital: f64,
+
+        /// Commission in basis points
+        #[arg(short = 'm', long, default_value = "10")]
+        commission: f64,
+
+        /// Slippage in basis points
+        #[arg(short = 'l', long, default_value = "5")]
+        slippage: f64,
+
+        #[command(flatten)]
+        dataset: DatasetArgs,
+
+        #[command(flatten)]
+        range: DateRangeArgs,
+    },
+
+    /// Check a strategy for look-ahead bias by rerunning it on truncated data
+    CheckLookahead {
+        /// Strategy to check
+        #[arg(short = 't', long, default_value = "sma")]
+        strategy: String,
+
+        /// Rule file (TOML or JSON) to check instead of --strategy
+        #[arg(long)]
+        strategy_file: Option<String>,
+
+        /// Rhai strategy script to check instead of --strategy
+        #[arg(long, conflicts_with = "strategy_file")]
+        script: Option<String>,
+
+        #[command(flatten)]
+        params: StrategyArgs,
+
+        /// First bar to check (skips indicator warm-up)
+        #[arg(long, default_value = "0")]
+        start: usize,
+
+        /// Check every n-th bar
+        #[arg(long, default_value = "1")]
+        every: usize,
+
+        #[command(flatten)]
+        dataset: DatasetArgs,
+
+        #[command(flatten)]
+        range: DateRangeArgs,
+    },
+}
+
+#[derive(Subcommand)]
+enum DataCommands {
+    /// List all datasets in the catalog
+    List,
+
+    /// Show metadata for a dataset
+    Info {
+        #[command(flatten)]
+        dataset: DatasetArgs,
+    },
+}
+
+fn main() {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Commands::Download {
+            start,
+            end,
+            interval,
+            symbol,
+        } => {
+            download_data(&start, &end, &interval, &symbol);
+        }
+        Commands::Import {
+            input,
+            output,
+            dataset,
+            columns,
+            timestamp_unit,
+        } => {
+            import_data(
+                &input,
+                output.as_deref(),
+                &dataset,
+                &columns,
+                &timestamp_unit,
+            );
+        }
+        Commands::DownloadTrades { start, end, symbol } => {
+            download_trades(&start, &end, &symbol);
+        }
+        Commands::BuildBars { dataset, range } => {
+            build_bars_from_trades(&dataset, &range);
+        }
+        Commands::Synth {
+            model,
+            bars,
+            seed,
+            interval,
+            symbol,
+            start_price,
+            drift,
+            volatility,
+            block_size,
+            source_symbol,
+        } => {
+            let params = SynthParams {
+                bars,
+                seed,
+                start_price,
+                drift,
+                volatility,
+                block_size,
+            };
+            generate_synthetic(
+                &model,
+                &interval,
+                symbol.as_deref(),
+                &source_symbol,
+                &params,
+            );
+        }
+        Commands::Data { action } => match action {
+            DataCommands::List => list_datasets(),
+            DataCommands::Info { dataset } => show_dataset_info(&dataset),
+        },
+        Commands::Backtest {
+            strategy,
+            strategy_file,
+            script,
+            model_file,
+            onnx,
+            trend_filter,
+            regime,
+            params,
+            capital,
+            commission,
+            slippage,
+            dataset,
+            range,
+            plot,
+        } => {
+            run_backtest(
+                &strategy,
+                strategy_file.as_deref(),
+                script.as_deref(),
+                model_file.as_deref(),
+                onnx.as_deref(),
+                trend_filter.as_deref(),
+                &regime,
+                &params,
+                capital,
+                commission,
+                slippage,
+                &dataset,
+                &range,
+                plot,
+            );
+        }
+        Commands::Optimize {
+            strategy,
+            script,
+            onnx,
+            grid,
+            fast_range,
+            slow_range,
+            step,
+            capital,
+            commission,
+            slippage,
+            dataset,
+            range,
+        } => {
+            let definition = strategy_definition(&strategy, script.as_deref(), onnx.as_deref());
+            let mut grid = parse_grid(&definition, &grid);
+            if let Some(fast_range) = fast_range {
+                let (min, max) = parse_range(&fast_range);
+                grid.set_values(
+                    "fast",
+                    (min..=max).step_by(step).map(|v| v as f64).collect(),
+                );
+            }
+            if let Some(slow_range) = slow_range {
+                let (min, max) = parse_range(&slow_range);
+                grid.set_values(
+                    "slow",
+                    (min..=max).step_by(step).map(|v| v as f64).collect(),
+                );
+            }
+            run_optimization(
+                &definition,
+                &grid,
+                capital,
+                commission,
+                slippage,
+                &dataset,
+                &range,
+            );
+        }
+        Commands::Walkforward {
+            strategy,
+            script,
+            onnx,
+            grid,
+            train_ratio,
+            capital,
+            commission,
+            slippage,
+            dataset,
+            range,
+        } => {
+            let definition = strategy_definition(&strategy, script.as_deref(), onnx.as_deref());
+            let grid = parse_grid(&definition, &grid);
+            run_walkforward(
+                &definition,
+                &grid,
+                train_ratio,
+                capital,
+                commission,
+                slippage,
+                &dataset,
+                &range,
+            );
+        }
+        Commands::Strategies => list_strategies(),
+        Commands::Paper {
+            strategy,
+            params,
+            capital,
+            commission,
+            slippage,
+            stop_loss,
+            max_drawdown,
+            dataset,
+            range,
+            source,
+            replay_file,
+            speed,
+            warmup,
+            url,
+            state,
+            trades,
+            broker,
+            broker_url,
+            live_orders,
+            latency_ms,
+        } => {
+            let stop_loss = match StopLossMethod::parse(&stop_loss) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+            let mut rules = TradingRules::new(ExecutionModel::new(commission, slippage));
+            rules.stop_loss = stop_loss;
+            rules.risk_limits = RiskLimits {
+                max_drawdown_threshold: max_drawdown / 100.0,
+                ..RiskLimits::default()
+            };
+
+            let registry = StrategyRegistry::builtin();
+            let definition = lookup_strategy(&registry, &strategy);
+            let (strategy, file_stem) = select_strategy(definition, &params);
+            let options = PaperOptions {
+                source,
+                replay_file,
+                speed,
+                warmup,
+                url,
+                state_path: state
+                    .unwrap_or_else(|| format!("results/paper/{}_state.json", file_stem)),
+                trades_path: trades
+                    .unwrap_or_else(|| format!("results/paper/{}_trades.csv", file_stem)),
+                broker,
+                broker_url,
+                live_orders,
+                latency_ms,
+            };
+
+            run_paper(strategy, capital, rules, &dataset, &range, &options);
+        }
+        Commands::Compare {
+            script,
+            onnx,
+            capital,
+            commission,
+            slippage,
+            dataset,
+            range,
+        } => {
+            run_comparison(
+                &script, &onnx, capital, commission, slippage, &dataset, &range,
+            );
+        }
+        Commands::Rebalance {
+            weight,
+            every,
+            drift,
+            dca,
+            contribute,
+            contribute_every,
+            capital,
+            commission,
+            slippage,
+            dataset,
+            range,
+        } => {
+            let (rebalancer, file_stem) = select_rebalancer(weight, every.as_deref(), drift, dca);
+            let contributions = (contribute > 0.0)
+                .then(|| Contributions::new(contribute, parse_schedule(&contribute_every)));
+            run_rebalance(
+                rebalancer.as_ref(),
+                &file_stem,
+                contributions.as_ref(),
+                capital,
+                commission,
+                slippage,
+                &dataset,
+                &range,
+            );
+        }
+        Commands::Grid {
+            lower,
+            upper,
+            levels,
+            spacing,
+            order_size,
+            capital,
+            commission,
+            slippage,
+            dataset,
+            range,
+        } => {
+            let grid = select_grid(lower, upper, levels, &spacing, order_size);
+            run_grid(&grid, capital, commission, slippage, &dataset, &range);
+        }
+        Commands::Pairs {
+            symbol_a,
+            symbol_b,
+            exchange,
+            interval,
+            hedge,
+            hedge_window,
+            window,
+            entry,
+            exit,
+            stop,
+            allocation,
+            lags,
+            capital,
+            commission,
+            slippage,
+            range,
+        } => {
+            let strategy = select_pairs_strategy(&hedge, hedge_window, window, entry, exit, stop);
+            if allocation <= 0.0 {
+                eprintln!("--allocation must be greater than 0");
+                std::process::exit(1);
+            }
+            let datasets = [&symbol_a, &symbol_b].map(|symbol| DatasetArgs {
+                exchange: exchange.clone(),
+                symbol: symbol.clone(),
+                interval: interval.clone(),
+            });
+            run_pairs(
+                &strategy,
+                &datasets,
+                allocation / 100.0,
+                lags,
+                capital,
+                commission,
+                slippage,
+                &range,
+            );
+        }
+        Commands::Ml {
+            model,
+            features,
+            train,
+            test,
+            threshold,
+            capital,
+            commission,
+            slippage,
+            dataset,
+            range,
+        } => {
+            let (kind, pipeline) = select_model(&model, features.as_deref());
+            if train == 0 || test == 0 {
+                eprintln!("--train and --test must be at least 1");
+                std::process::exit(1);
+            }
+            run_ml(
+                kind, &pipeline, train, test, threshold, capital, commission, slippage, &dataset,
+                &range,
+            );
+        }
+        Commands::CheckLookahead {
+            strategy,
+            strategy_file,
+            script,
+            params,
+            start,
+            every,
+            dataset,
+            range,
+        } => {
+            run_lookahead_check(
+                &strategy,
+                strategy_file.as_deref(),
+                script.as_deref(),
+                &params,
+                start,
+                every,
+                &dataset,
+                &range,
+            );
+        }
+    }
+}
+
+fn download_data(start: &str, end: &str, interval: &str, symbol: &str) {
+    println!("StrataQuant - Data Download");
+    println!("===========================\n");
+
+    let downloader = BinanceDownloader::new(symbol, interval);
+
+    let start_dt = DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", start))
+        .expect("Invalid start date")
+        .with_timezone(&Utc);
+
+    let end_dt = DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", end))
+        .expect("Invalid end date")
+        .with_timezone(&Utc);
+
+    println!("Downloading {} {} data", symbol, interval);
+    println!("From: {}", start_dt);
+    println!("To:   {}\n", end_dt);
+
+    match downloader.fetch_range(start_dt, end_dt) {
+        Ok(data) => {
+            println!("\nDownloaded {} candles", data.len());
+
+            let catalog = DataCatalog::default();
+            let key = DatasetKey::new("binance", symbol, interval);
+            let output_path = catalog.dataset_path(&key);
+            println!("Saving to: {}", output_path.display());
+
+            match catalog.save(&key, &data, "binance-api") {
+                Ok(_) => {
+                    let file_size = std::fs::metadata(&output_path).unwrap().len();
+                    println!(
+                        "Success! File size: {:.2} MB",
+                        file_size as f64 / 1_024_000.0
+                    );
+                }
+                Err(e) => {
+                    eprintln!("Failed to save: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Err(e) => {
+            eprintln!("Download failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn import_data(
+    input: &str,
+    output: Option<&str>,
+    dataset: &DatasetArgs,
+    columns: &str,
+    timestamp_unit: &str,
+) {
+    println!("StrataQuant - Data Import");
+    println!("=========================\n");
+
+    let mapping = match ColumnMapping::parse(columns) {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("Invalid column mapping: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let unit = match TimestampUnit::parse(timestamp_unit) {
+        Ok(u) => u,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let input_path = Path::new(input);
+    println!("Importing from: {}", input_path.display());
+
+    let data = match import_file(input_path, &mapping, unit) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("Import failed: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("Imported {} candles", data.len());
+    if let (Some(first), Some(last)) = (data.first(), data.last()) {
+        println!(
+            "Period: {} to {}",
+            chrono::DateTime::from_timestamp_millis(first.timestamp).unwrap(),
+            chrono::DateTime::from_timestamp_millis(last.timestamp).unwrap()
+        );
+    }
+
+    let saved = match output {
+        Some(output) => {
+            println!("Saving to: {}", output);
+            save_to_parquet(&data, Path::new(output))
+        }
+        None => {
+            let catalog = DataCatalog::default();
+            let key = dataset.key();
+            println!("Saving to: {}", catalog.dataset_path(&key).display());
+            catalog
+                .save(&key, &data, &format!("import:{}", input))
+                .map(|_| ())
+        }
+    };
+
+    if let Err(e) = saved {
+        eprintln!("Failed to save: {}", e);
+        std::process::exit(1);
+    }
+
+    println!("Success!");
+}
+
+fn download_trades(start: &str, end: &str, symbol: &str) {
+    println!("StrataQuant - Aggregate Trade Download");
+    println!("======================================\n");
+
+    let downloader = BinanceDownloader::new(symbol, "1m");
+
+    let start_dt = DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", start))
+        .expect("Invalid start date")
+        .with_timezone(&Utc);
+
+    let end_dt = DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", end))
+        .expect("Invalid end date")
+        .with_timezone(&Utc);
+
+    println!("Downloading {} aggregate trades", symbol);
+    println!("From: {}", start_dt);
+    println!("To:   {}\n", end_dt);
+
+    let trades = match downloader.fetch_agg_trades(start_dt, end_dt) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("Download failed: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("\nDownloaded {} aggregate trades", trades.len());
+
+    let output_path = DataCatalog::default().trades_path("binance", symbol);
+    println!("Saving to: {}", output_path.display());
+
+    if let Err(e) = save_trades_to_parquet(&trades, &output_path) {
+        eprintln!("Failed to save: {}", e);
+        std::process::exit(1);
+    }
+
+    println!("Success!");
+}
+
+fn build_bars_from_trades(dataset: &DatasetArgs, range: &DateRangeArgs) {
+    println!("StrataQuant - Bar Builder");
+    println!("=========================\n");
+
+    let catalog = DataCatalog::default();
+    let key = dataset.key();
+
+    let interval_ms = match interval_to_millis(&key.interval) {
+        Ok(ms) => ms,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let trades_path = catalog.trades_path(&key.exchange, &key.symbol);
+    println!("Loading trades from: {}", trades_path.display());
+
+    let range = range.range();
+    let trades = match load_trades_from_parquet(&trades_path) {
+        Ok(t) => t
+            .into_iter()
+            .filter(|trade| range.contains(trade.timestamp))
+            .collect::<Vec<_>>(),
+        Err(e) => {
+            eprintln!("Failed to load trades: {}", e);
+            eprintln!("Run 'strataquant download-trades' first");
+            std::process::exit(1);
+        }
+    };
+
+    let data: Vec<OHLCV> = build_bars(&trades, interval_ms)
+        .into_iter()
+        .map(|b| b.bar)
+        .collect();
+
+    println!(
+        "Built {} {} bars from {} trades",
+        data.len(),
+        key.interval,
+        trades.len()
+    );
+    println!("Saving to: {}", catalog.dataset_path(&key).display());
+
+    if let Err(e) = catalog.save(&key, &data, "aggtrades") {
+        eprintln!("Failed to save: {}", e);
+        std::process::exit(1);
+    }
+
+    println!("Success!");
+}
+
+struct SynthParams {
+    bars: usize,
+    seed: u64,
+    start_price: f64,
+    drift: Option<f64>,
+    volatility: Option<f64>,
+    block_size: usize,
+}
+
+fn generate_synthetic(
+    model_name: &str,
+    interval: &str,
+    symbol: Option<&str>,
+    source_symbol: &str,
+    params: &SynthParams,
+) {
+    println!("StrataQuant - Synthetic Data");
+    println!("============================\n");
+
+    if params.bars < 2 {
+        eprintln!("--bars must be at least 2, got {}", params.bars);
+        std::process::exit(1);
+    }
+    if !(params.start_price > 0.0 && params.start_price.is_finite()) {
+        eprintln!(
+            "--start-price must be a positive number, got {}",
+            params.start_price
+        );
+        std::process::exit(1);
+    }
+
+    let interval_ms = match interval_to_millis(interval) {
+        Ok(ms) => ms,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let config = SyntheticConfig {
+        bars: params.bars,
+        start_price: params.start_price,
+        interval_ms,
+        seed: params.seed,
+        ..SyntheticConfig::default()
+    };
+
+    let catalog = DataCatalog::default();
+
+    let data = if model_name == "bootstrap" {
+        let source_key = DatasetKey::new("binance", source_symbol, interval);
+        let source = match catalog.load(&source_key) {
+            Ok(d) if d.len() > params.block_size => d,
+            Ok(_) => {
+                eprintln!("Source dataset {} is shorter than one block", source_key);
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Failed to load source data: {}", e);
+                std::process::exit(1);
+            }
+        };
+        println!(
+            "Model: block bootstrap of {} ({} bars, block size {})",
+            source_key,
+            source.len(),
+            params.block_size
+        );
+        match block_bootstrap(&source, params.block_size, &config) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Failed to bootstrap: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        let mut model = match SyntheticModel::from_name(model_name) {
+            Some(m) => m,
+            None => {
+                eprintln!("Unknown model: {}", model_name);
+                eprintln!("Available models: gbm, garch, jump, regime, bootstrap");
+                std::process::exit(1);
+            }
+        };
+        if let Some(drift) = params.drift {
+            model = model.with_drift(drift);
+        }
+        if let Some(volatility) = params.volatility {
+            model = model.with_volatility(volatility);
+        }
+        println!("Model: {:?}", model);
+        model.generate(&config)
+    };
+
+    let symbol = symbol
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{}-{}", model_name, params.seed));
+    let key = DatasetKey::new("synthetic", &symbol, interval);
+
+    println!(
+        "Generated {} {} bars (seed {})",
+        data.len(),
+        interval,
+        params.seed
+    );
+    println!("Saving to: {}", catalog.dataset_path(&key).display());
+
+    if let Err(e) = catalog.save(&key, &data, &format!("synthetic:{}", model_name)) {
+        eprintln!("Failed to save: {}", e);
+        std::process::exit(1);
+    }
+
+    println!(
+        "\nUse with: strataquant backtest --exchange synthetic --symbol {} --interval {}",
+        key.symbol, interval
+    );
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(timestamp)
+        .map(|dt| dt.to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+fn list_datasets() {
+    println!("StrataQuant - Data Catalog");
+    println!("==========================\n");
+
+    let catalog = DataCatalog::default();
+    let keys = match catalog.list() {
+        Ok(k) => k,
+        Err(e) => {
+            eprintln!("Failed to list datasets: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if keys.is_empty() {
+        println!("No datasets in {}", catalog.root().display());
+        println!("Run 'strataquant download' or 'strataquant import' first");
+        return;
+    }
+
+    println!(
+        "{:<10} {:<12} {:<8} {:>10} {:<25} Last",
+        "Exchange", "Symbol", "Interval", "Rows", "First"
+    );
+    println!("{}", "=".repeat(93));
+
+    for key in keys {
+        match catalog.metadata(&key) {
+            Ok(meta) => println!(
+                "{:<10} {:<12} {:<8} {:>10} {:<25} {}",
+                key.exchange,
+                key.symbol,
+                key.interval,
+                meta.row_count,
+                format_timestamp(meta.first_timestamp),
+                format_timestamp(meta.last_timestamp)
+            ),
+            Err(_) => println!(
+                "{:<10} {:<12} {:<8} {:>10}",
+                key.exchange, key.symbol, key.interval, "?"
+            ),
+        }
+    }
+}
+
+fn show_dataset_info(dataset: &DatasetArgs) {
+    println!("StrataQuant - Dataset Info");
+    println!("==========================\n");
+
+    let catalog = DataCatalog::default();
+    let key = dataset.key();
+
+    let meta = match catalog.metadata(&key) {
+        Ok(m) => m,
+        Err(e) => {
+            eprintln!("No metadata for {}: {}", key, e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("Dataset:      {}", key);
+    println!("Path:         {}", catalog.dataset_path(&key).display());
+    println!("Source:       {}", meta.source);
+    println!("Rows:         {}", meta.row_count);
+    println!("First bar:    {}", format_timestamp(meta.first_timestamp));
+    println!("Last bar:     {}", format_timestamp(meta.last_timestamp));
+    println!("Downloaded:   {}", meta.downloaded_at);
+    println!("SHA-256:      {}", meta.content_hash);
+
+    match catalog.verify(&key) {
+        Ok(true) => println!("Integrity:    OK"),
+        Ok(false) => println!("Integrity:    MODIFIED (hash mismatch)"),
+        Err(e) => println!("Integrity:    unknown ({})", e),
+    }
+}
+
+fn load_dataset(dataset: &DatasetArgs, range: &DateRangeArgs) -> Vec<OHLCV> {
+    let catalog = DataCatalog::default();
+    let key = dataset.key();
+
+    let data_path = match catalog.resolve(&key) {
+        Some(p) => p,
+        None => {
+            eprintln!(
+                "Error: Dataset {} not found at {}",
+                key,
+                catalog.dataset_path(&key).display()
+            );
+            eprintln!("Run 'strataquant download' first");
+            std::process::exit(1);
+        }
+    };
+
+    println!("Loading data from: {}", data_path.display());
+    let data = match catalog.load_range(&key, &range.range()) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("Failed to load data: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if data.is_empty() {
+        eprintln!("Error: No data in the selected date range");
+        std::process::exit(1);
+    }
+
+    data
+}
+
+fn lookup_strategy<'a>(registry: &'a StrategyRegistry, name: &str) -> &'a StrategyDefinition {
+    match registry.get(name) {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Build a strategy from the registry; returns it with its file name stem
+fn select_strategy(
+    definition: &StrategyDefinition,
+    args: &StrategyArgs,
+) -> (Box<dyn Strategy>, String) {
+    let built = definition.resolve(&args.overrides()).and_then(|params| {
+        let strategy = definition.build(&params)?;
+        Ok((strategy, definition.file_stem(&params)))
+    });
+
+    match built {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("Invalid parameters for {}: {:#}", definition.name, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Parse `interval:period`, e.g. `1d:50`
+fn parse_trend_filter(spec: &str) -> (String, usize) {
+    let parsed = spec.split_once(':').and_then(|(interval, period)| {
+        let period: usize = period.trim().parse().ok().filter(|p| *p > 0)?;
+        interval_to_millis(interval.trim()).ok()?;
+        Some((interval.trim().to_string(), period))
+    });
+
+    match parsed {
+        Some(filter) => filter,
+        None => {
+            eprintln!(
+                "Invalid trend filter '{}', expected interval:period (e.g. 1d:50)",
+                spec
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Higher timeframes from the catalog, or resampled from the base bars
+fn load_timeframes(
+    dataset: &DatasetArgs,
+    data: &[OHLCV],
+    intervals: &[String],
+) -> TimeframeContext {
+    let catalog = DataCatalog::default();
+    let key = dataset.key();
+
+    for interval in intervals {
+        let htf_key = DatasetKey::new(&key.exchange, &key.symbol, interval);
+        if catalog.exists(&htf_key) {
+            println!("Timeframe {}: loaded from catalog ({})", interval, htf_key);
+        } else {
+            println!(
+                "Timeframe {}: resampled from {} bars",
+                interval, key.interval
+            );
+        }
+    }
+    println!();
+
+    match TimeframeContext::from_catalog(&catalog, &key, data, intervals) {
+        Ok(context) => context,
+        Err(e) => {
+            eprintln!("Failed to prepare timeframes: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Compiled Rhai script, exiting on read or compile errors
+fn load_script(path: &str) -> Script {
+    match Script::load(path) {
+        Ok(script) => script,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Registered strategy, or the script's or ONNX model's definition when
+/// `--script` or `--onnx` is given
+fn strategy_definition(
+    strategy_name: &str,
+    script: Option<&str>,
+    onnx: Option<&str>,
+) -> StrategyDefinition {
+    match (script, onnx) {
+        (Some(path), _) => load_script(path).definition(),
+        (None, Some(path)) => load_onnx(path).definition(),
+        (None, None) => lookup_strategy(&StrategyRegistry::builtin(), strategy_name).clone(),
+    }
+}
+
+/// Strategy from a rule file, with the file name as output stem
+fn load_rule_strategy(path: &str) -> (Box<dyn Strategy>, String) {
+    match RuleStrategy::load(path) {
+        Ok(strategy) => {
+            let stem = Path::new(path)
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "rules".to_string());
+            (Box::new(strategy), stem)
+        }
+        Err(e) => {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn load_model_strategy(path: &str) -> (Box<dyn Strategy>, String) {
+    match WalkForwardModel::load(Path::new(path)) {
+        Ok(model) => {
+            let stem = Path::new(path)
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_else(|| "model".to_string());
+            (Box::new(model), stem)
+        }
+        Err(e) => {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn load_onnx(path: &str) -> OnnxStrategy {
+    match OnnxStrategy::load(path) {
+        Ok(strategy) => strategy,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Default sweep ranges of `definition`, overridden by `--grid` entries
+fn parse_grid(definition: &StrategyDefinition, args: &GridArgs) -> ParamGrid {
+    let overrides = match ParamGrid::parse(&args.grid) {
+        Ok(g) => g,
+        Err(e) => {
+            eprintln!("Invalid grid: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut grid = definition.default_grid();
+    for name in overrides.names() {
+        grid.set_values(name, overrides.values(name).unwrap_or_default().to_vec());
+    }
+    grid
+}
+
+fn list_strategies() {
+    println!("StrataQuant - Strategies");
+    println!("========================\n");
+
+    let registry = StrategyRegistry::builtin();
+    for definition in registry.definitions() {
+        println!("{}  {}", definition.name, definition.description);
+        for spec in &definition.params {
+            println!(
+                "    {:<16} {:<8} default {:<6} range {:<12} {}",
+                spec.name,
+                match spec.kind {
+                    ParamKind::Integer => "integer",
+                    ParamKind::Float => "float",
+                },
+                spec.default,
+                if spec.max == f64::INFINITY {
+                    format!("{}..", spec.min)
+                } else {
+                    format!("{}..{}", spec.min, spec.max)
+                },
+                spec.description
+            );
+        }
+        for constraint in &definition.constraints {
+            println!("    requires {}", constraint);
+        }
+        println!();
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_backtest(
+    strategy_name: &str,
+    strategy_file: Option<&str>,
+    script: Option<&str>,
+    model_file: Option<&str>,
+    onnx: Option<&str>,
+    trend_filter: Option<&str>,
+    regimes: &[String],
+    params: &StrategyArgs,
+    capital: f64,
+    commission: f64,
+    slippage: f64,
+    dataset: &DatasetArgs,
+    range: &DateRangeArgs,
+    plot: bool,
+) {
+    println!("StrataQuant - Backtest");
+    println!("======================\n");
+
+    let (mut strategy_display, mut file_stem) = match (strategy_file, model_file) {
+        (Some(path), _) => load_rule_strategy(path),
+        (None, Some(path)) => load_model_strategy(path),
+        (None, None) => select_strategy(&strategy_definition(strategy_name, script, onnx), params),
+    };
+    if let Some(spec) = trend_filter {
+        let (interval, period) = parse_trend_filter(spec);
+        file_stem = format!("{}_{}_trend{}", file_stem, interval, period);
+        strategy_display = Box::new(HigherTimeframeFilter::new(
+            strategy_display,
+            &interval,
+            period,
+        ));
+    }
+
+    let classifiers: Vec<Box<dyn RegimeClassifier>> = regimes
+        .iter()
+        .map(|name| match classifier_by_name(name) {
+            Ok(classifier) => classifier,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        })
+        .collect();
+
+    let data = load_dataset(dataset, range);
+
+    println!("Loaded {} candles", data.len());
+    println!(
+        "Period: {} to {}\n",
+        chrono::DateTime::from_timestamp_millis(data[0].timestamp).unwrap(),
+        chrono::DateTime::from_timestamp_millis(data[data.len() - 1].timestamp).unwrap()
+    );
+
+    let execution_model = ExecutionModel::new(commission, slippage);
+
+    let output_filename = format!("{}.json", file_stem);
+
+    println!("Strategy: {}", strategy_display.name());
+    println!("Description: {}", strategy_display.description());
+    println!("Initial capital: ${:.2}", capital);
+    println!("Commission: {} bps", commission);
+    println!("Slippage: {} bps\n", slippage);
+
+    let timeframes = strategy_display.timeframes();
+    let context = (!timeframes.is_empty()).then(|| load_timeframes(dataset, &data, &timeframes));
+    let mut engine = BacktestEngine::new(data, capital, execution_model);
+    if let Some(context) = context {
+        engine = engine.with_timeframes(context);
+    }
+
+    println!("Running backtest...\n");
+    let result = match engine.try_run(strategy_display.as_ref()) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("=== RESULTS ===");
+    println!("Initial capital: ${:>12.2}", result.initial_capital);
+    println!("Final equity:    ${:>12.2}", result.final_equity);
+    println!("Total return:    {:>11.2}%", result.total_return * 100.0);
+    println!("Sharpe ratio:    {:>12.2}", result.sharpe_ratio);
+    println!("Max drawdown:    {:>11.2}%", result.max_drawdown * 100.0);
+    println!("Total trades:    {:>12}", result.total_trades);
+
+    let output_path = Path::new("results/backtests").join(&output_filename);
+    match result.save_to_file(&output_path) {
+        Ok(_) => println!("\nSaved to: {}", output_path.display()),
+        Err(e) => eprintln!("Failed to save: {}", e),
+    }
+
+    if !classifiers.is_empty() {
+        let reports: Vec<RegimeReport> = classifiers
+            .iter()
+            .map(|classifier| RegimeReport::new(classifier.as_ref(), engine.data(), &result))
+            .collect();
+        for report in &reports {
+            print_regime_report(report);
+        }
+
+        let regime_path =
+            Path::new("results/backtests").join(format!("{}_regimes.json", file_stem));
+        let saved = serde_json::to_string_pretty(&reports)
+            .map_err(std::io::Error::from)
+            .and_then(|json| std::fs::write(&regime_path, json));
+        match saved {
+            Ok(_) => println!("\nRegime breakdown saved to: {}", regime_path.display()),
+            Err(e) => eprintln!("Failed to save regime breakdown: {}", e),
+        }
+    }
+
+    // Generate charts if --plot flag is set
+    if plot {
+        let chart_dir = Path::new("results/charts");
+        if let Err(e) = std::fs::create_dir_all(chart_dir) {
+            eprintln!("Failed to create charts directory: {}", e);
+        } else {
+            let equity_path = chart_dir.join(format!("{file_stem}_equity.png"));
+            let drawdown_path = chart_dir.join(format!("{file_stem}_drawdown.png"));
+
+            match plotting::plot_equity_curve(&result, &equity_path) {
+                Ok(_) => println!("Equity chart: {}", equity_path.display()),
+                Err(e) => eprintln!("Failed to generate equity chart: {}", e),
+            }
+
+            match plotting::plot_drawdown(&result, &drawdown_path) {
+                Ok(_) => println!("Drawdown chart: {}", drawdown_path.display()),
+                Err(e) => eprintln!("Failed to generate drawdown chart: {}", e),
+            }
+        }
+    }
+}
+
+fn print_regime_report(report: &RegimeReport) {
+    println!("\n=== REGIMES: {} ===", report.classifier);
+    println!(
+        "{:<12} {:>6} {:>7} {:>10} {:>10} {:>7} {:>9} {:>7} {:>9}",
+        "Regime", "Bars", "Time", "Strategy", "Market", "Sharpe", "Max DD", "Trades", "Win rate"
+    );
+    for stats in &report.regimes {
+        println!(
+            "{:<12} {:>6} {:>6.1}% {:>9.2}% {:>9.2}% {:>7.2} {:>8.2}% {:>7} {:>8.1}%",
+            stats.regime,
+            stats.bars,
+            stats.time_fraction * 100.0,
+            stats.strategy_return * 100.0,
+            stats.market_return * 100.0,
+            stats.sharpe_ratio,
+            stats.max_drawdown * 100.0,
+            stats.trades,
+            stats.trade_stats.win_rate * 100.0
+        );
+    }
+    if report.unclassified_bars > 0 {
+        println!("({} warm-up bars unclassified)", report.unclassified_bars);
+    }
+}
+
+fn parse_range(range: &str) -> (usize, usize) {
+    let parts: Vec<&str> = range.split('-').collect();
+    if parts.len() != 2 {
+        eprintln!("Invalid range format: {}", range);
+        eprintln!("Expected format: min-max (e.g., 20-100)");
+        std::process::exit(1);
+    }
+
+    let min = parts[0].parse().expect("Invalid min value");
+    let max = parts[1].parse().expect("Invalid max value");
+
+    (min, max)
+}
+
+fn run_optimization(
+    definition: &StrategyDefinition,
+    grid: &ParamGrid,
+    capital: f64,
+    commission: f64,
+    slippage: f64,
+    dataset: &DatasetArgs,
+    range: &DateRangeArgs,
+) {
+    println!("StrataQuant - Parameter Optimization");
+    println!("====================================\n");
+
+    let data = load_dataset(dataset, range);
+    println!("Loaded {} candles\n", data.len());
+
+    println!("Strategy: {}", definition.name);
+    for name in grid.names() {
+        let values = grid.values(name).unwrap_or_default();
+        if let (Some(first), Some(last)) = (values.first(), values.last()) {
+            println!(
+                "{} range: {}-{} ({} values)",
+                name,
+                first,
+                last,
+                values.len()
+            );
+        }
+    }
+    println!();
+
+    let execution_model = ExecutionModel::new(commission, slippage);
+    let sweep = ParameterSweep::new(data, capital, execution_model);
+
+    let results = match sweep.sweep(definition, grid) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Optimization failed: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!(
+        "\nOptimization complete. Tested {} combinations.\n",
+        results.len()
+    );
+
+    let (best_sharpe, best_return) = match (
+        ParameterSweep::find_best_sharpe(&results),
+        ParameterSweep::find_best_return(&results),
+    ) {
+        (Some(s), Some(r)) => (s, r),
+        _ => {
+            eprintln!("No valid parameter combinations in the grid");
+            std::process::exit(1);
+        }
+    };
+
+    println!("=== BEST BY SHARPE RATIO ===");
+    println!("Parameters: {}", best_sharpe.params);
+    println!("Sharpe ratio: {:.2}", best_sharpe.sharpe_ratio);
+    println!("Total return: {:.2}%", best_sharpe.total_return * 100.0);
+    println!("Max drawdown: {:.2}%", best_sharpe.max_drawdown * 100.0);
+    println!("Total trades: {}\n", best_sharpe.total_trades);
+
+    println!("=== BEST BY TOTAL RETURN ===");
+    println!("Parameters: {}", best_return.params);
+    println!("Total return: {:.2}%", best_return.total_return * 100.0);
+    println!("Sharpe ratio: {:.2}", best_return.sharpe_ratio);
+    println!("Max drawdown: {:.2}%", best_return.max_drawdown * 100.0);
+    println!("Total trades: {}", best_return.total_trades);
+
+    let output_path = Path::new("results/optimization_results.json");
+    ParameterSweep::save_results(&results, output_path).expect("Failed to save results");
+    println!("\nFull results saved to: {}", output_path.display());
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_walkforward(
+    definition: &StrategyDefinition,
+    grid: &ParamGrid,
+    train_ratio: f64,
+    capital: f64,
+    commission: f64,
+    slippage: f64,
+    dataset: &DatasetArgs,
+    range: &DateRangeArgs,
+) {
+    println!("StrataQuant - Walk-Forward Validation");
+    println!("=====================================\n");
+
+    let data = load_dataset(dataset, range);
+
+    let execution_model = ExecutionModel::new(commission, slippage);
+    let walkforward = WalkForward::new(data, capital, execution_model);
+
+    let result = match walkforward.run(definition, grid, train_ratio) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("Walk-forward failed: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("=== WALK-FORWARD RESULTS ===");
+    println!("Optimal parameters: {}", result.best_params);
+    println!("\nIn-Sample (Training):");
+    println!("  Return:       {:>8.2}%", result.in_sample_return * 100.0);
+    println!("  Sharpe ratio: {:>8.2}", result.in_sample_sharpe);
+    println!("\nOut-of-Sample (Testing):");
+    println!(
+        "  Return:       {:>8.2}%",
+        result.out_of_sample_return * 100.0
+    );
+    println!("  Sharpe ratio: {:>8.2}", result.out_of_sample_sharpe);
+    println!("\nPerformance Degradation:");
+    println!("  Return:       {:>8.2}%", result.degradation_return);
+    println!("  Sharpe ratio: {:>8.2}%", result.degradation_sharpe);
+
+    if result.degradation_sharpe > 50.0 {
+        println!("\n⚠️  WARNING: Significant degradation detected!");
+        println!("This suggests the optimization may have overfit to the training data.");
+    }
+
+    let output_path = Path::new("results/walkforward_result.json");
+    WalkForward::save_result(&result, output_path).expect("Failed to save result");
+    println!("\nResults saved to: {}", output_path.display());
+}
+
+struct PaperOptions {
+    source: String,
+    replay_file: Option<String>,
+    speed: f64,
+    warmup: usize,
+    url: String,
+    state_path: String,
+    trades_path: String,
+    broker: String,
+    broker_url: String,
+    live_orders: bool,
+    latency_ms: u64,
+}
+
+/// Build the order router; the simulated exchange starts from the paper portfolio
+fn select_broker(
+    options: &PaperOptions,
+    symbol: &str,
+    execution_model: &ExecutionModel,
+    portfolio: &Portfolio,
+) -> Box<dyn Broker> {
+    let broker: anyhow::Result<Box<dyn Broker>> = match options.broker.as_str() {
+        "simulated" => {
+            let (base, quote) = split_symbol(symbol);
+            Ok(Box::new(
+                SimulatedExchange::new(symbol, execution_model.clone())
+                    .with_balance(&quote, portfolio.cash)
+                    .with_balance(&base, portfolio.btc_position)
+                    .with_latency(std::time::Duration::from_millis(options.latency_ms)),
+            ))
+        }
+        "binance" => BinanceBroker::from_env().map(|b| {
+            let b = b.with_base_url(&options.broker_url);
+            if !b.is_sandbox() && !options.live_orders {
+                eprintln!(
+                    "Refusing to send orders to {}: not the Binance testnet or a local mock",
+                    b.base_url()
+                );
+                eprintln!("Pass --live-orders to trade a real account");
+                std::process::exit(1);
+            }
+            Box::new(b) as Box<dyn Broker>
+        }),
+        other => {
+            eprintln!("Unknown broker: {}", other);
+            eprintln!("Available brokers: simulated, binance");
+            std::process::exit(1);
+        }
+    };
+
+    match broker {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("Failed to set up broker: {:#}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_paper(
+    strategy: Box<dyn Strategy>,
+    capital: f64,
+    rules: TradingRules,
+    dataset: &DatasetArgs,
+    range: &DateRangeArgs,
+    options: &PaperOptions,
+) {
+    println!("StrataQuant - Paper Trading");
+    println!("===========================\n");
+
+    let key = dataset.key();
+    let interval_ms = match interval_to_millis(&key.interval) {
+        Ok(ms) => ms,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let catalog = DataCatalog::default();
+    let stored = if catalog.exists(&key) {
+        load_dataset(dataset, range)
+    } else if options.source == "live" {
+        println!("No stored history for {}, starting cold", key);
+        Vec::new()
+    } else {
+        eprintln!("Error: Dataset {} not found", key);
+        eprintln!("Run 'strataquant download' first");
+        std::process::exit(1);
+    };
+
+    // Split stored data into warm-up history and bars still to be fed
+    let (history, replay) = match (options.source.as_str(), &options.replay_file) {
+        ("replay", None) => {
+            let split = options.warmup.min(stored.len());
+            (stored[..split].to_vec(), stored[split..].to_vec())
+        }
+        ("replay", Some(file)) => match load_range_from_parquet(Path::new(file), &range.range()) {
+            Ok(bars) => (stored, bars),
+            Err(e) => {
+                eprintln!("Failed to load replay file: {}", e);
+                std::process::exit(1);
+            }
+        },
+        ("live", _) => (stored, Vec::new()),
+        (other, _) => {
+            eprintln!("Unknown source: {}", other);
+            eprintln!("Available sources: replay, live");
+            std::process::exit(1);
+        }
+    };
+
+    let last_history = history.last().map(|b| b.timestamp);
+    let state_path = Path::new(&options.state_path);
+    let trades_path = Path::new(&options.trades_path);
+
+    let execution_model = rules.execution_model.clone();
+    let trader = PaperTrader::new(strategy, capital, rules, history)
+        .with_trades_file(trades_path)
+        .with_state_file(state_path);
+    let trader = match trader {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("Failed to load state: {:#}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let broker = select_broker(
+        options,
+        &key.symbol,
+        &execution_model,
+        &trader.state().trading.portfolio,
+    );
+    let mut trader = trader.with_broker(&key.symbol, broker);
+
+    println!("Strategy: {}", trader.strategy().name());
+    println!("Description: {}", trader.strategy().description());
+    println!("Broker: {}", options.broker);
+    println!("Warm-up bars: {}", trader.history().len());
+    println!("State file: {}", state_path.display());
+    if let Some(ts) = trader.state().last_timestamp {
+        println!(
+            "Resuming from {} (equity ${:.2}, {} trades)",
+            format_timestamp(ts),
+            trader.state().equity(),
+            trader.state().trading.trades.len()
+        );
+    }
+    println!();
+
+    // Declared here so the stream lives as long as we read from it
+    let stream;
+    let replay_receiver;
+    let receiver = if options.source == "live" {
+        let config = KlineStreamConfig::new(&options.url, &key.symbol, &key.interval);
+        let backfill = BinanceDownloader::new(&key.symbol, &key.interval);
+        println!(
+            "Connecting to {} ({} {})",
+            options.url, key.symbol, key.interval
+        );
+        stream = match KlineStream::spawn(config, Some(backfill), last_history) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Failed to start stream: {}", e);
+                std::process::exit(1);
+            }
+        };
+        stream.receiver()
+    } else {
+        println!(
+            "Replaying {} bars at {}",
+            replay.len(),
+            if options.speed > 0.0 {
+                format!("{}x", options.speed)
+            } else {
+                "full speed".to_string()
+            }
+        );
+        replay_receiver = replay_bars(replay, interval_ms, options.speed);
+        &replay_receiver
+    };
+
+    for bar in receiver.iter() {
+        let update = match trader.on_bar(bar) {
+            Ok(Some(u)) => u,
+            Ok(None) => continue,
+            Err(e) => {
+                eprintln!("Paper trading failed: {:#}", e);
+                std::process::exit(1);
+            }
+        };
+
+        for order in &update.orders {
+            println!(
+                "{}  {:<4}  {:.8} @ {:.2}  {:?}",
+                format_timestamp(update.timestamp),
+                order.side.as_str(),
+                order.quantity,
+                order.average_price,
+                order.status
+            );
+        }
+        for error in &update.order_errors {
+            eprintln!("Order failed: {}", error);
+        }
+        if let Some(difference) = update.position_mismatch {
+            eprintln!(
+                "{}  position mismatch: broker holds {:.8}, model {:+.8} off",
+                format_timestamp(update.timestamp),
+                trader.state().broker_position,
+                difference
+            );
+        }
+        for trade in &update.closed_trades {
+            println!(
+                "{}  closed trade  PnL ${:.2} ({:.2}%)",
+                format_timestamp(trade.exit_timestamp),
+                trade.pnl,
+                trade.pnl_pct * 100.0
+            );
+        }
+        if options.source == "live" || !update.closed_trades.is_empty() {
+            println!(
+                "{}  close {:.2}  target {:.2}  {}  equity ${:.2}",
+                format_timestamp(update.timestamp),
+                update.close,
+                update.target_position,
+                if update.in_position { "LONG" } else { "FLAT" },
+                update.equity
+            );
+        }
+    }
+
+    let broker_position = trader.state().broker_position;
+    if let Some(broker) = trader.broker_mut() {
+        match broker.balances() {
+            Ok(balances) => {
+                println!("\n=== BROKER BALANCES ({}) ===", broker.name());
+                for balance in balances.iter().filter(|b| b.total() > 0.0) {
+                    println!("{:<16} {:>12.8}", balance.asset, balance.total());
+                }
+                println!("Routed position: {:>12.8}", broker_position);
+            }
+            Err(e) => eprintln!("Failed to query balances: {:#}", e),
+        }
+    }
+
+    let state = trader.state();
+    println!("\n=== PAPER PORTFOLIO ===");
+    println!("Initial capital: ${:>12.2}", state.initial_capital);
+    println!("Equity:          ${:>12.2}", state.equity());
+    println!(
+        "Total return:    {:>11.2}%",
+        (state.equity() - state.initial_capital) / state.initial_capital * 100.0
+    );
+    println!("Closed trades:   {:>12}", state.trading.trades.len());
+    println!(
+        "Position:        {:>12}",
+        if state.trading.in_position() {
+            "LONG"
+        } else {
+            "FLAT"
+        }
+    );
+
+    match trader.save_trades(trades_path) {
+        Ok(_) => println!("\nTrades saved to: {}", trades_path.display()),
+        Err(e) => eprintln!("Failed to save trades: {}", e),
+    }
+    println!("State saved to: {}", state_path.display());
+}
+
+fn run_comparison(
+    scripts: &[String],
+    onnx: &[String],
+    capital: f64,
+    commission: f64,
+    slippage: f64,
+    dataset: &DatasetArgs,
+    range: &DateRangeArgs,
+) {
+    println!("StrataQuant - Strategy Comparison");
+    println!("=================================\n");
+
+    let scripted: Vec<Box<dyn Strategy>> = scripts
+        .iter()
+        .map(|path| load_script(path).definition())
+        .chain(onnx.iter().map(|path| load_onnx(path).definition()))
+        .map(|definition| select_strategy(&definition, &StrategyArgs::default()).0)
+        .collect();
+
+    let data = load_dataset(dataset, range);
+    println!("Loaded {} candles\n", data.len());
+
+    let execution_model = ExecutionModel::new(commission, slippage);
+
+    let mut strategies: Vec<Box<dyn Strategy>> = vec![
+        Box::new(BuyAndHold::new()),
+        Box::new(SMACrossover::new(50, 200)),
+        Box::new(SMACrossover::new(20, 50)),
+        Box::new(SMACrossover::new(100, 200)),
+        Box::new(EMACrossover::new(12, 26)),
+        Box::new(RSIMeanReversion::new(14, 30.0, 70.0)),
+        Box::new(DonchianBreakout::new(20, 10)),
+        Box::new(MACDCrossover::new(12, 26, 9)),
+        Box::new(BollingerReversion::new(20, 2.0)),
+        Box::new(MultiLookbackMomentum::new(90, 30)),
+        // SMA 20/50, only while price is above its 200-bar average
+        Box::new(Filter::new(
+            Box::new(SMACrossover::new(20, 50)),
+            Box::new(SMACrossover::new(1, 200)),
+        )),
+        Box::new(Ensemble::majority(vec![
+            Box::new(EMACrossover::new(12, 26)),
+            Box::new(MACDCrossover::new(12, 26, 9)),
+            Box::new(MultiLookbackMomentum::new(90, 30)),
+        ])),
+    ];
+    strategies.extend(scripted);
+
+    let width = strategies
+        .iter()
+        .map(|s| s.name().len())
+        .max()
+        .unwrap_or(0)
+        .max(20);
+
+    println!(
+        "{:<width$} {:>12} {:>12} {:>12} {:>12}",
+        "Strategy", "Return %", "Sharpe", "Max DD %", "Trades"
+    );
+    println!("{}", "=".repeat(width + 52));
+
+    for strategy in strategies {
+        let engine = BacktestEngine::new(&data, capital, execution_model.clone());
+        let result = match engine.try_run(strategy.as_ref()) {
+            Ok(result) => result,
+            Err(e) => {
+                println!("{:<width$} failed", strategy.name());
+                eprintln!("  {:#}", e);
+                continue;
             }
+        };
+
+        println!(
+            "{:<width$} {:>11.2}% {:>12.2} {:>11.2}% {:>12}",
+            strategy.name(),
+            result.total_return * 100.0,
+            result.sharpe_ratio,
+            result.max_drawdown * 100.0,
+            result.total_trades
+        );
+    }
+
+    println!("\nComparison complete.");
+}
+
+fn parse_schedule(name: &str) -> Schedule {
+    match Schedule::parse(name) {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
         }
     }
 }
 
-fn parse_range(range: &str) -> (usize, usize) {
-    let parts: Vec<&str> = range.split('-').collect();
-    if parts.len() != 2 {
-        eprintln!("Invalid range format: {}", range);
-        eprintln!("Expected format: min-max (e.g., 20-100)");
+/// Rebalancing rule from the command line; returns it with its file name stem
+fn select_rebalancer(
+    weight: f64,
+    every: Option<&str>,
+    drift: Option<f64>,
+    dca: bool,
+) -> (Box<dyn Rebalancer>, String) {
+    if !(0.0..=100.0).contains(&weight) {
+        eprintln!("--weight must be between 0 and 100");
         std::process::exit(1);
     }
 
-    let min = parts[0].parse().expect("Invalid min value");
-    let max = parts[1].parse().expect("Invalid max value");
-
-    (min, max)
+    if dca {
+        return (Box::new(DollarCostAveraging::new()), "dca".to_string());
+    }
+    match drift {
+        Some(drift) if drift > 0.0 => (
+            Box::new(ThresholdRebalance::new(weight / 100.0, drift / 100.0)),
+            format!("rebalance_{}_drift{}", weight, drift),
+        ),
+        Some(_) => {
+            eprintln!("--drift must be greater than 0");
+            std::process::exit(1);
+        }
+        None => {
+            let schedule = parse_schedule(every.unwrap_or("monthly"));
+            (
+                Box::new(CalendarRebalance::new(weight / 100.0, schedule)),
+                format!("rebalance_{}_{}", weight, schedule),
+            )
+        }
+    }
 }
 
-fn run_optimization(
-    fast_range: &str,
-    slow_range: &str,
-    step: usize,
+#[allow(clippy::too_many_arguments)]
+fn run_rebalance(
+    rebalancer: &dyn Rebalancer,
+    file_stem: &str,
+    contributions: Option<&Contributions>,
     capital: f64,
     commission: f64,
     slippage: f64,
+    dataset: &DatasetArgs,
+    range: &DateRangeArgs,
 ) {
-    println!("StrataQuant - Parameter Optimization");
-    println!("====================================\n");
+    println!("StrataQuant - Rebalancing Backtest");
+    println!("==================================\n");
 
-    let data_path = Path::new("data/processed/btc_1d.parquet");
-    if !data_path.exists() {
-        eprintln!("Error: Data file not found");
-        std::process::exit(1);
-    }
+    let data = load_dataset(dataset, range);
+    println!("Loaded {} candles", data.len());
+    println!(
+        "Period: {} to {}\n",
+        format_timestamp(data[0].timestamp),
+        format_timestamp(data[data.len() - 1].timestamp)
+    );
 
-    let data = load_from_parquet(data_path).expect("Failed to load data");
-    println!("Loaded {} candles\n", data.len());
+    println!("Portfolio: {}", rebalancer.name());
+    println!("Initial capital: ${:.2}", capital);
+    if let Some(c) = contributions {
+        println!("Contributions: ${:.2} {}", c.amount, c.schedule);
+    }
+    println!("Commission: {} bps", commission);
+    println!("Slippage: {} bps\n", slippage);
 
-    let (fast_min, fast_max) = parse_range(fast_range);
-    let (slow_min, slow_max) = parse_range(slow_range);
+    let engine = BacktestEngine::new(data, capital, ExecutionModel::new(commission, slippage));
+    let result = engine.run_rebalancing(rebalancer, contributions);
 
-    println!("Fast period range: {}-{}", fast_min, fast_max);
-    println!("Slow period range: {}-{}", slow_min, slow_max);
-    println!("Step size: {}\n", step);
+    println!("=== RESULTS ===");
+    println!("Initial capital: ${:>12.2}", result.initial_capital);
+    println!("Final equity:    ${:>12.2}", result.final_equity);
+    println!("Total return:    {:>11.2}%", result.total_return * 100.0);
+    println!("Sharpe ratio:    {:>12.2}", result.sharpe_ratio);
+    println!("Max drawdown:    {:>11.2}%", result.max_drawdown * 100.0);
+    println!("Total trades:    {:>12}", result.total_trades);
 
-    let execution_model = ExecutionModel::new(commission, slippage);
-    let sweep = ParameterSweep::new(data, capital, execution_model);
+    if let Some(flows) = &result.cash_flows {
+        println!("\n=== CASH FLOWS ===");
+        println!("Deposits:        ${:>12.2}", flows.total_deposits);
+        println!("Net profit:      ${:>12.2}", flows.net_profit);
+        println!(
+            "Time-weighted:   {:>11.2}% ({:.2}% annualized)",
+            flows.time_weighted_return * 100.0,
+            flows.annualized_time_weighted_return * 100.0
+        );
+        match flows.money_weighted_return {
+            Some(mwr) => println!("Money-weighted:  {:>11.2}% annualized", mwr * 100.0),
+            None => println!("Money-weighted:          n/a"),
+        }
+        if flows.total_deposits > 0.0 {
+            println!("\nTotal return counts deposits as gains; compare time-weighted returns.");
+        }
+    }
 
-    let results = sweep.sweep_sma_periods((fast_min, fast_max), (slow_min, slow_max), step);
+    let output_path = Path::new("results/backtests").join(format!("{}.json", file_stem));
+    match result.save_to_file(&output_path) {
+        Ok(_) => println!("\nSaved to: {}", output_path.display()),
+        Err(e) => eprintln!("Failed to save: {}", e),
+    }
+}
 
-    println!(
-        "\nOptimization complete. Tested {} combinations.\n",
-        results.len()
-    );
+fn select_grid(
+    lower: f64,
+    upper: f64,
+    levels: usize,
+    spacing: &str,
+    order_size: f64,
+) -> GridStrategy {
+    let spacing = match GridSpacing::parse(spacing) {
+        Ok(spacing) => spacing,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if !(0.0 < lower && lower < upper) {
+        eprintln!("Grid bounds must satisfy 0 < --lower < --upper");
+        std::process::exit(1);
+    }
+    if levels < 2 {
+        eprintln!("--levels must be at least 2");
+        std::process::exit(1);
+    }
+    if order_size <= 0.0 {
+        eprintln!("--order-size must be greater than 0");
+        std::process::exit(1);
+    }
+    GridStrategy::new(lower, upper, levels, spacing, order_size)
+}
 
-    let best_sharpe = ParameterSweep::find_best_sharpe(&results).unwrap();
-    let best_return = ParameterSweep::find_best_return(&results).unwrap();
+fn run_grid(
+    grid: &GridStrategy,
+    capital: f64,
+    commission: f64,
+    slippage: f64,
+    dataset: &DatasetArgs,
+    range: &DateRangeArgs,
+) {
+    println!("StrataQuant - Grid Backtest");
+    println!("===========================\n");
 
-    println!("=== BEST BY SHARPE RATIO ===");
+    let data = load_dataset(dataset, range);
+    println!("Loaded {} candles", data.len());
     println!(
-        "Parameters: {}/{}",
-        best_sharpe.fast_period, best_sharpe.slow_period
+        "Period: {} to {}\n",
+        format_timestamp(data[0].timestamp),
+        format_timestamp(data[data.len() - 1].timestamp)
     );
-    println!("Sharpe ratio: {:.2}", best_sharpe.sharpe_ratio);
-    println!("Total return: {:.2}%", best_sharpe.total_return * 100.0);
-    println!("Max drawdown: {:.2}%", best_sharpe.max_drawdown * 100.0);
-    println!("Total trades: {}\n", best_sharpe.total_trades);
 
-    println!("=== BEST BY TOTAL RETURN ===");
-    println!(
-        "Parameters: {}/{}",
-        best_return.fast_period, best_return.slow_period
-    );
-    println!("Total return: {:.2}%", best_return.total_return * 100.0);
-    println!("Sharpe ratio: {:.2}", best_return.sharpe_ratio);
-    println!("Max drawdown: {:.2}%", best_return.max_drawdown * 100.0);
-    println!("Total trades: {}", best_return.total_trades);
+    println!("Strategy: {}", grid.name());
+    println!("Initial capital: ${:.2}", capital);
+    println!("Commission: {} bps", commission);
+    println!("Slippage: {} bps\n", slippage);
 
-    let output_path = Path::new("results/optimization_results.json");
-    ParameterSweep::save_results(&results, output_path).expect("Failed to save results");
-    println!("\nFull results saved to: {}", output_path.display());
-}
+    let engine = BacktestEngine::new(data, capital, ExecutionModel::new(commission, slippage));
+    let result = engine.run_orders(grid);
 
-fn run_walkforward(train_ratio: f64, capital: f64, commission: f64, slippage: f64) {
-    println!("StrataQuant - Walk-Forward Validation");
-    println!("=====================================\n");
+    println!("=== RESULTS ===");
+    println!("Initial capital: ${:>12.2}", result.initial_capital);
+    println!("Final equity:    ${:>12.2}", result.final_equity);
+    println!("Total return:    {:>11.2}%", result.total_return * 100.0);
+    println!("Sharpe ratio:    {:>12.2}", result.sharpe_ratio);
+    println!("Max drawdown:    {:>11.2}%", result.max_drawdown * 100.0);
+    println!("Total trades:    {:>12}", result.total_trades);
 
-    let data_path = Path::new("data/processed/btc_1d.parquet");
-    if !data_path.exists() {
-        eprintln!("Error: Data file not found");
-        std::process::exit(1);
+    if let Some(stats) = &result.order_stats {
+        println!("\n=== GRID ===");
+        println!(
+            "Fills:           {:>12} ({} buys, {} sells)",
+            stats.buy_fills + stats.sell_fills,
+            stats.buy_fills,
+            stats.sell_fills
+        );
+        if stats.unfunded_fills > 0 {
+            println!("Unfunded fills:  {:>12}", stats.unfunded_fills);
+        }
+        println!("Realized profit: ${:>12.2}", stats.realized_profit);
+        println!("Unrealized:      ${:>12.2}", stats.unrealized_profit);
+        println!("Fees:            ${:>12.2}", stats.fees);
+        println!(
+            "Inventory:       {:>12.4} BTC (min {:.4}, max {:.4}, avg {:.4})",
+            stats.final_inventory,
+            stats.min_inventory,
+            stats.max_inventory,
+            stats.average_inventory
+        );
+
+        println!(
+            "\n{:>6} {:>12} {:>6} {:>6}",
+            "Level", "Price", "Buys", "Sells"
+        );
+        for level in stats.levels.iter().rev() {
+            println!(
+                "{:>6} {:>12.2} {:>6} {:>6}",
+                level.level, level.price, level.buys, level.sells
+            );
+        }
     }
 
-    let data = load_from_parquet(data_path).expect("Failed to load data");
+    let output_path = Path::new("results/backtests").join("grid.json");
+    match result.save_to_file(&output_path) {
+        Ok(_) => println!("\nSaved to: {}", output_path.display()),
+        Err(e) => eprintln!("Failed to save: {}", e),
+    }
+}
 
-    let execution_model = ExecutionModel::new(commission, slippage);
-    let walkforward = WalkForward::new(data, capital, execution_model);
+fn select_pairs_strategy(
+    hedge: &str,
+    hedge_window: usize,
+    window: usize,
+    entry: f64,
+    exit: f64,
+    stop: Option<f64>,
+) -> PairsStrategy {
+    let method = match HedgeMethod::parse(hedge, hedge_window) {
+        Ok(method) => method,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    if window < 2 {
+        eprintln!("--window must be at least 2");
+        std::process::exit(1);
+    }
+    if !(0.0 <= exit && exit < entry) {
+        eprintln!("Thresholds must satisfy 0 <= --exit < --entry");
+        std::process::exit(1);
+    }
 
-    let result = walkforward.run(train_ratio);
+    let strategy = PairsStrategy::new(method, window)
+        .with_entry(entry)
+        .with_exit(exit);
+    match stop {
+        Some(stop) if stop <= entry => {
+            eprintln!("--stop must be greater than --entry");
+            std::process::exit(1);
+        }
+        Some(stop) => strategy.with_stop(stop),
+        None => strategy,
+    }
+}
 
-    println!("=== WALK-FORWARD RESULTS ===");
+#[allow(clippy::too_many_arguments)]
+fn run_pairs(
+    strategy: &PairsStrategy,
+    datasets: &[DatasetArgs; 2],
+    allocation: f64,
+    lags: usize,
+    capital: f64,
+    commission: f64,
+    slippage: f64,
+    range: &DateRangeArgs,
+) {
+    println!("StrataQuant - Pairs Backtest");
+    println!("============================\n");
+
+    let a = load_dataset(&datasets[0], range);
+    let b = load_dataset(&datasets[1], range);
+    let mut aligned = align_series(&[&a, &b]);
+    let b = aligned.pop().unwrap();
+    let a = aligned.pop().unwrap();
+    if a.len() < 2 {
+        eprintln!("Error: The two datasets share fewer than 2 timestamps");
+        std::process::exit(1);
+    }
+    println!("Aligned {} common candles", a.len());
     println!(
-        "Optimal parameters: {}/{}",
-        result.best_fast_period, result.best_slow_period
+        "Period: {} to {}\n",
+        format_timestamp(a[0].timestamp),
+        format_timestamp(a[a.len() - 1].timestamp)
     );
-    println!("\nIn-Sample (Training):");
-    println!("  Return:       {:>8.2}%", result.in_sample_return * 100.0);
-    println!("  Sharpe ratio: {:>8.2}", result.in_sample_sharpe);
-    println!("\nOut-of-Sample (Testing):");
+
+    let (symbol_a, symbol_b) = (&datasets[0].symbol, &datasets[1].symbol);
+    let log_a: Vec<f64> = a.iter().map(|bar| bar.close.ln()).collect();
+    let log_b: Vec<f64> = b.iter().map(|bar| bar.close.ln()).collect();
+    let cointegration = engle_granger(&log_a, &log_b, lags);
+
+    println!("=== COINTEGRATION (Engle-Granger, full sample) ===");
+    match &cointegration {
+        Ok(test) => {
+            println!(
+                "ln({}) = {:.4} + {:.4} * ln({})",
+                symbol_a, test.hedge.alpha, test.hedge.beta, symbol_b
+            );
+            println!(
+                "ADF statistic:   {:>12.3} (1%: {:.3}, 5%: {:.3}, 10%: {:.3})",
+                test.adf_statistic,
+                test.critical_values.one_percent,
+                test.critical_values.five_percent,
+                test.critical_values.ten_percent
+            );
+            match test.half_life {
+                Some(h) => println!("Half-life:       {:>12.1} bars", h),
+                None => println!("Half-life:                n/a (spread does not revert)"),
+            }
+            if test.is_cointegrated() {
+                println!("Cointegrated at the 5% level");
+            } else {
+                println!("NOT cointegrated at the 5% level: the spread may never revert");
+            }
+        }
+        Err(e) => println!("Test failed: {}", e),
+    }
+
+    println!("\nStrategy: {}", strategy.name());
     println!(
-        "  Return:       {:>8.2}%",
-        result.out_of_sample_return * 100.0
+        "Legs: long {} / short {} when the spread is long",
+        symbol_a, symbol_b
     );
-    println!("  Sharpe ratio: {:>8.2}", result.out_of_sample_sharpe);
-    println!("\nPerformance Degradation:");
-    println!("  Return:       {:>8.2}%", result.degradation_return);
-    println!("  Sharpe ratio: {:>8.2}%", result.degradation_sharpe);
+    println!("Gross exposure: {:.0}% of equity", allocation * 100.0);
+    println!("Initial capital: ${:.2}", capital);
+    println!("Commission: {} bps", commission);
+    println!("Slippage: {} bps\n", slippage);
 
-    if result.degradation_sharpe > 50.0 {
-        println!("\n⚠️  WARNING: Significant degradation detected!");
-        println!("This suggests the optimization may have overfit to the training data.");
+    let pairs = PairsBacktest::new(a, b, capital, ExecutionModel::new(commission, slippage))
+        .with_symbols(symbol_a, symbol_b)
+        .with_allocation(allocation)
+        .run(strategy);
+    let result = &pairs.result;
+
+    println!("=== RESULTS ===");
+    println!("Initial capital: ${:>12.2}", result.initial_capital);
+    println!("Final equity:    ${:>12.2}", result.final_equity);
+    println!("Total return:    {:>11.2}%", result.total_return * 100.0);
+    println!("Sharpe ratio:    {:>12.2}", result.sharpe_ratio);
+    println!("Max drawdown:    {:>11.2}%", result.max_drawdown * 100.0);
+    println!("Pair trades:     {:>12}", pairs.pair_trades.len());
+    if let Some(stats) = &result.trade_stats {
+        println!("Win rate:        {:>11.1}%", stats.win_rate * 100.0);
+        println!("Profit factor:   {:>12.2}", stats.profit_factor);
     }
 
-    let output_path = Path::new("results/walkforward_result.json");
-    WalkForward::save_result(&result, output_path).expect("Failed to save result");
-    println!("\nResults saved to: {}", output_path.display());
-}
+    if !pairs.pair_trades.is_empty() {
+        println!("\n=== LEGS ===");
+        println!(
+            "{:<12} {:>8} {:>8} {:>14} {:>8}",
+            "Symbol", "Long", "Short", "P&L", "Wins"
+        );
+        for (leg, symbol) in [symbol_a, symbol_b].iter().enumerate() {
+            let legs: Vec<&LegTrade> = pairs.pair_trades.iter().map(|t| &t.legs[leg]).collect();
+            let longs = legs.iter().filter(|l| l.side == Direction::Long).count();
+            let wins = legs.iter().filter(|l| l.pnl > 0.0).count();
+            println!(
+                "{:<12} {:>8} {:>8} {:>14.2} {:>8}",
+                symbol,
+                longs,
+                legs.len() - longs,
+                legs.iter().map(|l| l.pnl).sum::<f64>(),
+                wins
+            );
+        }
+    }
 
-fn run_comparison(capital: f64, commission: f64, slippage: f64) {
-    println!("StrataQuant - Strategy Comparison");
-    println!("=================================\n");
+    let output_path =
+        Path::new("results/backtests").join(format!("pairs_{}_{}.json", symbol_a, symbol_b));
+    match pairs.save_to_file(&output_path) {
+        Ok(_) => println!("\nSaved to: {}", output_path.display()),
+        Err(e) => eprintln!("Failed to save: {}", e),
+    }
+}
 
-    let data_path = Path::new("data/processed/btc_1d.parquet");
-    if !data_path.exists() {
-        eprintln!("Error: Data file not found");
-        std::process::exit(1);
+fn select_model(model: &str, features: Option<&str>) -> (ModelKind, FeaturePipeline) {
+    let kind = ModelKind::parse(model);
+    let pipeline = features.map_or_else(|| Ok(FeaturePipeline::default()), FeaturePipeline::parse);
+    match (kind, pipeline) {
+        (Ok(kind), Ok(pipeline)) => (kind, pipeline),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{:#}", e);
+            std::process::exit(1);
+        }
     }
+}
 
-    let data = load_from_parquet(data_path).expect("Failed to load data");
-    println!("Loaded {} candles\n", data.len());
+fn format_hit_rate(rate: Option<f64>) -> String {
+    rate.map_or_else(|| "n/a".to_string(), |r| format!("{:.1}%", r * 100.0))
+}
 
-    let execution_model = ExecutionModel::new(commission, slippage);
+#[allow(clippy::too_many_arguments)]
+fn run_ml(
+    kind: ModelKind,
+    features: &FeaturePipeline,
+    train: usize,
+    test: usize,
+    threshold: Option<f64>,
+    capital: f64,
+    commission: f64,
+    slippage: f64,
+    dataset: &DatasetArgs,
+    range: &DateRangeArgs,
+) {
+    println!("StrataQuant - ML Walk-Forward");
+    println!("=============================\n");
 
-    let strategies: Vec<Box<dyn strataquant::strategies::Strategy>> = vec![
-        Box::new(BuyAndHold::new()),
-        Box::new(SMACrossover::new(50, 200)),
-        Box::new(SMACrossover::new(20, 50)),
-        Box::new(SMACrossover::new(100, 200)),
-    ];
+    let data = load_dataset(dataset, range);
+    println!("Loaded {} candles", data.len());
+    println!(
+        "Period: {} to {}\n",
+        format_timestamp(data[0].timestamp),
+        format_timestamp(data[data.len() - 1].timestamp)
+    );
 
+    println!("Model: {}", kind);
+    println!("Features: {}", features.names().join(", "));
     println!(
-        "{:<20} {:>12} {:>12} {:>12} {:>12}",
-        "Strategy", "Return %", "Sharpe", "Max DD %", "Trades"
+        "Threshold: {}",
+        threshold.unwrap_or_else(|| kind.default_threshold())
     );
-    println!("{}", "=".repeat(72));
+    println!("Windows: train {} bars, test {} bars", train, test);
+    println!("Initial capital: ${:.2}", capital);
+    println!("Commission: {} bps", commission);
+    println!("Slippage: {} bps\n", slippage);
 
-    for strategy in strategies {
-        let engine = BacktestEngine::new(data.clone(), capital, execution_model.clone());
-        let result = engine.run(strategy.as_ref());
+    println!("Training...\n");
+    let walkforward =
+        ModelWalkForward::new(data, capital, ExecutionModel::new(commission, slippage))
+            .with_windows(train, test);
+    let (model, report) = match walkforward.run(features, kind, threshold) {
+        Ok(trained) => trained,
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            std::process::exit(1);
+        }
+    };
 
+    println!("=== WINDOWS ===");
+    println!(
+        "{:<24} {:<24} {:<24} {:>6} {:>10} {:>10}",
+        "Train from", "Test from", "Test to", "Rows", "Train hit", "Test hit"
+    );
+    for window in &report.windows {
         println!(
-            "{:<20} {:>11.2}% {:>12.2} {:>11.2}% {:>12}",
-            strategy.name(),
-            result.total_return * 100.0,
-            result.sharpe_ratio,
-            result.max_drawdown * 100.0,
-            result.total_trades
+            "{:<24} {:<24} {:<24} {:>6} {:>10} {:>10}",
+            format_timestamp(window.train_start),
+            format_timestamp(window.test_start),
+            format_timestamp(window.test_end),
+            window.training_rows,
+            format_hit_rate(window.train_hit_rate),
+            format_hit_rate(window.test_hit_rate)
         );
     }
 
-    println!("\nComparison complete.");
+    let result = &report.out_of_sample;
+    println!("\n=== OUT-OF-SAMPLE RESULTS ===");
+    println!("Initial capital: ${:>12.2}", result.initial_capital);
+    println!("Final equity:    ${:>12.2}", result.final_equity);
+    println!("Total return:    {:>11.2}%", result.total_return * 100.0);
+    println!("Sharpe ratio:    {:>12.2}", result.sharpe_ratio);
+    println!("Max drawdown:    {:>11.2}%", result.max_drawdown * 100.0);
+    println!("Total trades:    {:>12}", result.total_trades);
+
+    let stem = format!("ml_{}_{}_{}", kind.name(), dataset.symbol, dataset.interval);
+    let model_path = Path::new("results/models").join(format!("{}.json", stem));
+    match model.save(&model_path) {
+        Ok(_) => println!("\nModel saved to: {}", model_path.display()),
+        Err(e) => eprintln!("Failed to save model: {:#}", e),
+    }
+    let output_path = Path::new("results/backtests").join(format!("{}_walkforward.json", stem));
+    match report.save_to_file(&output_path) {
+        Ok(_) => println!("Saved to: {}", output_path.display()),
+        Err(e) => eprintln!("Failed to save: {}", e),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_lookahead_check(
+    strategy_name: &str,
+    strategy_file: Option<&str>,
+    script: Option<&str>,
+    params: &StrategyArgs,
+    start: usize,
+    every: usize,
+    dataset: &DatasetArgs,
+    range: &DateRangeArgs,
+) {
+    println!("StrataQuant - Look-ahead Check");
+    println!("==============================\n");
+
+    if every == 0 {
+        eprintln!("--every must be greater than 0");
+        std::process::exit(1);
+    }
+
+    let (strategy, _) = match strategy_file {
+        Some(path) => load_rule_strategy(path),
+        None => select_strategy(&strategy_definition(strategy_name, script, None), params),
+    };
+    let data = load_dataset(dataset, range);
+
+    println!("Strategy: {}", strategy.name());
+    println!("Loaded {} candles", data.len());
+    println!("Rerunning the strategy on each truncated series...\n");
+
+    let report = LookaheadCheck::new()
+        .with_start(start)
+        .with_step(every)
+        .run(strategy.as_ref(), &data);
+
+    println!("Bars checked: {}", report.bars_checked);
+    match &report.first_violation {
+        None => println!("No look-ahead detected"),
+        Some(violation) => {
+            println!(
+                "LOOK-AHEAD DETECTED at bar {} ({})",
+                violation.index,
+                format_timestamp(violation.timestamp)
+            );
+            println!(
+                "  Signal with data ending at this bar: {}",
+                violation.prefix_signal
+            );
+            println!(
+                "  Signal with the full series:         {}",
+                violation.full_signal
+            );
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,96 @@
+/// Milliseconds in an average (Julian) year, for annualizing over timestamps
+pub const MS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0 * 1000.0;
+
+/// Time-weighted return: the growth of the portfolio with the effect of
+/// deposits removed
+///
+/// `deposits[i]` is the cash added at bar `i`, already included in
+/// `equity_curve[i]`. Each bar's return is `(equity - deposit) / previous
+/// equity`, and the bar returns are compounded.
+pub fn calculate_time_weighted_return(equity_curve: &[f64], deposits: &[f64]) -> f64 {
+    assert_eq!(
+        equity_curve.len(),
+        deposits.len(),
+        "Need one deposit entry per bar"
+    );
+    equity_curve
+        .windows(2)
+        .zip(&deposits[1..])
+        .filter(|(w, _)| w[0] > 0.0)
+        .map(|(w, deposit)| (w[1] - deposit) / w[0])
+        .product::<f64>()
+        - 1.0
+}
+
+/// Money-weighted return: the annualized internal rate of return of the
+/// deposits (including the initial capital) against the final value
+///
+/// `deposits` are `(timestamp, amount)` pairs. Returns `None` when there is
+/// no deposit, no time elapses, or no rate between -99.99% and +100,000%
+/// balances the flows.
+pub fn calculate_money_weighted_return(
+    deposits: &[(i64, f64)],
+    final_timestamp: i64,
+    final_value: f64,
+) -> Option<f64> {
+    let first = deposits.first()?.0;
+    if final_timestamp <= first {
+        return None;
+    }
+
+    // Value at the end of every flow compounded at `rate`, minus the final value
+    let surplus = |rate: f64| {
+        deposits
+            .iter()
+            .map(|&(timestamp, amount)| {
+                let years = (final_timestamp - timestamp) as f64 / MS_PER_YEAR;
+                amount * (1.0 + rate).powf(years)
+            })
+            .sum::<f64>()
+            - final_value
+    };
+
+    // Surplus increases with the rate, so bisect for its root
+    let (mut low, mut high) = (-0.9999, 1000.0);
+    if surplus(low) > 0.0 || surplus(high) < 0.0 {
+        return None;
+    }
+    for _ in 0..200 {
+        let mid = (low + high) / 2.0;
+        if surplus(mid) > 0.0 {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    Some((low + high) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YEAR: i64 = 31_557_600_000;
+
+    #[test]
+    fn test_deposits_do_not_count_as_growth() {
+        // 100 grows 10%, then 100 is deposited, then everything grows 10%
+        let equity = [100.0, 110.0, 210.0, 231.0];
+        let deposits = [0.0, 0.0, 100.0, 0.0];
+        let twr = calculate_time_weighted_return(&equity, &deposits);
+        assert!((twr - 0.21).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_money_weighted_return() {
+        let irr = calculate_money_weighted_return(&[(0, 100.0)], YEAR, 110.0).unwrap();
+        assert!((irr - 0.10).abs() < 1e-9);
+
+        // A deposit just before a loss weighs more than the earlier gain
+        let irr = calculate_money_weighted_return(&[(0, 100.0), (YEAR, 1000.0)], 2 * YEAR, 1045.0)
+            .unwrap();
+        assert!(irr < 0.0);
+
+        assert!(calculate_money_weighted_return(&[], YEAR, 1.0).is_none());
+    }
+}
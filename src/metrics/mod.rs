@@ -1,9 +1,11 @@
 pub mod calmar;
+pub mod cash_flow;
 pub mod drawdown;
 pub mod sharpe;
 pub mod sortino;
 
 pub use calmar::calculate_calmar_ratio;
+pub use cash_flow::{calculate_money_weighted_return, calculate_time_weighted_return, MS_PER_YEAR};
 pub use drawdown::calculate_max_drawdown;
 pub use sharpe::calculate_sharpe_ratio;
 pub use sortino::calculate_sortino_ratio;
@@ -0,0 +1,298 @@
+use crate::data::OHLCV;
+use crate::indicators::{atr, rsi, sma, zscore};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// One model input computed from the bars up to and including the current one
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Feature {
+    /// One-bar return `lag` bars ago (0 is the latest bar)
+    Return { lag: usize },
+    /// Return over the last `period` bars
+    Momentum { period: usize },
+    /// Close relative to its SMA, minus one
+    SmaRatio { period: usize },
+    /// RSI scaled to 0..1
+    Rsi { period: usize },
+    /// Standard deviation of one-bar returns over `window` bars
+    Volatility { window: usize },
+    /// ATR as a fraction of the close
+    AtrRatio { period: usize },
+    /// Volume relative to its SMA, minus one
+    VolumeRatio { period: usize },
+    /// Z-score of the close over `period` bars
+    ZScore { period: usize },
+}
+
+impl Feature {
+    /// Feature from `name:n`, e.g. `return:0`, `sma:20`, `rsi:14`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (name, value) = spec
+            .split_once(':')
+            .with_context(|| format!("Feature '{}' must be name:number", spec))?;
+        let n: usize = value
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid number in feature '{}'", spec))?;
+
+        let feature = match name.trim() {
+            "return" => Feature::Return { lag: n },
+            "momentum" => Feature::Momentum { period: n },
+            "sma" => Feature::SmaRatio { period: n },
+            "rsi" => Feature::Rsi { period: n },
+            "volatility" => Feature::Volatility { window: n },
+            "atr" => Feature::AtrRatio { period: n },
+            "volume" => Feature::VolumeRatio { period: n },
+            "zscore" => Feature::ZScore { period: n },
+            other => bail!(
+                "Unknown feature '{}' (expected return, momentum, sma, rsi, volatility, atr, volume or zscore)",
+                other
+            ),
+        };
+        if n == 0 && !matches!(feature, Feature::Return { .. }) {
+            bail!("Feature '{}' needs a period of at least 1", spec);
+        }
+        Ok(feature)
+    }
+
+    /// Bars before the first value
+    pub fn warmup(&self) -> usize {
+        match *self {
+            Feature::Return { lag } => lag + 1,
+            Feature::Momentum { period } => period,
+            Feature::Volatility { window } => window,
+            Feature::SmaRatio { period }
+            | Feature::AtrRatio { period }
+            | Feature::VolumeRatio { period }
+            | Feature::ZScore { period } => period - 1,
+            Feature::Rsi { period } => period,
+        }
+    }
+
+    /// Value at every bar; `NaN` during warm-up
+    pub fn compute(&self, data: &[OHLCV]) -> Vec<f64> {
+        let closes: Vec<f64> = data.iter().map(|b| b.close).collect();
+        let n = closes.len();
+        let lagged = |lag: usize, f: &dyn Fn(usize) -> f64| -> Vec<f64> {
+            (0..n)
+                .map(|i| if i >= lag { f(i) } else { f64::NAN })
+                .collect()
+        };
+
+        match *self {
+            Feature::Return { lag } => {
+                lagged(lag + 1, &|i| closes[i - lag] / closes[i - lag - 1] - 1.0)
+            }
+            Feature::Momentum { period } => {
+                lagged(period, &|i| closes[i] / closes[i - period] - 1.0)
+            }
+            Feature::SmaRatio { period } => sma(&closes, period)
+                .iter()
+                .zip(&closes)
+                .map(|(avg, close)| close / avg - 1.0)
+                .collect(),
+            Feature::Rsi { period } => rsi(&closes, period).iter().map(|v| v / 100.0).collect(),
+            Feature::Volatility { window } => {
+                let returns: Vec<f64> = (0..n)
+                    .map(|i| {
+                        if i > 0 {
+                            closes[i] / closes[i - 1] - 1.0
+                        } else {
+                            0.0
+                        }
+                    })
+                    .collect();
+                lagged(window, &|i| {
+                    let recent = &returns[i + 1 - window..=i];
+                    let mean = recent.iter().sum::<f64>() / window as f64;
+                    let variance =
+                        recent.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / window as f64;
+                    variance.sqrt()
+                })
+            }
+            Feature::AtrRatio { period } => atr(data, period)
+                .iter()
+                .zip(&closes)
+                .map(|(a, close)| a / close)
+                .collect(),
+            Feature::VolumeRatio { period } => {
+                let volumes: Vec<f64> = data.iter().map(|b| b.volume).collect();
+                sma(&volumes, period)
+                    .iter()
+                    .zip(&volumes)
+                    .map(|(avg, volume)| volume / avg - 1.0)
+                    .collect()
+            }
+            Feature::ZScore { period } => zscore(&closes, period),
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Feature::Return { lag } => write!(f, "return:{}", lag),
+            Feature::Momentum { period } => write!(f, "momentum:{}", period),
+            Feature::SmaRatio { period } => write!(f, "sma:{}", period),
+            Feature::Rsi { period } => write!(f, "rsi:{}", period),
+            Feature::Volatility { window } => write!(f, "volatility:{}", window),
+            Feature::AtrRatio { period } => write!(f, "atr:{}", period),
+            Feature::VolumeRatio { period } => write!(f, "volume:{}", period),
+            Feature::ZScore { period } => write!(f, "zscore:{}", period),
+        }
+    }
+}
+
+/// Ordered list of features forming one model input row per bar
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeaturePipeline {
+    pub features: Vec<Feature>,
+}
+
+impl Default for FeaturePipeline {
+    fn default() -> Self {
+        Self::new()
+            .with(Feature::Return { lag: 0 })
+            .with(Feature::Return { lag: 1 })
+            .with(Feature::Return { lag: 2 })
+            .with(Feature::Momentum { period: 10 })
+            .with(Feature::SmaRatio { period: 20 })
+            .with(Feature::Rsi { period: 14 })
+            .with(Feature::Volatility { window: 20 })
+            .with(Feature::AtrRatio { period: 14 })
+    }
+}
+
+impl FeaturePipeline {
+    pub fn new() -> Self {
+        Self {
+            features: Vec::new(),
+        }
+    }
+
+    pub fn with(mut self, feature: Feature) -> Self {
+        self.features.push(feature);
+        self
+    }
+
+    /// Pipeline from a comma-separated list such as `return:0,sma:20,rsi:14`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let features = spec
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(Feature::parse)
+            .collect::<Result<Vec<_>>>()?;
+        if features.is_empty() {
+            bail!("Feature list is empty");
+        }
+        Ok(Self { features })
+    }
+
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.features.iter().map(|f| f.to_string()).collect()
+    }
+
+    /// Bars before the first complete row
+    pub fn warmup(&self) -> usize {
+        self.features.iter().map(Feature::warmup).max().unwrap_or(0)
+    }
+
+    /// Feature row of every bar; `None` while any feature is warming up or
+    /// not finite
+    pub fn compute(&self, data: &[OHLCV]) -> Vec<Option<Vec<f64>>> {
+        let columns: Vec<Vec<f64>> = self.features.iter().map(|f| f.compute(data)).collect();
+        (0..data.len())
+            .map(|i| {
+                let row: Vec<f64> = columns.iter().map(|c| c[i]).collect();
+                row.iter().all(|v| v.is_finite()).then_some(row)
+            })
+            .collect()
+    }
+}
+
+/// Return from each close to the next; `None` for the last bar
+pub fn next_returns(data: &[OHLCV]) -> Vec<Option<f64>> {
+    (0..data.len())
+        .map(|i| data.get(i + 1).map(|next| next.close / data[i].close - 1.0))
+        .collect()
+}
+
+/// Standardizes each feature with the mean and standard deviation of the
+/// training rows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scaler {
+    pub means: Vec<f64>,
+    pub stds: Vec<f64>,
+}
+
+impl Scaler {
+    pub fn fit(rows: &[Vec<f64>]) -> Self {
+        let k = rows.first().map_or(0, |r| r.len());
+        let n = rows.len().max(1) as f64;
+        let means: Vec<f64> = (0..k)
+            .map(|j| rows.iter().map(|r| r[j]).sum::<f64>() / n)
+            .collect();
+        let stds = (0..k)
+            .map(|j| {
+                let variance = rows.iter().map(|r| (r[j] - means[j]).powi(2)).sum::<f64>() / n;
+                // Constant features pass through centred
+                if variance > 0.0 {
+                    variance.sqrt()
+                } else {
+                    1.0
+                }
+            })
+            .collect();
+        Self { means, stds }
+    }
+
+    pub fn transform(&self, row: &[f64]) -> Vec<f64> {
+        row.iter()
+            .zip(self.means.iter().zip(&self.stds))
+            .map(|(v, (mean, std))| (v - mean) / std)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::test_support::sample_bars;
+
+    #[test]
+    fn test_pipeline_rows() {
+        let data = sample_bars();
+        let pipeline = FeaturePipeline::parse("return:0, return:2, momentum:5, sma:20").unwrap();
+        assert_eq!(
+            pipeline.names(),
+            vec!["return:0", "return:2", "momentum:5", "sma:20"]
+        );
+        assert_eq!(pipeline.warmup(), 19);
+
+        let rows = pipeline.compute(&data);
+        assert!(rows[18].is_none());
+        let row = rows[30].as_ref().unwrap();
+        assert!((row[0] - (data[30].close / data[29].close - 1.0)).abs() < 1e-12);
+        assert!((row[1] - (data[28].close / data[27].close - 1.0)).abs() < 1e-12);
+        assert!((row[2] - (data[30].close / data[25].close - 1.0)).abs() < 1e-12);
+
+        // Every default feature is available once the pipeline is warmed up
+        let default = FeaturePipeline::default();
+        let rows = default.compute(&data);
+        assert!(rows[default.warmup()..].iter().all(Option::is_some));
+
+        assert!(FeaturePipeline::parse("macd:12").is_err());
+        assert!(FeaturePipeline::parse("sma:0").is_err());
+        assert_eq!(next_returns(&data).last().unwrap(), &None);
+    }
+}
@@ -0,0 +1,19 @@
+//! Learned signal models
+//!
+//! A [`FeaturePipeline`] turns bars into feature rows, a [`ModelKind`] is
+//! fitted on rows labelled with the next bar's return, and the fitted
+//! [`MlModel`] trades as a `Strategy`. [`ModelWalkForward`] refits the model
+//! on rolling windows so every prediction is out of sample. Models trained
+//! elsewhere run through [`OnnxStrategy`].
+
+pub mod features;
+pub mod models;
+pub mod onnx;
+pub mod strategy;
+pub mod walkforward;
+
+pub use features::{next_returns, Feature, FeaturePipeline, Scaler};
+pub use models::{BoostedStumps, FittedModel, LinearModel, ModelKind, Stump};
+pub use onnx::{OnnxSpec, OnnxStrategy, OnnxSweep};
+pub use strategy::{MlModel, ModelWindow, WalkForwardModel};
+pub use walkforward::{ModelWalkForward, ModelWalkForwardResult, WindowReport};
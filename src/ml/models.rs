@@ -0,0 +1,398 @@
+use crate::linalg::invert;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Model family and its hyperparameters
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ModelKind {
+    /// Probability that the next bar closes higher, by L2-regularized
+    /// logistic regression fitted with gradient descent
+    Logistic {
+        l2: f64,
+        learning_rate: f64,
+        epochs: usize,
+    },
+    /// Next-bar return by ridge regression (closed form)
+    Ridge { lambda: f64 },
+    /// Probability that the next bar closes higher, by gradient boosting of
+    /// one-split trees on the logistic loss
+    Boosting {
+        rounds: usize,
+        learning_rate: f64,
+        /// Candidate split points per feature, at quantiles
+        bins: usize,
+    },
+}
+
+impl ModelKind {
+    pub fn logistic() -> Self {
+        ModelKind::Logistic {
+            l2: 1e-3,
+            learning_rate: 0.1,
+            epochs: 500,
+        }
+    }
+
+    pub fn ridge() -> Self {
+        ModelKind::Ridge { lambda: 1.0 }
+    }
+
+    pub fn boosting() -> Self {
+        ModelKind::Boosting {
+            rounds: 100,
+            learning_rate: 0.1,
+            bins: 16,
+        }
+    }
+
+    /// Model with default hyperparameters: logistic, ridge or boosting
+    pub fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "logistic" => Self::logistic(),
+            "ridge" => Self::ridge(),
+            "boosting" => Self::boosting(),
+            _ => bail!(
+                "Unknown model '{}' (expected logistic, ridge or boosting)",
+                name
+            ),
+        })
+    }
+
+    /// Name accepted by [`ModelKind::parse`]
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModelKind::Logistic { .. } => "logistic",
+            ModelKind::Ridge { .. } => "ridge",
+            ModelKind::Boosting { .. } => "boosting",
+        }
+    }
+
+    /// Whether predictions are up-probabilities rather than returns
+    pub fn is_classifier(&self) -> bool {
+        !matches!(self, ModelKind::Ridge { .. })
+    }
+
+    /// Prediction above which the strategy goes long: 0.5 for probabilities,
+    /// 0 for returns
+    pub fn default_threshold(&self) -> f64 {
+        if self.is_classifier() {
+            0.5
+        } else {
+            0.0
+        }
+    }
+
+    /// Fit on standardized `rows` against the next-bar `returns`;
+    /// classifiers learn whether each return is positive
+    pub fn fit(&self, rows: &[Vec<f64>], returns: &[f64]) -> Result<FittedModel> {
+        if rows.len() != returns.len() {
+            bail!(
+                "Need one return per row ({} rows, {} returns)",
+                rows.len(),
+                returns.len()
+            );
+        }
+        let k = rows.first().map_or(0, |r| r.len());
+        if rows.len() <= k {
+            bail!(
+                "Need more training rows than features ({} rows, {} features)",
+                rows.len(),
+                k
+            );
+        }
+        let labels: Vec<f64> = returns
+            .iter()
+            .map(|&r| if r > 0.0 { 1.0 } else { 0.0 })
+            .collect();
+
+        Ok(match *self {
+            ModelKind::Logistic {
+                l2,
+                learning_rate,
+                epochs,
+            } => FittedModel::Logistic(fit_logistic(rows, &labels, l2, learning_rate, epochs)),
+            ModelKind::Ridge { lambda } => FittedModel::Ridge(fit_ridge(rows, returns, lambda)?),
+            ModelKind::Boosting {
+                rounds,
+                learning_rate,
+                bins,
+            } => FittedModel::Boosting(fit_boosting(rows, &labels, rounds, learning_rate, bins)),
+        })
+    }
+}
+
+impl fmt::Display for ModelKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModelKind::Logistic { l2, .. } => write!(f, "logistic regression (l2 {})", l2),
+            ModelKind::Ridge { lambda } => write!(f, "ridge regression (lambda {})", lambda),
+            ModelKind::Boosting {
+                rounds,
+                learning_rate,
+                ..
+            } => write!(
+                f,
+                "boosted stumps ({} rounds, learning rate {})",
+                rounds, learning_rate
+            ),
+        }
+    }
+}
+
+/// Intercept plus one weight per feature
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinearModel {
+    pub intercept: f64,
+    pub weights: Vec<f64>,
+}
+
+impl LinearModel {
+    fn score(&self, row: &[f64]) -> f64 {
+        self.intercept
+            + self
+                .weights
+                .iter()
+                .zip(row)
+                .map(|(w, x)| w * x)
+                .sum::<f64>()
+    }
+}
+
+/// One-split tree: `left` below `threshold` on `feature`, `right` otherwise
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stump {
+    pub feature: usize,
+    pub threshold: f64,
+    pub left: f64,
+    pub right: f64,
+}
+
+impl Stump {
+    fn value(&self, row: &[f64]) -> f64 {
+        if row[self.feature] < self.threshold {
+            self.left
+        } else {
+            self.right
+        }
+    }
+}
+
+/// Sum of shrunken stumps on the log-odds scale
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoostedStumps {
+    /// Log-odds of the training base rate
+    pub base: f64,
+    pub learning_rate: f64,
+    pub stumps: Vec<Stump>,
+}
+
+impl BoostedStumps {
+    fn log_odds(&self, row: &[f64]) -> f64 {
+        self.base + self.learning_rate * self.stumps.iter().map(|s| s.value(row)).sum::<f64>()
+    }
+}
+
+/// A trained model, serializable with its parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FittedModel {
+    Logistic(LinearModel),
+    Ridge(LinearModel),
+    Boosting(BoostedStumps),
+}
+
+impl FittedModel {
+    /// Up-probability for classifiers, expected next-bar return for ridge
+    pub fn predict(&self, row: &[f64]) -> f64 {
+        match self {
+            FittedModel::Logistic(model) => sigmoid(model.score(row)),
+            FittedModel::Ridge(model) => model.score(row),
+            FittedModel::Boosting(model) => sigmoid(model.log_odds(row)),
+        }
+    }
+}
+
+fn sigmoid(z: f64) -> f64 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+fn fit_logistic(
+    rows: &[Vec<f64>],
+    labels: &[f64],
+    l2: f64,
+    learning_rate: f64,
+    epochs: usize,
+) -> LinearModel {
+    let n = rows.len() as f64;
+    let mut model = LinearModel {
+        intercept: 0.0,
+        weights: vec![0.0; rows[0].len()],
+    };
+
+    for _ in 0..epochs {
+        let mut grad_intercept = 0.0;
+        let mut grad = vec![0.0; model.weights.len()];
+        for (row, label) in rows.iter().zip(labels) {
+            let error = sigmoid(model.score(row)) - label;
+            grad_intercept += error;
+            for (g, x) in grad.iter_mut().zip(row) {
+                *g += error * x;
+            }
+        }
+        model.intercept -= learning_rate * grad_intercept / n;
+        for (w, g) in model.weights.iter_mut().zip(&grad) {
+            *w -= learning_rate * (g / n + l2 * *w);
+        }
+    }
+    model
+}
+
+fn fit_ridge(rows: &[Vec<f64>], targets: &[f64], lambda: f64) -> Result<LinearModel> {
+    let n = rows.len() as f64;
+    let k = rows[0].len();
+    let mean_y = targets.iter().sum::<f64>() / n;
+    let means: Vec<f64> = (0..k)
+        .map(|j| rows.iter().map(|r| r[j]).sum::<f64>() / n)
+        .collect();
+
+    // Centred normal equations, so the intercept is not penalized
+    let mut xtx = vec![vec![0.0; k]; k];
+    let mut xty = vec![0.0; k];
+    for (row, target) in rows.iter().zip(targets) {
+        let centred: Vec<f64> = row.iter().zip(&means).map(|(x, m)| x - m).collect();
+        for i in 0..k {
+            xty[i] += centred[i] * (target - mean_y);
+            for j in 0..k {
+                xtx[i][j] += centred[i] * centred[j];
+            }
+        }
+    }
+    for (i, row) in xtx.iter_mut().enumerate() {
+        row[i] += lambda;
+    }
+
+    let inverse = match invert(xtx) {
+        Some(inverse) => inverse,
+        None => bail!("Ridge normal equations are singular; increase lambda"),
+    };
+    let weights: Vec<f64> = inverse
+        .iter()
+        .map(|r| r.iter().zip(&xty).map(|(a, b)| a * b).sum())
+        .collect();
+    let intercept = mean_y - weights.iter().zip(&means).map(|(w, m)| w * m).sum::<f64>();
+    Ok(LinearModel { intercept, weights })
+}
+
+fn fit_boosting(
+    rows: &[Vec<f64>],
+    labels: &[f64],
+    rounds: usize,
+    learning_rate: f64,
+    bins: usize,
+) -> BoostedStumps {
+    let n = rows.len();
+    let k = rows[0].len();
+    let rate = (labels.iter().sum::<f64>() / n as f64).clamp(1e-6, 1.0 - 1e-6);
+    let mut model = BoostedStumps {
+        base: (rate / (1.0 - rate)).ln(),
+        learning_rate,
+        stumps: Vec::new(),
+    };
+
+    // Candidate thresholds at the quantiles of each feature
+    let thresholds: Vec<Vec<f64>> = (0..k)
+        .map(|j| {
+            let mut values: Vec<f64> = rows.iter().map(|r| r[j]).collect();
+            values.sort_by(f64::total_cmp);
+            let mut cuts: Vec<f64> = (1..bins.max(2))
+                .map(|b| values[b * n / bins.max(2)])
+                .collect();
+            cuts.dedup();
+            cuts
+        })
+        .collect();
+
+    let mut log_odds = vec![model.base; n];
+    for _ in 0..rounds {
+        // Gradient and Hessian of the logistic loss at the current fit
+        let p: Vec<f64> = log_odds.iter().map(|&z| sigmoid(z)).collect();
+        let gradient: Vec<f64> = labels.iter().zip(&p).map(|(y, p)| y - p).collect();
+        let hessian: Vec<f64> = p.iter().map(|p| (p * (1.0 - p)).max(1e-12)).collect();
+
+        let mut best: Option<(f64, Stump)> = None;
+        for (j, cuts) in thresholds.iter().enumerate() {
+            for &threshold in cuts {
+                let (mut gl, mut hl, mut gr, mut hr) = (0.0, 0.0, 0.0, 0.0);
+                for ((row, g), h) in rows.iter().zip(&gradient).zip(&hessian) {
+                    if row[j] < threshold {
+                        gl += g;
+                        hl += h;
+                    } else {
+                        gr += g;
+                        hr += h;
+                    }
+                }
+                // Loss reduction of Newton steps in both leaves
+                let gain = gl * gl / hl.max(1e-12) + gr * gr / hr.max(1e-12);
+                if best.as_ref().is_none_or(|(g, _)| gain > *g) {
+                    let stump = Stump {
+                        feature: j,
+                        threshold,
+                        left: gl / hl.max(1e-12),
+                        right: gr / hr.max(1e-12),
+                    };
+                    best = Some((gain, stump));
+                }
+            }
+        }
+
+        let Some((_, stump)) = best else { break };
+        for (z, row) in log_odds.iter_mut().zip(rows) {
+            *z += learning_rate * stump.value(row);
+        }
+        model.stumps.push(stump);
+    }
+    model
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::SeededRng;
+
+    #[test]
+    fn test_models_learn_simple_relations() {
+        let mut rng = SeededRng::new(8);
+        let rows: Vec<Vec<f64>> = (0..400).map(|_| vec![rng.normal(), rng.normal()]).collect();
+        // Returns driven by the first feature only
+        let returns: Vec<f64> = rows
+            .iter()
+            .map(|r| 0.01 * r[0] + 0.001 * rng.normal())
+            .collect();
+
+        let ridge = ModelKind::Ridge { lambda: 1e-6 }
+            .fit(&rows, &returns)
+            .unwrap();
+        let FittedModel::Ridge(linear) = &ridge else {
+            panic!("expected a ridge model")
+        };
+        assert!((linear.weights[0] - 0.01).abs() < 1e-3);
+        assert!(linear.weights[1].abs() < 1e-3);
+
+        for kind in [ModelKind::logistic(), ModelKind::boosting()] {
+            let model = kind.fit(&rows, &returns).unwrap();
+            assert!(model.predict(&[2.0, 0.0]) > 0.8, "{}", kind);
+            assert!(model.predict(&[-2.0, 0.0]) < 0.2, "{}", kind);
+
+            // Fitted models round-trip through JSON unchanged
+            let json = serde_json::to_string(&model).unwrap();
+            let restored: FittedModel = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored.predict(&[0.3, -1.0]), model.predict(&[0.3, -1.0]));
+        }
+
+        assert!(ModelKind::parse("svm").is_err());
+        assert!(ModelKind::ridge().fit(&rows[..2], &returns[..2]).is_err());
+    }
+}
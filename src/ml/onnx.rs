@@ -0,0 +1,560 @@
+use crate::data::OHLCV;
+use crate::ml::FeaturePipeline;
+use crate::strategies::{signals_or_flat, Constraint, ParamSpec, Strategy, StrategyDefinition};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tract_onnx::prelude::*;
+
+/// How an ONNX model is fed and how its output becomes a position
+///
+/// Read from TOML or JSON, e.g.
+///
+/// ```toml
+/// model = "direction.onnx"
+/// features = ["return:0", "return:1", "rsi:14"]
+/// column = 1          # probability of the up class
+/// enter_above = 0.55
+/// exit_below = 0.5
+///
+/// [sweep]             # optional optimizer ranges as [start, end, step]
+/// enter_above = [0.5, 0.6, 0.02]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OnnxSpec {
+    /// Display name; defaults to the spec file name
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Model file, relative to the spec file
+    pub model: PathBuf,
+    /// Features as `name:n`, in the column order the model was trained on
+    pub features: Vec<String>,
+    /// Bars per input: 1 feeds `[1, features]`, more feed
+    /// `[1, window, features]` with the oldest bar first
+    #[serde(default = "default_window")]
+    pub window: usize,
+    /// Model output to read
+    #[serde(default)]
+    pub output: usize,
+    /// Element of that output to read, e.g. 1 for a classifier's up-class
+    /// probability
+    #[serde(default)]
+    pub column: usize,
+    /// Go long when the output rises above this
+    #[serde(default = "default_enter_above")]
+    pub enter_above: f64,
+    /// Go flat when the output falls below this; defaults to `enter_above`
+    #[serde(default)]
+    pub exit_below: Option<f64>,
+    /// Default optimizer ranges for the thresholds
+    #[serde(default)]
+    pub sweep: OnnxSweep,
+}
+
+/// Threshold ranges swept by `optimize` and `walkforward` when no `--grid`
+/// is given; thresholds without a range stay at their spec values
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OnnxSweep {
+    #[serde(default)]
+    pub enter_above: Option<[f64; 3]>,
+    #[serde(default)]
+    pub exit_below: Option<[f64; 3]>,
+}
+
+fn default_window() -> usize {
+    1
+}
+
+fn default_enter_above() -> f64 {
+    0.5
+}
+
+impl OnnxSpec {
+    /// Spec from a TOML or JSON file; the model path is resolved against the
+    /// file's directory
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read ONNX spec {}", path.display()))?;
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let mut spec: OnnxSpec = if is_json {
+            serde_json::from_str(&source)
+                .with_context(|| format!("Invalid ONNX spec {}", path.display()))?
+        } else {
+            toml::from_str(&source)
+                .with_context(|| format!("Invalid ONNX spec {}", path.display()))?
+        };
+
+        if let Some(dir) = path.parent() {
+            spec.model = dir.join(&spec.model);
+        }
+        if spec.name.is_none() {
+            spec.name = path.file_stem().map(|s| s.to_string_lossy().to_string());
+        }
+        Ok(spec)
+    }
+}
+
+/// Strategy running an externally trained ONNX model on CPU
+///
+/// Each bar's input is built from the spec's features; the model is long
+/// from the bar its output rises above `enter_above` until it falls below
+/// `exit_below`, and flat while the features warm up. Models exported from
+/// scikit-learn need `zipmap=False` so probabilities come out as a tensor.
+pub struct OnnxStrategy {
+    name: String,
+    spec: OnnxSpec,
+    features: FeaturePipeline,
+    model: Arc<TypedRunnableModel>,
+}
+
+impl OnnxStrategy {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::new(OnnxSpec::load(path)?)
+    }
+
+    /// Load the spec's model with its input fixed to one window of features,
+    /// and check that the configured output exists
+    pub fn new(spec: OnnxSpec) -> Result<Self> {
+        let features = FeaturePipeline::parse(&spec.features.join(","))?;
+        if spec.window == 0 {
+            bail!("ONNX window must be at least 1 bar");
+        }
+        if let Some(exit) = spec.exit_below {
+            if exit > spec.enter_above {
+                bail!(
+                    "exit_below ({}) must not be above enter_above ({})",
+                    exit,
+                    spec.enter_above
+                );
+            }
+        }
+
+        let model = tract_onnx::onnx()
+            .model_for_path(&spec.model)
+            .with_context(|| format!("Failed to read ONNX model {}", spec.model.display()))?
+            .with_input_fact(0, f32::fact(input_shape(&spec, features.len())).into())?
+            .into_optimized()
+            .with_context(|| {
+                format!(
+                    "ONNX model {} does not accept a {:?} float input",
+                    spec.model.display(),
+                    input_shape(&spec, features.len())
+                )
+            })?
+            .into_runnable()?;
+
+        let strategy = Self {
+            name: spec.name.clone().unwrap_or_else(|| "onnx".to_string()),
+            spec,
+            features,
+            model,
+        };
+        strategy.infer(&vec![0.0; strategy.spec.window * strategy.features.len()])?;
+        Ok(strategy)
+    }
+
+    pub fn spec(&self) -> &OnnxSpec {
+        &self.spec
+    }
+
+    /// Registry definition that rebuilds this model's strategy with swept
+    /// thresholds, sharing the loaded model
+    ///
+    /// `exit_below` is a parameter only when the spec sets or sweeps it;
+    /// otherwise it follows `enter_above`. Built strategies are named after
+    /// their thresholds so that variants can be told apart.
+    pub fn definition(self) -> StrategyDefinition {
+        let base = Arc::new(self);
+        let enter_above = base.spec.enter_above;
+        let separate_exit = base.spec.exit_below.is_some() || base.spec.sweep.exit_below.is_some();
+        let threshold = |name: &str, default: f64, sweep: Option<[f64; 3]>| {
+            let spec = ParamSpec::float(name, default, f64::MIN, f64::MAX);
+            match sweep {
+                Some([start, end, step]) => spec.with_sweep(start, end, step),
+                None => spec,
+            }
+        };
+
+        let definition = StrategyDefinition::new(
+            &format!("onnx-{}", base.name),
+            &format!("ONNX model {}", base.spec.model.display()),
+            {
+                let base = Arc::clone(&base);
+                move |params| {
+                    let enter_above = params.get("enter_above");
+                    let spec = OnnxSpec {
+                        enter_above,
+                        exit_below: Some(if separate_exit {
+                            params.get("exit_below")
+                        } else {
+                            enter_above
+                        }),
+                        ..base.spec.clone()
+                    };
+                    Ok(Box::new(OnnxStrategy {
+                        name: format!("{}({})", base.name, params),
+                        spec,
+                        features: base.features.clone(),
+                        model: Arc::clone(&base.model),
+                    }) as Box<dyn Strategy>)
+                }
+            },
+        )
+        .with_param(
+            threshold("enter_above", enter_above, base.spec.sweep.enter_above)
+                .with_description("Go long when the model output rises above this"),
+        );
+        if !separate_exit {
+            return definition;
+        }
+        definition
+            .with_param(
+                threshold(
+                    "exit_below",
+                    base.spec.exit_below.unwrap_or(enter_above),
+                    base.spec.sweep.exit_below,
+                )
+                .with_description("Go flat when the model output falls below this"),
+            )
+            .with_constraint(Constraint::at_most("exit_below", "enter_above"))
+    }
+
+    /// Model output for one flattened input window
+    pub fn infer(&self, input: &[f32]) -> Result<f64> {
+        let shape = input_shape(&self.spec, self.features.len());
+        let tensor = tract_ndarray::ArrayD::from_shape_vec(shape, input.to_vec())?;
+        let outputs = self.model.run(tvec!(Tensor::from(tensor).into()))?;
+
+        let Some(output) = outputs.get(self.spec.output) else {
+            bail!(
+                "ONNX model has {} outputs, output {} requested",
+                outputs.len(),
+                self.spec.output
+            );
+        };
+        let values = output.cast_to::<f64>()?;
+        let values = values.to_plain_array_view::<f64>()?;
+        match values.iter().nth(self.spec.column) {
+            Some(&value) => Ok(value),
+            None => bail!(
+                "ONNX output {} has {} values, column {} requested",
+                self.spec.output,
+                values.len(),
+                self.spec.column
+            ),
+        }
+    }
+
+    /// Model output at every bar; `None` until a full window of features
+    pub fn predictions(&self, data: &[OHLCV]) -> Result<Vec<Option<f64>>> {
+        let rows = self.features.compute(data);
+        let window = self.spec.window;
+        let mut input = Vec::with_capacity(window * self.features.len());
+
+        (0..data.len())
+            .map(|i| {
+                if i + 1 < window || rows[i + 1 - window..=i].iter().any(Option::is_none) {
+                    return Ok(None);
+                }
+                input.clear();
+                for row in rows[i + 1 - window..=i].iter().flatten() {
+                    input.extend(row.iter().map(|&v| v as f32));
+                }
+                self.infer(&input).map(Some)
+            })
+            .collect()
+    }
+
+    fn positions(&self, data: &[OHLCV]) -> Result<Vec<f64>> {
+        let exit_below = self.spec.exit_below.unwrap_or(self.spec.enter_above);
+        let mut position = 0.0;
+        Ok(self
+            .predictions(data)?
+            .into_iter()
+            .map(|prediction| {
+                position = match prediction {
+                    Some(p) if p > self.spec.enter_above => 1.0,
+                    Some(p) if p < exit_below => 0.0,
+                    Some(_) => position,
+                    None => 0.0,
+                };
+                position
+            })
+            .collect())
+    }
+}
+
+fn input_shape(spec: &OnnxSpec, features: usize) -> Vec<usize> {
+    if spec.window == 1 {
+        vec![1, features]
+    } else {
+        vec![1, spec.window, features]
+    }
+}
+
+impl Strategy for OnnxStrategy {
+    fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+        signals_or_flat(&self.name, data, self.positions(data))
+    }
+
+    /// Run inference, surfacing model errors instead of going flat
+    fn try_generate_signals(&self, data: &[OHLCV]) -> Result<Vec<f64>> {
+        self.positions(data)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "ONNX model {} on [{}], long above {}",
+            self.spec.model.display(),
+            self.features.names().join(", "),
+            self.spec.enter_above
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::test_support::sample_bars;
+    use crate::strategies::Params;
+    use prost::Message;
+    use tract_onnx::pb;
+
+    fn float_tensor(name: &str, dims: &[i64], values: &[f32]) -> pb::TensorProto {
+        pb::TensorProto {
+            name: name.to_string(),
+            dims: dims.to_vec(),
+            data_type: pb::tensor_proto::DataType::Float as i32,
+            float_data: values.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    fn node(op: &str, inputs: &[&str], output: &str) -> pb::NodeProto {
+        pb::NodeProto {
+            op_type: op.to_string(),
+            input: inputs.iter().map(|s| s.to_string()).collect(),
+            output: vec![output.to_string()],
+            name: output.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// `y = flatten(x) * weights + bias`
+    fn write_linear_model(path: &Path, weights: &[f32], bias: f32) {
+        let value = |name: &str| pb::ValueInfoProto {
+            name: name.to_string(),
+            r#type: Some(pb::TypeProto {
+                value: Some(pb::type_proto::Value::TensorType(pb::type_proto::Tensor {
+                    elem_type: pb::tensor_proto::DataType::Float as i32,
+                    shape: None,
+                })),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let graph = pb::GraphProto {
+            name: "linear".to_string(),
+            node: vec![
+                node("Flatten", &["x"], "flat"),
+                node("MatMul", &["flat", "w"], "score"),
+                node("Add", &["score", "b"], "y"),
+            ],
+            initializer: vec![
+                float_tensor("w", &[weights.len() as i64, 1], weights),
+                float_tensor("b", &[1], &[bias]),
+            ],
+            input: vec![value("x")],
+            output: vec![value("y")],
+            ..Default::default()
+        };
+        let model = pb::ModelProto {
+            ir_version: 7,
+            opset_import: vec![pb::OperatorSetIdProto {
+                domain: String::new(),
+                version: 13,
+            }],
+            graph: Some(graph),
+            ..Default::default()
+        };
+        std::fs::write(path, model.encode_to_vec()).unwrap();
+    }
+
+    #[test]
+    fn test_onnx_model_signals() {
+        let dir = std::env::temp_dir().join("strataquant_onnx_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let data = sample_bars();
+        let returns: Vec<f64> = FeaturePipeline::parse("return:0")
+            .unwrap()
+            .compute(&data)
+            .iter()
+            .map(|row| row.as_ref().map_or(f64::NAN, |r| r[0]))
+            .collect();
+
+        // Sum of the last two returns, from a spec file next to the model
+        write_linear_model(&dir.join("sum.onnx"), &[1.0, 1.0], 0.0);
+        let spec_path = dir.join("sum.toml");
+        std::fs::write(
+            &spec_path,
+            "model = \"sum.onnx\"\nfeatures = [\"return:0\", \"return:1\"]\nenter_above = 0.0\n",
+        )
+        .unwrap();
+        let strategy = OnnxStrategy::load(&spec_path).unwrap();
+        assert_eq!(strategy.name(), "sum");
+
+        let predictions = strategy.predictions(&data).unwrap();
+        assert_eq!(predictions[1], None);
+        for i in 2..data.len() {
+            let expected = returns[i] + returns[i - 1];
+            assert!((predictions[i].unwrap() - expected).abs() < 1e-6);
+        }
+        let signals = strategy.generate_signals(&data);
+        assert_eq!(
+            signals[10],
+            if returns[10] + returns[9] > 0.0 {
+                1.0
+            } else {
+                0.0
+            }
+        );
+
+        // A window of two bars is fed oldest first, so this weight picks the
+        // latest return
+        write_linear_model(&dir.join("latest.onnx"), &[0.0, 1.0], 0.0);
+        let spec = OnnxSpec {
+            name: None,
+            model: dir.join("latest.onnx"),
+            features: vec!["return:0".to_string()],
+            window: 2,
+            output: 0,
+            column: 0,
+            enter_above: 0.001,
+            exit_below: Some(-0.001),
+            sweep: OnnxSweep::default(),
+        };
+        let strategy = OnnxStrategy::new(spec.clone()).unwrap();
+        let predictions = strategy.predictions(&data).unwrap();
+        assert_eq!(predictions[1], None);
+        assert!((predictions[50].unwrap() - returns[50]).abs() < 1e-6);
+
+        // Between the thresholds the position is held
+        let signals = strategy.generate_signals(&data);
+        for i in 3..data.len() {
+            let expected = if returns[i] > 0.001 {
+                1.0
+            } else if returns[i] < -0.001 {
+                0.0
+            } else {
+                signals[i - 1]
+            };
+            assert_eq!(signals[i], expected);
+        }
+
+        // Outputs that do not exist are rejected on load
+        assert!(OnnxStrategy::new(OnnxSpec {
+            column: 1,
+            ..spec.clone()
+        })
+        .is_err());
+        assert!(OnnxStrategy::new(OnnxSpec { window: 3, ..spec }).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_onnx_definition_sweeps_thresholds() {
+        let dir = std::env::temp_dir().join("strataquant_onnx_definition_test");
+        std::fs::create_dir_all(&dir).unwrap();
+        let data = sample_bars();
+
+        write_linear_model(&dir.join("latest.onnx"), &[1.0], 0.0);
+        let spec_path = dir.join("latest.toml");
+        std::fs::write(
+            &spec_path,
+            "model = \"latest.onnx\"\nfeatures = [\"return:0\"]\nenter_above = 0.0\n\n\
+             [sweep]\nenter_above = [0.0, 0.002, 0.001]\nexit_below = [-0.001, 0.001, 0.001]\n",
+        )
+        .unwrap();
+        let definition = OnnxStrategy::load(&spec_path).unwrap().definition();
+        assert_eq!(definition.name, "onnx-latest");
+
+        // Defaults come from the spec, with exit_below following enter_above
+        let params = definition.resolve(&Params::new()).unwrap();
+        assert_eq!(params.get("enter_above"), 0.0);
+        assert_eq!(params.get("exit_below"), 0.0);
+        assert_eq!(definition.file_stem(&params), "onnx_latest_0_0");
+        assert!(definition
+            .resolve(&Params::new().with("exit_below", 0.001))
+            .is_err());
+
+        // 3 x 3 thresholds, minus exit_below 0.001 over enter_above 0
+        let combinations = definition
+            .grid_combinations(&definition.default_grid())
+            .unwrap();
+        assert_eq!(combinations.len(), 8);
+
+        // Built strategies use the swept thresholds
+        let strategy = definition
+            .build(
+                &Params::new()
+                    .with("enter_above", 0.002)
+                    .with("exit_below", -0.002),
+            )
+            .unwrap();
+        assert_eq!(
+            strategy.name(),
+            "latest(enter_above=0.002 exit_below=-0.002)"
+        );
+        let direct = OnnxStrategy::new(OnnxSpec {
+            enter_above: 0.002,
+            exit_below: Some(-0.002),
+            ..OnnxSpec::load(&spec_path).unwrap()
+        })
+        .unwrap();
+        assert_eq!(
+            strategy.generate_signals(&data),
+            direct.generate_signals(&data)
+        );
+
+        // Without exit_below in the spec, the exit follows each swept entry
+        std::fs::write(
+            &spec_path,
+            "model = \"latest.onnx\"\nfeatures = [\"return:0\"]\nenter_above = 0.001\n\n\
+             [sweep]\nenter_above = [0.0, 0.002, 0.001]\n",
+        )
+        .unwrap();
+        let definition = OnnxStrategy::load(&spec_path).unwrap().definition();
+        assert!(definition.param("exit_below").is_none());
+        let combinations = definition
+            .grid_combinations(&definition.default_grid())
+            .unwrap();
+        assert_eq!(combinations.len(), 3);
+        for params in &combinations {
+            let strategy = definition.build(params).unwrap();
+            let enter_above = params.get("enter_above");
+            assert_eq!(
+                strategy.name(),
+                format!("latest(enter_above={})", enter_above)
+            );
+            let direct = OnnxStrategy::new(OnnxSpec {
+                enter_above,
+                exit_below: None,
+                ..OnnxSpec::load(&spec_path).unwrap()
+            })
+            .unwrap();
+            assert_eq!(
+                strategy.generate_signals(&data),
+                direct.generate_signals(&data)
+            );
+        }
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,229 @@
+use crate::data::OHLCV;
+use crate::ml::{next_returns, FeaturePipeline, FittedModel, ModelKind, Scaler};
+use crate::strategies::Strategy;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A fitted model with the features and scaling it was trained on
+///
+/// Goes long when the prediction is above `threshold` and is flat otherwise.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MlModel {
+    pub name: String,
+    pub features: FeaturePipeline,
+    pub scaler: Scaler,
+    pub model: FittedModel,
+    pub threshold: f64,
+    /// Rows the model was fitted on
+    pub training_rows: usize,
+}
+
+impl MlModel {
+    /// Fit `kind` on every bar of `data` that has both a feature row and a
+    /// next bar, so nothing after `data` is used
+    pub fn train(features: &FeaturePipeline, kind: ModelKind, data: &[OHLCV]) -> Result<Self> {
+        let (rows, returns) = training_set(features, data);
+        if rows.is_empty() {
+            bail!(
+                "No training rows: {} bars do not cover the {}-bar feature warm-up",
+                data.len(),
+                features.warmup()
+            );
+        }
+
+        let scaler = Scaler::fit(&rows);
+        let scaled: Vec<Vec<f64>> = rows.iter().map(|r| scaler.transform(r)).collect();
+        let model = kind.fit(&scaled, &returns)?;
+
+        Ok(Self {
+            name: kind.to_string(),
+            features: features.clone(),
+            scaler,
+            model,
+            threshold: kind.default_threshold(),
+            training_rows: rows.len(),
+        })
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Prediction for one unscaled feature row
+    pub fn predict(&self, row: &[f64]) -> f64 {
+        self.model.predict(&self.scaler.transform(row))
+    }
+
+    /// Prediction at every bar; `None` during the feature warm-up
+    pub fn predictions(&self, data: &[OHLCV]) -> Vec<Option<f64>> {
+        self.features
+            .compute(data)
+            .iter()
+            .map(|row| row.as_ref().map(|r| self.predict(r)))
+            .collect()
+    }
+
+    /// Share of bars where the position matched the direction of the next bar
+    pub fn hit_rate(&self, data: &[OHLCV]) -> Option<f64> {
+        directional_hit_rate(&self.predictions(data), &next_returns(data), self.threshold)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        save_json(self, path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        load_json(path)
+    }
+}
+
+impl Strategy for MlModel {
+    fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+        self.predictions(data)
+            .iter()
+            .map(|p| match p {
+                Some(p) if *p > self.threshold => 1.0,
+                _ => 0.0,
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "{} on [{}], long above {}",
+            self.name,
+            self.features.names().join(", "),
+            self.threshold
+        )
+    }
+}
+
+/// Model trained on one walk-forward window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelWindow {
+    pub train_start: i64,
+    pub train_end: i64,
+    /// First bar the model trades
+    pub test_start: i64,
+    pub model: MlModel,
+}
+
+/// Models from successive walk-forward windows, each trading from its
+/// window's test start until the next one takes over
+///
+/// Bars before the first test window are flat; bars after the last window
+/// use the latest model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalkForwardModel {
+    pub name: String,
+    pub features: FeaturePipeline,
+    pub windows: Vec<ModelWindow>,
+}
+
+impl WalkForwardModel {
+    pub fn new(name: &str, features: FeaturePipeline, windows: Vec<ModelWindow>) -> Self {
+        assert!(
+            windows.iter().all(|w| w.model.features == features),
+            "Every window must use the same features"
+        );
+        assert!(
+            windows
+                .windows(2)
+                .all(|w| w[0].test_start < w[1].test_start),
+            "Windows must be in time order"
+        );
+        Self {
+            name: name.to_string(),
+            features,
+            windows,
+        }
+    }
+
+    /// Model trading at `timestamp`
+    pub fn model_at(&self, timestamp: i64) -> Option<&MlModel> {
+        let count = self.windows.partition_point(|w| w.test_start <= timestamp);
+        count.checked_sub(1).map(|i| &self.windows[i].model)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        save_json(self, path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        load_json(path)
+    }
+}
+
+impl Strategy for WalkForwardModel {
+    fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+        self.features
+            .compute(data)
+            .iter()
+            .zip(data)
+            .map(|(row, bar)| match (row, self.model_at(bar.timestamp)) {
+                (Some(row), Some(model)) if model.predict(row) > model.threshold => 1.0,
+                _ => 0.0,
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "{} retrained over {} walk-forward windows on [{}]",
+            self.name,
+            self.windows.len(),
+            self.features.names().join(", ")
+        )
+    }
+}
+
+/// Feature rows with a next-bar return inside `data`
+pub(crate) fn training_set(
+    features: &FeaturePipeline,
+    data: &[OHLCV],
+) -> (Vec<Vec<f64>>, Vec<f64>) {
+    features
+        .compute(data)
+        .into_iter()
+        .zip(next_returns(data))
+        .filter_map(|(row, ret)| Some((row?, ret?)))
+        .unzip()
+}
+
+pub(crate) fn directional_hit_rate(
+    predictions: &[Option<f64>],
+    returns: &[Option<f64>],
+    threshold: f64,
+) -> Option<f64> {
+    let outcomes: Vec<bool> = predictions
+        .iter()
+        .zip(returns)
+        .filter_map(|(p, r)| Some(((*p)? > threshold) == ((*r)? > 0.0)))
+        .collect();
+    (!outcomes.is_empty())
+        .then(|| outcomes.iter().filter(|&&hit| hit).count() as f64 / outcomes.len() as f64)
+}
+
+fn save_json<T: Serialize>(value: &T, path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(value)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn load_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&json).with_context(|| format!("Invalid model file {}", path.display()))
+}
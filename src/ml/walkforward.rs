@@ -0,0 +1,232 @@
+use crate::backtest::{BacktestEngine, BacktestResult, ExecutionModel};
+use crate::data::OHLCV;
+use crate::ml::strategy::directional_hit_rate;
+use crate::ml::{next_returns, FeaturePipeline, MlModel, ModelKind, ModelWindow, WalkForwardModel};
+use anyhow::{bail, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::Path;
+
+/// Fit and out-of-sample accuracy of one window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowReport {
+    pub train_start: i64,
+    pub train_end: i64,
+    pub test_start: i64,
+    pub test_end: i64,
+    pub training_rows: usize,
+    /// Share of training bars whose next-bar direction the model got right
+    pub train_hit_rate: Option<f64>,
+    /// The same over the test bars, which the model never saw
+    pub test_hit_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelWalkForwardResult {
+    pub model: String,
+    pub features: Vec<String>,
+    pub train_size: usize,
+    pub test_size: usize,
+    pub windows: Vec<WindowReport>,
+    /// Backtest of the stitched test windows, each traded by the model
+    /// trained just before it
+    pub out_of_sample: BacktestResult,
+}
+
+impl ModelWalkForwardResult {
+    pub fn save_to_file(&self, path: &Path) -> Result<(), std::io::Error> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// Rolling walk-forward training of an ML model
+///
+/// The model is refitted on each `train_size`-bar window and trades the
+/// following `test_size` bars; windows then roll forward by `test_size`.
+/// Training rows end one bar before the test window, so no label looks into
+/// it. Feature history before a window may be read, since it is in the past.
+pub struct ModelWalkForward<'a> {
+    data: Cow<'a, [OHLCV]>,
+    initial_capital: f64,
+    execution_model: ExecutionModel,
+    train_size: usize,
+    test_size: usize,
+}
+
+impl<'a> ModelWalkForward<'a> {
+    pub fn new(
+        data: impl Into<Cow<'a, [OHLCV]>>,
+        initial_capital: f64,
+        execution_model: ExecutionModel,
+    ) -> Self {
+        Self {
+            data: data.into(),
+            initial_capital,
+            execution_model,
+            train_size: 500,
+            test_size: 100,
+        }
+    }
+
+    pub fn with_windows(mut self, train_size: usize, test_size: usize) -> Self {
+        assert!(train_size > 0, "Training window must not be empty");
+        assert!(test_size > 0, "Test window must not be empty");
+        self.train_size = train_size;
+        self.test_size = test_size;
+        self
+    }
+
+    /// Train a model per window; returns the stitched strategy and its report
+    pub fn run(
+        &self,
+        features: &FeaturePipeline,
+        kind: ModelKind,
+        threshold: Option<f64>,
+    ) -> Result<(WalkForwardModel, ModelWalkForwardResult)> {
+        let n = self.data.len();
+        if n <= self.train_size {
+            bail!(
+                "Need more than {} bars for one training window, got {}",
+                self.train_size,
+                n
+            );
+        }
+
+        let starts: Vec<usize> = (0..)
+            .map(|k| k * self.test_size)
+            .take_while(|s| s + self.train_size < n)
+            .collect();
+        let warmup = features.warmup();
+        let next = next_returns(&self.data);
+
+        let trained: Vec<(ModelWindow, WindowReport)> = starts
+            .par_iter()
+            .map(|&start| {
+                let test_start = start + self.train_size;
+                let test_end = (test_start + self.test_size).min(n);
+                let history = start.saturating_sub(warmup);
+
+                let mut model = MlModel::train(features, kind, &self.data[history..test_start])?;
+                if let Some(threshold) = threshold {
+                    model = model.with_threshold(threshold);
+                }
+
+                let predictions = model.predictions(&self.data[history..test_end]);
+                let offset = |i: usize| i - history;
+                let hit_rate = |from: usize, to: usize, last_label: usize| {
+                    let returns: Vec<Option<f64>> = (from..to)
+                        .map(|i| if i < last_label { next[i] } else { None })
+                        .collect();
+                    directional_hit_rate(
+                        &predictions[offset(from)..offset(to)],
+                        &returns,
+                        model.threshold,
+                    )
+                };
+
+                let report = WindowReport {
+                    train_start: self.data[start].timestamp,
+                    train_end: self.data[test_start - 1].timestamp,
+                    test_start: self.data[test_start].timestamp,
+                    test_end: self.data[test_end - 1].timestamp,
+                    training_rows: model.training_rows,
+                    train_hit_rate: hit_rate(start, test_start, test_start - 1),
+                    test_hit_rate: hit_rate(test_start, test_end, n),
+                };
+                let window = ModelWindow {
+                    train_start: report.train_start,
+                    train_end: report.train_end,
+                    test_start: report.test_start,
+                    model,
+                };
+                Ok((window, report))
+            })
+            .collect::<Result<_>>()?;
+        let (windows, reports): (Vec<ModelWindow>, Vec<WindowReport>) = trained.into_iter().unzip();
+
+        let strategy = WalkForwardModel::new(&kind.to_string(), features.clone(), windows);
+
+        // Trade from the first test bar, with enough history for the features
+        let first_test = self.train_size;
+        let engine = BacktestEngine::new(
+            &self.data[first_test.saturating_sub(warmup)..],
+            self.initial_capital,
+            self.execution_model.clone(),
+        );
+        let out_of_sample = engine.run(&strategy);
+
+        let result = ModelWalkForwardResult {
+            model: kind.to_string(),
+            features: features.names(),
+            train_size: self.train_size,
+            test_size: self.test_size,
+            windows: reports,
+            out_of_sample,
+        };
+        Ok((strategy, result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ml::Feature;
+    use crate::strategies::Strategy;
+
+    #[test]
+    fn test_models_learn_only_from_past_windows() {
+        // Up and down bars alternate, then the series only rises
+        let mut close = 100.0;
+        let data: Vec<OHLCV> = (0..600)
+            .map(|i| {
+                let up = i % 2 == 0 || i >= 300;
+                close *= if up { 1.01 } else { 0.99 };
+                OHLCV::new(i as i64 * 86_400_000, close, close, close, close, 1.0)
+            })
+            .collect();
+
+        let features = FeaturePipeline::new().with(Feature::Return { lag: 0 });
+        let (strategy, result) =
+            ModelWalkForward::new(&data, 10_000.0, ExecutionModel::new(0.0, 0.0))
+                .with_windows(100, 50)
+                .run(&features, ModelKind::logistic(), None)
+                .unwrap();
+
+        assert_eq!(result.windows.len(), 10);
+        assert_eq!(strategy.windows.len(), 10);
+        for window in &result.windows {
+            assert!(window.train_end < window.test_start);
+        }
+        // The last training bar has no label, the first window also loses
+        // its first bar to the warm-up
+        assert_eq!(result.windows[0].training_rows, 98);
+        assert_eq!(result.windows[1].training_rows, 99);
+
+        // Alternation is learned perfectly and trades well out of sample
+        let first = &result.windows[0];
+        assert_eq!(first.train_hit_rate, Some(1.0));
+        assert_eq!(first.test_hit_rate, Some(1.0));
+        // A model trained before the trend gets it wrong until refitted on it
+        assert_eq!(result.windows[4].test_hit_rate, Some(0.0));
+        assert_eq!(result.windows[5].test_hit_rate, Some(1.0));
+
+        // Flat before the first test window, trading after it
+        let signals = strategy.generate_signals(&data);
+        assert!(signals[..100].iter().all(|&s| s == 0.0));
+        assert!(signals[100..].contains(&1.0));
+        assert_eq!(result.out_of_sample.equity_curve.len(), 501);
+
+        // The saved strategy reproduces its signals
+        let path = std::env::temp_dir().join("strataquant_ml_walkforward_test.json");
+        strategy.save(&path).unwrap();
+        let restored = WalkForwardModel::load(&path).unwrap();
+        assert_eq!(restored.generate_signals(&data), signals);
+        std::fs::remove_file(&path).ok();
+    }
+}
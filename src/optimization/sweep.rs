@@ -1,83 +1,149 @@
 use crate::backtest::{BacktestEngine, ExecutionModel, StopLossMethod};
 use crate::data::OHLCV;
-use crate::strategies::SMACrossover;
+use crate::indicators::IndicatorCache;
+use crate::strategies::{ParamGrid, Params, StrategyDefinition, StrategyRegistry};
+use anyhow::{bail, Result};
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::path::Path;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OptimizationResult {
-    pub fast_period: usize,
-    pub slow_period: usize,
+    pub strategy: String,
+    pub params: Params,
     pub total_return: f64,
     pub sharpe_ratio: f64,
     pub max_drawdown: f64,
     pub total_trades: u32,
 }
 
-pub struct ParameterSweep {
-    data: Vec<OHLCV>,
+/// Grid search whose backtests all borrow the same bars
+pub struct ParameterSweep<'a> {
+    data: Cow<'a, [OHLCV]>,
     initial_capital: f64,
     execution_model: ExecutionModel,
+    stop_loss: StopLossMethod,
 }
 
-impl ParameterSweep {
-    pub fn new(data: Vec<OHLCV>, initial_capital: f64, execution_model: ExecutionModel) -> Self {
+impl<'a> ParameterSweep<'a> {
+    pub fn new(
+        data: impl Into<Cow<'a, [OHLCV]>>,
+        initial_capital: f64,
+        execution_model: ExecutionModel,
+    ) -> Self {
         Self {
-            data,
+            data: data.into(),
             initial_capital,
             execution_model,
+            stop_loss: StopLossMethod::None,
         }
     }
 
-    pub fn sweep_sma_periods(
+    pub fn with_stop_loss(mut self, stop_loss: StopLossMethod) -> Self {
+        self.stop_loss = stop_loss;
+        self
+    }
+
+    /// Backtest every valid parameter combination in `grid`
+    ///
+    /// Combinations whose strategy fails to run (e.g. a script error) are
+    /// left out of the results and reported; it is an error if all fail.
+    pub fn sweep(
         &self,
-        fast_range: (usize, usize),
-        slow_range: (usize, usize),
-        step: usize,
-    ) -> Vec<OptimizationResult> {
-        let fast_periods: Vec<usize> = (fast_range.0..=fast_range.1).step_by(step).collect();
-        let slow_periods: Vec<usize> = (slow_range.0..=slow_range.1).step_by(step).collect();
-
-        let mut parameter_combinations = Vec::new();
-        for fast in &fast_periods {
-            for slow in &slow_periods {
-                if fast < slow {
-                    parameter_combinations.push((*fast, *slow));
-                }
-            }
-        }
+        definition: &StrategyDefinition,
+        grid: &ParamGrid,
+    ) -> Result<Vec<OptimizationResult>> {
+        let parameter_combinations = definition.grid_combinations(grid)?;
 
         println!(
             "Testing {} parameter combinations...",
             parameter_combinations.len()
         );
 
-        let results: Vec<OptimizationResult> = parameter_combinations
+        // Combinations sharing a period share its indicator series
+        let cache = Arc::new(IndicatorCache::new(&self.data));
+
+        let strategies = parameter_combinations
+            .iter()
+            .map(|params| definition.build(params))
+            .collect::<Result<Vec<_>>>()?;
+
+        let outcomes: Vec<Result<OptimizationResult>> = parameter_combinations
             .par_iter()
-            .map(|(fast, slow)| {
-                let strategy = SMACrossover::new(*fast, *slow);
+            .zip(&strategies)
+            .map(|(params, strategy)| {
                 let engine = BacktestEngine::new(
-                    self.data.clone(),
+                    &self.data[..],
                     self.initial_capital,
                     self.execution_model.clone(),
-                );
-                let backtest_result = engine.run(&strategy);
+                )
+                .with_stop_loss(self.stop_loss.clone())
+                .with_indicator_cache(Arc::clone(&cache));
+                let backtest_result = engine.try_run(strategy.as_ref())?;
 
-                OptimizationResult {
-                    fast_period: *fast,
-                    slow_period: *slow,
+                Ok(OptimizationResult {
+                    strategy: definition.name.clone(),
+                    params: params.clone(),
                     total_return: backtest_result.total_return,
                     sharpe_ratio: backtest_result.sharpe_ratio,
                     max_drawdown: backtest_result.max_drawdown,
                     total_trades: backtest_result.total_trades,
-                }
+                })
             })
             .collect();
 
-        results
+        let total = outcomes.len();
+        let (results, failures): (Vec<_>, Vec<_>) = outcomes.into_iter().partition(Result::is_ok);
+        let results: Vec<OptimizationResult> = results.into_iter().flatten().collect();
+        if let Some(Err(first)) = failures.into_iter().next() {
+            if results.is_empty() {
+                bail!("All {} parameter combinations failed: {:#}", total, first);
+            }
+            eprintln!(
+                "Skipped {} of {} parameter combinations that failed, e.g. {:#}",
+                total - results.len(),
+                total,
+                first
+            );
+        }
+        Ok(results)
     }
 
+    /// SMA crossover sweep over `fast_range` x `slow_range` (inclusive)
+    #[deprecated(
+        since = "0.6.0",
+        note = "use `sweep` with the registry's `sma` definition"
+    )]
+    pub fn sweep_sma_periods(
+        &self,
+        fast_range: (usize, usize),
+        slow_range: (usize, usize),
+        step: usize,
+    ) -> Vec<OptimizationResult> {
+        let grid = ParamGrid::new()
+            .with_range(
+                "fast",
+                fast_range.0 as f64,
+                fast_range.1 as f64,
+                step as f64,
+            )
+            .with_range(
+                "slow",
+                slow_range.0 as f64,
+                slow_range.1 as f64,
+                step as f64,
+            );
+        let registry = StrategyRegistry::builtin();
+        let sma = registry.get("sma").expect("sma is a builtin strategy");
+        self.sweep(sma, &grid).expect("SMA sweep failed")
+    }
+
+    #[deprecated(
+        since = "0.6.0",
+        note = "use `with_stop_loss` and `sweep` with the registry's `sma` definition"
+    )]
     pub fn sweep_sma_periods_with_stops(
         &self,
         fast_range: (usize, usize),
@@ -85,43 +151,14 @@ impl ParameterSweep {
         step: usize,
         stop_loss: StopLossMethod,
     ) -> Vec<OptimizationResult> {
-        let fast_periods: Vec<usize> = (fast_range.0..=fast_range.1).step_by(step).collect();
-        let slow_periods: Vec<usize> = (slow_range.0..=slow_range.1).step_by(step).collect();
-
-        let mut parameter_combinations = Vec::new();
-        for fast in &fast_periods {
-            for slow in &slow_periods {
-                if fast < slow {
-                    parameter_combinations.push((*fast, *slow));
-                }
-            }
-        }
-
-        let results: Vec<OptimizationResult> = parameter_combinations
-            .par_iter()
-            .map(|(fast, slow)| {
-                let strategy = SMACrossover::new(*fast, *slow);
-                let engine = BacktestEngine::new(
-                    self.data.clone(),
-                    self.initial_capital,
-                    self.execution_model.clone(),
-                )
-                .with_stop_loss(stop_loss.clone());
-
-                let backtest_result = engine.run(&strategy);
-
-                OptimizationResult {
-                    fast_period: *fast,
-                    slow_period: *slow,
-                    total_return: backtest_result.total_return,
-                    sharpe_ratio: backtest_result.sharpe_ratio,
-                    max_drawdown: backtest_result.max_drawdown,
-                    total_trades: backtest_result.total_trades,
-                }
-            })
-            .collect();
-
-        results
+        let sweep = ParameterSweep::new(
+            &self.data[..],
+            self.initial_capital,
+            self.execution_model.clone(),
+        )
+        .with_stop_loss(stop_loss);
+        #[allow(deprecated)]
+        sweep.sweep_sma_periods(fast_range, slow_range, step)
     }
 
     pub fn save_results(results: &[OptimizationResult], path: &Path) -> Result<(), std::io::Error> {
@@ -150,3 +187,115 @@ impl ParameterSweep {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{SyntheticConfig, SyntheticModel};
+    use crate::strategies::Script;
+
+    #[test]
+    fn test_sweep_over_borrowed_bars() {
+        let data = SyntheticModel::gbm().generate(&SyntheticConfig::new(400, 3));
+        let model = ExecutionModel::new(10.0, 5.0);
+
+        // Engines over a slice use the caller's bars in place
+        let engine = BacktestEngine::new(&data[100..], 10_000.0, model.clone());
+        assert!(std::ptr::eq(engine.data(), &data[100..]));
+
+        let registry = StrategyRegistry::builtin();
+        let sma = registry.get("sma").unwrap();
+        let grid = ParamGrid::new()
+            .with_values("fast", vec![5.0, 10.0])
+            .with_values("slow", vec![30.0, 50.0]);
+        let results = ParameterSweep::new(&data, 10_000.0, model.clone())
+            .sweep(sma, &grid)
+            .unwrap();
+        assert_eq!(results.len(), 4);
+
+        // Same results as a backtest that owns its copy of the bars
+        for result in &results {
+            let strategy = sma.build(&result.params).unwrap();
+            let owned = BacktestEngine::new(data.clone(), 10_000.0, model.clone());
+            assert_eq!(
+                owned.run(strategy.as_ref()).total_return,
+                result.total_return
+            );
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated_sma_sweep() {
+        let data = SyntheticModel::gbm().generate(&SyntheticConfig::new(300, 3));
+        let sweep = ParameterSweep::new(&data, 10_000.0, ExecutionModel::new(10.0, 5.0));
+
+        // 30/30 is dropped by the fast < slow constraint
+        let results = sweep.sweep_sma_periods((10, 30), (30, 40), 10);
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|r| r.strategy == "sma"));
+        assert!(results
+            .iter()
+            .all(|r| r.params.get("fast") < r.params.get("slow")));
+
+        // Minute-bar periods well past any fixed cap still sweep
+        let results = sweep.sweep_sma_periods((1440, 1440), (1440, 2880), 720);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].params.get("slow"), 2880.0);
+    }
+
+    #[test]
+    fn test_sweep_grid_without_period_cap() {
+        let data = SyntheticModel::gbm().generate(&SyntheticConfig::new(300, 3));
+        let sweep = ParameterSweep::new(&data, 10_000.0, ExecutionModel::new(10.0, 5.0));
+        let registry = StrategyRegistry::builtin();
+
+        let grid = ParamGrid::parse(&["slow=1440:4320:720"]).unwrap();
+        let results = sweep.sweep(registry.get("sma").unwrap(), &grid).unwrap();
+        assert_eq!(results.len(), 5);
+    }
+
+    #[test]
+    fn test_sweep_skips_failing_combinations() {
+        let data = SyntheticModel::gbm().generate(&SyntheticConfig::new(300, 3));
+        let model = ExecutionModel::new(10.0, 5.0);
+        let definition = Script::compile(
+            "flaky",
+            "flaky.rhai",
+            r#"
+                fn parameters() {
+                    #{ period: #{ value: 10, min: 1, max: 100, sweep: [5, 15, 5] } }
+                }
+                fn generate_signals(bars) {
+                    if param("period") == 10 { throw "unsupported period"; }
+                    bars.close.map(|c| 1.0)
+                }
+            "#,
+        )
+        .unwrap()
+        .definition();
+
+        // The failing combination is left out rather than scored as flat
+        let sweep = ParameterSweep::new(&data, 10_000.0, model.clone());
+        let results = sweep
+            .sweep(&definition, &definition.default_grid())
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.params.get("period") != 10.0));
+
+        let only_failing = ParamGrid::new().with_values("period", vec![10.0]);
+        let err = sweep.sweep(&definition, &only_failing).unwrap_err();
+        assert!(err.to_string().contains("unsupported period"), "{}", err);
+
+        let strategy = definition.build(&Params::new()).unwrap();
+        assert_eq!(strategy.name(), "flaky(period=10)");
+        let err = BacktestEngine::new(&data, 10_000.0, model)
+            .try_run(strategy.as_ref())
+            .unwrap_err();
+        assert!(
+            format!("{:#}", err).contains("unsupported period"),
+            "{:#}",
+            err
+        );
+    }
+}
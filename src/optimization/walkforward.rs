@@ -1,16 +1,18 @@
 use crate::backtest::{BacktestEngine, ExecutionModel, StopLossMethod};
 use crate::data::OHLCV;
 use crate::optimization::ParameterSweep;
-use crate::strategies::SMACrossover;
+use crate::strategies::{ParamGrid, Params, StrategyDefinition, StrategyRegistry};
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::path::Path;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WalkForwardResult {
+    pub strategy: String,
     pub train_size: usize,
     pub test_size: usize,
-    pub best_fast_period: usize,
-    pub best_slow_period: usize,
+    pub best_params: Params,
     pub in_sample_return: f64,
     pub in_sample_sharpe: f64,
     pub out_of_sample_return: f64,
@@ -19,22 +21,40 @@ pub struct WalkForwardResult {
     pub degradation_sharpe: f64,
 }
 
-pub struct WalkForward {
-    data: Vec<OHLCV>,
+pub struct WalkForward<'a> {
+    data: Cow<'a, [OHLCV]>,
     initial_capital: f64,
     execution_model: ExecutionModel,
+    stop_loss: StopLossMethod,
 }
 
-impl WalkForward {
-    pub fn new(data: Vec<OHLCV>, initial_capital: f64, execution_model: ExecutionModel) -> Self {
+impl<'a> WalkForward<'a> {
+    pub fn new(
+        data: impl Into<Cow<'a, [OHLCV]>>,
+        initial_capital: f64,
+        execution_model: ExecutionModel,
+    ) -> Self {
         Self {
-            data,
+            data: data.into(),
             initial_capital,
             execution_model,
+            stop_loss: StopLossMethod::None,
         }
     }
 
-    pub fn run(&self, train_ratio: f64) -> WalkForwardResult {
+    pub fn with_stop_loss(mut self, stop_loss: StopLossMethod) -> Self {
+        self.stop_loss = stop_loss;
+        self
+    }
+
+    /// Optimize `definition` over `grid` on the training split, then test the
+    /// best parameters (by Sharpe) on the remaining data
+    pub fn run(
+        &self,
+        definition: &StrategyDefinition,
+        grid: &ParamGrid,
+        train_ratio: f64,
+    ) -> Result<WalkForwardResult> {
         assert!(
             train_ratio > 0.0 && train_ratio < 1.0,
             "Train ratio must be between 0 and 1"
@@ -46,6 +66,7 @@ impl WalkForward {
 
         println!("Walk-Forward Validation");
         println!("======================");
+        println!("Strategy: {}", definition.name);
         println!("Total bars: {}", self.data.len());
         println!(
             "Train bars: {} ({:.1}%)",
@@ -60,31 +81,32 @@ impl WalkForward {
 
         println!("Phase 1: Optimization on training set...");
         let sweep = ParameterSweep::new(
-            train_data.to_vec(),
+            train_data,
             self.initial_capital,
             self.execution_model.clone(),
-        );
+        )
+        .with_stop_loss(self.stop_loss.clone());
 
-        let results = sweep.sweep_sma_periods((20, 100), (50, 200), 10);
+        let results = sweep.sweep(definition, grid)?;
 
-        let best = ParameterSweep::find_best_sharpe(&results).expect("No optimization results");
+        let best = ParameterSweep::find_best_sharpe(&results).context("No optimization results")?;
 
         println!(
-            "Best in-sample parameters: {}/{} (Sharpe: {:.2}, Return: {:.2}%)\n",
-            best.fast_period,
-            best.slow_period,
+            "Best in-sample parameters: {} (Sharpe: {:.2}, Return: {:.2}%)\n",
+            best.params,
             best.sharpe_ratio,
             best.total_return * 100.0
         );
 
         println!("Phase 2: Testing on out-of-sample data...");
-        let strategy = SMACrossover::new(best.fast_period, best.slow_period);
+        let strategy = definition.build(&best.params)?;
         let test_engine = BacktestEngine::new(
-            test_data.to_vec(),
+            test_data,
             self.initial_capital,
             self.execution_model.clone(),
-        );
-        let test_result = test_engine.run(&strategy);
+        )
+        .with_stop_loss(self.stop_loss.clone());
+        let test_result = test_engine.try_run(strategy.as_ref())?;
 
         println!(
             "Out-of-sample: Sharpe: {:.2}, Return: {:.2}%\n",
@@ -97,98 +119,36 @@ impl WalkForward {
         let degradation_sharpe =
             ((best.sharpe_ratio - test_result.sharpe_ratio) / best.sharpe_ratio.abs()) * 100.0;
 
-        WalkForwardResult {
+        Ok(WalkForwardResult {
+            strategy: definition.name.clone(),
             train_size: train_data.len(),
             test_size: test_data.len(),
-            best_fast_period: best.fast_period,
-            best_slow_period: best.slow_period,
+            best_params: best.params.clone(),
             in_sample_return: best.total_return,
             in_sample_sharpe: best.sharpe_ratio,
             out_of_sample_return: test_result.total_return,
             out_of_sample_sharpe: test_result.sharpe_ratio,
             degradation_return,
             degradation_sharpe,
-        }
+        })
     }
 
+    /// SMA crossover walk-forward over the `sma` definition's default grid
+    #[deprecated(
+        since = "0.6.0",
+        note = "use `with_stop_loss` and `run` with the registry's `sma` definition"
+    )]
     pub fn run_with_stops(&self, train_ratio: f64, stop_loss: StopLossMethod) -> WalkForwardResult {
-        assert!(
-            train_ratio > 0.0 && train_ratio < 1.0,
-            "Train ratio must be between 0 and 1"
-        );
-
-        let split_point = (self.data.len() as f64 * train_ratio) as usize;
-        let train_data = &self.data[..split_point];
-        let test_data = &self.data[split_point..];
-
-        println!("Walk-Forward Validation");
-        println!("======================");
-        println!("Total bars: {}", self.data.len());
-        println!(
-            "Train bars: {} ({:.1}%)",
-            train_data.len(),
-            train_ratio * 100.0
-        );
-        println!(
-            "Test bars:  {} ({:.1}%)\n",
-            test_data.len(),
-            (1.0 - train_ratio) * 100.0
-        );
-
-        println!("Phase 1: Optimization on training set...");
-        let sweep = ParameterSweep::new(
-            train_data.to_vec(),
-            self.initial_capital,
-            self.execution_model.clone(),
-        );
-
-        let results =
-            sweep.sweep_sma_periods_with_stops((20, 100), (50, 200), 10, stop_loss.clone());
-
-        let best = ParameterSweep::find_best_sharpe(&results).expect("No optimization results");
-
-        println!(
-            "Best in-sample parameters: {}/{} (Sharpe: {:.2}, Return: {:.2}%)\n",
-            best.fast_period,
-            best.slow_period,
-            best.sharpe_ratio,
-            best.total_return * 100.0
-        );
-
-        println!("Phase 2: Testing on out-of-sample data...");
-        let strategy = SMACrossover::new(best.fast_period, best.slow_period);
-        let test_engine = BacktestEngine::new(
-            test_data.to_vec(),
+        let registry = StrategyRegistry::builtin();
+        let sma = registry.get("sma").expect("sma is a builtin strategy");
+        WalkForward::new(
+            &self.data[..],
             self.initial_capital,
             self.execution_model.clone(),
         )
-        .with_stop_loss(stop_loss);
-
-        let test_result = test_engine.run(&strategy);
-
-        println!(
-            "Out-of-sample: Sharpe: {:.2}, Return: {:.2}%\n",
-            test_result.sharpe_ratio,
-            test_result.total_return * 100.0
-        );
-
-        let degradation_return =
-            ((best.total_return - test_result.total_return) / best.total_return.abs()) * 100.0;
-        let degradation_sharpe =
-            ((best.sharpe_ratio - test_result.sharpe_ratio) / best.sharpe_ratio.abs()) * 100.0;
-
-        WalkForwardResult {
-            train_size: train_data.len(),
-            test_size: test_data.len(),
-            best_fast_period: best.fast_period,
-            best_slow_period: best.slow_period,
-            in_sample_return: best.total_return,
-            in_sample_sharpe: best.sharpe_ratio,
-            out_of_sample_return: test_result.total_return,
-            out_of_sample_sharpe: test_result.sharpe_ratio,
-            degradation_return,
-            degradation_sharpe,
-        }
+        .with_stop_loss(stop_loss)
+        .run(sma, &sma.default_grid(), train_ratio)
+        .expect("SMA walk-forward failed")
     }
 
     pub fn save_result(result: &WalkForwardResult, path: &Path) -> Result<(), std::io::Error> {
@@ -201,3 +161,25 @@ impl WalkForward {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{SyntheticConfig, SyntheticModel};
+
+    #[test]
+    fn test_walkforward_any_registered_strategy() {
+        let data = SyntheticModel::gbm().generate(&SyntheticConfig::new(400, 3));
+        let registry = StrategyRegistry::builtin();
+        let walkforward = WalkForward::new(data, 10_000.0, ExecutionModel::new(10.0, 5.0));
+
+        let rsi = registry.get("rsi").unwrap();
+        let grid = ParamGrid::new().with_values("period", vec![7.0, 14.0]);
+        let result = walkforward.run(rsi, &grid, 0.7).unwrap();
+
+        assert_eq!(result.strategy, "rsi");
+        assert!([7.0, 14.0].contains(&result.best_params.get("period")));
+        assert_eq!(result.best_params.get("oversold"), 30.0);
+        assert_eq!(result.train_size + result.test_size, 400);
+    }
+}
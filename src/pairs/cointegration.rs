@@ -0,0 +1,204 @@
+use crate::linalg::invert;
+use crate::pairs::{ols, HedgeRatio};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Fewest residuals the test runs on
+const MIN_OBSERVATIONS: usize = 20;
+
+/// Critical values of the Engle-Granger test statistic
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CriticalValues {
+    pub one_percent: f64,
+    pub five_percent: f64,
+    pub ten_percent: f64,
+}
+
+impl CriticalValues {
+    /// MacKinnon (2010) response surface for two variables with a constant,
+    /// at `observations` residuals
+    pub fn engle_granger(observations: usize) -> Self {
+        let t = observations as f64;
+        let value = |tau: f64, b1: f64, b2: f64| tau + b1 / t + b2 / (t * t);
+        Self {
+            one_percent: value(-3.89644, -10.9519, -22.527),
+            five_percent: value(-3.33613, -6.1101, -6.823),
+            ten_percent: value(-3.04445, -4.2412, -2.720),
+        }
+    }
+}
+
+/// Engle-Granger two-step cointegration test
+///
+/// Step one fits `y = alpha + beta * x` by OLS; step two runs an augmented
+/// Dickey-Fuller test on the residuals. A statistic below the critical
+/// value rejects "no cointegration", i.e. the spread is mean-reverting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngleGranger {
+    pub hedge: HedgeRatio,
+    pub adf_statistic: f64,
+    /// Lagged differences in the ADF regression
+    pub lags: usize,
+    pub observations: usize,
+    pub critical_values: CriticalValues,
+    /// Bars for a deviation of the spread to halve; `None` if it does not revert
+    pub half_life: Option<f64>,
+}
+
+impl EngleGranger {
+    /// Whether the test rejects "no cointegration" at the 5% level
+    pub fn is_cointegrated(&self) -> bool {
+        self.adf_statistic < self.critical_values.five_percent
+    }
+}
+
+/// Run the Engle-Granger test of `y` against `x` with `lags` lagged
+/// differences in the ADF regression
+pub fn engle_granger(y: &[f64], x: &[f64], lags: usize) -> Result<EngleGranger> {
+    if y.len() != x.len() {
+        bail!("Series must have equal length ({} vs {})", y.len(), x.len());
+    }
+    if y.len() < MIN_OBSERVATIONS + lags + 1 {
+        bail!(
+            "Need at least {} observations for the test with {} lags, got {}",
+            MIN_OBSERVATIONS + lags + 1,
+            lags,
+            y.len()
+        );
+    }
+    let hedge = match ols(y, x) {
+        Some(h) => h,
+        None => bail!("Cannot fit a hedge ratio against a constant series"),
+    };
+
+    let residuals: Vec<f64> = y
+        .iter()
+        .zip(x)
+        .map(|(&yi, &xi)| hedge.spread(yi, xi))
+        .collect();
+    let adf_statistic = match adf_statistic(&residuals, lags) {
+        Some(stat) => stat,
+        None => bail!("ADF regression is singular (constant spread?)"),
+    };
+
+    Ok(EngleGranger {
+        hedge,
+        adf_statistic,
+        lags,
+        observations: residuals.len(),
+        critical_values: CriticalValues::engle_granger(residuals.len()),
+        half_life: half_life(&residuals),
+    })
+}
+
+/// Augmented Dickey-Fuller t-statistic of a zero-mean series, without
+/// constant or trend: `d[t] = gamma * s[t-1] + sum(phi_k * d[t-k]) + e`
+pub fn adf_statistic(series: &[f64], lags: usize) -> Option<f64> {
+    let diffs: Vec<f64> = series.windows(2).map(|w| w[1] - w[0]).collect();
+    // diffs[t - 1] is the change into series[t]
+    let (rows, targets): (Vec<Vec<f64>>, Vec<f64>) = (lags + 1..series.len())
+        .map(|t| {
+            let mut row = vec![series[t - 1]];
+            row.extend((1..=lags).map(|k| diffs[t - 1 - k]));
+            (row, diffs[t - 1])
+        })
+        .unzip();
+
+    let (coefficients, errors) = least_squares(&rows, &targets)?;
+    (errors[0] > 0.0).then(|| coefficients[0] / errors[0])
+}
+
+/// Half-life of mean reversion from an AR(1) fit of the changes on the
+/// previous level
+pub fn half_life(series: &[f64]) -> Option<f64> {
+    let (rows, targets): (Vec<Vec<f64>>, Vec<f64>) = series
+        .windows(2)
+        .map(|w| (vec![w[0], 1.0], w[1] - w[0]))
+        .unzip();
+    let (coefficients, _) = least_squares(&rows, &targets)?;
+    let lambda = coefficients[0];
+    (-1.0 < lambda && lambda < 0.0).then(|| -std::f64::consts::LN_2 / (1.0 + lambda).ln())
+}
+
+/// Least squares coefficients and their standard errors; `None` if there
+/// are too few rows or the regressors are collinear
+fn least_squares(rows: &[Vec<f64>], targets: &[f64]) -> Option<(Vec<f64>, Vec<f64>)> {
+    let k = rows.first()?.len();
+    let n = rows.len();
+    if n <= k {
+        return None;
+    }
+
+    let mut xtx = vec![vec![0.0; k]; k];
+    let mut xty = vec![0.0; k];
+    for (row, &target) in rows.iter().zip(targets) {
+        for i in 0..k {
+            xty[i] += row[i] * target;
+            for j in 0..k {
+                xtx[i][j] += row[i] * row[j];
+            }
+        }
+    }
+
+    let inverse = invert(xtx)?;
+    let coefficients: Vec<f64> = inverse
+        .iter()
+        .map(|r| r.iter().zip(&xty).map(|(a, b)| a * b).sum())
+        .collect();
+    let ssr: f64 = rows
+        .iter()
+        .zip(targets)
+        .map(|(row, target)| {
+            let fitted: f64 = row.iter().zip(&coefficients).map(|(a, b)| a * b).sum();
+            (target - fitted).powi(2)
+        })
+        .sum();
+    let variance = ssr / (n - k) as f64;
+    let errors = (0..k)
+        .map(|i| (variance * inverse[i][i]).max(0.0).sqrt())
+        .collect();
+    Some((coefficients, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::SeededRng;
+
+    fn random_walk(rng: &mut SeededRng, n: usize) -> Vec<f64> {
+        let mut walk = vec![10.0];
+        for _ in 1..n {
+            walk.push(walk[walk.len() - 1] + 0.02 * rng.normal());
+        }
+        walk
+    }
+
+    #[test]
+    fn test_engle_granger_separates_cointegrated_pairs() {
+        let mut rng = SeededRng::new(11);
+        let x = random_walk(&mut rng, 750);
+
+        // y tracks 2 + 0.7x with an AR(1) spread that halves in ~7 bars
+        let mut noise = 0.0;
+        let y: Vec<f64> = x
+            .iter()
+            .map(|xi| {
+                noise = 0.9 * noise + 0.01 * rng.normal();
+                2.0 + 0.7 * xi + noise
+            })
+            .collect();
+
+        let test = engle_granger(&y, &x, 1).unwrap();
+        assert!(test.is_cointegrated());
+        assert!((test.hedge.beta - 0.7).abs() < 0.05);
+        let half_life = test.half_life.unwrap();
+        assert!((3.0..15.0).contains(&half_life), "half-life {}", half_life);
+        assert!(test.critical_values.one_percent < test.critical_values.ten_percent);
+
+        // Two independent random walks are not
+        let z = random_walk(&mut rng, 750);
+        assert!(!engle_granger(&z, &x, 1).unwrap().is_cointegrated());
+
+        assert!(engle_granger(&y[..10], &x[..10], 1).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Linear relation `y = alpha + beta * x` between two (log) price series
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct HedgeRatio {
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl HedgeRatio {
+    /// Residual of `y` against the fitted relation
+    pub fn spread(&self, y: f64, x: f64) -> f64 {
+        y - self.beta * x - self.alpha
+    }
+}
+
+/// Ordinary least squares fit of `y` on `x`; `None` for fewer than two
+/// points or a constant `x`
+pub fn ols(y: &[f64], x: &[f64]) -> Option<HedgeRatio> {
+    assert_eq!(y.len(), x.len(), "Series must have equal length");
+    let n = y.len() as f64;
+    if y.len() < 2 {
+        return None;
+    }
+    let mean_x = x.iter().sum::<f64>() / n;
+    let mean_y = y.iter().sum::<f64>() / n;
+    let (mut sxx, mut sxy) = (0.0, 0.0);
+    for (xi, yi) in x.iter().zip(y) {
+        sxx += (xi - mean_x) * (xi - mean_x);
+        sxy += (xi - mean_x) * (yi - mean_y);
+    }
+    if sxx <= f64::EPSILON {
+        return None;
+    }
+    let beta = sxy / sxx;
+    Some(HedgeRatio {
+        alpha: mean_y - beta * mean_x,
+        beta,
+    })
+}
+
+/// How the hedge ratio is estimated at each bar
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum HedgeMethod {
+    /// One fit over the whole series. Every bar uses a ratio fitted on
+    /// later data too, so backtests with it are in-sample.
+    Ols,
+    /// Fit over the trailing `window` bars, including the current one
+    RollingOls { window: usize },
+    /// Kalman filter tracking alpha and beta as random walks; each bar
+    /// uses the estimate from the bars before it
+    ///
+    /// `delta` sets how fast the coefficients may drift (state noise
+    /// `delta / (1 - delta)`); `observation_variance` is the noise of the
+    /// spread around the relation.
+    Kalman {
+        delta: f64,
+        observation_variance: f64,
+    },
+}
+
+impl HedgeMethod {
+    /// Kalman filter with defaults suited to daily log prices
+    pub fn kalman() -> Self {
+        HedgeMethod::Kalman {
+            delta: 1e-6,
+            observation_variance: 1e-3,
+        }
+    }
+
+    /// Method by name: ols, rolling (over `window` bars) or kalman
+    pub fn parse(name: &str, window: usize) -> Result<Self> {
+        Ok(match name {
+            "ols" => HedgeMethod::Ols,
+            "rolling" => {
+                if window < 2 {
+                    bail!("Rolling OLS needs a window of at least 2 bars");
+                }
+                HedgeMethod::RollingOls { window }
+            }
+            "kalman" => HedgeMethod::kalman(),
+            _ => bail!(
+                "Unknown hedge method '{}' (expected ols, rolling or kalman)",
+                name
+            ),
+        })
+    }
+
+    /// Hedge ratio at every bar; `None` until enough data is available
+    pub fn estimate(&self, y: &[f64], x: &[f64]) -> Vec<Option<HedgeRatio>> {
+        assert_eq!(y.len(), x.len(), "Series must have equal length");
+        match *self {
+            HedgeMethod::Ols => vec![ols(y, x); y.len()],
+            HedgeMethod::RollingOls { window } => rolling_ols(y, x, window),
+            HedgeMethod::Kalman {
+                delta,
+                observation_variance,
+            } => kalman(y, x, delta, observation_variance),
+        }
+    }
+}
+
+impl fmt::Display for HedgeMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HedgeMethod::Ols => write!(f, "OLS (full sample)"),
+            HedgeMethod::RollingOls { window } => write!(f, "rolling OLS ({} bars)", window),
+            HedgeMethod::Kalman { delta, .. } => write!(f, "Kalman filter (delta {})", delta),
+        }
+    }
+}
+
+fn rolling_ols(y: &[f64], x: &[f64], window: usize) -> Vec<Option<HedgeRatio>> {
+    let mut out = vec![None; y.len()];
+    if window < 2 || y.len() < window {
+        return out;
+    }
+
+    // Centre on the first value so the running sums stay well conditioned
+    let (x0, y0) = (x[0], y[0]);
+    let (mut sx, mut sy, mut sxx, mut sxy) = (0.0, 0.0, 0.0, 0.0);
+    let n = window as f64;
+    for i in 0..y.len() {
+        let (xi, yi) = (x[i] - x0, y[i] - y0);
+        sx += xi;
+        sy += yi;
+        sxx += xi * xi;
+        sxy += xi * yi;
+        if i >= window {
+            let (xo, yo) = (x[i - window] - x0, y[i - window] - y0);
+            sx -= xo;
+            sy -= yo;
+            sxx -= xo * xo;
+            sxy -= xo * yo;
+        }
+        if i + 1 >= window {
+            let var = sxx - sx * sx / n;
+            if var > 1e-12 {
+                let beta = (sxy - sx * sy / n) / var;
+                let alpha = (sy - beta * sx) / n + y0 - beta * x0;
+                out[i] = Some(HedgeRatio { alpha, beta });
+            }
+        }
+    }
+    out
+}
+
+fn kalman(y: &[f64], x: &[f64], delta: f64, observation_variance: f64) -> Vec<Option<HedgeRatio>> {
+    let drift = delta / (1.0 - delta);
+    // State [beta, alpha] and its covariance
+    let mut state = [0.0, 0.0];
+    let mut cov = [[1.0, 0.0], [0.0, 1.0]];
+
+    y.iter()
+        .zip(x)
+        .enumerate()
+        .map(|(i, (&yi, &xi))| {
+            // Predict: the coefficients follow a random walk
+            let r = [
+                [cov[0][0] + drift, cov[0][1]],
+                [cov[1][0], cov[1][1] + drift],
+            ];
+            // The ratio for this bar comes from the bars before it, so its
+            // spread is the filter's prediction error
+            let predicted = (i > 0).then_some(HedgeRatio {
+                alpha: state[1],
+                beta: state[0],
+            });
+
+            // Update with the observation y = beta * x + alpha
+            let f = [xi, 1.0];
+            let rf = [
+                r[0][0] * f[0] + r[0][1] * f[1],
+                r[1][0] * f[0] + r[1][1] * f[1],
+            ];
+            let q = f[0] * rf[0] + f[1] * rf[1] + observation_variance;
+            let error = yi - (state[0] * xi + state[1]);
+            let gain = [rf[0] / q, rf[1] / q];
+            state = [state[0] + gain[0] * error, state[1] + gain[1] * error];
+            // P = R - K F R, where F R is rf transposed since R is symmetric
+            for i in 0..2 {
+                for j in 0..2 {
+                    cov[i][j] = r[i][j] - gain[i] * rf[j];
+                }
+            }
+
+            predicted
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::SeededRng;
+
+    #[test]
+    fn test_hedge_estimators_recover_the_relation() {
+        let mut rng = SeededRng::new(5);
+        let mut x = vec![4.0];
+        for _ in 1..600 {
+            x.push(x[x.len() - 1] + 0.02 * rng.normal());
+        }
+        // beta 1.5 for the first half, 0.8 for the second
+        let y: Vec<f64> = x
+            .iter()
+            .enumerate()
+            .map(|(i, xi)| {
+                let beta = if i < 300 { 1.5 } else { 0.8 };
+                0.3 + beta * xi + 0.002 * rng.normal()
+            })
+            .collect();
+
+        let fit = ols(&y[..300], &x[..300]).unwrap();
+        assert!((fit.beta - 1.5).abs() < 0.05);
+        assert!((fit.spread(y[0], x[0])).abs() < 0.02);
+
+        let rolling = HedgeMethod::RollingOls { window: 100 }.estimate(&y, &x);
+        assert!(rolling[98].is_none());
+        assert!((rolling[299].unwrap().beta - 1.5).abs() < 0.05);
+        assert!((rolling[599].unwrap().beta - 0.8).abs() < 0.05);
+
+        // The filter adapts to the break without any window
+        let filtered = HedgeMethod::kalman().estimate(&y, &x);
+        assert!(filtered[0].is_none());
+        let last = filtered[599].unwrap();
+        assert!(last.spread(y[599], x[599]).abs() < 0.02);
+        assert!((last.beta - 0.8).abs() < 0.3);
+
+        assert!(HedgeMethod::parse("rolling", 1).is_err());
+        assert!(HedgeMethod::parse("tls", 60).is_err());
+    }
+}
@@ -0,0 +1,17 @@
+//! Pairs trading and statistical arbitrage
+//!
+//! Hedge ratios relate the log prices of two assets; the residual spread is
+//! traded when its z-score stretches and closed as it reverts.
+//! [`engle_granger`] tests whether the spread is mean-reverting at all.
+
+pub mod backtest;
+pub mod cointegration;
+pub mod hedge;
+pub mod spread;
+pub mod strategy;
+
+pub use backtest::{Direction, LegTrade, PairTrade, PairsBacktest, PairsResult};
+pub use cointegration::{adf_statistic, engle_granger, half_life, CriticalValues, EngleGranger};
+pub use hedge::{ols, HedgeMethod, HedgeRatio};
+pub use spread::{rolling_zscore, spread};
+pub use strategy::{PairSignal, PairsStrategy};
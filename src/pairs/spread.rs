@@ -0,0 +1,62 @@
+use crate::pairs::HedgeRatio;
+
+/// Spread `y - beta * x - alpha` at every bar with that bar's hedge ratio
+pub fn spread(y: &[f64], x: &[f64], hedges: &[Option<HedgeRatio>]) -> Vec<Option<f64>> {
+    assert!(
+        y.len() == x.len() && x.len() == hedges.len(),
+        "Series and hedge ratios must have equal length"
+    );
+    hedges
+        .iter()
+        .zip(y.iter().zip(x))
+        .map(|(hedge, (&yi, &xi))| hedge.map(|h| h.spread(yi, xi)))
+        .collect()
+}
+
+/// Z-score of each value against the trailing `window` values, including
+/// itself; `None` until `window` consecutive values exist or when they are
+/// all equal
+pub fn rolling_zscore(values: &[Option<f64>], window: usize) -> Vec<Option<f64>> {
+    assert!(window >= 2, "Z-score window must be at least 2");
+    let mut out = vec![None; values.len()];
+    let mut run = 0;
+    for i in 0..values.len() {
+        run = if values[i].is_some() { run + 1 } else { 0 };
+        if run < window {
+            continue;
+        }
+        let recent: Vec<f64> = values[i + 1 - window..=i]
+            .iter()
+            .flatten()
+            .copied()
+            .collect();
+        let mean = recent.iter().sum::<f64>() / window as f64;
+        let variance = recent.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / window as f64;
+        if variance > 0.0 {
+            out[i] = Some((recent[window - 1] - mean) / variance.sqrt());
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zscore_needs_a_full_window() {
+        let values = [Some(1.0), None, Some(1.0), Some(2.0), Some(3.0), Some(3.0)];
+        let z = rolling_zscore(&values, 3);
+        assert_eq!(&z[..4], &[None, None, None, None]);
+        // Window [1, 2, 3]: mean 2, population std sqrt(2/3)
+        assert!((z[4].unwrap() - 1.0 / (2.0f64 / 3.0).sqrt()).abs() < 1e-12);
+        assert!(z[5].unwrap() > 0.0);
+
+        let hedge = HedgeRatio {
+            alpha: 1.0,
+            beta: 2.0,
+        };
+        let s = spread(&[5.0, 7.0], &[2.0, 3.0], &[Some(hedge), None]);
+        assert_eq!(s, vec![Some(0.0), None]);
+    }
+}
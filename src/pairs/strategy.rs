@@ -0,0 +1,146 @@
+use crate::data::OHLCV;
+use crate::pairs::{rolling_zscore, spread, HedgeMethod, HedgeRatio};
+
+/// Target of a pairs strategy at one bar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PairSignal {
+    pub hedge: Option<HedgeRatio>,
+    pub zscore: Option<f64>,
+    /// 1.0 long the spread (long A, short B), -1.0 short it, 0.0 flat
+    pub position: f64,
+}
+
+/// Mean reversion of the log-price spread between two assets
+///
+/// The spread is `ln(A) - beta * ln(B) - alpha` with the hedge ratio from
+/// `hedge`, scored against its trailing `window` bars. A z-score beyond
+/// `-entry` buys the spread and beyond `entry` sells it; the position is
+/// closed once the z-score is back within `exit`. With a stop, a z-score
+/// beyond `stop` closes any position, and no new one opens until the
+/// z-score has returned inside `entry`.
+#[derive(Debug, Clone)]
+pub struct PairsStrategy {
+    hedge: HedgeMethod,
+    window: usize,
+    entry: f64,
+    exit: f64,
+    stop: Option<f64>,
+}
+
+impl PairsStrategy {
+    pub fn new(hedge: HedgeMethod, window: usize) -> Self {
+        assert!(window >= 2, "Z-score window must be at least 2");
+        Self {
+            hedge,
+            window,
+            entry: 2.0,
+            exit: 0.5,
+            stop: None,
+        }
+    }
+
+    pub fn with_entry(mut self, z: f64) -> Self {
+        self.entry = z;
+        self
+    }
+
+    pub fn with_exit(mut self, z: f64) -> Self {
+        self.exit = z;
+        self
+    }
+
+    pub fn with_stop(mut self, z: f64) -> Self {
+        self.stop = Some(z);
+        self
+    }
+
+    pub fn hedge_method(&self) -> HedgeMethod {
+        self.hedge
+    }
+
+    pub fn name(&self) -> String {
+        let stop = self
+            .stop
+            .map(|s| format!(", stop {}", s))
+            .unwrap_or_default();
+        format!(
+            "Pairs z({}) entry {} exit {}{}, {}",
+            self.window, self.entry, self.exit, stop, self.hedge
+        )
+    }
+
+    /// Signal for every bar of two aligned series
+    pub fn signals(&self, a: &[OHLCV], b: &[OHLCV]) -> Vec<PairSignal> {
+        assert_eq!(a.len(), b.len(), "Pair legs must be aligned");
+        let y: Vec<f64> = a.iter().map(|bar| bar.close.ln()).collect();
+        let x: Vec<f64> = b.iter().map(|bar| bar.close.ln()).collect();
+
+        let hedges = self.hedge.estimate(&y, &x);
+        let zscores = rolling_zscore(&spread(&y, &x, &hedges), self.window);
+
+        let mut position = 0.0;
+        let mut stopped = false;
+        hedges
+            .into_iter()
+            .zip(zscores)
+            .map(|(hedge, zscore)| {
+                position = match zscore {
+                    None => 0.0,
+                    Some(z) => {
+                        if stopped && z.abs() < self.entry {
+                            stopped = false;
+                        }
+                        if self.stop.is_some_and(|s| z.abs() > s) {
+                            stopped = true;
+                            0.0
+                        } else if position > 0.0 && z < -self.exit
+                            || position < 0.0 && z > self.exit
+                        {
+                            position
+                        } else if !stopped && z < -self.entry {
+                            1.0
+                        } else if !stopped && z > self.entry {
+                            -1.0
+                        } else {
+                            0.0
+                        }
+                    }
+                };
+                PairSignal {
+                    hedge,
+                    zscore,
+                    position,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_and_exits_follow_the_zscore() {
+        // B drifts steadily; A oscillates, with one spike far outside the band
+        let closes = [
+            100.0, 101.0, 99.0, 100.0, 101.0, 99.0, 100.0, 90.0, 97.0, 100.0, 112.0, 104.0, 100.0,
+        ];
+        let bar = |i: usize, close: f64| OHLCV::new(i as i64, close, close, close, close, 1.0);
+        let a: Vec<OHLCV> = closes.iter().enumerate().map(|(i, &c)| bar(i, c)).collect();
+        let b: Vec<OHLCV> = (0..closes.len()).map(|i| bar(i, 50.0 + i as f64)).collect();
+
+        let strategy = PairsStrategy::new(HedgeMethod::Ols, 5).with_entry(1.5);
+        let signals = strategy.signals(&a, &b);
+        assert!(signals[..4].iter().all(|s| s.zscore.is_none()));
+        let positions: Vec<f64> = signals.iter().map(|s| s.position).collect();
+        // Long after the drop, short after the spike, flat once they revert
+        assert_eq!(positions[7], 1.0);
+        assert_eq!(positions[10], -1.0);
+        assert_eq!(positions[12], 0.0);
+
+        // A stop tighter than the spike keeps the strategy out of it
+        let stopped = strategy.clone().with_stop(1.6).signals(&a, &b);
+        assert_eq!(stopped[10].position, 0.0);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::data::OHLCV;
+use crate::indicators::sma;
+use crate::regime::{RegimeClassifier, Regimes};
+
+/// Bull, bear or sideways by the slope of a moving average
+///
+/// The slope is the change of the `period`-bar SMA over the last `lookback`
+/// bars; beyond `threshold` either way the market is trending.
+#[derive(Debug, Clone)]
+pub struct TrendRegime {
+    period: usize,
+    lookback: usize,
+    threshold: f64,
+}
+
+impl TrendRegime {
+    pub fn new(period: usize, lookback: usize, threshold: f64) -> Self {
+        assert!(period > 0, "Period must be greater than 0");
+        assert!(lookback > 0, "Lookback must be greater than 0");
+        assert!(threshold >= 0.0, "Threshold must not be negative");
+        Self {
+            period,
+            lookback,
+            threshold,
+        }
+    }
+}
+
+impl Default for TrendRegime {
+    fn default() -> Self {
+        Self::new(50, 10, 0.01)
+    }
+}
+
+impl RegimeClassifier for TrendRegime {
+    fn name(&self) -> String {
+        format!(
+            "Trend (SMA {} slope over {} bars, ±{:.1}%)",
+            self.period,
+            self.lookback,
+            self.threshold * 100.0
+        )
+    }
+
+    fn classify(&self, data: &[OHLCV]) -> Regimes {
+        let closes: Vec<f64> = data.iter().map(|b| b.close).collect();
+        let average = sma(&closes, self.period);
+
+        let labels = (0..data.len())
+            .map(|i| {
+                let past = average[i.checked_sub(self.lookback)?];
+                if past.is_nan() || average[i].is_nan() {
+                    return None;
+                }
+                let slope = average[i] / past - 1.0;
+                Some(if slope > self.threshold {
+                    0
+                } else if slope < -self.threshold {
+                    1
+                } else {
+                    2
+                })
+            })
+            .collect();
+
+        Regimes::new(&["bull", "bear", "sideways"], labels)
+    }
+}
+
+/// Low, medium or high volatility by terciles of rolling return volatility
+///
+/// The terciles are taken over the whole series, so labels are an after-the-
+/// fact description and must not feed back into signals.
+#[derive(Debug, Clone)]
+pub struct VolatilityRegime {
+    window: usize,
+}
+
+impl VolatilityRegime {
+    pub fn new(window: usize) -> Self {
+        assert!(window > 1, "Window must be greater than 1");
+        Self { window }
+    }
+}
+
+impl Default for VolatilityRegime {
+    fn default() -> Self {
+        Self::new(20)
+    }
+}
+
+impl RegimeClassifier for VolatilityRegime {
+    fn name(&self) -> String {
+        format!("Volatility ({}-bar terciles)", self.window)
+    }
+
+    fn classify(&self, data: &[OHLCV]) -> Regimes {
+        let volatility = rolling_volatility(data, self.window);
+
+        let mut sorted: Vec<f64> = volatility.iter().copied().filter(|v| !v.is_nan()).collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let labels = if sorted.is_empty() {
+            vec![None; data.len()]
+        } else {
+            let low = sorted[sorted.len() / 3];
+            let high = sorted[sorted.len() * 2 / 3];
+            volatility
+                .iter()
+                .map(|&v| {
+                    if v.is_nan() {
+                        None
+                    } else if v < low {
+                        Some(0)
+                    } else if v < high {
+                        Some(1)
+                    } else {
+                        Some(2)
+                    }
+                })
+                .collect()
+        };
+
+        Regimes::new(&["low-vol", "mid-vol", "high-vol"], labels)
+    }
+}
+
+/// Standard deviation of the last `window` log returns at each bar
+fn rolling_volatility(data: &[OHLCV], window: usize) -> Vec<f64> {
+    let returns: Vec<f64> = data
+        .windows(2)
+        .map(|w| (w[1].close / w[0].close).ln())
+        .collect();
+
+    let mut result = vec![f64::NAN; data.len()];
+    for i in window..data.len() {
+        // returns[i - 1] is the return into bar i
+        let slice = &returns[i - window..i];
+        let mean = slice.iter().sum::<f64>() / window as f64;
+        let variance = slice.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / window as f64;
+        result[i] = variance.sqrt();
+    }
+    result
+}
+
+/// Distance below the all-time high close
+///
+/// Within `correction` of the high the market is near its high; beyond `bear`
+/// it is in a bear market, and in a correction in between.
+#[derive(Debug, Clone)]
+pub struct DrawdownRegime {
+    correction: f64,
+    bear: f64,
+}
+
+impl DrawdownRegime {
+    pub fn new(correction: f64, bear: f64) -> Self {
+        assert!(
+            0.0 < correction && correction < bear && bear < 1.0,
+            "Thresholds must satisfy 0 < correction < bear < 1"
+        );
+        Self { correction, bear }
+    }
+}
+
+impl Default for DrawdownRegime {
+    fn default() -> Self {
+        Self::new(0.1, 0.2)
+    }
+}
+
+impl RegimeClassifier for DrawdownRegime {
+    fn name(&self) -> String {
+        format!(
+            "Drawdown from high ({:.0}% / {:.0}%)",
+            self.correction * 100.0,
+            self.bear * 100.0
+        )
+    }
+
+    fn classify(&self, data: &[OHLCV]) -> Regimes {
+        let mut high = f64::NEG_INFINITY;
+        let labels = data
+            .iter()
+            .map(|bar| {
+                high = high.max(bar.close);
+                let drawdown = 1.0 - bar.close / high;
+                Some(if drawdown < self.correction {
+                    0
+                } else if drawdown < self.bear {
+                    1
+                } else {
+                    2
+                })
+            })
+            .collect();
+
+        Regimes::new(&["near-high", "correction", "bear-market"], labels)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::test_support::sample_bars;
+
+    fn bars(closes: &[f64]) -> Vec<OHLCV> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| OHLCV::new(i as i64, c, c, c, c, 1.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_trend_and_drawdown_labels() {
+        let mut closes: Vec<f64> = (0..60).map(|i| 100.0 + i as f64).collect();
+        closes.extend((0..60).map(|i| 160.0 - 2.0 * i as f64));
+        let data = bars(&closes);
+
+        let trend = TrendRegime::new(10, 5, 0.01).classify(&data);
+        assert_eq!(trend.labels[13], None);
+        assert_eq!(trend.name_at(14), Some("bull"));
+        assert_eq!(trend.name_at(119), Some("bear"));
+
+        let drawdown = DrawdownRegime::default().classify(&data);
+        assert_eq!(drawdown.name_at(59), Some("near-high"));
+        // 1 - 130 / 159 = 18%
+        assert_eq!(drawdown.name_at(75), Some("correction"));
+        assert_eq!(drawdown.name_at(119), Some("bear-market"));
+    }
+
+    #[test]
+    fn test_volatility_terciles_are_balanced() {
+        let data = sample_bars();
+        let regimes = VolatilityRegime::new(20).classify(&data);
+        assert!(regimes.labels[..20].iter().all(|l| l.is_none()));
+
+        let counts: Vec<usize> = (0..3)
+            .map(|k| regimes.labels.iter().filter(|l| **l == Some(k)).count())
+            .collect();
+        assert_eq!(counts.iter().sum::<usize>(), data.len() - 20);
+        for count in counts {
+            assert!((155..=165).contains(&count), "{}", count);
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use crate::data::OHLCV;
+use crate::regime::{RegimeClassifier, Regimes};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Smallest emission density, so outliers cannot zero a whole forward step
+const MIN_DENSITY: f64 = 1e-300;
+
+/// Hidden Markov model with one Gaussian emission per state
+///
+/// States are ordered by variance, lowest first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GaussianHmm {
+    pub initial: Vec<f64>,
+    /// `transition[i][j]` is the probability of moving from state i to j
+    pub transition: Vec<Vec<f64>>,
+    pub means: Vec<f64>,
+    pub variances: Vec<f64>,
+    pub log_likelihood: f64,
+    pub iterations: usize,
+}
+
+impl GaussianHmm {
+    /// Fit by expectation-maximization (Baum-Welch), stopping once the
+    /// log-likelihood improves by less than `tolerance`
+    pub fn fit(
+        observations: &[f64],
+        states: usize,
+        max_iterations: usize,
+        tolerance: f64,
+    ) -> Result<Self> {
+        if states < 2 {
+            bail!("An HMM needs at least 2 states");
+        }
+        if observations.len() < states * 10 {
+            bail!(
+                "Need at least {} observations to fit {} states, got {}",
+                states * 10,
+                states,
+                observations.len()
+            );
+        }
+
+        let mut model = Self::initial_guess(observations, states);
+        let n = observations.len() as f64;
+        let overall_mean = observations.iter().sum::<f64>() / n;
+        let overall_variance = observations
+            .iter()
+            .map(|x| (x - overall_mean).powi(2))
+            .sum::<f64>()
+            / n;
+        let variance_floor = (overall_variance * 1e-4).max(f64::MIN_POSITIVE);
+
+        let mut previous = f64::NEG_INFINITY;
+        for iteration in 1..=max_iterations {
+            let (gamma, xi, log_likelihood) = model.forward_backward(observations);
+            model.log_likelihood = log_likelihood;
+            model.iterations = iteration;
+            model.maximize(observations, &gamma, &xi, variance_floor);
+
+            if log_likelihood - previous < tolerance {
+                break;
+            }
+            previous = log_likelihood;
+        }
+
+        model.log_likelihood = model.forward_backward(observations).2;
+        model.sort_by_variance();
+        Ok(model)
+    }
+
+    /// Equal means, variances spread across quantiles of squared deviations,
+    /// and sticky transitions
+    fn initial_guess(observations: &[f64], states: usize) -> Self {
+        let n = observations.len();
+        let mean = observations.iter().sum::<f64>() / n as f64;
+        let mut squared: Vec<f64> = observations.iter().map(|x| (x - mean).powi(2)).collect();
+        squared.sort_by(|a, b| a.total_cmp(b));
+
+        let variances = (0..states)
+            .map(|k| {
+                let chunk = &squared[k * n / states..(k + 1) * n / states];
+                (chunk.iter().sum::<f64>() / chunk.len() as f64).max(f64::MIN_POSITIVE)
+            })
+            .collect();
+
+        let stay = 0.95;
+        let leave = (1.0 - stay) / (states - 1) as f64;
+        let transition = (0..states)
+            .map(|i| {
+                (0..states)
+                    .map(|j| if i == j { stay } else { leave })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            initial: vec![1.0 / states as f64; states],
+            transition,
+            means: vec![mean; states],
+            variances,
+            log_likelihood: f64::NEG_INFINITY,
+            iterations: 0,
+        }
+    }
+
+    pub fn states(&self) -> usize {
+        self.means.len()
+    }
+
+    fn density(&self, state: usize, x: f64) -> f64 {
+        let variance = self.variances[state];
+        let z = (x - self.means[state]).powi(2) / variance;
+        ((-0.5 * z).exp() / (2.0 * std::f64::consts::PI * variance).sqrt()).max(MIN_DENSITY)
+    }
+
+    /// Scaled forward-backward pass: state posteriors per observation, summed
+    /// transition posteriors, and the log-likelihood
+    fn forward_backward(&self, observations: &[f64]) -> (Vec<Vec<f64>>, Vec<Vec<f64>>, f64) {
+        let k = self.states();
+        let t_len = observations.len();
+        let emissions: Vec<Vec<f64>> = observations
+            .iter()
+            .map(|&x| (0..k).map(|s| self.density(s, x)).collect())
+            .collect();
+
+        let mut alpha = vec![vec![0.0; k]; t_len];
+        let mut scale = vec![0.0; t_len];
+        for t in 0..t_len {
+            for j in 0..k {
+                let prior = if t == 0 {
+                    self.initial[j]
+                } else {
+                    (0..k)
+                        .map(|i| alpha[t - 1][i] * self.transition[i][j])
+                        .sum()
+                };
+                alpha[t][j] = prior * emissions[t][j];
+            }
+            scale[t] = alpha[t].iter().sum::<f64>().max(f64::MIN_POSITIVE);
+            alpha[t].iter_mut().for_each(|a| *a /= scale[t]);
+        }
+
+        let mut beta = vec![vec![1.0; k]; t_len];
+        for t in (0..t_len - 1).rev() {
+            for i in 0..k {
+                beta[t][i] = (0..k)
+                    .map(|j| self.transition[i][j] * emissions[t + 1][j] * beta[t + 1][j])
+                    .sum::<f64>()
+                    / scale[t + 1];
+            }
+        }
+
+        let gamma: Vec<Vec<f64>> = (0..t_len)
+            .map(|t| {
+                let row: Vec<f64> = (0..k).map(|i| alpha[t][i] * beta[t][i]).collect();
+                let total = row.iter().sum::<f64>().max(f64::MIN_POSITIVE);
+                row.into_iter().map(|g| g / total).collect()
+            })
+            .collect();
+
+        let mut xi = vec![vec![0.0; k]; k];
+        for t in 0..t_len - 1 {
+            for (i, row) in xi.iter_mut().enumerate() {
+                for (j, cell) in row.iter_mut().enumerate() {
+                    *cell +=
+                        alpha[t][i] * self.transition[i][j] * emissions[t + 1][j] * beta[t + 1][j]
+                            / scale[t + 1];
+                }
+            }
+        }
+
+        let log_likelihood = scale.iter().map(|c| c.ln()).sum();
+        (gamma, xi, log_likelihood)
+    }
+
+    fn maximize(
+        &mut self,
+        observations: &[f64],
+        gamma: &[Vec<f64>],
+        xi: &[Vec<f64>],
+        variance_floor: f64,
+    ) {
+        let k = self.states();
+        self.initial = gamma[0].clone();
+
+        for i in 0..k {
+            let row_total: f64 = xi[i].iter().sum();
+            if row_total > 0.0 {
+                for (p, x) in self.transition[i].iter_mut().zip(&xi[i]) {
+                    *p = x / row_total;
+                }
+            }
+
+            let weight: f64 = gamma.iter().map(|g| g[i]).sum();
+            if weight <= 0.0 {
+                continue;
+            }
+            let mean = gamma
+                .iter()
+                .zip(observations)
+                .map(|(g, x)| g[i] * x)
+                .sum::<f64>()
+                / weight;
+            let variance = gamma
+                .iter()
+                .zip(observations)
+                .map(|(g, x)| g[i] * (x - mean).powi(2))
+                .sum::<f64>()
+                / weight;
+            self.means[i] = mean;
+            self.variances[i] = variance.max(variance_floor);
+        }
+    }
+
+    fn sort_by_variance(&mut self) {
+        let mut order: Vec<usize> = (0..self.states()).collect();
+        order.sort_by(|&a, &b| self.variances[a].total_cmp(&self.variances[b]));
+
+        self.initial = order.iter().map(|&i| self.initial[i]).collect();
+        self.means = order.iter().map(|&i| self.means[i]).collect();
+        self.variances = order.iter().map(|&i| self.variances[i]).collect();
+        self.transition = order
+            .iter()
+            .map(|&i| order.iter().map(|&j| self.transition[i][j]).collect())
+            .collect();
+    }
+
+    /// Most likely state sequence (Viterbi)
+    pub fn decode(&self, observations: &[f64]) -> Vec<usize> {
+        let k = self.states();
+        if observations.is_empty() {
+            return Vec::new();
+        }
+        let log_transition: Vec<Vec<f64>> = self
+            .transition
+            .iter()
+            .map(|row| row.iter().map(|p| p.ln()).collect())
+            .collect();
+
+        let mut score: Vec<f64> = (0..k)
+            .map(|s| self.initial[s].ln() + self.density(s, observations[0]).ln())
+            .collect();
+        let mut backpointers = Vec::with_capacity(observations.len());
+
+        for &x in &observations[1..] {
+            let mut next = vec![f64::NEG_INFINITY; k];
+            let mut pointers = vec![0; k];
+            for j in 0..k {
+                for i in 0..k {
+                    let candidate = score[i] + log_transition[i][j];
+                    if candidate > next[j] {
+                        next[j] = candidate;
+                        pointers[j] = i;
+                    }
+                }
+                next[j] += self.density(j, x).ln();
+            }
+            score = next;
+            backpointers.push(pointers);
+        }
+
+        let mut state = (0..k)
+            .max_by(|&a, &b| score[a].total_cmp(&score[b]))
+            .unwrap();
+        let mut path = vec![state; observations.len()];
+        for (t, pointers) in backpointers.iter().enumerate().rev() {
+            state = pointers[state];
+            path[t] = state;
+        }
+        path
+    }
+}
+
+/// Regimes from a Gaussian HMM fitted to log returns, named by volatility
+///
+/// The model is fitted on the whole series, so like volatility terciles the
+/// labels describe the past rather than being usable as signals.
+#[derive(Debug, Clone)]
+pub struct HmmRegime {
+    states: usize,
+    max_iterations: usize,
+}
+
+impl HmmRegime {
+    pub fn new(states: usize) -> Self {
+        assert!(states >= 2, "An HMM needs at least 2 states");
+        Self {
+            states,
+            max_iterations: 200,
+        }
+    }
+
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    fn state_names(&self) -> Vec<String> {
+        match self.states {
+            2 => vec!["calm".to_string(), "turbulent".to_string()],
+            3 => vec![
+                "calm".to_string(),
+                "normal".to_string(),
+                "turbulent".to_string(),
+            ],
+            n => (0..n).map(|i| format!("state-{}", i)).collect(),
+        }
+    }
+}
+
+impl Default for HmmRegime {
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
+
+impl RegimeClassifier for HmmRegime {
+    fn name(&self) -> String {
+        format!("HMM ({} states on log returns)", self.states)
+    }
+
+    fn classify(&self, data: &[OHLCV]) -> Regimes {
+        let returns: Vec<f64> = data
+            .windows(2)
+            .map(|w| (w[1].close / w[0].close).ln())
+            .collect();
+
+        let mut labels = vec![None; data.len()];
+        // Too little data leaves every bar unlabelled
+        if let Ok(model) = GaussianHmm::fit(&returns, self.states, self.max_iterations, 1e-6) {
+            for (i, state) in model.decode(&returns).into_iter().enumerate() {
+                labels[i + 1] = Some(state);
+            }
+        }
+
+        Regimes {
+            names: self.state_names(),
+            labels,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::test_support::sample_bars;
+
+    #[test]
+    fn test_fit_separates_volatility_regimes() {
+        // Standardized returns, quiet for 250 bars then wild for 250
+        let bars = sample_bars();
+        let raw: Vec<f64> = bars
+            .windows(2)
+            .map(|w| (w[1].close / w[0].close).ln())
+            .collect();
+        let mean = raw.iter().sum::<f64>() / raw.len() as f64;
+        let std = (raw.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / raw.len() as f64).sqrt();
+        let returns: Vec<f64> = raw
+            .iter()
+            .enumerate()
+            .map(|(i, r)| (r - mean) / std * if i < 250 { 0.005 } else { 0.04 })
+            .collect();
+
+        let model = GaussianHmm::fit(&returns, 2, 200, 1e-8).unwrap();
+        assert!(model.variances[0] < model.variances[1]);
+        assert!((model.variances[1] / model.variances[0]).sqrt() > 4.0);
+        assert!(model.transition[0][0] > 0.9 && model.transition[1][1] > 0.9);
+
+        let path = model.decode(&returns);
+        let calm_early = path[..250].iter().filter(|&&s| s == 0).count();
+        let turbulent_late = path[250..].iter().filter(|&&s| s == 1).count();
+        assert!(calm_early > 240, "{}", calm_early);
+        assert!(turbulent_late > 230, "{}", turbulent_late);
+
+        assert!(GaussianHmm::fit(&returns[..15], 2, 10, 1e-6).is_err());
+    }
+}
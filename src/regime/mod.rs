@@ -0,0 +1,59 @@
+//! Market regime detection
+//!
+//! A classifier labels every bar with one of a fixed set of regimes (or none
+//! during warm-up); [`RegimeReport`] then splits a backtest by those labels.
+
+pub mod classifiers;
+pub mod hmm;
+pub mod report;
+
+pub use classifiers::{DrawdownRegime, TrendRegime, VolatilityRegime};
+pub use hmm::{GaussianHmm, HmmRegime};
+pub use report::{RegimeReport, RegimeStats};
+
+use crate::data::OHLCV;
+use anyhow::{bail, Result};
+
+/// Regime label of each bar
+#[derive(Debug, Clone)]
+pub struct Regimes {
+    /// Regime names, indexed by label
+    pub names: Vec<String>,
+    /// Label of each bar; `None` while the classifier is warming up
+    pub labels: Vec<Option<usize>>,
+}
+
+impl Regimes {
+    pub fn new(names: &[&str], labels: Vec<Option<usize>>) -> Self {
+        Self {
+            names: names.iter().map(|n| n.to_string()).collect(),
+            labels,
+        }
+    }
+
+    /// Name of the regime at bar `index`
+    pub fn name_at(&self, index: usize) -> Option<&str> {
+        self.labels[index].map(|label| self.names[label].as_str())
+    }
+}
+
+pub trait RegimeClassifier {
+    fn name(&self) -> String;
+
+    fn classify(&self, data: &[OHLCV]) -> Regimes;
+}
+
+/// Classifier with default settings by name: trend, volatility, drawdown or hmm
+pub fn classifier_by_name(name: &str) -> Result<Box<dyn RegimeClassifier>> {
+    let classifier: Box<dyn RegimeClassifier> = match name {
+        "trend" => Box::new(TrendRegime::default()),
+        "volatility" => Box::new(VolatilityRegime::default()),
+        "drawdown" => Box::new(DrawdownRegime::default()),
+        "hmm" => Box::new(HmmRegime::default()),
+        _ => bail!(
+            "Unknown regime classifier '{}' (expected trend, volatility, drawdown or hmm)",
+            name
+        ),
+    };
+    Ok(classifier)
+}
@@ -0,0 +1,169 @@
+use crate::backtest::{BacktestResult, Trade, TradeStats};
+use crate::data::OHLCV;
+use crate::metrics::{calculate_max_drawdown, calculate_sharpe_ratio, calculate_sortino_ratio};
+use crate::regime::{RegimeClassifier, Regimes};
+use serde::Serialize;
+
+/// Backtest performance over the bars of one regime
+#[derive(Debug, Clone, Serialize)]
+pub struct RegimeStats {
+    pub regime: String,
+    pub bars: usize,
+    /// Share of classified bars spent in this regime
+    pub time_fraction: f64,
+    /// Compounded strategy return over the regime's bars
+    pub strategy_return: f64,
+    /// Compounded close-to-close return of the market over the same bars
+    pub market_return: f64,
+    pub sharpe_ratio: f64,
+    pub sortino_ratio: f64,
+    /// Worst drawdown of the regime's returns chained together
+    pub max_drawdown: f64,
+    /// Trades entered during the regime
+    pub trades: usize,
+    pub trade_stats: TradeStats,
+}
+
+/// A backtest split by the regime of each bar
+///
+/// The return into bar `i` counts towards the regime of bar `i`, and a trade
+/// belongs to the regime of its entry bar.
+#[derive(Debug, Clone, Serialize)]
+pub struct RegimeReport {
+    pub classifier: String,
+    pub regimes: Vec<RegimeStats>,
+    /// Bars without a label (classifier warm-up)
+    pub unclassified_bars: usize,
+}
+
+impl RegimeReport {
+    pub fn new(classifier: &dyn RegimeClassifier, data: &[OHLCV], result: &BacktestResult) -> Self {
+        Self::from_regimes(classifier.name(), &classifier.classify(data), data, result)
+    }
+
+    pub fn from_regimes(
+        classifier: String,
+        regimes: &Regimes,
+        data: &[OHLCV],
+        result: &BacktestResult,
+    ) -> Self {
+        assert_eq!(
+            regimes.labels.len(),
+            data.len(),
+            "Regimes must label every bar"
+        );
+        assert_eq!(
+            result.equity_curve.len(),
+            data.len(),
+            "Backtest must cover the same bars"
+        );
+
+        let count = regimes.names.len();
+        let mut strategy_returns = vec![Vec::new(); count];
+        let mut market_returns = vec![Vec::new(); count];
+        for i in 1..data.len() {
+            if let Some(label) = regimes.labels[i] {
+                let equity = &result.equity_curve;
+                strategy_returns[label].push(equity[i] / equity[i - 1] - 1.0);
+                market_returns[label].push(data[i].close / data[i - 1].close - 1.0);
+            }
+        }
+
+        let mut trades: Vec<Vec<Trade>> = vec![Vec::new(); count];
+        for trade in result.trades.iter().flatten() {
+            let entry = data.partition_point(|b| b.timestamp < trade.entry_timestamp);
+            if let Some(label) = regimes.labels.get(entry).copied().flatten() {
+                trades[label].push(trade.clone());
+            }
+        }
+
+        let classified = regimes.labels.iter().filter(|l| l.is_some()).count();
+        let stats = (0..count)
+            .map(|label| {
+                let bars = regimes.labels.iter().filter(|l| **l == Some(label)).count();
+                let returns = &strategy_returns[label];
+                let chained = chain(returns);
+
+                RegimeStats {
+                    regime: regimes.names[label].clone(),
+                    bars,
+                    time_fraction: if classified > 0 {
+                        bars as f64 / classified as f64
+                    } else {
+                        0.0
+                    },
+                    strategy_return: chained.last().copied().unwrap_or(1.0) - 1.0,
+                    market_return: chain(&market_returns[label]).last().copied().unwrap_or(1.0)
+                        - 1.0,
+                    sharpe_ratio: calculate_sharpe_ratio(returns, 252.0),
+                    sortino_ratio: calculate_sortino_ratio(returns, 252.0),
+                    max_drawdown: calculate_max_drawdown(&chained),
+                    trades: trades[label].len(),
+                    trade_stats: TradeStats::from_trades(&trades[label]),
+                }
+            })
+            .collect();
+
+        Self {
+            classifier,
+            regimes: stats,
+            unclassified_bars: data.len() - classified,
+        }
+    }
+}
+
+/// Growth of 1.0 through `returns`, starting with 1.0 itself
+fn chain(returns: &[f64]) -> Vec<f64> {
+    let mut curve = Vec::with_capacity(returns.len() + 1);
+    curve.push(1.0);
+    for r in returns {
+        curve.push(curve[curve.len() - 1] * (1.0 + r));
+    }
+    curve
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::{BacktestEngine, ExecutionModel};
+    use crate::indicators::test_support::sample_bars;
+    use crate::regime::DrawdownRegime;
+    use crate::strategies::SMACrossover;
+
+    #[test]
+    fn test_report_partitions_the_backtest() {
+        let data = sample_bars();
+        let result = BacktestEngine::new(&data, 100_000.0, ExecutionModel::new(10.0, 5.0))
+            .run(&SMACrossover::new(10, 30));
+        let report = RegimeReport::new(&DrawdownRegime::new(0.05, 0.15), &data, &result);
+
+        assert_eq!(report.unclassified_bars, 0);
+        assert_eq!(report.regimes.len(), 3);
+        assert_eq!(
+            report.regimes.iter().map(|r| r.bars).sum::<usize>(),
+            data.len()
+        );
+
+        // Regime returns compound back to the whole backtest (bar 0 has none)
+        let compounded: f64 = report
+            .regimes
+            .iter()
+            .map(|r| 1.0 + r.strategy_return)
+            .product();
+        let whole = result.equity_curve[data.len() - 1] / result.equity_curve[0];
+        assert!((compounded - whole).abs() < 1e-9);
+
+        let market: f64 = report
+            .regimes
+            .iter()
+            .map(|r| 1.0 + r.market_return)
+            .product();
+        assert!((market - data[data.len() - 1].close / data[0].close).abs() < 1e-9);
+
+        let trades = result.trades.as_ref().unwrap().len();
+        assert_eq!(
+            report.regimes.iter().map(|r| r.trades).sum::<usize>(),
+            trades
+        );
+    }
+}
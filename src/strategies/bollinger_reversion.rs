@@ -0,0 +1,79 @@
+use crate::data::OHLCV;
+use crate::indicators::bollinger;
+use crate::strategies::Strategy;
+
+/// Bollinger Band Reversion Strategy
+/// Buy when the close falls below the lower band, sell when it recovers
+/// to the middle band
+pub struct BollingerReversion {
+    period: usize,
+    std_dev: f64,
+    /// Display name including the parameters
+    name: String,
+}
+
+impl BollingerReversion {
+    pub fn new(period: usize, std_dev: f64) -> Self {
+        assert!(period > 1, "Bollinger period must be greater than 1");
+        assert!(std_dev > 0.0, "Band width must be positive");
+
+        Self {
+            period,
+            std_dev,
+            name: format!("Bollinger({}, {})", period, std_dev),
+        }
+    }
+}
+
+impl Strategy for BollingerReversion {
+    fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+        let closes: Vec<f64> = data.iter().map(|d| d.close).collect();
+        let bands = bollinger(&closes, self.period, self.std_dev);
+
+        let mut current_position = 0.0;
+        closes
+            .iter()
+            .zip(&bands)
+            .map(|(&close, band)| {
+                if close < band.lower {
+                    current_position = 1.0;
+                } else if close >= band.middle {
+                    current_position = 0.0;
+                }
+                current_position
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Bollinger({}, {}) reversion - Buy below lower band, sell at middle band",
+            self.period, self.std_dev
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buys_dip_and_exits_at_mean() {
+        let closes = [100.0, 101.0, 99.0, 100.0, 101.0, 90.0, 95.0, 101.0, 100.0];
+        let data: Vec<OHLCV> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| OHLCV::new(i as i64, c, c, c, c, 1.0))
+            .collect();
+        let signals = BollingerReversion::new(5, 1.5).generate_signals(&data);
+
+        assert_eq!(signals[4], 0.0);
+        assert_eq!(signals[5], 1.0);
+        assert_eq!(signals[6], 1.0);
+        assert_eq!(signals[7], 0.0);
+    }
+}
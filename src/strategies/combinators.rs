@@ -0,0 +1,723 @@
+//! Strategies built from other strategies
+//!
+//! Signals are treated as "on" when positive. AND/OR take the minimum/maximum
+//! of their inputs, which is plain boolean logic for 0/1 signals and keeps
+//! partial positions meaningful otherwise.
+
+use crate::data::{TimeframeContext, OHLCV};
+use crate::indicators::IndicatorCache;
+use crate::strategies::{signals_or_flat, Strategy};
+use anyhow::Result;
+
+/// Which `Strategy` hook a combinator was called through, so inner
+/// strategies get the same timeframe context or indicator cache
+#[derive(Clone, Copy)]
+enum Source<'a> {
+    Plain,
+    Context(&'a TimeframeContext),
+    Cached(&'a IndicatorCache),
+}
+
+impl Source<'_> {
+    fn signals(self, strategy: &dyn Strategy, data: &[OHLCV]) -> Result<Vec<f64>> {
+        match self {
+            Source::Plain => strategy.try_generate_signals(data),
+            Source::Context(context) => strategy.generate_signals_with_context(data, context),
+            Source::Cached(cache) => strategy.generate_signals_cached(data, cache),
+        }
+    }
+}
+
+/// Combinator logic over the signals of its inner strategies
+trait Combinator {
+    fn inner(&self) -> Vec<&dyn Strategy>;
+
+    fn signals(&self, data: &[OHLCV], source: Source) -> Result<Vec<f64>>;
+}
+
+/// `Strategy` hooks of a combinator, forwarded to its inner strategies
+macro_rules! forward_hooks {
+    () => {
+        fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+            signals_or_flat(&self.name, data, self.signals(data, Source::Plain))
+        }
+
+        fn try_generate_signals(&self, data: &[OHLCV]) -> Result<Vec<f64>> {
+            self.signals(data, Source::Plain)
+        }
+
+        /// Every timeframe requested by an inner strategy
+        fn timeframes(&self) -> Vec<String> {
+            let mut timeframes: Vec<String> = Vec::new();
+            for strategy in self.inner() {
+                for timeframe in strategy.timeframes() {
+                    if !timeframes.contains(&timeframe) {
+                        timeframes.push(timeframe);
+                    }
+                }
+            }
+            timeframes
+        }
+
+        fn generate_signals_with_context(
+            &self,
+            data: &[OHLCV],
+            context: &TimeframeContext,
+        ) -> Result<Vec<f64>> {
+            self.signals(data, Source::Context(context))
+        }
+
+        fn generate_signals_cached(
+            &self,
+            data: &[OHLCV],
+            cache: &IndicatorCache,
+        ) -> Result<Vec<f64>> {
+            self.signals(data, Source::Cached(cache))
+        }
+    };
+}
+
+fn names(strategies: &[Box<dyn Strategy>], separator: &str) -> String {
+    strategies
+        .iter()
+        .map(|s| s.name())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn descriptions(strategies: &[Box<dyn Strategy>]) -> String {
+    strategies
+        .iter()
+        .map(|s| s.description())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Combine per-bar signals of several strategies with `f`
+fn combine(
+    strategies: &[Box<dyn Strategy>],
+    data: &[OHLCV],
+    source: Source,
+    f: impl Fn(&[f64]) -> f64,
+) -> Result<Vec<f64>> {
+    let all = strategies
+        .iter()
+        .map(|s| source.signals(s.as_ref(), data))
+        .collect::<Result<Vec<_>>>()?;
+    let mut bar = vec![0.0; all.len()];
+
+    Ok((0..data.len())
+        .map(|i| {
+            for (value, signals) in bar.iter_mut().zip(&all) {
+                *value = signals[i];
+            }
+            f(&bar)
+        })
+        .collect())
+}
+
+/// Long only while every strategy is long
+pub struct And {
+    strategies: Vec<Box<dyn Strategy>>,
+    name: String,
+}
+
+impl And {
+    pub fn new(strategies: Vec<Box<dyn Strategy>>) -> Self {
+        assert!(!strategies.is_empty(), "AND needs at least one strategy");
+        let name = format!("({})", names(&strategies, " AND "));
+        Self { strategies, name }
+    }
+}
+
+impl Combinator for And {
+    fn inner(&self) -> Vec<&dyn Strategy> {
+        self.strategies.iter().map(|s| s.as_ref()).collect()
+    }
+
+    fn signals(&self, data: &[OHLCV], source: Source) -> Result<Vec<f64>> {
+        combine(&self.strategies, data, source, |s| {
+            s.iter().copied().fold(f64::INFINITY, f64::min)
+        })
+    }
+}
+
+impl Strategy for And {
+    forward_hooks!();
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!("Long when all agree: {}", descriptions(&self.strategies))
+    }
+}
+
+/// Long while any strategy is long
+pub struct Or {
+    strategies: Vec<Box<dyn Strategy>>,
+    name: String,
+}
+
+impl Or {
+    pub fn new(strategies: Vec<Box<dyn Strategy>>) -> Self {
+        assert!(!strategies.is_empty(), "OR needs at least one strategy");
+        let name = format!("({})", names(&strategies, " OR "));
+        Self { strategies, name }
+    }
+}
+
+impl Combinator for Or {
+    fn inner(&self) -> Vec<&dyn Strategy> {
+        self.strategies.iter().map(|s| s.as_ref()).collect()
+    }
+
+    fn signals(&self, data: &[OHLCV], source: Source) -> Result<Vec<f64>> {
+        combine(&self.strategies, data, source, |s| {
+            s.iter().copied().fold(f64::NEG_INFINITY, f64::max)
+        })
+    }
+}
+
+impl Strategy for Or {
+    forward_hooks!();
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!("Long when any agrees: {}", descriptions(&self.strategies))
+    }
+}
+
+/// Long exactly when the inner strategy is flat
+pub struct Not {
+    strategy: Box<dyn Strategy>,
+    name: String,
+}
+
+impl Not {
+    pub fn new(strategy: Box<dyn Strategy>) -> Self {
+        let name = format!("NOT {}", strategy.name());
+        Self { strategy, name }
+    }
+}
+
+impl Combinator for Not {
+    fn inner(&self) -> Vec<&dyn Strategy> {
+        vec![self.strategy.as_ref()]
+    }
+
+    fn signals(&self, data: &[OHLCV], source: Source) -> Result<Vec<f64>> {
+        Ok(source
+            .signals(self.strategy.as_ref(), data)?
+            .into_iter()
+            .map(|s| if s > 0.0 { 0.0 } else { 1.0 })
+            .collect())
+    }
+}
+
+impl Strategy for Not {
+    forward_hooks!();
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!("Long when flat under: {}", self.strategy.description())
+    }
+}
+
+/// Mirror of the inner position: `1 - signal`
+///
+/// Same as [`Not`] for 0/1 signals; a strategy holding 30% becomes 70%.
+pub struct Invert {
+    strategy: Box<dyn Strategy>,
+    name: String,
+}
+
+impl Invert {
+    pub fn new(strategy: Box<dyn Strategy>) -> Self {
+        let name = format!("Inverse {}", strategy.name());
+        Self { strategy, name }
+    }
+}
+
+impl Combinator for Invert {
+    fn inner(&self) -> Vec<&dyn Strategy> {
+        vec![self.strategy.as_ref()]
+    }
+
+    fn signals(&self, data: &[OHLCV], source: Source) -> Result<Vec<f64>> {
+        Ok(source
+            .signals(self.strategy.as_ref(), data)?
+            .into_iter()
+            .map(|s| (1.0 - s).clamp(0.0, 1.0))
+            .collect())
+    }
+}
+
+impl Strategy for Invert {
+    forward_hooks!();
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!("Inverted exposure of: {}", self.strategy.description())
+    }
+}
+
+/// Weighted average of several strategies' positions
+///
+/// With a threshold the ensemble goes fully long when the weighted vote
+/// reaches it and flat otherwise, e.g. 0.5 for a majority vote.
+pub struct Ensemble {
+    members: Vec<(Box<dyn Strategy>, f64)>,
+    threshold: Option<f64>,
+    name: String,
+}
+
+impl Ensemble {
+    pub fn new(members: Vec<(Box<dyn Strategy>, f64)>) -> Self {
+        assert!(!members.is_empty(), "Ensemble needs at least one strategy");
+        assert!(
+            members.iter().all(|(_, w)| *w >= 0.0) && members.iter().any(|(_, w)| *w > 0.0),
+            "Ensemble weights must be non-negative and not all zero"
+        );
+        let mut ensemble = Self {
+            members,
+            threshold: None,
+            name: String::new(),
+        };
+        ensemble.name = ensemble.build_name();
+        ensemble
+    }
+
+    /// Equal-weight ensemble
+    pub fn equal(strategies: Vec<Box<dyn Strategy>>) -> Self {
+        Self::new(strategies.into_iter().map(|s| (s, 1.0)).collect())
+    }
+
+    /// Equal-weight majority vote: long when more than half are long
+    pub fn majority(strategies: Vec<Box<dyn Strategy>>) -> Self {
+        Self::equal(strategies).with_threshold(0.5 + 1e-9)
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = Some(threshold);
+        self.name = self.build_name();
+        self
+    }
+
+    fn build_name(&self) -> String {
+        // Weights are only shown when they differ
+        let equal = self.members.iter().all(|(_, w)| *w == self.members[0].1);
+        let members: Vec<String> = self
+            .members
+            .iter()
+            .map(|(s, w)| {
+                if equal {
+                    s.name().to_string()
+                } else {
+                    format!("{} x{}", s.name(), w)
+                }
+            })
+            .collect();
+        match self.threshold {
+            Some(_) => format!("Vote({})", members.join(", ")),
+            None => format!("Ensemble({})", members.join(", ")),
+        }
+    }
+}
+
+impl Combinator for Ensemble {
+    fn inner(&self) -> Vec<&dyn Strategy> {
+        self.members.iter().map(|(s, _)| s.as_ref()).collect()
+    }
+
+    fn signals(&self, data: &[OHLCV], source: Source) -> Result<Vec<f64>> {
+        let total_weight: f64 = self.members.iter().map(|(_, w)| w).sum();
+        let all = self
+            .members
+            .iter()
+            .map(|(s, w)| Ok((source.signals(s.as_ref(), data)?, *w)))
+            .collect::<Result<Vec<(Vec<f64>, f64)>>>()?;
+
+        Ok((0..data.len())
+            .map(|i| {
+                let average =
+                    all.iter().map(|(signals, w)| signals[i] * w).sum::<f64>() / total_weight;
+                match self.threshold {
+                    Some(t) if average >= t => 1.0,
+                    Some(_) => 0.0,
+                    None => average,
+                }
+            })
+            .collect())
+    }
+}
+
+impl Strategy for Ensemble {
+    forward_hooks!();
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        let members: Vec<String> = self.members.iter().map(|(s, _)| s.description()).collect();
+        match self.threshold {
+            Some(t) => format!(
+                "Long when weighted vote >= {:.2}: {}",
+                t,
+                members.join("; ")
+            ),
+            None => format!("Weighted average position of: {}", members.join("; ")),
+        }
+    }
+}
+
+/// Regime filter: trade `strategy` only while `filter` is long
+///
+/// E.g. an SMA crossover gated by `SMACrossover::new(1, 200)` only takes
+/// trades while price is above its 200-bar average.
+pub struct Filter {
+    strategy: Box<dyn Strategy>,
+    filter: Box<dyn Strategy>,
+    name: String,
+}
+
+impl Filter {
+    pub fn new(strategy: Box<dyn Strategy>, filter: Box<dyn Strategy>) -> Self {
+        let name = format!("{} [when {}]", strategy.name(), filter.name());
+        Self {
+            strategy,
+            filter,
+            name,
+        }
+    }
+}
+
+impl Combinator for Filter {
+    fn inner(&self) -> Vec<&dyn Strategy> {
+        vec![self.strategy.as_ref(), self.filter.as_ref()]
+    }
+
+    fn signals(&self, data: &[OHLCV], source: Source) -> Result<Vec<f64>> {
+        let signals = source.signals(self.strategy.as_ref(), data)?;
+        let regime = source.signals(self.filter.as_ref(), data)?;
+
+        Ok(signals
+            .iter()
+            .zip(&regime)
+            .map(|(&s, &r)| if r > 0.0 { s } else { 0.0 })
+            .collect())
+    }
+}
+
+impl Strategy for Filter {
+    forward_hooks!();
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "{} - only while: {}",
+            self.strategy.description(),
+            self.filter.description()
+        )
+    }
+}
+
+/// Delay signals by a number of bars
+pub struct Lag {
+    strategy: Box<dyn Strategy>,
+    bars: usize,
+    name: String,
+}
+
+impl Lag {
+    pub fn new(strategy: Box<dyn Strategy>, bars: usize) -> Self {
+        let name = format!("{} lag {}", strategy.name(), bars);
+        Self {
+            strategy,
+            bars,
+            name,
+        }
+    }
+}
+
+impl Combinator for Lag {
+    fn inner(&self) -> Vec<&dyn Strategy> {
+        vec![self.strategy.as_ref()]
+    }
+
+    fn signals(&self, data: &[OHLCV], source: Source) -> Result<Vec<f64>> {
+        let signals = source.signals(self.strategy.as_ref(), data)?;
+        Ok((0..signals.len())
+            .map(|i| {
+                if i >= self.bars {
+                    signals[i - self.bars]
+                } else {
+                    0.0
+                }
+            })
+            .collect())
+    }
+}
+
+impl Strategy for Lag {
+    forward_hooks!();
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "{} - delayed {} bars",
+            self.strategy.description(),
+            self.bars
+        )
+    }
+}
+
+/// Smooth out whipsaws: long while the inner strategy was long for at least
+/// half of the last `period` bars
+pub struct Smooth {
+    strategy: Box<dyn Strategy>,
+    period: usize,
+    name: String,
+}
+
+impl Smooth {
+    pub fn new(strategy: Box<dyn Strategy>, period: usize) -> Self {
+        assert!(period > 0, "Smoothing period must be greater than 0");
+        let name = format!("{} smoothed {}", strategy.name(), period);
+        Self {
+            strategy,
+            period,
+            name,
+        }
+    }
+}
+
+impl Combinator for Smooth {
+    fn inner(&self) -> Vec<&dyn Strategy> {
+        vec![self.strategy.as_ref()]
+    }
+
+    fn signals(&self, data: &[OHLCV], source: Source) -> Result<Vec<f64>> {
+        let signals = source.signals(self.strategy.as_ref(), data)?;
+        let mut sum = 0.0;
+
+        Ok((0..signals.len())
+            .map(|i| {
+                sum += signals[i];
+                if i >= self.period {
+                    sum -= signals[i - self.period];
+                }
+                let window = (i + 1).min(self.period) as f64;
+                if sum / window >= 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            })
+            .collect())
+    }
+}
+
+impl Strategy for Smooth {
+    forward_hooks!();
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "{} - long while long for half of the last {} bars",
+            self.strategy.description(),
+            self.period
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::{BacktestEngine, ExecutionModel};
+    use crate::data::{resample, AlignedTimeframe, SyntheticConfig, SyntheticModel};
+    use crate::strategies::{BuyAndHold, HigherTimeframeFilter, SMACrossover};
+
+    /// Fixed signal sequence for testing
+    struct Fixed(Vec<f64>);
+
+    impl Strategy for Fixed {
+        fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+            self.0[..data.len()].to_vec()
+        }
+
+        fn name(&self) -> &str {
+            "Fixed"
+        }
+    }
+
+    fn fixed(signals: &[f64]) -> Box<dyn Strategy> {
+        Box::new(Fixed(signals.to_vec()))
+    }
+
+    fn bars(n: usize) -> Vec<OHLCV> {
+        (0..n)
+            .map(|i| OHLCV::new(i as i64, 1.0, 1.0, 1.0, 1.0, 1.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_boolean_combinators() {
+        let a = [1.0, 1.0, 0.0, 0.0];
+        let b = [1.0, 0.0, 1.0, 0.0];
+        let data = bars(4);
+
+        let and = And::new(vec![fixed(&a), fixed(&b)]);
+        assert_eq!(and.generate_signals(&data), vec![1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(and.name(), "(Fixed AND Fixed)");
+
+        let or = Or::new(vec![fixed(&a), fixed(&b)]);
+        assert_eq!(or.generate_signals(&data), vec![1.0, 1.0, 1.0, 0.0]);
+
+        let not = Not::new(fixed(&a));
+        assert_eq!(not.generate_signals(&data), vec![0.0, 0.0, 1.0, 1.0]);
+
+        let invert = Invert::new(fixed(&[0.3, 1.0, 0.0, 0.5]));
+        assert_eq!(invert.generate_signals(&data), vec![0.7, 0.0, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn test_ensemble_and_vote() {
+        let data = bars(3);
+        let members = || {
+            vec![
+                fixed(&[1.0, 1.0, 0.0]),
+                fixed(&[1.0, 0.0, 0.0]),
+                fixed(&[0.0, 1.0, 1.0]),
+            ]
+        };
+
+        let vote = Ensemble::majority(members());
+        assert_eq!(vote.generate_signals(&data), vec![1.0, 1.0, 0.0]);
+        assert_eq!(vote.name(), "Vote(Fixed, Fixed, Fixed)");
+
+        let weighted = Ensemble::new(members().into_iter().zip([2.0, 1.0, 1.0]).collect());
+        assert_eq!(weighted.generate_signals(&data), vec![0.75, 0.75, 0.25]);
+        assert_eq!(weighted.name(), "Ensemble(Fixed x2, Fixed x1, Fixed x1)");
+    }
+
+    #[test]
+    fn test_filter_lag_and_smooth() {
+        let data = bars(6);
+
+        let filtered = Filter::new(fixed(&[1.0; 6]), fixed(&[0.0, 0.0, 1.0, 1.0, 0.0, 1.0]));
+        assert_eq!(
+            filtered.generate_signals(&data),
+            vec![0.0, 0.0, 1.0, 1.0, 0.0, 1.0]
+        );
+
+        let lagged = Lag::new(fixed(&[1.0, 0.0, 1.0, 1.0, 0.0, 0.0]), 2);
+        assert_eq!(
+            lagged.generate_signals(&data),
+            vec![0.0, 0.0, 1.0, 0.0, 1.0, 1.0]
+        );
+
+        // A one-bar dropout inside a long run is ignored
+        let smoothed = Smooth::new(fixed(&[1.0, 1.0, 0.0, 1.0, 0.0, 0.0]), 3);
+        assert_eq!(
+            smoothed.generate_signals(&data),
+            vec![1.0, 1.0, 1.0, 1.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_nested_combinators_are_strategies() {
+        let data = bars(10);
+        let trend_filtered: Box<dyn Strategy> = Box::new(Filter::new(
+            Box::new(SMACrossover::new(2, 5)),
+            Box::new(SMACrossover::new(1, 5)),
+        ));
+        let combo = Or::new(vec![
+            trend_filtered,
+            Box::new(Not::new(Box::new(BuyAndHold::new()))),
+        ]);
+
+        assert_eq!(combo.generate_signals(&data).len(), 10);
+        assert_eq!(combo.name(), "(SMA 2/5 [when SMA 1/5] OR NOT Buy and Hold)");
+    }
+
+    #[test]
+    fn test_combinators_forward_context_and_cache() {
+        const HOUR: i64 = 3_600_000;
+        let data: Vec<OHLCV> = SyntheticModel::garch()
+            .generate(&SyntheticConfig::new(24 * 120, 11))
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut bar)| {
+                bar.timestamp = i as i64 * HOUR;
+                bar
+            })
+            .collect();
+        let trend = |interval: &str, period| -> Box<dyn Strategy> {
+            Box::new(HigherTimeframeFilter::new(
+                Box::new(BuyAndHold::new()),
+                interval,
+                period,
+            ))
+        };
+
+        let wrapped = Filter::new(
+            trend("1d", 10),
+            Box::new(Ensemble::equal(vec![
+                trend("1d", 20),
+                Box::new(BuyAndHold::new()),
+            ])),
+        );
+        assert_eq!(wrapped.timeframes(), vec!["1d".to_string()]);
+        let union = Or::new(vec![trend("1d", 10), trend("4h", 10)]);
+        assert_eq!(union.timeframes(), vec!["1d".to_string(), "4h".to_string()]);
+
+        // The engine's context reaches the wrapped filters: flat daily closes
+        // never trade above their average
+        let flat: Vec<OHLCV> = resample(&data, 24 * HOUR)
+            .into_iter()
+            .map(|mut bar| {
+                bar.close = 100.0;
+                bar
+            })
+            .collect();
+        let mut context = TimeframeContext::new();
+        context.insert(AlignedTimeframe::new("1d", &data, HOUR, flat, 24 * HOUR));
+        let model = ExecutionModel::new(10.0, 5.0);
+        let resampled = BacktestEngine::new(&data, 100_000.0, model.clone()).run(&wrapped);
+        let with_context = BacktestEngine::new(&data, 100_000.0, model)
+            .with_timeframes(context)
+            .run(&wrapped);
+        assert!(resampled.total_trades > 0);
+        assert_eq!(with_context.total_trades, 0);
+
+        // Crossovers inside combinators read their averages from the cache
+        let cache = IndicatorCache::new(&data);
+        let nested = Not::new(Box::new(Lag::new(Box::new(SMACrossover::new(5, 20)), 1)));
+        assert_eq!(
+            nested.generate_signals_cached(&data, &cache).unwrap(),
+            nested.generate_signals(&data)
+        );
+        assert_eq!(cache.len(), 2);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::data::OHLCV;
+use crate::indicators::donchian;
+use crate::strategies::Strategy;
+
+/// Donchian Channel Breakout Strategy (turtle style)
+/// Buy when the close breaks above the highest high of the previous
+/// `entry_period` bars, sell when it breaks below the lowest low of the
+/// previous `exit_period` bars
+pub struct DonchianBreakout {
+    entry_period: usize,
+    exit_period: usize,
+    /// Display name including the parameters
+    name: String,
+}
+
+impl DonchianBreakout {
+    pub fn new(entry_period: usize, exit_period: usize) -> Self {
+        assert!(entry_period > 0, "Entry period must be greater than 0");
+        assert!(exit_period > 0, "Exit period must be greater than 0");
+
+        Self {
+            entry_period,
+            exit_period,
+            name: format!("Donchian {}/{}", entry_period, exit_period),
+        }
+    }
+}
+
+impl Strategy for DonchianBreakout {
+    fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+        let entry_channel = donchian(data, self.entry_period);
+        let exit_channel = donchian(data, self.exit_period);
+
+        let mut signals = Vec::with_capacity(data.len());
+        let mut current_position = 0.0;
+
+        for i in 0..data.len() {
+            // Channels end at the previous bar so the current close can break them
+            if i > 0 {
+                let close = data[i].close;
+                if close > entry_channel[i - 1].upper {
+                    current_position = 1.0;
+                } else if close < exit_channel[i - 1].lower {
+                    current_position = 0.0;
+                }
+            }
+            signals.push(current_position);
+        }
+
+        signals
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Donchian {}/{} breakout - Buy {}-bar highs, sell {}-bar lows",
+            self.entry_period, self.exit_period, self.entry_period, self.exit_period
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breakout_entry_and_exit() {
+        let closes = [10.0, 11.0, 10.5, 10.8, 12.0, 11.5, 11.8, 9.0, 9.5];
+        let data: Vec<OHLCV> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| OHLCV::new(i as i64, c, c, c, c, 1.0))
+            .collect();
+        let signals = DonchianBreakout::new(3, 2).generate_signals(&data);
+
+        // 12.0 breaks the 3-bar high of 11.0; 9.0 breaks the 2-bar low of 11.5
+        assert_eq!(signals, vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 0.0, 0.0]);
+    }
+}
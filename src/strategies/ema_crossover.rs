@@ -0,0 +1,78 @@
+use crate::data::OHLCV;
+use crate::indicators::ema;
+use crate::strategies::Strategy;
+
+/// Exponential Moving Average (EMA) Crossover Strategy
+/// Long while the fast EMA is above the slow EMA, flat otherwise
+pub struct EMACrossover {
+    fast_period: usize,
+    slow_period: usize,
+    /// Display name including the parameters
+    name: String,
+}
+
+impl EMACrossover {
+    pub fn new(fast_period: usize, slow_period: usize) -> Self {
+        assert!(
+            fast_period < slow_period,
+            "Fast period must be less than slow period"
+        );
+        assert!(fast_period > 0, "Fast period must be greater than 0");
+
+        Self {
+            fast_period,
+            slow_period,
+            name: format!("EMA {}/{}", fast_period, slow_period),
+        }
+    }
+}
+
+impl Strategy for EMACrossover {
+    fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+        let closes: Vec<f64> = data.iter().map(|d| d.close).collect();
+        let fast_ema = ema(&closes, self.fast_period);
+        let slow_ema = ema(&closes, self.slow_period);
+
+        fast_ema
+            .iter()
+            .zip(&slow_ema)
+            .map(|(fast, slow)| if fast > slow { 1.0 } else { 0.0 })
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "EMA {}/{} crossover - Long when fast > slow, flat otherwise",
+            self.fast_period, self.slow_period
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_follows_trend() {
+        let data: Vec<OHLCV> = (0..60)
+            .map(|i| {
+                // Up for 30 bars, then down
+                let price = if i < 30 {
+                    100.0 + i as f64
+                } else {
+                    160.0 - i as f64
+                };
+                OHLCV::new(i * 86_400_000, price, price, price, price, 1.0)
+            })
+            .collect();
+        let signals = EMACrossover::new(3, 10).generate_signals(&data);
+
+        assert!(signals[..9].iter().all(|&s| s == 0.0));
+        assert_eq!(signals[20], 1.0);
+        assert_eq!(signals[59], 0.0);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::backtest::{Fill, LimitOrder, OrderStrategy, Side};
+use anyhow::{bail, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridSpacing {
+    /// Equal price steps between levels
+    Arithmetic,
+    /// Equal percentage steps between levels
+    Geometric,
+}
+
+impl GridSpacing {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name {
+            "arithmetic" => Ok(GridSpacing::Arithmetic),
+            "geometric" => Ok(GridSpacing::Geometric),
+            _ => bail!(
+                "Unknown grid spacing '{}' (expected arithmetic or geometric)",
+                name
+            ),
+        }
+    }
+}
+
+/// Grid bot: resting buys below the price and sells above it, between
+/// `lower` and `upper`
+///
+/// Every filled buy places a sell one level up and every filled sell a buy
+/// one level down, so each up-and-down move between neighbouring levels
+/// earns the spacing. The level nearest the starting price is left empty,
+/// and the sells above it start from inventory bought at market. Each buy
+/// spends `order_size` in quote currency.
+#[derive(Debug, Clone)]
+pub struct GridStrategy {
+    prices: Vec<f64>,
+    spacing: GridSpacing,
+    order_size: f64,
+}
+
+impl GridStrategy {
+    pub fn new(
+        lower: f64,
+        upper: f64,
+        levels: usize,
+        spacing: GridSpacing,
+        order_size: f64,
+    ) -> Self {
+        assert!(
+            0.0 < lower && lower < upper,
+            "Grid bounds must satisfy 0 < lower < upper"
+        );
+        assert!(levels >= 2, "A grid needs at least 2 levels");
+        assert!(order_size > 0.0, "Order size must be greater than 0");
+
+        let steps = (levels - 1) as f64;
+        let prices = (0..levels)
+            .map(|i| {
+                let t = i as f64 / steps;
+                match spacing {
+                    GridSpacing::Arithmetic => lower + (upper - lower) * t,
+                    GridSpacing::Geometric => lower * (upper / lower).powf(t),
+                }
+            })
+            .collect();
+
+        Self {
+            prices,
+            spacing,
+            order_size,
+        }
+    }
+
+    /// Level prices, lowest first
+    pub fn prices(&self) -> &[f64] {
+        &self.prices
+    }
+
+    /// Quantity bought at `level`, and sold one level above
+    fn quantity(&self, level: usize) -> f64 {
+        self.order_size / self.prices[level]
+    }
+
+    fn nearest_level(&self, price: f64) -> usize {
+        (0..self.prices.len())
+            .min_by(|&a, &b| {
+                (self.prices[a] - price)
+                    .abs()
+                    .total_cmp(&(self.prices[b] - price).abs())
+            })
+            .unwrap()
+    }
+}
+
+impl OrderStrategy for GridStrategy {
+    fn name(&self) -> String {
+        let spacing = match self.spacing {
+            GridSpacing::Arithmetic => "arithmetic",
+            GridSpacing::Geometric => "geometric",
+        };
+        format!(
+            "Grid {:.2}-{:.2} ({} {} levels, ${:.2} per level)",
+            self.prices[0],
+            self.prices[self.prices.len() - 1],
+            self.prices.len(),
+            spacing,
+            self.order_size
+        )
+    }
+
+    fn initial_inventory(&self, price: f64) -> f64 {
+        let start = self.nearest_level(price);
+        (start + 1..self.prices.len())
+            .map(|level| self.quantity(level - 1))
+            .sum()
+    }
+
+    fn initial_orders(&self, price: f64) -> Vec<LimitOrder> {
+        let start = self.nearest_level(price);
+        let buys = (0..start)
+            .map(|level| LimitOrder::buy(level, self.prices[level], self.quantity(level)));
+        let sells = (start + 1..self.prices.len())
+            .map(|level| LimitOrder::sell(level, self.prices[level], self.quantity(level - 1)));
+        buys.chain(sells).collect()
+    }
+
+    fn on_fill(&self, fill: &Fill) -> Vec<LimitOrder> {
+        let order = &fill.order;
+        match order.side {
+            Side::Buy if order.level + 1 < self.prices.len() => {
+                let level = order.level + 1;
+                vec![LimitOrder::sell(level, self.prices[level], order.quantity)
+                    .with_cost(fill.price)]
+            }
+            Side::Sell if order.level > 0 => {
+                let level = order.level - 1;
+                vec![LimitOrder::buy(
+                    level,
+                    self.prices[level],
+                    self.quantity(level),
+                )]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::{BacktestEngine, ExecutionModel};
+    use crate::data::OHLCV;
+
+    #[test]
+    fn test_level_spacing() {
+        let arithmetic = GridStrategy::new(90.0, 110.0, 5, GridSpacing::Arithmetic, 1000.0);
+        assert_eq!(arithmetic.prices(), &[90.0, 95.0, 100.0, 105.0, 110.0]);
+
+        let geometric = GridStrategy::new(100.0, 400.0, 3, GridSpacing::Geometric, 1000.0);
+        assert!((geometric.prices()[1] - 200.0).abs() < 1e-9);
+
+        // Starting at 100: buys at 90 and 95, sells at 105 and 110
+        let orders = arithmetic.initial_orders(100.0);
+        let levels: Vec<(usize, Side)> = orders.iter().map(|o| (o.level, o.side)).collect();
+        assert_eq!(
+            levels,
+            vec![
+                (0, Side::Buy),
+                (1, Side::Buy),
+                (3, Side::Sell),
+                (4, Side::Sell)
+            ]
+        );
+        let inventory = 1000.0 / 100.0 + 1000.0 / 105.0;
+        assert!((arithmetic.initial_inventory(100.0) - inventory).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_round_trips_realize_the_spacing() {
+        let grid = GridStrategy::new(90.0, 110.0, 5, GridSpacing::Arithmetic, 1000.0);
+        let bar = |i: i64, open: f64, high: f64, low: f64, close: f64| {
+            OHLCV::new(i * 86_400_000, open, high, low, close, 1.0)
+        };
+        // Start at 100, dip to 95 and recover to 100, three times
+        let mut data = vec![bar(0, 100.0, 100.0, 100.0, 100.0)];
+        for i in 0..3 {
+            data.push(bar(2 * i + 1, 100.0, 100.0, 94.0, 95.0));
+            data.push(bar(2 * i + 2, 95.0, 101.0, 95.0, 100.0));
+        }
+
+        let result =
+            BacktestEngine::new(data, 10_000.0, ExecutionModel::new(0.0, 0.0)).run_orders(&grid);
+        let stats = result.order_stats.unwrap();
+
+        assert_eq!(stats.buy_fills, 3);
+        assert_eq!(stats.sell_fills, 3);
+        assert!((stats.realized_profit - 3.0 * 1000.0 / 95.0 * 5.0).abs() < 1e-9);
+        assert_eq!(stats.levels[1].buys, 3);
+        assert_eq!(stats.levels[2].sells, 3);
+        assert_eq!(stats.levels[2].price, 100.0);
+
+        let base = 1000.0 / 100.0 + 1000.0 / 105.0;
+        assert!((stats.min_inventory - base).abs() < 1e-9);
+        assert!((stats.max_inventory - (base + 1000.0 / 95.0)).abs() < 1e-9);
+        assert!((result.final_equity - (10_000.0 + stats.realized_profit)).abs() < 1e-6);
+    }
+}
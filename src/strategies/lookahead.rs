@@ -0,0 +1,169 @@
+//! Detection of look-ahead bias in strategies
+//!
+//! A strategy without look-ahead produces the same signal for bar `i`
+//! whether or not later bars exist. The checker reruns the strategy on
+//! prefixes of the data and compares the last signal of each prefix with
+//! the full run.
+
+use crate::data::OHLCV;
+use crate::strategies::Strategy;
+use rayon::prelude::*;
+use serde::Serialize;
+
+/// Signals closer than this are treated as equal
+const TOLERANCE: f64 = 1e-9;
+
+/// A bar whose signal changes once later bars are available
+#[derive(Debug, Clone, Serialize)]
+pub struct LookaheadViolation {
+    pub index: usize,
+    pub timestamp: i64,
+    /// Signal at `index` when the data ends at that bar
+    pub prefix_signal: f64,
+    /// Signal at `index` with the full data
+    pub full_signal: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LookaheadReport {
+    pub strategy: String,
+    pub total_bars: usize,
+    pub bars_checked: usize,
+    /// Earliest checked bar that disagrees with the full run
+    pub first_violation: Option<LookaheadViolation>,
+}
+
+impl LookaheadReport {
+    pub fn passed(&self) -> bool {
+        self.first_violation.is_none()
+    }
+}
+
+/// Prefix-rerun checker
+///
+/// Checking every bar costs one strategy run per bar; `with_step` checks
+/// every n-th bar instead and `with_start` skips the first bars.
+#[derive(Debug, Clone)]
+pub struct LookaheadCheck {
+    start: usize,
+    step: usize,
+}
+
+impl Default for LookaheadCheck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LookaheadCheck {
+    pub fn new() -> Self {
+        Self { start: 0, step: 1 }
+    }
+
+    /// First bar index to check
+    pub fn with_start(mut self, start: usize) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// Check every `step`-th bar
+    pub fn with_step(mut self, step: usize) -> Self {
+        assert!(step > 0, "Step must be greater than 0");
+        self.step = step;
+        self
+    }
+
+    pub fn run(&self, strategy: &dyn Strategy, data: &[OHLCV]) -> LookaheadReport {
+        let full = strategy.generate_signals(data);
+        // The last bar has no later bars to peek at, so only earlier ones count
+        let indices: Vec<usize> = (self.start..data.len().saturating_sub(1))
+            .step_by(self.step)
+            .collect();
+
+        let first_violation = indices.par_iter().find_map_first(|&index| {
+            let prefix = strategy.generate_signals(&data[..=index]);
+            let prefix_signal = prefix.last().copied().unwrap_or(f64::NAN);
+            let full_signal = full[index];
+
+            if same_signal(prefix_signal, full_signal) {
+                None
+            } else {
+                Some(LookaheadViolation {
+                    index,
+                    timestamp: data[index].timestamp,
+                    prefix_signal,
+                    full_signal,
+                })
+            }
+        });
+
+        LookaheadReport {
+            strategy: strategy.name().to_string(),
+            total_bars: data.len(),
+            bars_checked: indices.len(),
+            first_violation,
+        }
+    }
+}
+
+fn same_signal(a: f64, b: f64) -> bool {
+    (a.is_nan() && b.is_nan()) || (a - b).abs() <= TOLERANCE
+}
+
+/// Check every bar of `data` for look-ahead bias
+pub fn check_lookahead(strategy: &dyn Strategy, data: &[OHLCV]) -> LookaheadReport {
+    LookaheadCheck::new().run(strategy, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::test_support::sample_bars;
+    use crate::strategies::{Params, StrategyRegistry};
+
+    /// Long when the next bar closes higher
+    struct Peeking;
+
+    impl Strategy for Peeking {
+        fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+            (0..data.len())
+                .map(|i| match data.get(i + 1) {
+                    Some(next) if next.close > data[i].close => 1.0,
+                    _ => 0.0,
+                })
+                .collect()
+        }
+
+        fn name(&self) -> &str {
+            "Peeking"
+        }
+    }
+
+    #[test]
+    fn test_detects_future_peeking() {
+        let data = sample_bars();
+        let report = check_lookahead(&Peeking, &data);
+        let violation = report.first_violation.unwrap();
+
+        let expected = (0..data.len() - 1)
+            .find(|&i| data[i + 1].close > data[i].close)
+            .unwrap();
+        assert_eq!(violation.index, expected);
+        assert_eq!(violation.prefix_signal, 0.0);
+        assert_eq!(violation.full_signal, 1.0);
+    }
+
+    #[test]
+    fn test_builtin_strategies_pass() {
+        let data = sample_bars();
+        let registry = StrategyRegistry::builtin();
+        for name in registry.names() {
+            let strategy = registry.build(name, &Params::new()).unwrap();
+            let report = LookaheadCheck::new()
+                .with_step(7)
+                .run(strategy.as_ref(), &data);
+            assert!(report.passed(), "{}: {:?}", name, report.first_violation);
+            assert_eq!(report.bars_checked, 72);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::data::OHLCV;
+use crate::indicators::macd;
+use crate::strategies::Strategy;
+
+/// MACD Signal-Line Crossover Strategy
+/// Long while the MACD line is above its signal line, flat otherwise
+pub struct MACDCrossover {
+    fast_period: usize,
+    slow_period: usize,
+    signal_period: usize,
+    /// Display name including the parameters
+    name: String,
+}
+
+impl MACDCrossover {
+    pub fn new(fast_period: usize, slow_period: usize, signal_period: usize) -> Self {
+        assert!(
+            fast_period < slow_period,
+            "Fast period must be less than slow period"
+        );
+        assert!(fast_period > 0, "Fast period must be greater than 0");
+        assert!(signal_period > 0, "Signal period must be greater than 0");
+
+        Self {
+            fast_period,
+            slow_period,
+            signal_period,
+            name: format!("MACD {}/{}/{}", fast_period, slow_period, signal_period),
+        }
+    }
+}
+
+impl Strategy for MACDCrossover {
+    fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+        let closes: Vec<f64> = data.iter().map(|d| d.close).collect();
+
+        macd(
+            &closes,
+            self.fast_period,
+            self.slow_period,
+            self.signal_period,
+        )
+        .iter()
+        .map(|m| if m.histogram > 0.0 { 1.0 } else { 0.0 })
+        .collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "MACD {}/{}/{} - Long when MACD > signal line, flat otherwise",
+            self.fast_period, self.slow_period, self.signal_period
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_while_momentum_accelerates() {
+        let data: Vec<OHLCV> = (0..30)
+            .map(|i| {
+                // Accelerating rally for 18 bars, then a steady selloff
+                let price = if i < 18 {
+                    100.0 + 0.5 * (i * i) as f64
+                } else {
+                    262.0 - 15.0 * (i - 18) as f64
+                };
+                OHLCV::new(i * 86_400_000, price, price, price, price, 1.0)
+            })
+            .collect();
+        let signals = MACDCrossover::new(3, 6, 3).generate_signals(&data);
+
+        // The signal line needs 6 + 3 - 1 bars before the first reading
+        assert!(signals[..7].iter().all(|&s| s == 0.0));
+        assert!(signals[7..19].iter().all(|&s| s == 1.0));
+        assert!(signals[19..].iter().all(|&s| s == 0.0));
+    }
+}
@@ -1,7 +1,36 @@
+pub mod bollinger_reversion;
 pub mod buy_and_hold;
+pub mod combinators;
+pub mod donchian_breakout;
+pub mod ema_crossover;
+pub mod grid;
+pub mod lookahead;
+pub mod macd_crossover;
+pub mod multi_lookback_momentum;
+pub mod params;
+pub mod registry;
+pub mod rsi_reversion;
+pub mod rules;
+pub mod script;
 pub mod sma_crossover;
+pub mod timeframe_filter;
 mod r#trait;
 
+pub use bollinger_reversion::BollingerReversion;
 pub use buy_and_hold::BuyAndHold;
+pub use combinators::{And, Ensemble, Filter, Invert, Lag, Not, Or, Smooth};
+pub use donchian_breakout::DonchianBreakout;
+pub use ema_crossover::EMACrossover;
+pub use grid::{GridSpacing, GridStrategy};
+pub use lookahead::{check_lookahead, LookaheadCheck, LookaheadReport, LookaheadViolation};
+pub use macd_crossover::MACDCrossover;
+pub use multi_lookback_momentum::MultiLookbackMomentum;
+pub use params::{Constraint, ParamGrid, ParamKind, ParamSpec, Params};
+pub(crate) use r#trait::signals_or_flat;
 pub use r#trait::Strategy;
+pub use registry::{StrategyDefinition, StrategyRegistry};
+pub use rsi_reversion::RSIMeanReversion;
+pub use rules::RuleStrategy;
+pub use script::{Script, ScriptStrategy};
 pub use sma_crossover::SMACrossover;
+pub use timeframe_filter::HigherTimeframeFilter;
@@ -0,0 +1,87 @@
+use crate::data::OHLCV;
+use crate::strategies::Strategy;
+
+/// Multi-Lookback Momentum Strategy
+/// Long only while the trailing return is positive over both a long and a
+/// short lookback; the short window exits faster when a trend rolls over.
+/// Absolute momentum only: there is no relative leg against another asset
+pub struct MultiLookbackMomentum {
+    lookback: usize,
+    short_lookback: usize,
+    /// Display name including the parameters
+    name: String,
+}
+
+impl MultiLookbackMomentum {
+    pub fn new(lookback: usize, short_lookback: usize) -> Self {
+        assert!(
+            short_lookback < lookback,
+            "Short lookback must be less than lookback"
+        );
+        assert!(short_lookback > 0, "Short lookback must be greater than 0");
+
+        Self {
+            lookback,
+            short_lookback,
+            name: format!("Momentum {}/{}", lookback, short_lookback),
+        }
+    }
+}
+
+impl Strategy for MultiLookbackMomentum {
+    fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+        (0..data.len())
+            .map(|i| {
+                if i < self.lookback {
+                    return 0.0;
+                }
+                let close = data[i].close;
+                let long_momentum = close / data[i - self.lookback].close - 1.0;
+                let short_momentum = close / data[i - self.short_lookback].close - 1.0;
+
+                if long_momentum > 0.0 && short_momentum > 0.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Momentum {}/{} - Long when both trailing returns are positive",
+            self.lookback, self.short_lookback
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_lookback_exits_first() {
+        let closes = [
+            100.0, 101.0, 102.0, 103.0, 104.0, 105.0, 106.0, 107.0, 108.0, 109.0, 107.5, 106.0,
+            110.0,
+        ];
+        let data: Vec<OHLCV> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| OHLCV::new(i as i64, c, c, c, c, 1.0))
+            .collect();
+        let signals = MultiLookbackMomentum::new(5, 2).generate_signals(&data);
+
+        // Flat until the 5-bar lookback exists; the dip turns the 2-bar return
+        // negative while the 5-bar return is still positive
+        assert_eq!(
+            signals,
+            vec![0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 1.0]
+        );
+    }
+}
@@ -0,0 +1,338 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParamKind {
+    /// Whole number, e.g. a period in bars
+    Integer,
+    Float,
+}
+
+/// Declared parameter of a strategy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParamSpec {
+    pub name: String,
+    pub kind: ParamKind,
+    pub default: f64,
+    pub min: f64,
+    pub max: f64,
+    pub description: String,
+    /// Default optimizer range as (start, end, step)
+    pub sweep: Option<(f64, f64, f64)>,
+}
+
+impl ParamSpec {
+    pub fn integer(name: &str, default: usize, min: usize, max: usize) -> Self {
+        Self::new(
+            name,
+            ParamKind::Integer,
+            default as f64,
+            min as f64,
+            max as f64,
+        )
+    }
+
+    /// Window length in bars, with no upper bound
+    pub fn period(name: &str, default: usize, min: usize) -> Self {
+        Self::new(
+            name,
+            ParamKind::Integer,
+            default as f64,
+            min as f64,
+            f64::INFINITY,
+        )
+    }
+
+    pub fn float(name: &str, default: f64, min: f64, max: f64) -> Self {
+        Self::new(name, ParamKind::Float, default, min, max)
+    }
+
+    fn new(name: &str, kind: ParamKind, default: f64, min: f64, max: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            default,
+            min,
+            max,
+            description: String::new(),
+            sweep: None,
+        }
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_string();
+        self
+    }
+
+    pub fn with_sweep(mut self, start: f64, end: f64, step: f64) -> Self {
+        self.sweep = Some((start, end, step));
+        self
+    }
+
+    pub fn validate(&self, value: f64) -> Result<()> {
+        if !value.is_finite() {
+            bail!("{} must be a finite number", self.name);
+        }
+        if self.kind == ParamKind::Integer && value.fract() != 0.0 {
+            bail!("{} must be a whole number, got {}", self.name, value);
+        }
+        if self.max == f64::INFINITY && value < self.min {
+            bail!("{} must be at least {}, got {}", self.name, self.min, value);
+        }
+        if value < self.min || value > self.max {
+            bail!(
+                "{} must be between {} and {}, got {}",
+                self.name,
+                self.min,
+                self.max,
+                value
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Relationship that must hold between two parameters
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Constraint {
+    /// First parameter must be strictly less than the second (e.g. fast < slow)
+    LessThan(String, String),
+    /// First parameter must not exceed the second (e.g. exit_below <= enter_above)
+    AtMost(String, String),
+}
+
+impl Constraint {
+    pub fn less_than(lower: &str, upper: &str) -> Self {
+        Constraint::LessThan(lower.to_string(), upper.to_string())
+    }
+
+    pub fn at_most(lower: &str, upper: &str) -> Self {
+        Constraint::AtMost(lower.to_string(), upper.to_string())
+    }
+
+    pub fn check(&self, params: &Params) -> Result<()> {
+        match self {
+            Constraint::LessThan(lower, upper) => {
+                let (a, b) = (params.get(lower), params.get(upper));
+                if a >= b {
+                    bail!("{} must be less than {} ({} >= {})", lower, upper, a, b);
+                }
+            }
+            Constraint::AtMost(lower, upper) => {
+                let (a, b) = (params.get(lower), params.get(upper));
+                if a > b {
+                    bail!("{} must not exceed {} ({} > {})", lower, upper, a, b);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Constraint::LessThan(lower, upper) => write!(f, "{} < {}", lower, upper),
+            Constraint::AtMost(lower, upper) => write!(f, "{} <= {}", lower, upper),
+        }
+    }
+}
+
+/// Named parameter values for a strategy
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Params(BTreeMap<String, f64>);
+
+impl Params {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: &str, value: f64) -> Self {
+        self.set(name, value);
+        self
+    }
+
+    pub fn set(&mut self, name: &str, value: f64) {
+        self.0.insert(name.to_string(), value);
+    }
+
+    /// Value of a parameter; resolved params always contain every declared name
+    pub fn get(&self, name: &str) -> f64 {
+        self.0.get(name).copied().unwrap_or(f64::NAN)
+    }
+
+    pub fn get_usize(&self, name: &str) -> usize {
+        self.get(name) as usize
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), *v))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Parse `name=value` assignments
+    pub fn parse<S: AsRef<str>>(assignments: &[S]) -> Result<Self> {
+        let mut params = Self::new();
+        for assignment in assignments {
+            let assignment = assignment.as_ref();
+            let (name, value) = assignment
+                .split_once('=')
+                .with_context(|| format!("Expected name=value, got '{}'", assignment))?;
+            let value: f64 = value
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid value for {}: '{}'", name.trim(), value))?;
+            params.set(name.trim(), value);
+        }
+        Ok(params)
+    }
+}
+
+impl fmt::Display for Params {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts: Vec<String> = self.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// Candidate values per parameter for an optimizer sweep
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ParamGrid(BTreeMap<String, Vec<f64>>);
+
+impl ParamGrid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_values(mut self, name: &str, values: Vec<f64>) -> Self {
+        self.set_values(name, values);
+        self
+    }
+
+    /// Inclusive range `start..=end` in increments of `step`
+    pub fn with_range(self, name: &str, start: f64, end: f64, step: f64) -> Self {
+        self.with_values(name, range_values(start, end, step))
+    }
+
+    pub fn set_values(&mut self, name: &str, values: Vec<f64>) {
+        self.0.insert(name.to_string(), values);
+    }
+
+    pub fn values(&self, name: &str) -> Option<&[f64]> {
+        self.0.get(name).map(|v| v.as_slice())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(|k| k.as_str())
+    }
+
+    /// Parse `name=start:end:step`, `name=start:end` (step 1) or `name=value`
+    pub fn parse<S: AsRef<str>>(specs: &[S]) -> Result<Self> {
+        let mut grid = Self::new();
+        for spec in specs {
+            let spec = spec.as_ref();
+            let (name, range) = spec
+                .split_once('=')
+                .with_context(|| format!("Expected name=start:end:step, got '{}'", spec))?;
+            let parts = range
+                .split(':')
+                .map(|p| p.trim().parse::<f64>())
+                .collect::<Result<Vec<_>, _>>()
+                .with_context(|| format!("Invalid range for {}: '{}'", name.trim(), range))?;
+            let values = match parts.as_slice() {
+                [value] => vec![*value],
+                [start, end] => range_values(*start, *end, 1.0),
+                [start, end, step] if *step > 0.0 => range_values(*start, *end, *step),
+                _ => bail!("Invalid range for {}: '{}'", name.trim(), range),
+            };
+            grid.set_values(name.trim(), values);
+        }
+        Ok(grid)
+    }
+}
+
+fn range_values(start: f64, end: f64, step: f64) -> Vec<f64> {
+    let mut values = Vec::new();
+    if step <= 0.0 {
+        return values;
+    }
+    let mut i = 0;
+    loop {
+        let value = start + i as f64 * step;
+        // Tolerance so float steps like 0.1 still reach the end point
+        if value > end + step * 1e-9 {
+            break;
+        }
+        values.push(value);
+        i += 1;
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_params_and_grid() {
+        let params = Params::parse(&["fast=20", " slow = 50 "]).unwrap();
+        assert_eq!(params.get_usize("fast"), 20);
+        assert_eq!(params.get("slow"), 50.0);
+        assert_eq!(params.to_string(), "fast=20 slow=50");
+        assert!(Params::parse(&["fast"]).is_err());
+        assert!(Params::parse(&["fast=abc"]).is_err());
+
+        let grid = ParamGrid::parse(&["fast=10:30:10", "std_dev=1.5:2.5:0.5", "signal=9"]).unwrap();
+        assert_eq!(grid.values("fast").unwrap(), &[10.0, 20.0, 30.0]);
+        assert_eq!(grid.values("std_dev").unwrap(), &[1.5, 2.0, 2.5]);
+        assert_eq!(grid.values("signal").unwrap(), &[9.0]);
+        assert!(ParamGrid::parse(&["fast=10:30:0"]).is_err());
+    }
+
+    #[test]
+    fn test_spec_validation() {
+        let spec = ParamSpec::integer("period", 14, 2, 100);
+        assert!(spec.validate(14.0).is_ok());
+        assert!(spec.validate(14.5).is_err());
+        assert!(spec.validate(1.0).is_err());
+
+        let period = ParamSpec::period("slow", 200, 2);
+        assert!(period.validate(100_000.0).is_ok());
+        assert!(period.validate(1.0).is_err());
+        assert!(period.validate(f64::INFINITY).is_err());
+
+        let constraint = Constraint::less_than("fast", "slow");
+        assert!(constraint
+            .check(&Params::new().with("fast", 10.0).with("slow", 20.0))
+            .is_ok());
+        assert!(constraint
+            .check(&Params::new().with("fast", 20.0).with("slow", 20.0))
+            .is_err());
+
+        let constraint = Constraint::at_most("exit_below", "enter_above");
+        assert!(constraint
+            .check(
+                &Params::new()
+                    .with("exit_below", 0.5)
+                    .with("enter_above", 0.5)
+            )
+            .is_ok());
+        assert!(constraint
+            .check(
+                &Params::new()
+                    .with("exit_below", 0.6)
+                    .with("enter_above", 0.5)
+            )
+            .is_err());
+    }
+}
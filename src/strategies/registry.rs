@@ -0,0 +1,417 @@
+use crate::strategies::params::{Constraint, ParamGrid, ParamSpec, Params};
+use crate::strategies::{
+    BollingerReversion, BuyAndHold, DonchianBreakout, EMACrossover, MACDCrossover,
+    MultiLookbackMomentum, RSIMeanReversion, SMACrossover, Strategy,
+};
+use anyhow::{bail, Result};
+use std::sync::Arc;
+
+type Constructor = Arc<dyn Fn(&Params) -> Result<Box<dyn Strategy>> + Send + Sync>;
+
+/// A named strategy with its parameter schema and constructor
+#[derive(Clone)]
+pub struct StrategyDefinition {
+    pub name: String,
+    pub description: String,
+    pub params: Vec<ParamSpec>,
+    pub constraints: Vec<Constraint>,
+    constructor: Constructor,
+}
+
+impl StrategyDefinition {
+    /// `constructor` receives resolved params: every declared name present,
+    /// within bounds and satisfying the constraints
+    pub fn new<F>(name: &str, description: &str, constructor: F) -> Self
+    where
+        F: Fn(&Params) -> Result<Box<dyn Strategy>> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            params: Vec::new(),
+            constraints: Vec::new(),
+            constructor: Arc::new(constructor),
+        }
+    }
+
+    pub fn with_param(mut self, spec: ParamSpec) -> Self {
+        self.params.push(spec);
+        self
+    }
+
+    pub fn with_constraint(mut self, constraint: Constraint) -> Self {
+        self.constraints.push(constraint);
+        self
+    }
+
+    pub fn param(&self, name: &str) -> Option<&ParamSpec> {
+        self.params.iter().find(|p| p.name == name)
+    }
+
+    pub fn default_params(&self) -> Params {
+        self.params
+            .iter()
+            .fold(Params::new(), |p, spec| p.with(&spec.name, spec.default))
+    }
+
+    /// Fill in defaults for missing params and validate the result
+    pub fn resolve(&self, overrides: &Params) -> Result<Params> {
+        for (name, _) in overrides.iter() {
+            if self.param(name).is_none() {
+                bail!(
+                    "Unknown parameter '{}' for {} (expected: {})",
+                    name,
+                    self.name,
+                    self.param_names()
+                );
+            }
+        }
+
+        let mut params = self.default_params();
+        for (name, value) in overrides.iter() {
+            params.set(name, value);
+        }
+        for spec in &self.params {
+            spec.validate(params.get(&spec.name))?;
+        }
+        for constraint in &self.constraints {
+            constraint.check(&params)?;
+        }
+        Ok(params)
+    }
+
+    pub fn build(&self, overrides: &Params) -> Result<Box<dyn Strategy>> {
+        let params = self.resolve(overrides)?;
+        (self.constructor)(&params)
+    }
+
+    /// Name plus parameter values in schema order, e.g. `sma_50_200`
+    pub fn file_stem(&self, params: &Params) -> String {
+        let mut stem = self.name.replace('-', "_");
+        for spec in &self.params {
+            stem.push_str(&format!("_{}", params.get(&spec.name)));
+        }
+        stem
+    }
+
+    /// Grid from each parameter's default sweep range
+    pub fn default_grid(&self) -> ParamGrid {
+        self.params
+            .iter()
+            .fold(ParamGrid::new(), |grid, spec| match spec.sweep {
+                Some((start, end, step)) => grid.with_range(&spec.name, start, end, step),
+                None => grid,
+            })
+    }
+
+    /// Every combination of grid values that satisfies the constraints;
+    /// parameters missing from the grid stay at their defaults
+    pub fn grid_combinations(&self, grid: &ParamGrid) -> Result<Vec<Params>> {
+        for name in grid.names() {
+            if self.param(name).is_none() {
+                bail!(
+                    "Unknown parameter '{}' for {} (expected: {})",
+                    name,
+                    self.name,
+                    self.param_names()
+                );
+            }
+        }
+
+        let mut combinations = vec![Params::new()];
+        for spec in &self.params {
+            let values = match grid.values(&spec.name) {
+                Some(values) => values.to_vec(),
+                None => vec![spec.default],
+            };
+            for value in &values {
+                spec.validate(*value)?;
+            }
+
+            combinations = combinations
+                .iter()
+                .flat_map(|params| values.iter().map(|v| params.clone().with(&spec.name, *v)))
+                .collect();
+        }
+
+        Ok(combinations
+            .into_iter()
+            .filter(|params| self.constraints.iter().all(|c| c.check(params).is_ok()))
+            .collect())
+    }
+
+    fn param_names(&self) -> String {
+        if self.params.is_empty() {
+            return "none".to_string();
+        }
+        self.params
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Lookup of strategies by name
+#[derive(Clone, Default)]
+pub struct StrategyRegistry {
+    definitions: Vec<StrategyDefinition>,
+}
+
+impl StrategyRegistry {
+    /// Empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with all strategies shipped in this crate
+    pub fn builtin() -> Self {
+        Self {
+            definitions: builtin_definitions(),
+        }
+    }
+
+    pub fn register(&mut self, definition: StrategyDefinition) -> Result<()> {
+        if self.definitions.iter().any(|d| d.name == definition.name) {
+            bail!("Strategy '{}' is already registered", definition.name);
+        }
+        self.definitions.push(definition);
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Result<&StrategyDefinition> {
+        match self.definitions.iter().find(|d| d.name == name) {
+            Some(d) => Ok(d),
+            None => bail!(
+                "Unknown strategy: {} (available: {})",
+                name,
+                self.names().join(", ")
+            ),
+        }
+    }
+
+    pub fn definitions(&self) -> &[StrategyDefinition] {
+        &self.definitions
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.definitions.iter().map(|d| d.name.as_str()).collect()
+    }
+
+    pub fn build(&self, name: &str, overrides: &Params) -> Result<Box<dyn Strategy>> {
+        self.get(name)?.build(overrides)
+    }
+}
+
+fn builtin_definitions() -> Vec<StrategyDefinition> {
+    vec![
+        StrategyDefinition::new("buy-and-hold", "Always long (benchmark)", |_| {
+            Ok(Box::new(BuyAndHold::new()))
+        }),
+        StrategyDefinition::new("sma", "Long while fast SMA > slow SMA", |p| {
+            Ok(Box::new(SMACrossover::new(
+                p.get_usize("fast"),
+                p.get_usize("slow"),
+            )))
+        })
+        .with_param(
+            ParamSpec::period("fast", 50, 1)
+                .with_description("Fast SMA period")
+                .with_sweep(20.0, 100.0, 10.0),
+        )
+        .with_param(
+            ParamSpec::period("slow", 200, 2)
+                .with_description("Slow SMA period")
+                .with_sweep(50.0, 200.0, 10.0),
+        )
+        .with_constraint(Constraint::less_than("fast", "slow")),
+        StrategyDefinition::new("ema", "Long while fast EMA > slow EMA", |p| {
+            Ok(Box::new(EMACrossover::new(
+                p.get_usize("fast"),
+                p.get_usize("slow"),
+            )))
+        })
+        .with_param(
+            ParamSpec::period("fast", 12, 1)
+                .with_description("Fast EMA period")
+                .with_sweep(5.0, 50.0, 5.0),
+        )
+        .with_param(
+            ParamSpec::period("slow", 26, 2)
+                .with_description("Slow EMA period")
+                .with_sweep(20.0, 100.0, 10.0),
+        )
+        .with_constraint(Constraint::less_than("fast", "slow")),
+        StrategyDefinition::new(
+            "rsi",
+            "Buy when RSI < oversold, sell when RSI > overbought",
+            |p| {
+                Ok(Box::new(RSIMeanReversion::new(
+                    p.get_usize("period"),
+                    p.get("oversold"),
+                    p.get("overbought"),
+                )))
+            },
+        )
+        .with_param(
+            ParamSpec::period("period", 14, 2)
+                .with_description("RSI period")
+                .with_sweep(7.0, 21.0, 7.0),
+        )
+        .with_param(
+            ParamSpec::float("oversold", 30.0, 0.0, 100.0)
+                .with_description("RSI level to buy below")
+                .with_sweep(20.0, 35.0, 5.0),
+        )
+        .with_param(
+            ParamSpec::float("overbought", 70.0, 0.0, 100.0)
+                .with_description("RSI level to sell above")
+                .with_sweep(65.0, 80.0, 5.0),
+        )
+        .with_constraint(Constraint::less_than("oversold", "overbought")),
+        StrategyDefinition::new(
+            "donchian",
+            "Turtle breakout: buy entry-bar highs, sell exit-bar lows",
+            |p| {
+                Ok(Box::new(DonchianBreakout::new(
+                    p.get_usize("entry"),
+                    p.get_usize("exit"),
+                )))
+            },
+        )
+        .with_param(
+            ParamSpec::period("entry", 20, 1)
+                .with_description("Breakout period for entries")
+                .with_sweep(10.0, 55.0, 5.0),
+        )
+        .with_param(
+            ParamSpec::period("exit", 10, 1)
+                .with_description("Breakout period for exits")
+                .with_sweep(5.0, 20.0, 5.0),
+        )
+        .with_constraint(Constraint::less_than("exit", "entry")),
+        StrategyDefinition::new("macd", "Long while MACD line > signal line", |p| {
+            Ok(Box::new(MACDCrossover::new(
+                p.get_usize("fast"),
+                p.get_usize("slow"),
+                p.get_usize("signal"),
+            )))
+        })
+        .with_param(
+            ParamSpec::period("fast", 12, 1)
+                .with_description("Fast EMA period")
+                .with_sweep(8.0, 16.0, 2.0),
+        )
+        .with_param(
+            ParamSpec::period("slow", 26, 2)
+                .with_description("Slow EMA period")
+                .with_sweep(20.0, 32.0, 4.0),
+        )
+        .with_param(
+            ParamSpec::period("signal", 9, 1)
+                .with_description("Signal line EMA period")
+                .with_sweep(6.0, 12.0, 3.0),
+        )
+        .with_constraint(Constraint::less_than("fast", "slow")),
+        StrategyDefinition::new(
+            "bollinger",
+            "Buy below the lower band, sell at the middle band",
+            |p| {
+                Ok(Box::new(BollingerReversion::new(
+                    p.get_usize("period"),
+                    p.get("std_dev"),
+                )))
+            },
+        )
+        .with_param(
+            ParamSpec::period("period", 20, 2)
+                .with_description("Moving average period")
+                .with_sweep(10.0, 40.0, 5.0),
+        )
+        .with_param(
+            ParamSpec::float("std_dev", 2.0, 0.1, 10.0)
+                .with_description("Band width in standard deviations")
+                .with_sweep(1.5, 3.0, 0.5),
+        ),
+        StrategyDefinition::new(
+            "multi-momentum",
+            "Long while returns over both lookbacks are positive",
+            |p| {
+                Ok(Box::new(MultiLookbackMomentum::new(
+                    p.get_usize("lookback"),
+                    p.get_usize("short_lookback"),
+                )))
+            },
+        )
+        .with_param(
+            ParamSpec::period("lookback", 90, 2)
+                .with_description("Long momentum lookback in bars")
+                .with_sweep(60.0, 180.0, 30.0),
+        )
+        .with_param(
+            ParamSpec::period("short_lookback", 30, 1)
+                .with_description("Short momentum lookback in bars")
+                .with_sweep(10.0, 50.0, 10.0),
+        )
+        .with_constraint(Constraint::less_than("short_lookback", "lookback")),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_defaults_build() {
+        let registry = StrategyRegistry::builtin();
+        for definition in registry.definitions() {
+            let strategy = definition.build(&Params::new()).unwrap();
+            assert!(!strategy.name().is_empty());
+            // Default sweep ranges must produce at least one valid combination
+            let combinations = definition
+                .grid_combinations(&definition.default_grid())
+                .unwrap();
+            assert!(!combinations.is_empty(), "{}", definition.name);
+        }
+        assert!(registry.get("nope").is_err());
+    }
+
+    #[test]
+    fn test_resolve_validates_params() {
+        let sma = StrategyRegistry::builtin().get("sma").unwrap().clone();
+
+        let params = sma.resolve(&Params::new().with("fast", 20.0)).unwrap();
+        assert_eq!(params.get("slow"), 200.0);
+        assert_eq!(sma.file_stem(&params), "sma_20_200");
+
+        assert!(sma.resolve(&Params::new().with("fast", 300.0)).is_err());
+        assert!(sma.resolve(&Params::new().with("fast", 20.5)).is_err());
+        assert!(sma.resolve(&Params::new().with("period", 5.0)).is_err());
+    }
+
+    #[test]
+    fn test_grid_applies_constraints() {
+        let sma = StrategyRegistry::builtin().get("sma").unwrap().clone();
+        let grid = ParamGrid::new()
+            .with_values("fast", vec![10.0, 20.0, 30.0])
+            .with_values("slow", vec![20.0, 30.0]);
+        let combinations = sma.grid_combinations(&grid).unwrap();
+
+        // (10,20), (10,30), (20,30)
+        assert_eq!(combinations.len(), 3);
+        assert!(combinations.iter().all(|p| p.get("fast") < p.get("slow")));
+        assert!(sma
+            .grid_combinations(&ParamGrid::new().with_values("fast", vec![0.0]))
+            .is_err());
+    }
+
+    #[test]
+    fn test_register_custom_strategy() {
+        let mut registry = StrategyRegistry::builtin();
+        let custom =
+            StrategyDefinition::new("always-long", "Test", |_| Ok(Box::new(BuyAndHold::new())));
+        registry.register(custom.clone()).unwrap();
+        assert!(registry.register(custom).is_err());
+        assert!(registry.build("always-long", &Params::new()).is_ok());
+    }
+}
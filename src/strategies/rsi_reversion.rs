@@ -0,0 +1,87 @@
+use crate::data::OHLCV;
+use crate::indicators::rsi;
+use crate::strategies::Strategy;
+
+/// RSI Mean Reversion Strategy
+/// Buy when RSI drops below the oversold level, sell when it rises above
+/// the overbought level
+pub struct RSIMeanReversion {
+    period: usize,
+    oversold: f64,
+    overbought: f64,
+    /// Display name including the parameters
+    name: String,
+}
+
+impl RSIMeanReversion {
+    pub fn new(period: usize, oversold: f64, overbought: f64) -> Self {
+        assert!(period > 0, "RSI period must be greater than 0");
+        assert!(
+            (0.0..100.0).contains(&oversold) && oversold < overbought && overbought <= 100.0,
+            "RSI levels must satisfy 0 <= oversold < overbought <= 100"
+        );
+
+        Self {
+            period,
+            oversold,
+            overbought,
+            name: format!("RSI({}) {}/{}", period, oversold, overbought),
+        }
+    }
+}
+
+impl Strategy for RSIMeanReversion {
+    fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+        let closes: Vec<f64> = data.iter().map(|d| d.close).collect();
+        let values = rsi(&closes, self.period);
+
+        let mut current_position = 0.0;
+        values
+            .iter()
+            .map(|&r| {
+                if r < self.oversold {
+                    current_position = 1.0;
+                } else if r > self.overbought {
+                    current_position = 0.0;
+                }
+                current_position
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "RSI({}) reversion - Buy below {}, sell above {}",
+            self.period, self.oversold, self.overbought
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buys_oversold_and_sells_overbought() {
+        let closes = [
+            100.0, 99.0, 98.0, 97.0, 96.0, 97.0, 99.0, 102.0, 105.0, 104.0,
+        ];
+        let data: Vec<OHLCV> = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| OHLCV::new(i as i64, c, c, c, c, 1.0))
+            .collect();
+        let signals = RSIMeanReversion::new(3, 30.0, 70.0).generate_signals(&data);
+
+        // Flat while RSI warms up despite the selloff, long once it reads
+        // oversold, held through the bounce until it reads overbought
+        assert_eq!(
+            signals,
+            vec![0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0]
+        );
+    }
+}
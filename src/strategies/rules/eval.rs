@@ -0,0 +1,184 @@
+//! Evaluation of parsed rule expressions over a bar series
+
+use super::parser::{ArithOp, CompareOp, Condition, NumExpr, Source};
+use crate::data::OHLCV;
+use crate::indicators::{
+    adx, atr, bollinger, donchian, ema, keltner, macd, obv, rolling_vwap, rsi, sma, stochastic,
+    vwap, wma, zscore,
+};
+use std::collections::HashMap;
+
+/// Evaluates expressions against one bar series, computing each indicator
+/// series once even when a rule references it several times
+pub struct Evaluator<'a> {
+    data: &'a [OHLCV],
+    cache: HashMap<String, Vec<f64>>,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(data: &'a [OHLCV]) -> Self {
+        Self {
+            data,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Per-bar truth values; comparisons involving NaN are false
+    pub fn condition(&mut self, condition: &Condition) -> Vec<bool> {
+        match condition {
+            Condition::Compare(op, lhs, rhs) => {
+                let (a, b) = (self.number(lhs), self.number(rhs));
+                a.iter()
+                    .zip(&b)
+                    .map(|(&x, &y)| compare(*op, x, y))
+                    .collect()
+            }
+            Condition::CrossesAbove(lhs, rhs) => {
+                let (a, b) = (self.number(lhs), self.number(rhs));
+                crosses(&a, &b)
+            }
+            Condition::CrossesBelow(lhs, rhs) => {
+                let (a, b) = (self.number(lhs), self.number(rhs));
+                crosses(&b, &a)
+            }
+            Condition::And(lhs, rhs) => {
+                let (a, b) = (self.condition(lhs), self.condition(rhs));
+                a.iter().zip(&b).map(|(&x, &y)| x && y).collect()
+            }
+            Condition::Or(lhs, rhs) => {
+                let (a, b) = (self.condition(lhs), self.condition(rhs));
+                a.iter().zip(&b).map(|(&x, &y)| x || y).collect()
+            }
+            Condition::Not(inner) => self.condition(inner).iter().map(|&x| !x).collect(),
+        }
+    }
+
+    /// Per-bar values of a numeric expression, NaN where undefined
+    pub fn number(&mut self, expr: &NumExpr) -> Vec<f64> {
+        match expr {
+            NumExpr::Number(n) => vec![*n; self.data.len()],
+            NumExpr::Series(source) => self.data.iter().map(|b| value(b, *source)).collect(),
+            NumExpr::Indicator { name, args } => self.indicator(name, args),
+            NumExpr::Offset(inner, bars) => {
+                let values = self.number(inner);
+                (0..values.len())
+                    .map(|i| {
+                        if i >= *bars {
+                            values[i - bars]
+                        } else {
+                            f64::NAN
+                        }
+                    })
+                    .collect()
+            }
+            NumExpr::Neg(inner) => self.number(inner).iter().map(|v| -v).collect(),
+            NumExpr::Arith(op, lhs, rhs) => {
+                let (a, b) = (self.number(lhs), self.number(rhs));
+                a.iter()
+                    .zip(&b)
+                    .map(|(&x, &y)| match op {
+                        ArithOp::Add => x + y,
+                        ArithOp::Sub => x - y,
+                        ArithOp::Mul => x * y,
+                        ArithOp::Div if y == 0.0 => f64::NAN,
+                        ArithOp::Div => x / y,
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    fn indicator(&mut self, name: &str, args: &[f64]) -> Vec<f64> {
+        let key = format!("{}{:?}", name, args);
+        if let Some(values) = self.cache.get(&key) {
+            return values.clone();
+        }
+
+        let data = self.data;
+        let closes: Vec<f64> = data.iter().map(|b| b.close).collect();
+        let n = |i: usize| args[i] as usize;
+        let values = match name {
+            "sma" => sma(&closes, n(0)),
+            "ema" => ema(&closes, n(0)),
+            "wma" => wma(&closes, n(0)),
+            "rsi" => rsi(&closes, n(0)),
+            "zscore" => zscore(&closes, n(0)),
+            "atr" => atr(data, n(0)),
+            "adx" => adx(data, n(0)).iter().map(|v| v.adx).collect(),
+            "plus_di" => adx(data, n(0)).iter().map(|v| v.plus_di).collect(),
+            "minus_di" => adx(data, n(0)).iter().map(|v| v.minus_di).collect(),
+            "obv" => obv(data),
+            "vwap" => vwap(data),
+            "rolling_vwap" => rolling_vwap(data, n(0)),
+            "macd" => macd(&closes, n(0), n(1), n(2))
+                .iter()
+                .map(|v| v.macd)
+                .collect(),
+            "macd_signal" => macd(&closes, n(0), n(1), n(2))
+                .iter()
+                .map(|v| v.signal)
+                .collect(),
+            "macd_hist" => macd(&closes, n(0), n(1), n(2))
+                .iter()
+                .map(|v| v.histogram)
+                .collect(),
+            "bb_upper" => bollinger(&closes, n(0), args[1])
+                .iter()
+                .map(|b| b.upper)
+                .collect(),
+            "bb_middle" => bollinger(&closes, n(0), args[1])
+                .iter()
+                .map(|b| b.middle)
+                .collect(),
+            "bb_lower" => bollinger(&closes, n(0), args[1])
+                .iter()
+                .map(|b| b.lower)
+                .collect(),
+            "donchian_upper" => donchian(data, n(0)).iter().map(|b| b.upper).collect(),
+            "donchian_lower" => donchian(data, n(0)).iter().map(|b| b.lower).collect(),
+            "keltner_upper" => keltner(data, n(0), n(1), args[2])
+                .iter()
+                .map(|b| b.upper)
+                .collect(),
+            "keltner_lower" => keltner(data, n(0), n(1), args[2])
+                .iter()
+                .map(|b| b.lower)
+                .collect(),
+            "stoch_k" => stochastic(data, n(0), n(1)).iter().map(|v| v.k).collect(),
+            "stoch_d" => stochastic(data, n(0), n(1)).iter().map(|v| v.d).collect(),
+            // The parser only admits names from its indicator table
+            _ => unreachable!("unknown indicator '{}'", name),
+        };
+
+        self.cache.insert(key, values.clone());
+        values
+    }
+}
+
+fn value(bar: &OHLCV, source: Source) -> f64 {
+    match source {
+        Source::Open => bar.open,
+        Source::High => bar.high,
+        Source::Low => bar.low,
+        Source::Close => bar.close,
+        Source::Volume => bar.volume,
+    }
+}
+
+fn compare(op: CompareOp, a: f64, b: f64) -> bool {
+    match op {
+        CompareOp::Lt => a < b,
+        CompareOp::Le => a <= b,
+        CompareOp::Gt => a > b,
+        CompareOp::Ge => a >= b,
+        CompareOp::Eq => a == b,
+        CompareOp::Ne => !a.is_nan() && !b.is_nan() && a != b,
+    }
+}
+
+/// True on bars where `a` moves from at or below `b` to strictly above it
+fn crosses(a: &[f64], b: &[f64]) -> Vec<bool> {
+    (0..a.len())
+        .map(|i| i > 0 && a[i] > b[i] && a[i - 1] <= b[i - 1])
+        .collect()
+}
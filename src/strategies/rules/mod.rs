@@ -0,0 +1,309 @@
+//! Declarative strategies defined by rule files
+//!
+//! A rule file names an entry condition and an optional exit condition
+//! written in a small expression language over the indicator library:
+//!
+//! ```toml
+//! name = "EMA trend with RSI filter"
+//! entry = "ema(12) crosses_above ema(26) and rsi(14) < 70"
+//! exit = "ema(12) crosses_below ema(26)"
+//! ```
+//!
+//! Without an exit rule the strategy is long exactly while the entry
+//! condition holds. Files ending in `.json` are read as JSON, anything
+//! else as TOML.
+
+mod eval;
+pub mod parser;
+
+pub use eval::Evaluator;
+pub use parser::{parse_condition, Condition, NumExpr, ParseError};
+
+use crate::data::OHLCV;
+use crate::strategies::Strategy;
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Contents of a rule file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleFile {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub entry: String,
+    pub exit: Option<String>,
+}
+
+/// Strategy evaluating entry/exit rules each bar
+#[derive(Debug, Clone)]
+pub struct RuleStrategy {
+    name: String,
+    description: Option<String>,
+    entry_source: String,
+    exit_source: Option<String>,
+    entry: Condition,
+    exit: Option<Condition>,
+}
+
+impl RuleStrategy {
+    /// Build from rule expressions; errors show the offending position
+    pub fn new(name: &str, entry: &str, exit: Option<&str>) -> Result<Self> {
+        let parse =
+            |expr: &str| parse_condition(expr).map_err(|e| anyhow!("{}", describe_error(expr, &e)));
+        Ok(Self {
+            name: name.to_string(),
+            description: None,
+            entry_source: entry.to_string(),
+            exit_source: exit.map(str::to_string),
+            entry: parse(entry)?,
+            exit: exit.map(parse).transpose()?,
+        })
+    }
+
+    /// Load a TOML or JSON rule file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read rule file {}", path.display()))?;
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        let default_name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "rules".to_string());
+
+        Self::parse_source(&source, &path.display().to_string(), is_json, &default_name)
+    }
+
+    /// Parse rule file contents; `label` prefixes error locations
+    pub fn parse_source(
+        source: &str,
+        label: &str,
+        is_json: bool,
+        default_name: &str,
+    ) -> Result<Self> {
+        let file: RuleFile = if is_json {
+            serde_json::from_str(source).with_context(|| format!("Invalid rule file {}", label))?
+        } else {
+            toml::from_str(source).with_context(|| format!("Invalid rule file {}", label))?
+        };
+
+        let parse = |key: &str, expr: &str| {
+            parse_condition(expr)
+                .map_err(|e| anyhow!("{}", locate_error(source, label, key, expr, &e)))
+        };
+        let entry = parse("entry", &file.entry)?;
+        let exit = match &file.exit {
+            Some(expr) => Some(parse("exit", expr)?),
+            None => None,
+        };
+
+        Ok(Self {
+            name: file.name.unwrap_or_else(|| default_name.to_string()),
+            description: file.description,
+            entry_source: file.entry,
+            exit_source: file.exit,
+            entry,
+            exit,
+        })
+    }
+}
+
+impl Strategy for RuleStrategy {
+    fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+        let mut evaluator = Evaluator::new(data);
+        let entries = evaluator.condition(&self.entry);
+
+        let exit = match &self.exit {
+            Some(exit) => evaluator.condition(exit),
+            None => {
+                return entries
+                    .iter()
+                    .map(|&on| if on { 1.0 } else { 0.0 })
+                    .collect()
+            }
+        };
+
+        let mut position = 0.0;
+        entries
+            .iter()
+            .zip(&exit)
+            .map(|(&enter, &leave)| {
+                if position > 0.0 && leave {
+                    position = 0.0;
+                } else if position == 0.0 && enter {
+                    position = 1.0;
+                }
+                position
+            })
+            .collect()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        if let Some(description) = &self.description {
+            return description.clone();
+        }
+        match &self.exit_source {
+            Some(exit) => format!("Enter when {}, exit when {}", self.entry_source, exit),
+            None => format!("Long while {}", self.entry_source),
+        }
+    }
+}
+
+/// Error message with the expression and a caret under the offending token
+fn describe_error(expr: &str, error: &ParseError) -> String {
+    let column = expr[..error.offset.min(expr.len())].chars().count();
+    format!("{}\n  {}\n  {}^", error.message, expr, " ".repeat(column))
+}
+
+/// Map an error inside a rule expression back to its line and column in
+/// the file. Expressions containing escapes are not found verbatim, in
+/// which case the line of the key is reported instead.
+fn locate_error(source: &str, label: &str, key: &str, expr: &str, error: &ParseError) -> String {
+    let key_start = key_position(source, key);
+    let from = key_start.unwrap_or(0);
+    let position = source[from..]
+        .find(expr)
+        .filter(|_| !expr.is_empty())
+        .map(|start| from + start + error.offset.min(expr.len()))
+        .or(key_start);
+
+    let position = match position {
+        Some(position) => position,
+        None => return format!("{}: {}: {}", label, key, describe_error(expr, error)),
+    };
+
+    let line_start = source[..position].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[position..]
+        .find('\n')
+        .map_or(source.len(), |i| position + i);
+    let line_number = source[..position].matches('\n').count() + 1;
+    let column = source[line_start..position].chars().count();
+
+    format!(
+        "{}:{}:{}: {}\n  {}\n  {}^",
+        label,
+        line_number,
+        column + 1,
+        error.message,
+        source[line_start..line_end].trim_end(),
+        " ".repeat(column)
+    )
+}
+
+/// Byte offset of `key` where it starts a TOML line or appears as a JSON key
+fn key_position(source: &str, key: &str) -> Option<usize> {
+    let quoted = format!("\"{}\"", key);
+    let mut offset = 0;
+    for line in source.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with(key) || trimmed.starts_with(&quoted) {
+            return Some(offset + line.len() - trimmed.len());
+        }
+        offset += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::test_support::sample_bars;
+    use crate::strategies::EMACrossover;
+
+    fn bars_from_closes(closes: &[f64]) -> Vec<OHLCV> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| OHLCV::new(i as i64 * 60_000, c, c, c, c, 1.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_rule_matches_builtin_strategy() {
+        let bars = sample_bars();
+        let rules = RuleStrategy::new("ema", "ema(12) > ema(26)", None).unwrap();
+        assert_eq!(
+            rules.generate_signals(&bars),
+            EMACrossover::new(12, 26).generate_signals(&bars)
+        );
+    }
+
+    #[test]
+    fn test_crosses_and_exit_rule() {
+        let bars = bars_from_closes(&[5.0, 4.0, 6.0, 7.0, 3.0, 2.0, 8.0]);
+        let strategy = RuleStrategy::new(
+            "cross",
+            "close crosses_above 5",
+            Some("close crosses_below 5"),
+        )
+        .unwrap();
+        assert_eq!(
+            strategy.generate_signals(&bars),
+            vec![0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0]
+        );
+
+        let lagged =
+            RuleStrategy::new("lag", "close > close[1] and close[1] > close[2]", None).unwrap();
+        assert_eq!(
+            lagged.generate_signals(&bars),
+            vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_parse_toml_and_json_files() {
+        let toml_source = r#"
+name = "Trend"
+entry = "ema(12) crosses_above ema(26) and rsi(14) < 70"
+exit = "ema(12) crosses_below ema(26)"
+"#;
+        let strategy =
+            RuleStrategy::parse_source(toml_source, "trend.toml", false, "trend").unwrap();
+        assert_eq!(strategy.name(), "Trend");
+        assert_eq!(strategy.generate_signals(&sample_bars()).len(), 500);
+
+        let json_source = r#"{ "entry": "close > sma(20)" }"#;
+        let strategy =
+            RuleStrategy::parse_source(json_source, "trend.json", true, "trend").unwrap();
+        assert_eq!(strategy.name(), "trend");
+
+        let unknown = "entry = \"close > 1\"\nstop = \"close < 1\"\n";
+        assert!(RuleStrategy::parse_source(unknown, "bad.toml", false, "bad").is_err());
+    }
+
+    #[test]
+    fn test_errors_point_at_offending_line() {
+        let source =
+            "name = \"Broken\"\nentry = \"close > sma(20)\"\nexit = \"close < smaa(50)\"\n";
+        let err = RuleStrategy::parse_source(source, "rules.toml", false, "rules")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with("rules.toml:3:17: unknown indicator"),
+            "{}",
+            err
+        );
+        assert!(
+            err.contains("exit = \"close < smaa(50)\"\n                  ^"),
+            "{}",
+            err
+        );
+
+        let json = "{\n  \"entry\": \"rsi(14) <\"\n}";
+        let err = RuleStrategy::parse_source(json, "rules.json", true, "rules")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.starts_with("rules.json:2:22: expected a value"),
+            "{}",
+            err
+        );
+    }
+}
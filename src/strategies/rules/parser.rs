@@ -0,0 +1,600 @@
+//! Lexer and parser for rule expressions
+//!
+//! Grammar (lowest precedence first):
+//!
+//! ```text
+//! or      := and ("or" and)*
+//! and     := not ("and" not)*
+//! not     := "not" not | compare
+//! compare := sum (("<" | "<=" | ">" | ">=" | "==" | "!="
+//!                  | "crosses_above" | "crosses_below") sum)?
+//! sum     := product (("+" | "-") product)*
+//! product := unary (("*" | "/") unary)*
+//! unary   := "-" unary | postfix
+//! postfix := primary ("[" integer "]")*
+//! primary := number | "(" or ")" | series | indicator "(" args ")"
+//! ```
+//!
+//! Types are checked while parsing: logic operators take conditions,
+//! arithmetic and comparisons take numeric series.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// Byte offset into the expression
+    pub offset: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(offset: usize, message: impl Into<String>) -> Self {
+        Self {
+            offset,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (at offset {})", self.message, self.offset)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Open,
+    High,
+    Low,
+    Close,
+    Volume,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+/// Numeric series expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumExpr {
+    Number(f64),
+    Series(Source),
+    Indicator {
+        name: String,
+        args: Vec<f64>,
+    },
+    /// Value `bars` bars ago
+    Offset(Box<NumExpr>, usize),
+    Neg(Box<NumExpr>),
+    Arith(ArithOp, Box<NumExpr>, Box<NumExpr>),
+}
+
+/// Per-bar condition
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Compare(CompareOp, NumExpr, NumExpr),
+    CrossesAbove(NumExpr, NumExpr),
+    CrossesBelow(NumExpr, NumExpr),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+/// Argument kinds of an indicator function
+#[derive(Clone, Copy, PartialEq)]
+enum Arg {
+    /// Positive whole number (a period)
+    Period,
+    /// Any positive number (e.g. a band multiplier)
+    Factor,
+}
+
+/// Indicator functions available in rules, with their arguments
+const INDICATORS: &[(&str, &[Arg])] = &[
+    ("sma", &[Arg::Period]),
+    ("ema", &[Arg::Period]),
+    ("wma", &[Arg::Period]),
+    ("rsi", &[Arg::Period]),
+    ("zscore", &[Arg::Period]),
+    ("atr", &[Arg::Period]),
+    ("adx", &[Arg::Period]),
+    ("plus_di", &[Arg::Period]),
+    ("minus_di", &[Arg::Period]),
+    ("obv", &[]),
+    ("vwap", &[]),
+    ("rolling_vwap", &[Arg::Period]),
+    ("macd", &[Arg::Period, Arg::Period, Arg::Period]),
+    ("macd_signal", &[Arg::Period, Arg::Period, Arg::Period]),
+    ("macd_hist", &[Arg::Period, Arg::Period, Arg::Period]),
+    ("bb_upper", &[Arg::Period, Arg::Factor]),
+    ("bb_middle", &[Arg::Period, Arg::Factor]),
+    ("bb_lower", &[Arg::Period, Arg::Factor]),
+    ("donchian_upper", &[Arg::Period]),
+    ("donchian_lower", &[Arg::Period]),
+    ("keltner_upper", &[Arg::Period, Arg::Period, Arg::Factor]),
+    ("keltner_lower", &[Arg::Period, Arg::Period, Arg::Factor]),
+    ("stoch_k", &[Arg::Period, Arg::Period]),
+    ("stoch_d", &[Arg::Period, Arg::Period]),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Symbol(&'static str),
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "number {}", n),
+            Token::Ident(s) => write!(f, "'{}'", s),
+            Token::Symbol(s) => write!(f, "'{}'", s),
+            Token::End => write!(f, "end of expression"),
+        }
+    }
+}
+
+const SYMBOLS: [&str; 14] = [
+    "<=", ">=", "==", "!=", "<", ">", "+", "-", "*", "/", "(", ")", "[", "]",
+];
+
+fn tokenize(input: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let bytes = input.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i] as char;
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+                i += 1;
+            }
+            let text = &input[start..i];
+            let value = text
+                .parse()
+                .map_err(|_| ParseError::new(start, format!("invalid number '{}'", text)))?;
+            tokens.push((Token::Number(value), start));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+                i += 1;
+            }
+            tokens.push((Token::Ident(input[start..i].to_lowercase()), start));
+        } else if c == ',' {
+            tokens.push((Token::Symbol(","), i));
+            i += 1;
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| input[i..].starts_with(**s)) {
+            tokens.push((Token::Symbol(symbol), i));
+            i += symbol.len();
+        } else {
+            let ch = input[i..].chars().next().unwrap_or(c);
+            return Err(ParseError::new(i, format!("unexpected character '{}'", ch)));
+        }
+    }
+
+    tokens.push((Token::End, input.len()));
+    Ok(tokens)
+}
+
+/// Parsed sub-expression, which is either numeric or a condition
+enum Node {
+    Num(NumExpr),
+    Cond(Condition),
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn offset(&self) -> usize {
+        self.tokens[self.pos].1
+    }
+
+    fn next(&mut self) -> (Token, usize) {
+        let token = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Token::Ident(s) if s == keyword)
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Token::Symbol(s) if *s == symbol)
+    }
+
+    fn expect_symbol(&mut self, symbol: &str, context: &str) -> Result<(), ParseError> {
+        if self.is_symbol(symbol) {
+            self.next();
+            Ok(())
+        } else {
+            Err(ParseError::new(
+                self.offset(),
+                format!("expected '{}' {}, found {}", symbol, context, self.peek()),
+            ))
+        }
+    }
+
+    fn condition(&mut self, node: Node, offset: usize) -> Result<Condition, ParseError> {
+        match node {
+            Node::Cond(c) => Ok(c),
+            Node::Num(_) => Err(ParseError::new(
+                offset,
+                "expected a condition (e.g. a comparison), found a number",
+            )),
+        }
+    }
+
+    fn number(&mut self, node: Node, offset: usize) -> Result<NumExpr, ParseError> {
+        match node {
+            Node::Num(n) => Ok(n),
+            Node::Cond(_) => Err(ParseError::new(
+                offset,
+                "expected a number, found a condition",
+            )),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Node, ParseError> {
+        let start = self.offset();
+        let mut node = self.parse_and()?;
+        while self.is_keyword("or") {
+            let lhs = self.condition(node, start)?;
+            self.next();
+            let rhs_start = self.offset();
+            let rhs = self.parse_and()?;
+            let rhs = self.condition(rhs, rhs_start)?;
+            node = Node::Cond(Condition::Or(Box::new(lhs), Box::new(rhs)));
+        }
+        Ok(node)
+    }
+
+    fn parse_and(&mut self) -> Result<Node, ParseError> {
+        let start = self.offset();
+        let mut node = self.parse_not()?;
+        while self.is_keyword("and") {
+            let lhs = self.condition(node, start)?;
+            self.next();
+            let rhs_start = self.offset();
+            let rhs = self.parse_not()?;
+            let rhs = self.condition(rhs, rhs_start)?;
+            node = Node::Cond(Condition::And(Box::new(lhs), Box::new(rhs)));
+        }
+        Ok(node)
+    }
+
+    fn parse_not(&mut self) -> Result<Node, ParseError> {
+        if self.is_keyword("not") {
+            self.next();
+            let start = self.offset();
+            let inner = self.parse_not()?;
+            let inner = self.condition(inner, start)?;
+            return Ok(Node::Cond(Condition::Not(Box::new(inner))));
+        }
+        self.parse_compare()
+    }
+
+    fn parse_compare(&mut self) -> Result<Node, ParseError> {
+        let start = self.offset();
+        let lhs = self.parse_sum()?;
+
+        let op = match self.peek() {
+            Token::Symbol("<") => Some("<"),
+            Token::Symbol("<=") => Some("<="),
+            Token::Symbol(">") => Some(">"),
+            Token::Symbol(">=") => Some(">="),
+            Token::Symbol("==") => Some("=="),
+            Token::Symbol("!=") => Some("!="),
+            Token::Ident(s) if s == "crosses_above" => Some("crosses_above"),
+            Token::Ident(s) if s == "crosses_below" => Some("crosses_below"),
+            _ => None,
+        };
+        let op = match op {
+            Some(op) => op,
+            None => return Ok(lhs),
+        };
+
+        let lhs = self.number(lhs, start)?;
+        self.next();
+        let rhs_start = self.offset();
+        let rhs = self.parse_sum()?;
+        let rhs = self.number(rhs, rhs_start)?;
+
+        let condition = match op {
+            "crosses_above" => Condition::CrossesAbove(lhs, rhs),
+            "crosses_below" => Condition::CrossesBelow(lhs, rhs),
+            "<" => Condition::Compare(CompareOp::Lt, lhs, rhs),
+            "<=" => Condition::Compare(CompareOp::Le, lhs, rhs),
+            ">" => Condition::Compare(CompareOp::Gt, lhs, rhs),
+            ">=" => Condition::Compare(CompareOp::Ge, lhs, rhs),
+            "==" => Condition::Compare(CompareOp::Eq, lhs, rhs),
+            _ => Condition::Compare(CompareOp::Ne, lhs, rhs),
+        };
+        Ok(Node::Cond(condition))
+    }
+
+    fn parse_sum(&mut self) -> Result<Node, ParseError> {
+        let start = self.offset();
+        let mut node = self.parse_product()?;
+        loop {
+            let op = if self.is_symbol("+") {
+                ArithOp::Add
+            } else if self.is_symbol("-") {
+                ArithOp::Sub
+            } else {
+                return Ok(node);
+            };
+            let lhs = self.number(node, start)?;
+            self.next();
+            let rhs_start = self.offset();
+            let rhs = self.parse_product()?;
+            let rhs = self.number(rhs, rhs_start)?;
+            node = Node::Num(NumExpr::Arith(op, Box::new(lhs), Box::new(rhs)));
+        }
+    }
+
+    fn parse_product(&mut self) -> Result<Node, ParseError> {
+        let start = self.offset();
+        let mut node = self.parse_unary()?;
+        loop {
+            let op = if self.is_symbol("*") {
+                ArithOp::Mul
+            } else if self.is_symbol("/") {
+                ArithOp::Div
+            } else {
+                return Ok(node);
+            };
+            let lhs = self.number(node, start)?;
+            self.next();
+            let rhs_start = self.offset();
+            let rhs = self.parse_unary()?;
+            let rhs = self.number(rhs, rhs_start)?;
+            node = Node::Num(NumExpr::Arith(op, Box::new(lhs), Box::new(rhs)));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, ParseError> {
+        if self.is_symbol("-") {
+            self.next();
+            let start = self.offset();
+            let inner = self.parse_unary()?;
+            let inner = self.number(inner, start)?;
+            return Ok(Node::Num(NumExpr::Neg(Box::new(inner))));
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Node, ParseError> {
+        let start = self.offset();
+        let mut node = self.parse_primary()?;
+        while self.is_symbol("[") {
+            let inner = self.number(node, start)?;
+            self.next();
+            let bars = match self.next() {
+                (Token::Number(n), _) if n >= 0.0 && n.fract() == 0.0 => n as usize,
+                (token, offset) => {
+                    return Err(ParseError::new(
+                        offset,
+                        format!("expected a whole number of bars, found {}", token),
+                    ))
+                }
+            };
+            self.expect_symbol("]", "after offset")?;
+            node = Node::Num(NumExpr::Offset(Box::new(inner), bars));
+        }
+        Ok(node)
+    }
+
+    fn parse_primary(&mut self) -> Result<Node, ParseError> {
+        let (token, offset) = self.next();
+        match token {
+            Token::Number(n) => Ok(Node::Num(NumExpr::Number(n))),
+            Token::Symbol("(") => {
+                let node = self.parse_or()?;
+                self.expect_symbol(")", "to close '('")?;
+                Ok(node)
+            }
+            Token::Ident(name) => {
+                let source = match name.as_str() {
+                    "open" => Some(Source::Open),
+                    "high" => Some(Source::High),
+                    "low" => Some(Source::Low),
+                    "close" => Some(Source::Close),
+                    "volume" => Some(Source::Volume),
+                    _ => None,
+                };
+                if let Some(source) = source {
+                    return Ok(Node::Num(NumExpr::Series(source)));
+                }
+                self.parse_indicator(name, offset)
+            }
+            other => Err(ParseError::new(
+                offset,
+                format!("expected a value, found {}", other),
+            )),
+        }
+    }
+
+    fn parse_indicator(&mut self, name: String, offset: usize) -> Result<Node, ParseError> {
+        let spec = match INDICATORS.iter().find(|(n, _)| *n == name) {
+            Some((_, spec)) => *spec,
+            None => {
+                return Err(ParseError::new(
+                    offset,
+                    format!("unknown indicator or series '{}'", name),
+                ))
+            }
+        };
+
+        let mut args = Vec::new();
+        self.expect_symbol("(", &format!("after '{}'", name))?;
+        while !self.is_symbol(")") {
+            if !args.is_empty() {
+                self.expect_symbol(",", "between arguments")?;
+            }
+            match self.next() {
+                (Token::Number(n), arg_offset) => args.push((n, arg_offset)),
+                (token, arg_offset) => {
+                    return Err(ParseError::new(
+                        arg_offset,
+                        format!("expected a number argument, found {}", token),
+                    ))
+                }
+            }
+        }
+        let close = self.offset();
+        self.expect_symbol(")", "after arguments")?;
+
+        if args.len() != spec.len() {
+            return Err(ParseError::new(
+                close,
+                format!(
+                    "{} takes {} argument{}, got {}",
+                    name,
+                    spec.len(),
+                    if spec.len() == 1 { "" } else { "s" },
+                    args.len()
+                ),
+            ));
+        }
+        for ((value, arg_offset), kind) in args.iter().zip(spec) {
+            let valid = match kind {
+                Arg::Period => *value >= 1.0 && value.fract() == 0.0,
+                Arg::Factor => *value > 0.0,
+            };
+            if !valid {
+                let expected = match kind {
+                    Arg::Period => "a whole number of bars >= 1",
+                    Arg::Factor => "a positive number",
+                };
+                return Err(ParseError::new(
+                    *arg_offset,
+                    format!("{} argument must be {}, got {}", name, expected, value),
+                ));
+            }
+        }
+
+        Ok(Node::Num(NumExpr::Indicator {
+            name,
+            args: args.into_iter().map(|(v, _)| v).collect(),
+        }))
+    }
+}
+
+/// Parse a rule expression that must evaluate to a condition
+pub fn parse_condition(input: &str) -> Result<Condition, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(input)?,
+        pos: 0,
+    };
+    if parser.peek() == &Token::End {
+        return Err(ParseError::new(0, "empty expression"));
+    }
+
+    let start = parser.offset();
+    let node = parser.parse_or()?;
+    if parser.peek() != &Token::End {
+        return Err(ParseError::new(
+            parser.offset(),
+            format!("unexpected {}", parser.peek()),
+        ));
+    }
+    parser.condition(node, start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_precedence() {
+        let parsed = parse_condition(
+            "ema(12) crosses_above ema(26) and rsi(14) < 70 or not close > 1 + 2 * 3",
+        )
+        .unwrap();
+        let ema = |n: f64| NumExpr::Indicator {
+            name: "ema".to_string(),
+            args: vec![n],
+        };
+
+        let expected = Condition::Or(
+            Box::new(Condition::And(
+                Box::new(Condition::CrossesAbove(ema(12.0), ema(26.0))),
+                Box::new(Condition::Compare(
+                    CompareOp::Lt,
+                    NumExpr::Indicator {
+                        name: "rsi".to_string(),
+                        args: vec![14.0],
+                    },
+                    NumExpr::Number(70.0),
+                )),
+            )),
+            Box::new(Condition::Not(Box::new(Condition::Compare(
+                CompareOp::Gt,
+                NumExpr::Series(Source::Close),
+                NumExpr::Arith(
+                    ArithOp::Add,
+                    Box::new(NumExpr::Number(1.0)),
+                    Box::new(NumExpr::Arith(
+                        ArithOp::Mul,
+                        Box::new(NumExpr::Number(2.0)),
+                        Box::new(NumExpr::Number(3.0)),
+                    )),
+                ),
+            )))),
+        );
+        assert_eq!(parsed, expected);
+
+        assert!(parse_condition("close > donchian_upper(20)[1]").is_ok());
+        assert!(parse_condition("(close > open) and (close[1] < open[1])").is_ok());
+    }
+
+    #[test]
+    fn test_parse_errors_point_at_offending_token() {
+        let cases = [
+            ("emaa(12) > close", 0, "unknown indicator"),
+            ("ema(12 > close", 7, "expected ','"),
+            ("ema(12) crosses_above", 21, "expected a value"),
+            ("close + 1", 0, "expected a condition"),
+            ("rsi(14) < 70 and 5", 17, "expected a condition"),
+            ("bb_upper(20) > close", 11, "takes 2 arguments"),
+            ("sma(2.5) > close", 4, "whole number"),
+            ("close > open)", 12, "unexpected ')'"),
+            ("close $ open", 6, "unexpected character"),
+        ];
+        for (input, offset, message) in cases {
+            let err = parse_condition(input).unwrap_err();
+            assert_eq!(err.offset, offset, "{}: {}", input, err);
+            assert!(err.message.contains(message), "{}: {}", input, err);
+        }
+    }
+}
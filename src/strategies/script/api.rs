@@ -0,0 +1,250 @@
+//! Types and indicator functions exposed to strategy scripts
+
+use crate::data::OHLCV;
+use crate::indicators::{
+    adx, atr, bollinger, donchian, ema, keltner, macd, obv, rolling_vwap, rsi, sma, stochastic,
+    vwap, wma, zscore, Atr, BollingerBands, Ema, Indicator, Rsi, Sma, Wma, ZScore,
+};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, FLOAT, INT};
+use std::sync::Arc;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Longest indicator period a script may ask for
+pub const MAX_PERIOD: usize = 1 << 20;
+
+/// Array length allowed however short the bar series
+pub const MIN_ARRAY_SIZE: usize = 1 << 16;
+
+/// Bar series passed to `generate_signals`
+#[derive(Clone)]
+pub struct Bars(pub Arc<Vec<OHLCV>>);
+
+impl Bars {
+    fn column(&mut self, field: fn(&OHLCV) -> f64) -> Array {
+        self.0
+            .iter()
+            .map(|b| Dynamic::from_float(field(b)))
+            .collect()
+    }
+}
+
+/// Configure `engine` with the sandbox limits and the script API
+pub fn register(engine: &mut Engine, max_operations: u64, max_array_size: usize) {
+    engine
+        .set_max_operations(max_operations)
+        .set_max_call_levels(64)
+        .set_max_expr_depths(64, 64)
+        .set_max_string_size(1 << 20)
+        .set_max_array_size(max_array_size)
+        .set_max_map_size(10_000);
+    engine.disable_symbol("eval");
+
+    register_bars(engine);
+    register_batch(engine);
+    register_streaming(engine);
+}
+
+fn register_bars(engine: &mut Engine) {
+    engine
+        .register_type_with_name::<Bars>("Bars")
+        .register_get("open", |b: &mut Bars| b.column(|x| x.open))
+        .register_get("high", |b: &mut Bars| b.column(|x| x.high))
+        .register_get("low", |b: &mut Bars| b.column(|x| x.low))
+        .register_get("close", |b: &mut Bars| b.column(|x| x.close))
+        .register_get("volume", |b: &mut Bars| b.column(|x| x.volume))
+        .register_get("timestamp", |b: &mut Bars| -> Array {
+            b.0.iter().map(|x| Dynamic::from_int(x.timestamp)).collect()
+        })
+        .register_fn("len", |b: &mut Bars| b.0.len() as INT)
+        .register_indexer_get(|b: &mut Bars, i: INT| -> ScriptResult<OHLCV> {
+            usize::try_from(i)
+                .ok()
+                .and_then(|i| b.0.get(i).cloned())
+                .ok_or_else(|| format!("bar index {} out of range", i).into())
+        });
+
+    engine
+        .register_type_with_name::<OHLCV>("Bar")
+        .register_get("open", |b: &mut OHLCV| b.open)
+        .register_get("high", |b: &mut OHLCV| b.high)
+        .register_get("low", |b: &mut OHLCV| b.low)
+        .register_get("close", |b: &mut OHLCV| b.close)
+        .register_get("volume", |b: &mut OHLCV| b.volume)
+        .register_get("timestamp", |b: &mut OHLCV| b.timestamp as INT);
+}
+
+/// Numbers of a script array; integers are widened to floats
+fn floats(values: &Array) -> ScriptResult<Vec<f64>> {
+    values
+        .iter()
+        .map(|v| {
+            v.as_float()
+                .or_else(|_| v.as_int().map(|i| i as f64))
+                .map_err(|t| format!("expected an array of numbers, found {}", t).into())
+        })
+        .collect()
+}
+
+fn period(value: INT) -> ScriptResult<usize> {
+    if value < 1 {
+        return Err(format!("period must be at least 1, got {}", value).into());
+    }
+    match usize::try_from(value) {
+        Ok(period) if period <= MAX_PERIOD => Ok(period),
+        _ => Err(format!("period must be at most {}, got {}", MAX_PERIOD, value).into()),
+    }
+}
+
+fn array(values: Vec<f64>) -> Array {
+    values.into_iter().map(Dynamic::from_float).collect()
+}
+
+fn map<const N: usize>(columns: [(&str, Vec<f64>); N]) -> Map {
+    columns
+        .into_iter()
+        .map(|(name, values)| (name.into(), Dynamic::from_array(array(values))))
+        .collect()
+}
+
+/// Batch indicators over arrays or bar series; NaN during warm-up
+fn register_batch(engine: &mut Engine) {
+    macro_rules! series_fn {
+        ($name:literal, $f:ident) => {
+            engine.register_fn($name, |values: Array, n: INT| -> ScriptResult<Array> {
+                Ok(array($f(&floats(&values)?, period(n)?)))
+            });
+        };
+    }
+    series_fn!("sma", sma);
+    series_fn!("ema", ema);
+    series_fn!("wma", wma);
+    series_fn!("rsi", rsi);
+    series_fn!("zscore", zscore);
+
+    engine
+        .register_fn(
+            "bollinger",
+            |values: Array, n: INT, k: FLOAT| -> ScriptResult<Map> {
+                let bands = bollinger(&floats(&values)?, period(n)?, k);
+                Ok(map([
+                    ("upper", bands.iter().map(|b| b.upper).collect()),
+                    ("middle", bands.iter().map(|b| b.middle).collect()),
+                    ("lower", bands.iter().map(|b| b.lower).collect()),
+                ]))
+            },
+        )
+        .register_fn(
+            "macd",
+            |values: Array, fast: INT, slow: INT, signal: INT| -> ScriptResult<Map> {
+                let values = macd(
+                    &floats(&values)?,
+                    period(fast)?,
+                    period(slow)?,
+                    period(signal)?,
+                );
+                Ok(map([
+                    ("macd", values.iter().map(|v| v.macd).collect()),
+                    ("signal", values.iter().map(|v| v.signal).collect()),
+                    ("histogram", values.iter().map(|v| v.histogram).collect()),
+                ]))
+            },
+        )
+        .register_fn("atr", |bars: Bars, n: INT| -> ScriptResult<Array> {
+            Ok(array(atr(&bars.0, period(n)?)))
+        })
+        .register_fn("adx", |bars: Bars, n: INT| -> ScriptResult<Map> {
+            let values = adx(&bars.0, period(n)?);
+            Ok(map([
+                ("adx", values.iter().map(|v| v.adx).collect()),
+                ("plus_di", values.iter().map(|v| v.plus_di).collect()),
+                ("minus_di", values.iter().map(|v| v.minus_di).collect()),
+            ]))
+        })
+        .register_fn("donchian", |bars: Bars, n: INT| -> ScriptResult<Map> {
+            let bands = donchian(&bars.0, period(n)?);
+            Ok(map([
+                ("upper", bands.iter().map(|b| b.upper).collect()),
+                ("middle", bands.iter().map(|b| b.middle).collect()),
+                ("lower", bands.iter().map(|b| b.lower).collect()),
+            ]))
+        })
+        .register_fn(
+            "keltner",
+            |bars: Bars, ema_n: INT, atr_n: INT, k: FLOAT| -> ScriptResult<Map> {
+                let bands = keltner(&bars.0, period(ema_n)?, period(atr_n)?, k);
+                Ok(map([
+                    ("upper", bands.iter().map(|b| b.upper).collect()),
+                    ("middle", bands.iter().map(|b| b.middle).collect()),
+                    ("lower", bands.iter().map(|b| b.lower).collect()),
+                ]))
+            },
+        )
+        .register_fn(
+            "stochastic",
+            |bars: Bars, k: INT, d: INT| -> ScriptResult<Map> {
+                let values = stochastic(&bars.0, period(k)?, period(d)?);
+                Ok(map([
+                    ("k", values.iter().map(|v| v.k).collect()),
+                    ("d", values.iter().map(|v| v.d).collect()),
+                ]))
+            },
+        )
+        .register_fn("obv", |bars: Bars| array(obv(&bars.0)))
+        .register_fn("vwap", |bars: Bars| array(vwap(&bars.0)))
+        .register_fn("vwap", |bars: Bars, n: INT| -> ScriptResult<Array> {
+            Ok(array(rolling_vwap(&bars.0, period(n)?)))
+        });
+}
+
+/// Streaming indicators for `on_bar` scripts: `update` returns NaN during warm-up
+fn register_streaming(engine: &mut Engine) {
+    macro_rules! streaming {
+        ($name:literal, $t:ty) => {
+            engine
+                .register_type_with_name::<$t>($name)
+                .register_fn($name, |n: INT| -> ScriptResult<$t> {
+                    Ok(<$t>::new(period(n)?))
+                })
+                .register_fn("update", |ind: &mut $t, value: FLOAT| {
+                    ind.update(&value).unwrap_or(f64::NAN)
+                })
+                .register_fn("update", |ind: &mut $t, value: INT| {
+                    ind.update(&(value as f64)).unwrap_or(f64::NAN)
+                });
+        };
+    }
+    streaming!("Sma", Sma);
+    streaming!("Ema", Ema);
+    streaming!("Wma", Wma);
+    streaming!("Rsi", Rsi);
+    streaming!("ZScore", ZScore);
+
+    engine
+        .register_type_with_name::<Atr>("Atr")
+        .register_fn("Atr", |n: INT| -> ScriptResult<Atr> {
+            Ok(Atr::new(period(n)?))
+        })
+        .register_fn("update", |ind: &mut Atr, bar: OHLCV| {
+            ind.update(&bar).unwrap_or(f64::NAN)
+        });
+
+    engine
+        .register_type_with_name::<BollingerBands>("Bollinger")
+        .register_fn(
+            "Bollinger",
+            |n: INT, k: FLOAT| -> ScriptResult<BollingerBands> {
+                Ok(BollingerBands::new(period(n)?, k))
+            },
+        )
+        .register_fn("update", |ind: &mut BollingerBands, value: FLOAT| -> Map {
+            let (upper, middle, lower) = match ind.update(&value) {
+                Some(b) => (b.upper, b.middle, b.lower),
+                None => (f64::NAN, f64::NAN, f64::NAN),
+            };
+            [("upper", upper), ("middle", middle), ("lower", lower)]
+                .into_iter()
+                .map(|(k, v)| (k.into(), Dynamic::from_float(v)))
+                .collect()
+        });
+}
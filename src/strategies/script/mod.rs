@@ -0,0 +1,561 @@
+//! Strategies written as Rhai scripts
+//!
+//! A script defines one of two entry points:
+//!
+//! ```text
+//! // Whole series at once: return one signal per bar
+//! fn generate_signals(bars) {
+//!     let fast = ema(bars.close, param("fast"));
+//!     let slow = ema(bars.close, param("slow"));
+//!     fast.map(|f, i| if f > slow[i] { 1.0 } else { 0.0 })
+//! }
+//!
+//! // Or bar by bar, keeping state in `this`; returning () holds the signal
+//! fn init() { this.fast = Ema(param("fast")); this.slow = Ema(param("slow")); }
+//! fn on_bar(bar) {
+//!     let f = this.fast.update(bar.close);
+//!     let s = this.slow.update(bar.close);
+//!     if f > s { 1.0 } else { 0.0 }
+//! }
+//! ```
+//!
+//! An optional `parameters()` returns a map of parameter declarations
+//! (`value` for the default, `min`, `max`, optional `sweep: [start, end, step]` and
+//! `description`) and `constraints()` an array like `["fast < slow"]`, so
+//! scripts plug into the registry, optimizer and walk-forward like built-in
+//! strategies. Scripts run sandboxed: no file or network access, each call
+//! into the script is limited to a number of operations, and arrays to one
+//! element per bar.
+
+mod api;
+
+use crate::data::OHLCV;
+use crate::strategies::params::{Constraint, ParamKind, ParamSpec, Params};
+use crate::strategies::{signals_or_flat, Strategy, StrategyDefinition};
+use anyhow::{anyhow, bail, Context, Result};
+use api::Bars;
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Operations allowed per call into a script
+pub const DEFAULT_MAX_OPERATIONS: u64 = 100_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EntryPoint {
+    GenerateSignals,
+    OnBar { init: bool },
+}
+
+/// A compiled strategy script with its declared parameters
+pub struct Script {
+    name: String,
+    label: String,
+    ast: AST,
+    entry: EntryPoint,
+    params: Vec<ParamSpec>,
+    constraints: Vec<Constraint>,
+    max_operations: u64,
+}
+
+impl Script {
+    /// Compile a script file; the strategy is named after the file stem
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read script {}", path.display()))?;
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "script".to_string());
+        Self::compile(&name, &path.display().to_string(), &source)
+    }
+
+    /// Compile script source; `label` identifies it in error messages
+    pub fn compile(name: &str, label: &str, source: &str) -> Result<Self> {
+        let engine = new_engine(
+            DEFAULT_MAX_OPERATIONS,
+            api::MIN_ARRAY_SIZE,
+            Params::new(),
+            &[],
+        );
+        let ast = engine
+            .compile(source)
+            .map_err(|e| anyhow!("{}: {}", label, e))?;
+
+        let has_fn = |fn_name: &str, arity: usize| {
+            ast.iter_functions()
+                .any(|f| f.name == fn_name && f.params.len() == arity)
+        };
+        let entry = if has_fn("generate_signals", 1) {
+            EntryPoint::GenerateSignals
+        } else if has_fn("on_bar", 1) {
+            EntryPoint::OnBar {
+                init: has_fn("init", 0),
+            }
+        } else {
+            bail!(
+                "{}: script must define generate_signals(bars) or on_bar(bar)",
+                label
+            );
+        };
+        let declares_params = has_fn("parameters", 0);
+        let declares_constraints = has_fn("constraints", 0);
+
+        let mut script = Self {
+            name: name.to_string(),
+            label: label.to_string(),
+            ast,
+            entry,
+            params: Vec::new(),
+            constraints: Vec::new(),
+            max_operations: DEFAULT_MAX_OPERATIONS,
+        };
+
+        if declares_params {
+            let declared = script
+                .call(&engine, "parameters")
+                .with_context(|| format!("{}: parameters() failed", label))?
+                .try_cast::<Map>()
+                .with_context(|| format!("{}: parameters() must return a map", label))?;
+            for (name, spec) in declared {
+                let spec = spec
+                    .try_cast::<Map>()
+                    .with_context(|| format!("{}: parameter '{}' must be a map", label, name))?;
+                script.params.push(
+                    param_spec(&name, spec)
+                        .with_context(|| format!("{}: parameter '{}'", label, name))?,
+                );
+            }
+        }
+        if declares_constraints {
+            let declared = script
+                .call(&engine, "constraints")
+                .with_context(|| format!("{}: constraints() failed", label))?
+                .into_array()
+                .map_err(|t| {
+                    anyhow!("{}: constraints() must return an array, found {}", label, t)
+                })?;
+            for item in declared {
+                let text = item
+                    .into_string()
+                    .map_err(|t| anyhow!("{}: constraints must be strings, found {}", label, t))?;
+                script.constraints.push(script.parse_constraint(&text)?);
+            }
+        }
+
+        Ok(script)
+    }
+
+    /// Limit the operations per call into the script
+    pub fn with_max_operations(mut self, max_operations: u64) -> Self {
+        self.max_operations = max_operations;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Registry definition that builds this script with resolved params
+    pub fn definition(self) -> StrategyDefinition {
+        let script = Arc::new(self);
+        let mut definition =
+            StrategyDefinition::new(&script.name, &format!("Rhai script {}", script.label), {
+                let script = Arc::clone(&script);
+                move |params| {
+                    Ok(
+                        Box::new(ScriptStrategy::new(Arc::clone(&script), params.clone()))
+                            as Box<dyn Strategy>,
+                    )
+                }
+            });
+        for spec in &script.params {
+            definition = definition.with_param(spec.clone());
+        }
+        for constraint in &script.constraints {
+            definition = definition.with_constraint(constraint.clone());
+        }
+        definition
+    }
+
+    fn call(&self, engine: &Engine, fn_name: &str) -> Result<Dynamic> {
+        engine
+            .call_fn(&mut Scope::new(), &self.ast, fn_name, ())
+            .map_err(|e| anyhow!("{}", e))
+    }
+
+    fn parse_constraint(&self, text: &str) -> Result<Constraint> {
+        let (lower, upper) = text.split_once('<').with_context(|| {
+            format!(
+                "{}: expected a constraint like 'fast < slow', got '{}'",
+                self.label, text
+            )
+        })?;
+        let (lower, upper) = (lower.trim(), upper.trim());
+        for name in [lower, upper] {
+            if !self.params.iter().any(|p| p.name == name) {
+                bail!(
+                    "{}: constraint '{}' uses undeclared parameter '{}'",
+                    self.label,
+                    text,
+                    name
+                );
+            }
+        }
+        Ok(Constraint::less_than(lower, upper))
+    }
+}
+
+fn number(value: &Dynamic) -> Option<f64> {
+    value
+        .as_float()
+        .ok()
+        .or_else(|| value.as_int().ok().map(|i| i as f64))
+}
+
+/// Parameter declaration from a script map
+fn param_spec(name: &str, mut spec: Map) -> Result<ParamSpec> {
+    let mut field = |key: &str| -> Result<(f64, bool)> {
+        let value = spec
+            .remove(key)
+            .with_context(|| format!("missing '{}'", key))?;
+        let n = number(&value).with_context(|| format!("'{}' must be a number", key))?;
+        Ok((n, value.is_int()))
+    };
+    let (default, default_int) = field("value")?;
+    let (min, min_int) = field("min")?;
+    let (max, max_int) = field("max")?;
+
+    let mut param = ParamSpec::float(name, default, min, max);
+    if default_int && min_int && max_int {
+        param.kind = ParamKind::Integer;
+    }
+    if let Some(description) = spec.remove("description") {
+        let description = description
+            .into_string()
+            .map_err(|_| anyhow!("'description' must be a string"))?;
+        param = param.with_description(&description);
+    }
+    if let Some(sweep) = spec.remove("sweep") {
+        let values: Option<Vec<f64>> = sweep
+            .into_array()
+            .ok()
+            .map(|a| a.iter().filter_map(number).collect());
+        match values.as_deref() {
+            Some(&[start, end, step]) if step > 0.0 => param = param.with_sweep(start, end, step),
+            _ => bail!("'sweep' must be [start, end, step] with step > 0"),
+        }
+    }
+    if let Some(key) = spec.keys().next() {
+        bail!("unknown field '{}'", key);
+    }
+
+    param.validate(default).context("invalid default")?;
+    Ok(param)
+}
+
+/// Sandboxed engine with the script API and `param(name)` bound to `params`
+fn new_engine(
+    max_operations: u64,
+    max_array_size: usize,
+    params: Params,
+    specs: &[ParamSpec],
+) -> Engine {
+    let mut engine = Engine::new();
+    api::register(&mut engine, max_operations, max_array_size);
+
+    let integers: Vec<String> = specs
+        .iter()
+        .filter(|s| s.kind == ParamKind::Integer)
+        .map(|s| s.name.clone())
+        .collect();
+    engine.register_fn(
+        "param",
+        move |name: &str| -> Result<Dynamic, Box<EvalAltResult>> {
+            if !params.contains(name) {
+                return Err(format!("unknown parameter '{}'", name).into());
+            }
+            let value = params.get(name);
+            if integers.iter().any(|n| n == name) {
+                Ok(Dynamic::from_int(value as rhai::INT))
+            } else {
+                Ok(Dynamic::from_float(value))
+            }
+        },
+    );
+    engine
+}
+
+/// Strategy backed by a script with fixed parameter values
+pub struct ScriptStrategy {
+    script: Arc<Script>,
+    params: Params,
+    name: String,
+}
+
+impl ScriptStrategy {
+    /// Strategy running `script` with `params`, named after both so that
+    /// variants of one script can be told apart
+    pub fn new(script: Arc<Script>, params: Params) -> Self {
+        let name = if params.is_empty() {
+            script.name.clone()
+        } else {
+            format!("{}({})", script.name, params)
+        };
+        Self {
+            script,
+            params,
+            name,
+        }
+    }
+
+    /// Engine for a run over `bars` bars, sizing the array limit to the series
+    fn engine(&self, bars: usize) -> Engine {
+        new_engine(
+            self.script.max_operations,
+            bars.max(api::MIN_ARRAY_SIZE),
+            self.params.clone(),
+            &self.script.params,
+        )
+    }
+
+    fn run(&self, data: &[OHLCV]) -> Result<Vec<f64>> {
+        let script = &self.script;
+        let engine = self.engine(data.len());
+        let signals = match script.entry {
+            EntryPoint::GenerateSignals => {
+                let bars = Bars(Arc::new(data.to_vec()));
+                let output: Dynamic = engine
+                    .call_fn(&mut Scope::new(), &script.ast, "generate_signals", (bars,))
+                    .map_err(|e| anyhow!("{}: {}", script.label, e))?;
+                let output = output.into_array().map_err(|t| {
+                    anyhow!(
+                        "{}: generate_signals must return an array, found {}",
+                        script.label,
+                        t
+                    )
+                })?;
+                output
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| {
+                        signal(v).with_context(|| {
+                            format!(
+                                "{}: signal {} is not a number ({})",
+                                script.label,
+                                i,
+                                v.type_name()
+                            )
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?
+            }
+            EntryPoint::OnBar { init } => self.run_on_bar(&engine, data, init)?,
+        };
+
+        if signals.len() != data.len() {
+            bail!(
+                "{}: expected {} signals, got {}",
+                script.label,
+                data.len(),
+                signals.len()
+            );
+        }
+        Ok(signals)
+    }
+
+    fn run_on_bar(&self, engine: &Engine, data: &[OHLCV], init: bool) -> Result<Vec<f64>> {
+        let script = &self.script;
+        let mut state = Dynamic::from_map(Map::new());
+        let mut scope = Scope::new();
+
+        if init {
+            let options = CallFnOptions::new().bind_this_ptr(&mut state);
+            let _: Dynamic = engine
+                .call_fn_with_options(options, &mut scope, &script.ast, "init", ())
+                .map_err(|e| anyhow!("{}: {}", script.label, e))?;
+        }
+
+        let mut current = 0.0;
+        let mut signals = Vec::with_capacity(data.len());
+        for (i, bar) in data.iter().enumerate() {
+            let options = CallFnOptions::new()
+                .eval_ast(false)
+                .bind_this_ptr(&mut state);
+            let output: Dynamic = engine
+                .call_fn_with_options(options, &mut scope, &script.ast, "on_bar", (bar.clone(),))
+                .map_err(|e| anyhow!("{}: bar {}: {}", script.label, i, e))?;
+            if !output.is_unit() {
+                current = signal(&output).with_context(|| {
+                    format!(
+                        "{}: on_bar must return a number, found {}",
+                        script.label,
+                        output.type_name()
+                    )
+                })?;
+            }
+            signals.push(current);
+        }
+        Ok(signals)
+    }
+}
+
+/// Signal value from a number or boolean
+fn signal(value: &Dynamic) -> Option<f64> {
+    number(value).or_else(|| value.as_bool().ok().map(|b| if b { 1.0 } else { 0.0 }))
+}
+
+impl Strategy for ScriptStrategy {
+    fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+        signals_or_flat(&self.name, data, self.run(data))
+    }
+
+    /// Run the script, surfacing script errors instead of going flat
+    fn try_generate_signals(&self, data: &[OHLCV]) -> Result<Vec<f64>> {
+        self.run(data)
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        if self.params.is_empty() {
+            format!("Rhai script {}", self.script.label)
+        } else {
+            format!("Rhai script {} ({})", self.script.label, self.params)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::test_support::sample_bars;
+    use crate::strategies::EMACrossover;
+
+    const BATCH: &str = r#"
+        fn parameters() {
+            #{
+                fast: #{ value: 12, min: 2, max: 100, sweep: [5, 20, 5] },
+                slow: #{ value: 26, min: 3, max: 200, sweep: [20, 40, 10] },
+            }
+        }
+        fn constraints() { ["fast < slow"] }
+        fn generate_signals(bars) {
+            let fast = ema(bars.close, param("fast"));
+            let slow = ema(bars.close, param("slow"));
+            let signals = [];
+            for i in 0..fast.len() {
+                signals.push(if fast[i] > slow[i] { 1.0 } else { 0.0 });
+            }
+            signals
+        }
+    "#;
+
+    const ON_BAR: &str = r#"
+        fn init() {
+            this.fast = Ema(12);
+            this.slow = Ema(26);
+        }
+        fn on_bar(bar) {
+            let f = this.fast.update(bar.close);
+            let s = this.slow.update(bar.close);
+            f > s
+        }
+    "#;
+
+    #[test]
+    fn test_scripts_match_builtin_strategy() {
+        let bars = sample_bars();
+        let expected = EMACrossover::new(12, 26).generate_signals(&bars);
+
+        let definition = Script::compile("ema", "ema.rhai", BATCH)
+            .unwrap()
+            .definition();
+        let strategy = definition.build(&Params::new()).unwrap();
+        assert_eq!(strategy.generate_signals(&bars), expected);
+
+        let script = Arc::new(Script::compile("ema", "ema.rhai", ON_BAR).unwrap());
+        let strategy = ScriptStrategy::new(script, Params::new());
+        assert_eq!(strategy.try_generate_signals(&bars).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_declared_parameters_drive_the_registry() {
+        let definition = Script::compile("ema", "ema.rhai", BATCH)
+            .unwrap()
+            .definition();
+        assert_eq!(definition.param("fast").unwrap().kind, ParamKind::Integer);
+        assert!(definition
+            .resolve(&Params::new().with("fast", 30.0).with("slow", 20.0))
+            .is_err());
+
+        let combos = definition
+            .grid_combinations(&definition.default_grid())
+            .unwrap();
+        assert_eq!(combos.len(), 11);
+
+        let bars = sample_bars();
+        let strategy = definition
+            .build(&Params::new().with("fast", 5.0).with("slow", 20.0))
+            .unwrap();
+        assert_eq!(
+            strategy.generate_signals(&bars),
+            EMACrossover::new(5, 20).generate_signals(&bars)
+        );
+    }
+
+    #[test]
+    fn test_sandbox_and_errors() {
+        let endless = "fn generate_signals(bars) { loop { } }";
+        let script = Script::compile("loop", "loop.rhai", endless)
+            .unwrap()
+            .with_max_operations(10_000);
+        let strategy = ScriptStrategy::new(Arc::new(script), Params::new());
+        let err = strategy.try_generate_signals(&sample_bars()).unwrap_err();
+        assert!(err.to_string().contains("Too many operations"), "{}", err);
+        assert_eq!(strategy.generate_signals(&sample_bars()), vec![0.0; 500]);
+
+        let err = Script::compile(
+            "bad",
+            "bad.rhai",
+            "fn generate_signals(bars) {\n  let x = ;\n}",
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("line 2"), "{}", err);
+
+        assert!(Script::compile("none", "none.rhai", "fn helper() { 1 }").is_err());
+        assert!(
+            Script::compile("short", "short.rhai", "fn generate_signals(bars) { [1.0] }")
+                .map(|s| ScriptStrategy::new(Arc::new(s), Params::new()))
+                .unwrap()
+                .try_generate_signals(&sample_bars())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_sandbox_limits_allocations() {
+        let run = |source: &str| {
+            let script = Script::compile("alloc", "alloc.rhai", source).unwrap();
+            ScriptStrategy::new(Arc::new(script), Params::new())
+                .try_generate_signals(&sample_bars())
+                .unwrap_err()
+                .to_string()
+        };
+
+        let huge_period = "fn init() { this.s = Sma(9223372036854775807); }\n\
+                           fn on_bar(bar) { 0.0 }";
+        let err = run(huge_period);
+        assert!(err.contains("period must be at most"), "{}", err);
+        let err = run("fn generate_signals(bars) { sma(bars.close, 1 << 40) }");
+        assert!(err.contains("period must be at most"), "{}", err);
+
+        let growing = "fn generate_signals(bars) { let a = [1.0]; loop { a += a; } }";
+        let err = run(growing);
+        assert!(err.contains("array"), "{}", err);
+    }
+}
@@ -1,5 +1,7 @@
 use crate::data::OHLCV;
+use crate::indicators::{sma, IndicatorCache};
 use crate::strategies::Strategy;
+use anyhow::Result;
 
 /// Simple Moving Average (SMA) Crossover Strategy
 /// Go long when fast MA crosses above slow MA
@@ -7,6 +9,8 @@ use crate::strategies::Strategy;
 pub struct SMACrossover {
     fast_period: usize,
     slow_period: usize,
+    /// Display name including the parameters
+    name: String,
 }
 
 impl SMACrossover {
@@ -21,41 +25,16 @@ impl SMACrossover {
         Self {
             fast_period,
             slow_period,
+            name: format!("SMA {}/{}", fast_period, slow_period),
         }
     }
 
-    fn calculate_sma(&self, prices: &[f64], period: usize) -> Vec<f64> {
-        let mut sma = Vec::with_capacity(prices.len());
-
-        if period == 0 || prices.is_empty() {
-            return vec![f64::NAN; prices.len()];
-        }
-
-        for i in 0..prices.len() {
-            if i + 1 < period {
-                sma.push(f64::NAN);
-            } else {
-                let start = i + 1 - period;
-                let sum: f64 = prices[start..=i].iter().sum();
-                sma.push(sum / period as f64);
-            }
-        }
-
-        sma
-    }
-}
-
-impl Strategy for SMACrossover {
-    fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
-        let closes: Vec<f64> = data.iter().map(|d| d.close).collect();
-
-        let fast_sma = self.calculate_sma(&closes, self.fast_period);
-        let slow_sma = self.calculate_sma(&closes, self.slow_period);
-
-        let mut signals = Vec::with_capacity(data.len());
+    /// Position at every bar from the two SMA series
+    fn crossover_signals(&self, fast_sma: &[f64], slow_sma: &[f64]) -> Vec<f64> {
+        let mut signals = Vec::with_capacity(fast_sma.len());
         let mut current_position = 0.0;
 
-        for i in 0..data.len() {
+        for i in 0..fast_sma.len() {
             if fast_sma[i].is_nan() || slow_sma[i].is_nan() {
                 signals.push(0.0);
                 continue;
@@ -85,9 +64,25 @@ impl Strategy for SMACrossover {
 
         signals
     }
+}
+
+impl Strategy for SMACrossover {
+    fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+        let closes: Vec<f64> = data.iter().map(|d| d.close).collect();
+
+        let fast_sma = sma(&closes, self.fast_period);
+        let slow_sma = sma(&closes, self.slow_period);
+        self.crossover_signals(&fast_sma, &slow_sma)
+    }
+
+    fn generate_signals_cached(&self, data: &[OHLCV], cache: &IndicatorCache) -> Result<Vec<f64>> {
+        let fast_sma = cache.sma(data, self.fast_period);
+        let slow_sma = cache.sma(data, self.slow_period);
+        Ok(self.crossover_signals(&fast_sma, &slow_sma))
+    }
 
     fn name(&self) -> &str {
-        "SMA Crossover"
+        &self.name
     }
 
     fn description(&self) -> String {
@@ -0,0 +1,157 @@
+use crate::data::{TimeframeContext, OHLCV};
+use crate::indicators::sma;
+use crate::strategies::{signals_or_flat, Strategy};
+use anyhow::Result;
+
+/// Trade `strategy` only while a higher timeframe is in an uptrend
+///
+/// The trend is on while the last completed `interval` bar closes above its
+/// `period`-bar SMA, e.g. 1h signals gated by the daily close above its
+/// 50-day average.
+pub struct HigherTimeframeFilter {
+    strategy: Box<dyn Strategy>,
+    interval: String,
+    period: usize,
+    name: String,
+}
+
+impl HigherTimeframeFilter {
+    pub fn new(strategy: Box<dyn Strategy>, interval: &str, period: usize) -> Self {
+        assert!(period > 0, "Trend period must be greater than 0");
+        let name = format!("{} [{} > SMA {}]", strategy.name(), interval, period);
+        Self {
+            strategy,
+            interval: interval.to_string(),
+            period,
+            name,
+        }
+    }
+}
+
+impl Strategy for HigherTimeframeFilter {
+    fn generate_signals(&self, data: &[OHLCV]) -> Vec<f64> {
+        signals_or_flat(&self.name, data, self.try_generate_signals(data))
+    }
+
+    fn try_generate_signals(&self, data: &[OHLCV]) -> Result<Vec<f64>> {
+        match TimeframeContext::resampled(data, None, &[&self.interval]) {
+            Ok(context) => self.generate_signals_with_context(data, &context),
+            // Too little data to infer the bar interval, or a timeframe
+            // shorter than the base bars: no trend, so no trades
+            Err(_) => Ok(vec![0.0; data.len()]),
+        }
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "{} - only while the {} close is above its {}-bar SMA",
+            self.strategy.description(),
+            self.interval,
+            self.period
+        )
+    }
+
+    /// This filter's timeframe and any the inner strategy requests
+    fn timeframes(&self) -> Vec<String> {
+        let mut timeframes = vec![self.interval.clone()];
+        for timeframe in self.strategy.timeframes() {
+            if !timeframes.contains(&timeframe) {
+                timeframes.push(timeframe);
+            }
+        }
+        timeframes
+    }
+
+    fn generate_signals_with_context(
+        &self,
+        data: &[OHLCV],
+        context: &TimeframeContext,
+    ) -> Result<Vec<f64>> {
+        let frame = match context.get(&self.interval) {
+            Some(frame) => frame,
+            None => return self.try_generate_signals(data),
+        };
+
+        let closes: Vec<f64> = frame.bars().iter().map(|b| b.close).collect();
+        let uptrend: Vec<f64> = closes
+            .iter()
+            .zip(sma(&closes, self.period))
+            .map(|(&close, average)| if close > average { 1.0 } else { 0.0 })
+            .collect();
+        let regime = frame.align(&uptrend);
+
+        Ok(self
+            .strategy
+            .generate_signals_with_context(data, context)?
+            .iter()
+            .zip(&regime)
+            .map(|(&s, &r)| if r > 0.0 { s } else { 0.0 })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtest::{BacktestEngine, ExecutionModel};
+    use crate::data::{resample, AlignedTimeframe, SyntheticConfig, SyntheticModel};
+    use crate::strategies::{BuyAndHold, SMACrossover};
+
+    const HOUR: i64 = 3_600_000;
+
+    fn hourly_bars() -> Vec<OHLCV> {
+        SyntheticModel::garch()
+            .generate(&SyntheticConfig::new(24 * 120, 11))
+            .into_iter()
+            .enumerate()
+            .map(|(i, mut bar)| {
+                bar.timestamp = i as i64 * HOUR;
+                bar
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_signals_never_use_unfinished_bars() {
+        let data = hourly_bars();
+        let strategy = HigherTimeframeFilter::new(Box::new(BuyAndHold::new()), "1d", 10);
+        let full = strategy.generate_signals(&data);
+        assert!(full.contains(&1.0) && full.contains(&0.0));
+
+        // Signals up to any bar are the same whether or not later bars exist
+        for end in [24 * 15 + 5, 24 * 40 + 23, 24 * 77 + 12] {
+            assert_eq!(strategy.generate_signals(&data[..end]), full[..end]);
+        }
+    }
+
+    #[test]
+    fn test_engine_supplies_context() {
+        let data = hourly_bars();
+        let strategy = HigherTimeframeFilter::new(Box::new(SMACrossover::new(20, 50)), "1d", 10);
+        assert_eq!(strategy.timeframes(), vec!["1d".to_string()]);
+
+        // A supplied context replaces the strategy's own resampling: flat
+        // daily closes never trade above their average
+        let flat: Vec<OHLCV> = resample(&data, 24 * HOUR)
+            .into_iter()
+            .map(|mut bar| {
+                bar.close = 100.0;
+                bar
+            })
+            .collect();
+        let mut context = TimeframeContext::new();
+        context.insert(AlignedTimeframe::new("1d", &data, HOUR, flat, 24 * HOUR));
+
+        let model = ExecutionModel::new(10.0, 5.0);
+        let resampled = BacktestEngine::new(&data, 100_000.0, model.clone()).run(&strategy);
+        let with_context = BacktestEngine::new(data, 100_000.0, model)
+            .with_timeframes(context)
+            .run(&strategy);
+        assert!(resampled.total_trades > 0);
+        assert_eq!(with_context.total_trades, 0);
+    }
+}
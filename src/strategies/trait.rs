@@ -1,4 +1,6 @@
-use crate::data::OHLCV;
+use crate::data::{TimeframeContext, OHLCV};
+use crate::indicators::IndicatorCache;
+use anyhow::Result;
 
 /// Core trait that all trading strategies must implement
 pub trait Strategy: Send + Sync {
@@ -15,4 +17,54 @@ pub trait Strategy: Send + Sync {
     fn description(&self) -> String {
         format!("{} strategy", self.name())
     }
+
+    /// Generate signals, reporting a failure (e.g. a script error) instead of
+    /// going flat
+    ///
+    /// Strategies that cannot fail need not override it; those that can
+    /// should make `generate_signals` fall back to flat on error.
+    fn try_generate_signals(&self, data: &[OHLCV]) -> Result<Vec<f64>> {
+        Ok(self.generate_signals(data))
+    }
+
+    /// Higher timeframes (e.g. `"1d"`) the strategy reads besides its base bars
+    fn timeframes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Generate signals with the timeframes requested by `timeframes()`
+    /// supplied by the caller, e.g. from the data catalog
+    ///
+    /// Strategies that request timeframes should also make
+    /// `generate_signals` work alone, typically by resampling the base bars.
+    fn generate_signals_with_context(
+        &self,
+        data: &[OHLCV],
+        context: &TimeframeContext,
+    ) -> Result<Vec<f64>> {
+        let _ = context;
+        self.try_generate_signals(data)
+    }
+
+    /// Generate signals reading indicator series of `data` from `cache`,
+    /// which is shared by every run of a parameter sweep
+    ///
+    /// Must return the same signals as `generate_signals`; strategies that
+    /// don't override it ignore the cache.
+    fn generate_signals_cached(&self, data: &[OHLCV], cache: &IndicatorCache) -> Result<Vec<f64>> {
+        let _ = cache;
+        self.try_generate_signals(data)
+    }
+}
+
+/// `generate_signals` for strategies that can fail: flat, with the error
+/// printed, when `signals` is an error
+pub(crate) fn signals_or_flat(name: &str, data: &[OHLCV], signals: Result<Vec<f64>>) -> Vec<f64> {
+    match signals {
+        Ok(signals) => signals,
+        Err(e) => {
+            eprintln!("{} failed, staying flat: {:#}", name, e);
+            vec![0.0; data.len()]
+        }
+    }
 }